@@ -0,0 +1,40 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const ROUNDS: u64 = 100000;
+
+/// Average cycles of one round-trip of `f`
+fn measure(f: fn() -> u16) -> u64 {
+    let pid = sys_get_pid();
+
+    // warm up
+    for _ in 0..100 {
+        assert_eq!(f(), pid);
+    }
+
+    let start = unsafe { core::arch::x86_64::_rdtsc() };
+    for _ in 0..ROUNDS {
+        core::hint::black_box(f());
+    }
+    let end = unsafe { core::arch::x86_64::_rdtsc() };
+
+    (end - start) / ROUNDS
+}
+
+fn main() -> isize {
+    println!("Benchmarking GetPid round-trip, {} rounds each...", ROUNDS);
+
+    let int80 = measure(sys_get_pid_int80);
+    let fast = measure(sys_get_pid_fast);
+
+    println!("int 0x80 : {:>6} cycles", int80);
+    println!("syscall  : {:>6} cycles", fast);
+
+    0
+}
+
+entry!(main);
@@ -9,13 +9,19 @@ extern crate lib;
 const THREAD_COUNT: usize = 8;
 static mut COUNTER: isize = 0;
 
-static SEMAPHORE: Semaphore = Semaphore::new(0);
+static SEMAPHORE: Semaphore = Semaphore::new("counter");
+
+// 生产者-消费者：计数信号量不应被误报为死锁
+const BUFFER_SIZE: usize = 4;
+const ITEM_COUNT: usize = 32;
+static EMPTY: Semaphore = Semaphore::new("counter-empty");
+static FULL: Semaphore = Semaphore::new("counter-full");
 
 fn main() -> isize {
     let mut pids = [0u16; THREAD_COUNT];
-    SEMAPHORE.init(1);
+    SEMAPHORE.init_mutex().unwrap();
     for i in 0..THREAD_COUNT {
-        let pid = sys_fork();
+        let pid = sys_fork().expect("Failed to fork");
         if pid == 0 {
             do_counter_inc();
             sys_exit(0);
@@ -30,21 +36,59 @@ fn main() -> isize {
 
     for i in 0..THREAD_COUNT {
         println!("#{} waiting for #{}...", cpid, pids[i]);
-        sys_wait_pid(pids[i]);
+        sys_wait_pid(pids[i]).unwrap();
     }
 
-    SEMAPHORE.remove();
+    SEMAPHORE.close().unwrap();
     println!("COUNTER result: {}", unsafe { COUNTER });
 
+    produce_consume();
+
     0
 }
 
+/// A producer fills all slots of a bounded buffer and blocks on `EMPTY` until
+/// the consumer takes an item, which must not be reported as a deadlock
+fn produce_consume() {
+    EMPTY.init(BUFFER_SIZE).unwrap();
+    FULL.init(0).unwrap();
+
+    let producer = sys_fork().expect("Failed to fork");
+    if producer == 0 {
+        for _ in 0..ITEM_COUNT {
+            EMPTY.wait_checked().expect("producer: unexpected EDEADLK");
+            FULL.signal().unwrap();
+        }
+        sys_exit(0);
+    }
+
+    let consumer = sys_fork().expect("Failed to fork");
+    if consumer == 0 {
+        // 先让生产者填满缓冲区并阻塞在 EMPTY 上
+        sleep(10);
+        for _ in 0..ITEM_COUNT {
+            FULL.wait_checked().expect("consumer: unexpected EDEADLK");
+            EMPTY.signal().unwrap();
+        }
+        sys_exit(0);
+    }
+
+    let producer_ret = sys_wait_pid(producer).unwrap();
+    let consumer_ret = sys_wait_pid(consumer).unwrap();
+    EMPTY.close().unwrap();
+    FULL.close().unwrap();
+    println!(
+        "Producer/consumer: {} items through {} slots, exit codes {} {}",
+        ITEM_COUNT, BUFFER_SIZE, producer_ret, consumer_ret
+    );
+}
+
 fn do_counter_inc() {
     for _ in 0..100 {
         // FIXME: protect the critical section
-        SEMAPHORE.wait();
+        SEMAPHORE.wait().unwrap();
         inc_counter();
-        SEMAPHORE.signal();
+        SEMAPHORE.signal().unwrap();
     }
 }
 
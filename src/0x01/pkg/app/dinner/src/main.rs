@@ -8,7 +8,13 @@ use lib::sync::Semaphore;
 extern crate lib;
 
 const PHI_SIZE: usize = 5;
-const CHOPSTICK: [Semaphore; 5] = semaphore_array![0, 1, 2, 3, 4];
+static CHOPSTICK: [Semaphore; 5] = semaphore_array![
+    "chopstick0",
+    "chopstick1",
+    "chopstick2",
+    "chopstick3",
+    "chopstick4",
+];
 
 // static S1: Semaphore = Semaphore::new(5);
 // static S2: Semaphore = Semaphore::new(6);
@@ -16,7 +22,7 @@ static mut PHILOSOPHER: [i32; PHI_SIZE] = [0; PHI_SIZE];
 
 fn main() -> isize {
     for i in 0..PHI_SIZE {
-        CHOPSTICK[i].init(1);
+        CHOPSTICK[i].init_mutex().unwrap();
     } // 初始化筷子信号量
 
     let help =r#"
@@ -24,6 +30,7 @@ fn main() -> isize {
         函数1：一般情况，会造成死锁。\n
         函数2：要求奇数号哲学家先拿左边的筷子，然后拿右边的筷子；偶数号哲学家相反。\n
         函数3：要求哲学家必须按照筷子从小到大拿去，会出现不公平甚至饥饿。\n
+        函数4：一般情况，但由内核检测死锁，即将死锁时放下已拿到的筷子重试。\n
         请输入对应调用函数序号：
     "#;
     println!("{}", help);
@@ -33,7 +40,7 @@ fn main() -> isize {
         "1" => {
             println!("函数1：一般情况，会造成死锁。");
             for i in 0..PHI_SIZE {
-                let pid = sys_fork();
+                let pid = sys_fork().expect("Failed to fork");
                 if pid == 0 {
                     philosopher1(i);
                     sys_exit(0); 
@@ -45,7 +52,7 @@ fn main() -> isize {
         "2" => {
             println!("函数2：要求奇数号哲学家先拿左边的筷子，然后拿右边的筷子；偶数号哲学家相反。");
             for i in 0..PHI_SIZE {
-                let pid = sys_fork();
+                let pid = sys_fork().expect("Failed to fork");
                 if pid == 0 {
                     philosopher2(i);
                     sys_exit(0);
@@ -57,7 +64,7 @@ fn main() -> isize {
         "3" => {
             println!("函数3：要求哲学家必须按照筷子从小到大拿去，会出现不公平甚至饥饿。");
             for i in 0..PHI_SIZE {
-                let pid = sys_fork();
+                let pid = sys_fork().expect("Failed to fork");
                 if pid == 0 {
                     philosopher3(i);
                     sys_exit(0);
@@ -65,10 +72,22 @@ fn main() -> isize {
                 pids[i] = pid;
             }
         }
+
+        "4" => {
+            println!("函数4：一般情况，但由内核检测死锁，即将死锁时放下已拿到的筷子重试。");
+            for i in 0..PHI_SIZE {
+                let pid = sys_fork().expect("Failed to fork");
+                if pid == 0 {
+                    philosopher4(i);
+                    sys_exit(0);
+                }
+                pids[i] = pid;
+            }
+        }
         _ => {
             println!("invaild input");
             for i in 0..PHI_SIZE {
-                CHOPSTICK[i].remove();
+                CHOPSTICK[i].close().unwrap();
             }
             return 0;
         }
@@ -76,11 +95,11 @@ fn main() -> isize {
     let cpid = sys_get_pid();
     for i in 0..PHI_SIZE {
         println!("#{} is waiting for #{}", cpid, pids[i]);
-        sys_wait_pid(pids[i]);
+        sys_wait_pid(pids[i]).unwrap();
     }
 
     for i in 0..PHI_SIZE {
-        CHOPSTICK[i].remove();
+        CHOPSTICK[i].close().unwrap();
     }
     return 0;
 }
@@ -92,18 +111,18 @@ fn philosopher1(i: usize){
     let right = (i + 1) % PHI_SIZE;
     for _a in 0..20{
         //thinking
-        CHOPSTICK[left].wait();
+        CHOPSTICK[left].wait().unwrap();
         println!("Philosopher {} get chopstick {}", i, left);
         sleep(SLEEP_TIME);
-        CHOPSTICK[right].wait();
+        CHOPSTICK[right].wait().unwrap();
         println!("Philosopher {} get chopstick {}", i, right);
         sleep(SLEEP_TIME);
         //eating
         println!("\x1b[32mPhilosopher {} is eating\x1b[0m", i);
-        CHOPSTICK[left].signal();
+        CHOPSTICK[left].signal().unwrap();
         println!("Philosopher {} release chopstick {}", i, left);
         sleep(SLEEP_TIME);
-        CHOPSTICK[right].signal();
+        CHOPSTICK[right].signal().unwrap();
         println!("Philosopher {} release chopstick {}", i, right);
     }
 }
@@ -118,10 +137,10 @@ fn philosopher2(i: usize){
             right = left ^ right;
             left  = left ^ right;
         } // 偶数号哲学家的左右可以认为是相反的
-        CHOPSTICK[left].wait();
+        CHOPSTICK[left].wait().unwrap();
         println!("Philosopher {} get first chopstick {}", i, left);
         sleep(SLEEP_TIME);
-        CHOPSTICK[right].wait();
+        CHOPSTICK[right].wait().unwrap();
         println!("Philosopher {} get second chopstick {}", i, right);
         sleep(SLEEP_TIME);
         //eating
@@ -129,10 +148,10 @@ fn philosopher2(i: usize){
             PHILOSOPHER[i] += 1;
             println!("\x1b[32mPhilosopher {} is eating, he has eaten {} times.\x1b[0m", i, PHILOSOPHER[i]);
         }
-        CHOPSTICK[left].signal();
+        CHOPSTICK[left].signal().unwrap();
         println!("Philosopher {} release chopstick {}", i, left);
         sleep(SLEEP_TIME);
-        CHOPSTICK[right].signal();
+        CHOPSTICK[right].signal().unwrap();
         println!("Philosopher {} release chopstick {}", i, right);
     }
 }
@@ -148,10 +167,10 @@ fn philosopher3(i: usize){
             right = left ^ right;
             left  = left ^ right;
         }
-        CHOPSTICK[left].wait();
+        CHOPSTICK[left].wait().unwrap();
 
         sleep(SLEEP_TIME);
-        CHOPSTICK[right].wait();
+        CHOPSTICK[right].wait().unwrap();
         sleep(SLEEP_TIME);
         //eating
         unsafe{
@@ -159,9 +178,42 @@ fn philosopher3(i: usize){
             println!("\x1b[32mPhilosopher {} is eating, he has eaten {} times.\x1b[0m", i, PHILOSOPHER[i]);
         }
         
-        CHOPSTICK[left].signal();
+        CHOPSTICK[left].signal().unwrap();
+        sleep(SLEEP_TIME);
+        CHOPSTICK[right].signal().unwrap();
+    }
+}
+
+// 函数4：与函数1相同的拿法，拿第二根筷子时由内核检测死锁，返回 EDEADLK 时放下第一根筷子重试。
+fn philosopher4(i: usize){
+    let left = i;
+    let right = (i + 1) % PHI_SIZE;
+    for _a in 0..5{
+        //thinking
+        loop {
+            CHOPSTICK[left].wait().unwrap();
+            println!("Philosopher {} get chopstick {}", i, left);
+            sleep(SLEEP_TIME);
+            match CHOPSTICK[right].wait_checked() {
+                Ok(()) => break,
+                Err(Errno::EDEADLK) => {
+                    println!("\x1b[33mPhilosopher {} would deadlock, release chopstick {}\x1b[0m", i, left);
+                    CHOPSTICK[left].signal().unwrap();
+                    sleep(SLEEP_TIME * (i as u64 + 1));
+                }
+                Err(err) => panic!("Failed to get chopstick {}: {}", right, err),
+            }
+        }
+        println!("Philosopher {} get chopstick {}", i, right);
+        sleep(SLEEP_TIME);
+        //eating
+        unsafe{
+            PHILOSOPHER[i] += 1;
+            println!("\x1b[32mPhilosopher {} is eating, he has eaten {} times.\x1b[0m", i, PHILOSOPHER[i]);
+        }
+        CHOPSTICK[left].signal().unwrap();
         sleep(SLEEP_TIME);
-        CHOPSTICK[right].signal();
+        CHOPSTICK[right].signal().unwrap();
     }
 }
 
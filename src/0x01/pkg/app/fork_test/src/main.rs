@@ -14,7 +14,7 @@ fn main() -> isize {
 
     // do not alloc heap before `fork`
     // which may cause unexpected behavior since we won't copy the heap in `fork`
-    let pid = sys_fork();
+    let pid = sys_fork().expect("Failed to fork");
 
     if pid == 0 {
         println!("I am the child process");
@@ -37,7 +37,7 @@ fn main() -> isize {
 
         println!("Waiting for child to exit...");
 
-        let ret = sys_wait_pid(pid);
+        let ret = sys_wait_pid(pid).expect("Failed to wait child");
 
         println!("Child exited with status {}", ret);
 
@@ -51,8 +51,13 @@ fn main() -> isize {
     let student_number = " 学号:23336345  姓名： 周海铭";
 
     loop {
-    print!("{BOLD}{R3}[YatSenOS]{R4}> {RESET}");
+        let cwd = sys_getcwd().unwrap_or_else(|_| string::String::from("?"));
+        print!("{BOLD}{R3}[YatSenOS]{R5}{cwd}{R4}> {RESET}");
         let binding = stdin().read_line();
+        if binding.contains('|') {
+            run_pipeline(binding.trim());
+            continue;
+        }
         let mut command = binding.trim().split(' '); // 去除首尾的空白字符，并按空格分隔命令和参数
         let op = command.next().unwrap();  // 第一个单词是命令op
         
@@ -64,7 +69,11 @@ fn main() -> isize {
                 let commands = [
                     ("la", "列出所有可用应用"),
                     ("run <路径>", "运行指定路径的应用程序"),
-                    ("ps", "显示系统状态"),
+                    ("run <a> | run <b>", "运行管道，a 的输出作为 b 的输入"),
+                    ("ps", "显示进程列表"),
+                    ("ls [路径]", "列出目录内容，默认为当前目录"),
+                    ("cat <文件>...", "显示文件内容"),
+                    ("cd [路径]", "切换工作目录，默认为根目录"),
                     ("clear", "清屏"),
                     ("exit", "退出终端")
                 ];
@@ -81,26 +90,54 @@ fn main() -> isize {
                 match command.next() {
                     Some(path) => {
                         let name: vec::Vec<&str> = path.rsplit('/').collect();
-                        let pid = sys_spawn(path);
-                        if pid == 0 {
-                            println!("Failed to run app: {}", name[0]);
-                            continue;
-                        } else {
-                            sys_stat();
-                            println!("exited with {}: {}", name[0], sys_wait_pid(pid));
+                        match sys_spawn(path) {
+                            Err(err) => {
+                                println!("Failed to run app: {}: {}", name[0], err);
+                                continue;
+                            }
+                            Ok(pid) => {
+                                sys_stat();
+                                // 运行期间由子进程接收 Ctrl-C
+                                let _ = stdin().set_foreground(Some(pid));
+                                let ret = sys_wait_pid(pid);
+                                let _ = stdin().set_foreground(None);
+                                match ret {
+                                    Ok(ret) => println!("exited with {}: {}", name[0], ret),
+                                    Err(err) => println!("Failed to wait {}: {}", name[0], err),
+                                }
+                            }
                         }
                     }
                     None => println!("Error: Please specify application path"),
                 }
             }
             "ps" => {
-                println!("=====系统状态=====");
-                sys_stat();
+                ps();
+            }
+            "ls" => {
+                list_dir(command.find(|arg| !arg.is_empty()).unwrap_or("."));
+            }
+            "cat" => {
+                let mut paths = command.filter(|arg| !arg.is_empty()).peekable();
+                if paths.peek().is_none() {
+                    println!("Error: Please specify file path");
+                }
+                for path in paths {
+                    cat(path);
+                }
+            }
+            "cd" => {
+                let path = command.find(|arg| !arg.is_empty()).unwrap_or("/");
+                if let Err(err) = sys_chdir(path) {
+                    println!("cd: {}: {}", path, err);
+                }
             }
             "exit" => {
                 let goodbye = "Goodbye! See you next time!";
                 break;
             }
+            // 空行（例如 Ctrl-C 之后）直接显示新的提示符
+            "" => {}
             "clear" => {
                 print!("\x1B[2J\x1B[H"); // 完成清屏
             }
@@ -112,4 +149,180 @@ fn main() -> isize {
     0
 }
 
+/// List the processes alive by reading `/proc/<pid>/status`
+fn ps() {
+    let entries = match fs::read_dir("/proc") {
+        Ok(entries) => entries,
+        Err(err) => {
+            println!("ps: /proc: {}", err);
+            return;
+        }
+    };
+
+    println!("  PID | PPID | Process Name |  Ticks  | Status");
+    for entry in entries.flatten().filter(|entry| entry.metadata().is_dir()) {
+        // 读取期间进程可能已经退出
+        let path = format!("/proc/{}/status", entry.file_name());
+        let Ok(status) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let field = |key: &str| {
+            status
+                .lines()
+                .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+                .map_or("?", str::trim)
+        };
+        println!(
+            " #{:<3} | #{:<3} | {:12} | {:>7} | {}",
+            field("Pid"),
+            field("PPid"),
+            field("Name"),
+            field("Ticks"),
+            field("State")
+        );
+    }
+}
+
+/// List the entries of the directory at `path`, directories end with `/`
+fn list_dir(path: &str) {
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(err) => {
+            println!("ls: {}: {}", path, err);
+            return;
+        }
+    };
+
+    for entry in entries {
+        match entry {
+            Ok(entry) => {
+                let meta = entry.metadata();
+                if meta.is_dir() {
+                    println!("{:>8}  \x1b[94m{}/\x1b[0m", "-", entry.file_name());
+                } else {
+                    println!("{:>8}  {}", meta.len(), entry.file_name());
+                }
+            }
+            Err(err) => {
+                println!("ls: {}: {}", path, err);
+                return;
+            }
+        }
+    }
+}
+
+/// Write the file at `path` to stdout as-is
+fn cat(path: &str) {
+    let mut file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(err) => {
+            println!("cat: {}: {}", path, err);
+            return;
+        }
+    };
+
+    let mut buf = [0u8; 512];
+    loop {
+        match file.read(&mut buf) {
+            Ok(0) => break,
+            Ok(len) => {
+                if let Err(err) = write_all(STDOUT, &buf[..len]) {
+                    println!("cat: {}: {}", path, err);
+                    break;
+                }
+            }
+            Err(err) => {
+                println!("cat: {}: {}", path, err);
+                break;
+            }
+        }
+    }
+}
+
+/// Run `run a | run b | ...`, the stdout of each app is connected to the stdin of the next one
+fn run_pipeline(line: &str) {
+    let paths: vec::Vec<&str> = line
+        .split('|')
+        .map(|cmd| {
+            let cmd = cmd.trim();
+            cmd.strip_prefix("run ").map(str::trim).unwrap_or(cmd)
+        })
+        .collect();
+    if paths.iter().any(|path| path.is_empty()) {
+        println!("Error: Please specify application path");
+        return;
+    }
+
+    // 保存 shell 自己的标准输入输出，子进程不继承这两个 fd
+    let (Ok(saved_in), Ok(saved_out)) = (sys_dup(STDIN), sys_dup(STDOUT)) else {
+        println!("Failed to save stdin/stdout");
+        return;
+    };
+    let _ = sys_set_cloexec(saved_in, true);
+    let _ = sys_set_cloexec(saved_out, true);
+
+    let mut pids = vec::Vec::new();
+    // 上一个管道的读端，作为下一个应用的标准输入
+    let mut input: Option<usize> = None;
+    for (i, path) in paths.iter().enumerate() {
+        let output = if i + 1 < paths.len() {
+            match sys_pipe() {
+                Ok((read_fd, write_fd)) => {
+                    let _ = sys_set_cloexec(read_fd, true);
+                    let _ = sys_set_cloexec(write_fd, true);
+                    Some((read_fd, write_fd))
+                }
+                Err(err) => {
+                    println!("Failed to create pipe: {}", err);
+                    break;
+                }
+            }
+        } else {
+            None
+        };
+
+        // dup2 得到的 fd 没有 FD_CLOEXEC，会被子进程继承
+        if let Some(read_fd) = input {
+            let _ = sys_dup2(read_fd, STDIN);
+        }
+        if let Some((_, write_fd)) = output {
+            let _ = sys_dup2(write_fd, STDOUT);
+        }
+        let ret = sys_spawn(path);
+        let _ = sys_dup2(saved_in, STDIN);
+        let _ = sys_dup2(saved_out, STDOUT);
+
+        // 关闭 shell 持有的管道端，否则读者收不到 EOF
+        if let Some(read_fd) = input.take() {
+            let _ = sys_close(read_fd);
+        }
+        if let Some((_, write_fd)) = output {
+            let _ = sys_close(write_fd);
+        }
+        input = output.map(|(read_fd, _)| read_fd);
+
+        match ret {
+            Ok(pid) => pids.push((pid, *path)),
+            Err(err) => println!("Failed to run app: {}: {}", path, err),
+        }
+    }
+    if let Some(read_fd) = input {
+        let _ = sys_close(read_fd);
+    }
+    let _ = sys_close(saved_in);
+    let _ = sys_close(saved_out);
+
+    // 管道的最后一个应用接收 Ctrl-C
+    if let Some(&(pid, _)) = pids.last() {
+        let _ = stdin().set_foreground(Some(pid));
+    }
+    for (pid, path) in pids {
+        match sys_wait_pid(pid) {
+            Ok(ret) => println!("exited with {}: {}", path, ret),
+            Err(err) => println!("Failed to wait {}: {}", path, err),
+        }
+    }
+    let _ = stdin().set_foreground(None);
+}
+
 entry!(main);
\ No newline at end of file
@@ -0,0 +1,41 @@
+#![no_std]
+#![no_main]
+
+extern crate lib;
+
+use core::arch::asm;
+use lib::*;
+
+const MSG: &[u8] = b"read into a stack page never touched before";
+
+/// Distance below `rsp` of the buffer, far beyond the pages mapped so far
+const STACK_GAP: usize = 0x10000;
+
+fn main() -> isize {
+    let (read_fd, write_fd) = sys_pipe().expect("Failed to create pipe");
+
+    // 局部数组在初始化时就会访问栈页，直接取 rsp 以下从未访问过的地址
+    let rsp: usize;
+    unsafe { asm!("mov {}, rsp", out(reg) rsp) };
+
+    // 缓冲区跨越两个未映射的栈页，由内核在写入前增长栈
+    let addr = (rsp - STACK_GAP) & !0xFFF;
+    let buf = unsafe { core::slice::from_raw_parts_mut((addr - 16) as *mut u8, MSG.len()) };
+
+    assert_eq!(sys_write(write_fd, MSG), Ok(MSG.len()));
+    assert_eq!(sys_read(read_fd, buf), Ok(MSG.len()));
+    assert_eq!(buf, MSG);
+    println!("Read {} bytes into the fresh stack at {:#x}", MSG.len(), addr - 16);
+
+    // 栈以外的未映射地址仍然报错
+    let bad = unsafe { core::slice::from_raw_parts_mut(0x1000 as *mut u8, MSG.len()) };
+    assert_eq!(sys_write(write_fd, MSG), Ok(MSG.len()));
+    assert_eq!(sys_read(read_fd, bad), Err(Errno::EFAULT));
+
+    sys_close(read_fd).unwrap();
+    sys_close(write_fd).unwrap();
+
+    0
+}
+
+entry!(main);
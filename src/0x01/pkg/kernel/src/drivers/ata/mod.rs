@@ -0,0 +1,98 @@
+//! ATA Drive
+//!
+//! reference: https://wiki.osdev.org/IDE
+//! reference: https://wiki.osdev.org/ATA_PIO_Mode
+//! reference: https://github.com/theseus-os/Theseus/blob/HEAD/kernel/ata/src/lib.rs
+
+mod bus;
+mod consts;
+
+use alloc::{boxed::Box, string::String};
+use bus::AtaBus;
+use consts::AtaDeviceType;
+use spin::Mutex;
+
+lazy_static! {
+    pub static ref BUSES: [Mutex<AtaBus>; 2] = {
+        let buses = [
+            Mutex::new(AtaBus::new(0, 14, 0x1F0, 0x3F6)),
+            Mutex::new(AtaBus::new(1, 15, 0x170, 0x376)),
+        ];
+
+        info!("Initialized ATA Buses.");
+
+        buses
+    };
+}
+
+#[derive(Clone)]
+pub struct AtaDrive {
+    pub bus: u8,
+    pub drive: u8,
+    blocks: u32,
+    model: Box<str>,
+    serial: Box<str>,
+}
+
+impl AtaDrive {
+    pub fn open(bus: u8, drive: u8) -> Option<Self> {
+        trace!("Opening drive {}@{}...", bus, drive);
+
+        // we only support PATA drives
+        if let Ok(AtaDeviceType::Pata(res)) = BUSES[bus as usize].lock().identify_drive(drive) {
+            let buf = res.map(u16::to_be_bytes).concat();
+            // 字符串按大端字节序存放：序列号位于字 10-19，型号位于字 27-46
+            let serial = String::from_utf8_lossy(&buf[20..40]).trim().into();
+            let model = String::from_utf8_lossy(&buf[54..94]).trim().into();
+            // 字 60-61 为 LBA28 可寻址的扇区数，低位字在前
+            let blocks = (res[61] as u32) << 16 | res[60] as u32;
+            let ata_drive = Self {
+                bus,
+                drive,
+                model,
+                serial,
+                blocks,
+            };
+            info!("Drive {} opened", ata_drive);
+            Some(ata_drive)
+        } else {
+            warn!("Drive {}@{} is not a PATA drive", bus, drive);
+            None
+        }
+    }
+
+    fn humanized_size(&self) -> (f32, &'static str) {
+        let size = self.block_size();
+        let count = self.block_count().unwrap();
+        let bytes = size * count;
+
+        crate::humanized_size(bytes as u64)
+    }
+}
+
+impl core::fmt::Display for AtaDrive {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let (size, unit) = self.humanized_size();
+        write!(f, "{} {} ({} {})", self.model, self.serial, size, unit)
+    }
+}
+
+use storage::{Block512, BlockDevice};
+
+impl BlockDevice<Block512> for AtaDrive {
+    fn block_count(&self) -> storage::FsResult<usize> {
+        Ok(self.blocks as usize)
+    }
+
+    fn read_block(&self, offset: usize, block: &mut Block512) -> storage::FsResult {
+        BUSES[self.bus as usize]
+            .lock()
+            .read_pio(self.drive, offset as u32, block.as_mut())
+    }
+
+    fn write_block(&self, offset: usize, block: &Block512) -> storage::FsResult {
+        BUSES[self.bus as usize]
+            .lock()
+            .write_pio(self.drive, offset as u32, block.as_ref())
+    }
+}
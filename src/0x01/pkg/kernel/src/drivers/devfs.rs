@@ -0,0 +1,383 @@
+//! The device registry and the devfs mounted at `/dev`
+//!
+//! every registered device is a file directly under `/dev`:
+//!
+//! - character devices: `null`, `zero`, `random` and `ttyS0` (the serial console)
+//! - block devices: `hda`, `hdb` (the drives on the primary ATA bus) and their partitions
+//!
+//! character devices are opened as `Resource::Device`, so they can block like the console,
+//! block devices are opened as plain files of fixed length,
+//! disks are accessed through a write-back `BlockCache`, see `sync`
+
+use super::ata::AtaDrive;
+use super::serial::get_serial;
+use super::tty;
+use crate::utils::resource::WaitFor;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::num::NonZeroUsize;
+use spin::{Mutex, RwLock};
+use storage::*;
+use syscall_def::{Errno, IoctlRequest, SyscallResult};
+use x86_64::instructions::random::RdRand;
+
+/// A device read and written as a stream of bytes
+///
+/// like `Resource`, returns `EAGAIN` if the caller should block and retry
+pub trait CharDevice: Debug + Send + Sync {
+    fn read(&self, buf: &mut [u8]) -> SyscallResult;
+
+    fn write(&self, buf: &[u8]) -> SyscallResult;
+
+    /// Queue the current process until the device is ready,
+    /// returns false if the device never blocks for `on`
+    fn wait(&self, _on: WaitFor) -> bool {
+        false
+    }
+
+    fn ioctl(&self, _request: IoctlRequest, _arg: usize) -> SyscallResult {
+        Err(Errno::ENOTTY)
+    }
+}
+
+#[derive(Clone)]
+pub enum Device {
+    Char(Arc<dyn CharDevice>),
+    Block(Arc<dyn BlockDevice<Block512>>),
+}
+
+/// All registered devices, by name
+static DEVICES: RwLock<BTreeMap<String, Device>> = RwLock::new(BTreeMap::new());
+
+/// Number of blocks cached for each disk, 256 KiB
+const DISK_CACHE_BLOCKS: NonZeroUsize = NonZeroUsize::new(512).unwrap();
+
+type DiskCache = BlockCache<AtaDrive, Block512>;
+
+/// Caches of the disks, to be written back by `sync`
+static DISK_CACHES: Mutex<Vec<Arc<DiskCache>>> = Mutex::new(Vec::new());
+
+/// Register `device` as `/dev/<name>`, a device of the same name is replaced
+pub fn register(name: &str, device: Device) {
+    if DEVICES.write().insert(String::from(name), device).is_some() {
+        warn!("Device {} is replaced.", name);
+    }
+}
+
+pub fn get(name: &str) -> Option<Device> {
+    DEVICES.read().get(name).cloned()
+}
+
+/// The character device opened as `meta`, if it is one
+///
+/// only devfs reports character devices, whose names are the keys of the registry
+pub fn char_device(meta: &Metadata) -> Option<Arc<dyn CharDevice>> {
+    if meta.entry_type != FileType::CharDevice {
+        return None;
+    }
+    match get(&meta.name)? {
+        Device::Char(device) => Some(device),
+        Device::Block(_) => None,
+    }
+}
+
+/// Register the built-in devices and the disks found
+pub fn init() {
+    register("null", Device::Char(Arc::new(Null)));
+    register("zero", Device::Char(Arc::new(Zero)));
+    register("random", Device::Char(Arc::new(Random::new())));
+    register("ttyS0", Device::Char(Arc::new(Serial)));
+
+    register_disks();
+
+    let names: Vec<String> = DEVICES.read().keys().cloned().collect();
+    info!("Registered devices: {}", names.join(" "));
+}
+
+/// Write the dirty blocks cached for all disks back
+pub fn sync() {
+    for cache in DISK_CACHES.lock().iter() {
+        if let Err(err) = cache.sync() {
+            warn!("Failed to sync disk cache: {:?}", err);
+        }
+        trace!("Disk cache synced: {:?}", cache);
+    }
+}
+
+/// Register the drives on the primary ATA bus and their MBR or GPT partitions
+///
+/// the partitions share the cache of their disk
+fn register_disks() {
+    for (drive, name) in [(0, "hda"), (1, "hdb")] {
+        let Some(disk) = AtaDrive::open(0, drive) else {
+            continue;
+        };
+        let cache = Arc::new(BlockCache::new(disk, DISK_CACHE_BLOCKS));
+        DISK_CACHES.lock().push(cache.clone());
+        let disk: Arc<dyn BlockDevice<Block512>> = cache;
+        register(name, Device::Block(disk.clone()));
+
+        match storage::probe(disk) {
+            Ok(parts) => {
+                for (i, part) in parts.into_iter().enumerate() {
+                    register(&format!("{}{}", name, i + 1), Device::Block(Arc::new(part)));
+                }
+            }
+            Err(err) => warn!("Failed to parse partition table of {}: {:?}", name, err),
+        }
+    }
+}
+
+/// Reads nothing and discards everything written
+#[derive(Debug)]
+struct Null;
+
+impl CharDevice for Null {
+    fn read(&self, _buf: &mut [u8]) -> SyscallResult {
+        Ok(0)
+    }
+
+    fn write(&self, buf: &[u8]) -> SyscallResult {
+        Ok(buf.len())
+    }
+}
+
+/// Reads zeros and discards everything written
+#[derive(Debug)]
+struct Zero;
+
+impl CharDevice for Zero {
+    fn read(&self, buf: &mut [u8]) -> SyscallResult {
+        buf.fill(0);
+        Ok(buf.len())
+    }
+
+    fn write(&self, buf: &[u8]) -> SyscallResult {
+        Ok(buf.len())
+    }
+}
+
+/// Random bytes from RDRAND, or from a xorshift PRNG seeded by the TSC without it
+#[derive(Debug)]
+struct Random {
+    rdrand: Option<RdRand>,
+    state: Mutex<u64>,
+}
+
+impl Random {
+    fn new() -> Self {
+        let rdrand = RdRand::new();
+        if rdrand.is_none() {
+            warn!("RDRAND is not supported, /dev/random falls back to a PRNG.");
+        }
+        // xorshift 的状态不能为 0
+        let seed = unsafe { core::arch::x86_64::_rdtsc() } | 1;
+        Self {
+            rdrand,
+            state: Mutex::new(seed),
+        }
+    }
+
+    fn next(&self) -> u64 {
+        // RDRAND 偶尔会暂时失败，此时使用 PRNG
+        if let Some(value) = self.rdrand.and_then(|rdrand| rdrand.get_u64()) {
+            return value;
+        }
+
+        let mut state = self.state.lock();
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state
+    }
+}
+
+impl CharDevice for Random {
+    fn read(&self, buf: &mut [u8]) -> SyscallResult {
+        for chunk in buf.chunks_mut(8) {
+            let bytes = self.next().to_ne_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+        Ok(buf.len())
+    }
+
+    /// Written data is discarded, RDRAND needs no entropy from users
+    fn write(&self, buf: &[u8]) -> SyscallResult {
+        Ok(buf.len())
+    }
+}
+
+/// The serial port, input comes from the tty like the console
+#[derive(Debug)]
+struct Serial;
+
+impl CharDevice for Serial {
+    fn read(&self, buf: &mut [u8]) -> SyscallResult {
+        tty::read(buf)
+    }
+
+    fn write(&self, buf: &[u8]) -> SyscallResult {
+        let Some(mut serial) = get_serial() else {
+            return Err(Errno::EIO);
+        };
+        for &byte in buf {
+            serial.send(byte);
+        }
+        Ok(buf.len())
+    }
+
+    fn wait(&self, on: WaitFor) -> bool {
+        if on == WaitFor::Read {
+            tty::wait_input();
+        }
+        on == WaitFor::Read
+    }
+
+    fn ioctl(&self, request: IoctlRequest, arg: usize) -> SyscallResult {
+        tty::ioctl(request, arg)
+    }
+}
+
+/// The devices under `/dev`, entries can not be created or removed through the filesystem
+#[derive(Debug, Default)]
+pub struct DevFs;
+
+impl DevFs {
+    /// The device at `path`, `None` for the root directory
+    fn lookup(path: &str) -> FsResult<Option<(String, Device)>> {
+        let name = path.trim_matches(PATH_SEPARATOR);
+        if name.is_empty() {
+            return Ok(None);
+        }
+        let device = get(name).ok_or(FsError::FileNotFound)?;
+        Ok(Some((String::from(name), device)))
+    }
+
+    fn device_meta(name: String, device: &Device) -> FsResult<Metadata> {
+        let (entry_type, len) = match device {
+            Device::Char(_) => (FileType::CharDevice, 0),
+            Device::Block(disk) => (
+                FileType::BlockDevice,
+                disk.block_count()? * Block512::size(),
+            ),
+        };
+        Ok(Metadata::new(name, entry_type, len, None, None, None))
+    }
+}
+
+impl FileSystem for DevFs {
+    fn read_dir(&self, path: &str) -> FsResult<Box<dyn Iterator<Item = Metadata> + Send>> {
+        if Self::lookup(path)?.is_some() {
+            return Err(FsError::NotADirectory);
+        }
+
+        let devices: Vec<(String, Device)> = DEVICES
+            .read()
+            .iter()
+            .map(|(name, device)| (name.clone(), device.clone()))
+            .collect();
+        let entries = devices
+            .into_iter()
+            .map(|(name, device)| Self::device_meta(name, &device))
+            .collect::<FsResult<Vec<_>>>()?;
+        Ok(Box::new(entries.into_iter()))
+    }
+
+    fn open_file(&self, path: &str) -> FsResult<FileHandle> {
+        let (name, device) = Self::lookup(path)?.ok_or(FsError::NotAFile)?;
+        let meta = Self::device_meta(name, &device)?;
+        let file: Box<dyn FileIO + Send> = match device {
+            Device::Char(device) => Box::new(CharFile(device)),
+            Device::Block(disk) => Box::new(BlockFile::new(disk)?),
+        };
+        Ok(FileHandle::new(meta, file))
+    }
+
+    fn metadata(&self, path: &str) -> FsResult<Metadata> {
+        match Self::lookup(path)? {
+            Some((name, device)) => Self::device_meta(name, &device),
+            None => Ok(Metadata::new(
+                String::from("/"),
+                FileType::Directory,
+                0,
+                None,
+                None,
+                None,
+            )),
+        }
+    }
+
+    fn exists(&self, path: &str) -> FsResult<bool> {
+        Ok(Self::lookup(path).is_ok())
+    }
+
+    fn create_file(&self, _path: &str) -> FsResult<FileHandle> {
+        Err(FsError::ReadOnly)
+    }
+
+    fn append_file(&self, _path: &str) -> FsResult<FileHandle> {
+        Err(FsError::ReadOnly)
+    }
+
+    fn create_dir(&self, _path: &str) -> FsResult {
+        Err(FsError::ReadOnly)
+    }
+
+    fn remove_file(&self, _path: &str) -> FsResult {
+        Err(FsError::ReadOnly)
+    }
+
+    fn remove_dir(&self, _path: &str) -> FsResult {
+        Err(FsError::ReadOnly)
+    }
+
+    fn copy_file(&self, _src: &str, _dst: &str) -> FsResult {
+        Err(FsError::ReadOnly)
+    }
+
+    fn move_file(&self, _src: &str, _dst: &str) -> FsResult {
+        Err(FsError::ReadOnly)
+    }
+
+    fn move_dir(&self, _src: &str, _dst: &str) -> FsResult {
+        Err(FsError::ReadOnly)
+    }
+}
+
+/// A character device opened through the VFS inside the kernel, it never blocks
+struct CharFile(Arc<dyn CharDevice>);
+
+impl Read for CharFile {
+    fn read(&mut self, buf: &mut [u8]) -> FsResult<usize> {
+        match self.0.read(buf) {
+            Ok(len) => Ok(len),
+            Err(Errno::EAGAIN) => Ok(0),
+            Err(_) => Err(FsError::DeviceError(DeviceError::ReadError)),
+        }
+    }
+}
+
+impl Write for CharFile {
+    fn write(&mut self, buf: &[u8]) -> FsResult<usize> {
+        match self.0.write(buf) {
+            Ok(len) => Ok(len),
+            Err(Errno::EAGAIN) => Ok(0),
+            Err(_) => Err(FsError::DeviceError(DeviceError::WriteError)),
+        }
+    }
+
+    fn flush(&mut self) -> FsResult {
+        Ok(())
+    }
+}
+
+impl Seek for CharFile {
+    fn seek(&mut self, _pos: SeekFrom) -> FsResult<usize> {
+        Err(FsError::NotSupported)
+    }
+}
@@ -0,0 +1,170 @@
+use super::devfs::{self, DevFs, Device};
+use crate::proc::procfs::ProcFs;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use chrono::NaiveDate;
+use storage::tmpfs::TmpFs;
+use storage::*;
+use syscall_def::{Errno, FileKind, FileStat, OpenFlags, TIME_UNKNOWN};
+
+/// The size limit of each in-memory filesystem
+const TMPFS_LIMIT: usize = 1024 * 1024; // 1 MiB
+
+/// The mount table of the whole system
+pub static VFS: Vfs = Vfs::new();
+
+#[inline]
+pub fn get_vfs() -> &'static Vfs {
+    &VFS
+}
+
+/// Mount the first partition of the first disk as the root filesystem,
+/// an empty tmpfs at `/tmp`, the procfs at `/proc` and the devfs at `/dev`
+///
+/// a missing disk is not fatal, apps are then loaded from the boot app list
+pub fn init() {
+    storage::set_clock(clock);
+
+    mount_root();
+
+    if let Err(err) = mount("tmpfs", "/tmp") {
+        warn!("Failed to mount tmpfs at /tmp: {:?}", err);
+    }
+    if let Err(err) = mount("proc", "/proc") {
+        warn!("Failed to mount procfs at /proc: {:?}", err);
+    }
+    if let Err(err) = mount("devfs", "/dev") {
+        warn!("Failed to mount devfs at /dev: {:?}", err);
+    }
+
+    trace!("Mounted filesystems: {:#?}", VFS);
+
+    info!("Initialized Filesystem.");
+}
+
+/// Mount the first partition of the first disk (`/dev/hda1`) at `/`
+fn mount_root() {
+    let Some(Device::Block(part)) = devfs::get("hda1") else {
+        warn!("No disk partition found, root filesystem is not mounted.");
+        return;
+    };
+
+    info!("Mounting filesystem...");
+
+    // FAT16 or FAT32, decided by the BPB
+    let fs = match open_fat(part) {
+        Ok(fs) => fs,
+        Err(err) => {
+            warn!("Failed to open filesystem: {:?}", err);
+            return;
+        }
+    };
+
+    if let Err(err) = VFS.mount("/", fs) {
+        warn!("Failed to mount root filesystem: {:?}", err);
+    }
+}
+
+/// Mount a new filesystem of type `fstype` at `target`
+pub fn mount(fstype: &str, target: &str) -> FsResult {
+    let fs: Box<dyn FileSystem> = match fstype {
+        "tmpfs" => Box::new(TmpFs::with_limit(TMPFS_LIMIT)),
+        "proc" => Box::new(ProcFs),
+        "devfs" => Box::new(DevFs),
+        _ => return Err(FsError::NotSupported),
+    };
+    VFS.mount(target, fs)
+}
+
+/// Unmount the filesystem at `target`
+pub fn umount(target: &str) -> FsResult {
+    VFS.umount(target)
+}
+
+/// Read the whole file at the absolute `path`
+pub fn read_file(path: &str) -> FsResult<Vec<u8>> {
+    let mut file = VFS.open_file(path)?;
+    let mut buf = Vec::with_capacity(file.meta.len);
+    file.read_all(&mut buf)?;
+    Ok(buf)
+}
+
+/// Open the file at the absolute `path`, see `OpenFlags`
+pub fn open(path: &str, flags: OpenFlags) -> FsResult<FileHandle> {
+    if flags.contains(OpenFlags::CREATE) {
+        let exists = VFS.exists(path)?;
+        if exists && flags.contains(OpenFlags::EXCL) {
+            return Err(FsError::AlreadyExists);
+        }
+        // create_file 会截断已有的文件
+        if !exists || flags.contains(OpenFlags::TRUNCATE) {
+            return VFS.create_file(path);
+        }
+    }
+
+    let mut file = VFS.open_file(path)?;
+    if flags.contains(OpenFlags::TRUNCATE) {
+        file.set_len(0)?;
+    }
+    Ok(file)
+}
+
+/// The metadata reported to user programs for `meta`
+pub fn file_stat(meta: &Metadata) -> FileStat {
+    let kind = match meta.entry_type {
+        FileType::File => FileKind::File,
+        FileType::Directory => FileKind::Directory,
+        FileType::Symlink => FileKind::Symlink,
+        FileType::CharDevice => FileKind::CharDevice,
+        FileType::BlockDevice => FileKind::BlockDevice,
+    };
+    let millis = |time: Option<FsTime>| time.map_or(TIME_UNKNOWN, |time| time.timestamp_millis());
+
+    FileStat {
+        kind: kind as usize,
+        size: meta.len,
+        created: millis(meta.created),
+        modified: millis(meta.modified),
+        accessed: millis(meta.accessed),
+    }
+}
+
+/// The errno reported to user programs for `err`
+pub fn errno(err: FsError) -> Errno {
+    match err {
+        FsError::FileNotFound => Errno::ENOENT,
+        FsError::NotADirectory => Errno::ENOTDIR,
+        FsError::NotAFile => Errno::EISDIR,
+        FsError::ReadOnly => Errno::EROFS,
+        FsError::AlreadyExists => Errno::EEXIST,
+        FsError::WriteZero => Errno::ENOSPC,
+        FsError::Busy => Errno::EBUSY,
+        FsError::CrossDevice => Errno::EXDEV,
+        FsError::NotSupported => Errno::ENOSYS,
+        FsError::InvalidOperation
+        | FsError::InvalidOffset
+        | FsError::InvalidPath(_)
+        | FsError::FileNameError(_) => Errno::EINVAL,
+        FsError::NotInSector
+        | FsError::EndOfFile
+        | FsError::BadCluster
+        | FsError::DeviceError(_) => Errno::EIO,
+    }
+}
+
+/// Wall-clock time from the UEFI runtime, used to stamp files and directories
+fn clock() -> FsTime {
+    uefi::runtime::get_time()
+        .ok()
+        .and_then(|time| {
+            NaiveDate::from_ymd_opt(time.year() as i32, time.month() as u32, time.day() as u32)?
+                .and_hms_nano_opt(
+                    time.hour() as u32,
+                    time.minute() as u32,
+                    time.second() as u32,
+                    time.nanosecond(),
+                )
+        })
+        .map(|time| time.and_utc())
+        .unwrap_or_default()
+}
@@ -1,3 +1,6 @@
-pub mod input;
+pub mod ata;
+pub mod devfs;
+pub mod filesystem;
 pub mod serial;
+pub mod tty;
 mod uart16550;
@@ -0,0 +1,272 @@
+//! Terminal line discipline
+//!
+//! Bytes received by the UART are fed into the TTY, which handles line
+//! editing, echo and special characters before the data is read by
+//! processes through `Resource::Console(StdIO::Stdin)` or `/dev/ttyS0`.
+//!
+//! - canonical mode: input is edited line by line, a read returns at most one line
+//! - raw mode: every byte is passed to the reader as-is
+//!
+//! Special characters in canonical mode:
+//!
+//! - Backspace / DEL: erase the last character
+//! - Ctrl-U: erase the whole line
+//! - Ctrl-D: end of file on an empty line, otherwise flush the line
+//! - escape sequences (e.g. arrow keys) are ignored
+//!
+//! Ctrl-C kills the foreground process if `TtyMode::SIGNAL` is set.
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use spin::Mutex;
+use syscall_def::{Errno, IoctlRequest, SyscallResult, TtyMode};
+
+use crate::drivers::serial::get_serial;
+use crate::proc::{self, ProcessId, WaitQueue};
+
+const CTRL_C: u8 = 0x03;
+const CTRL_D: u8 = 0x04;
+const CTRL_U: u8 = 0x15;
+const BACKSPACE: u8 = 0x08;
+const DEL: u8 = 0x7f;
+const ESC: u8 = 0x1b;
+
+/// Max length of the line being edited
+const LINE_MAX: usize = 1024;
+/// Max bytes waiting to be read
+const READY_MAX: usize = 4096;
+
+static TTY: Mutex<Tty> = Mutex::new(Tty::new());
+/// 等待输入的进程
+static INPUT_WAITERS: WaitQueue = WaitQueue::new();
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum EscapeState {
+    Normal,
+    Escape,
+    Csi,
+}
+
+/// Side effects of received bytes, applied after the tty is unlocked
+#[derive(Default)]
+struct Effects {
+    echo: Vec<u8>,
+    wake: bool,
+    interrupt: Option<ProcessId>,
+}
+
+struct Tty {
+    mode: TtyMode,
+    /// 规范模式下正在编辑的行
+    line: Vec<u8>,
+    /// 可供进程读取的数据
+    ready: VecDeque<u8>,
+    eof: bool,
+    escape: EscapeState,
+    foreground: Option<ProcessId>,
+}
+
+impl Tty {
+    const fn new() -> Self {
+        Self {
+            mode: TtyMode::all(),
+            line: Vec::new(),
+            ready: VecDeque::new(),
+            eof: false,
+            escape: EscapeState::Normal,
+            foreground: None,
+        }
+    }
+
+    fn echo(&self, fx: &mut Effects, bytes: &[u8]) {
+        if self.mode.contains(TtyMode::ECHO) {
+            fx.echo.extend_from_slice(bytes);
+        }
+    }
+
+    fn push_ready(&mut self, bytes: impl IntoIterator<Item = u8>) {
+        for byte in bytes {
+            if self.ready.len() >= READY_MAX {
+                warn!("TTY input buffer is full, dropping input.");
+                break;
+            }
+            self.ready.push_back(byte);
+        }
+    }
+
+    /// Erase the last character of the line, returns false if the line is empty
+    fn erase_char(&mut self, fx: &mut Effects) -> bool {
+        // 按 UTF-8 字符删除：弹出所有的后续字节，直到字符的首字节
+        while let Some(byte) = self.line.pop() {
+            if byte & 0xc0 != 0x80 {
+                self.echo(fx, b"\x08 \x08");
+                return true;
+            }
+        }
+        false
+    }
+
+    fn receive(&mut self, byte: u8, fx: &mut Effects) {
+        if byte == CTRL_C && self.mode.contains(TtyMode::SIGNAL) {
+            self.line.clear();
+            self.escape = EscapeState::Normal;
+            self.echo(fx, b"^C\r\n");
+            match self.foreground.filter(|pid| proc::still_alive(*pid)) {
+                Some(pid) => fx.interrupt = Some(pid),
+                None if self.mode.contains(TtyMode::CANONICAL) => {
+                    // 没有前台进程时交给读者一个空行，例如让 shell 重新显示提示符
+                    self.push_ready(*b"\n");
+                    fx.wake = true;
+                }
+                None => {}
+            }
+            return;
+        }
+
+        if !self.mode.contains(TtyMode::CANONICAL) {
+            self.echo(fx, &[byte]);
+            self.push_ready([byte]);
+            fx.wake = true;
+            return;
+        }
+
+        match self.escape {
+            EscapeState::Escape if byte == b'[' => {
+                self.escape = EscapeState::Csi;
+                return;
+            }
+            EscapeState::Escape => self.escape = EscapeState::Normal,
+            EscapeState::Csi => {
+                // 控制序列以 0x40..=0x7e 结尾，整个序列被忽略
+                if (0x40..=0x7e).contains(&byte) {
+                    self.escape = EscapeState::Normal;
+                }
+                return;
+            }
+            EscapeState::Normal => {}
+        }
+
+        match byte {
+            ESC => self.escape = EscapeState::Escape,
+            CTRL_D => {
+                if self.line.is_empty() {
+                    self.eof = true;
+                } else {
+                    let line = core::mem::take(&mut self.line);
+                    self.push_ready(line);
+                }
+                fx.wake = true;
+            }
+            CTRL_U => while self.erase_char(fx) {},
+            BACKSPACE | DEL => {
+                self.erase_char(fx);
+            }
+            b'\r' | b'\n' => {
+                let line = core::mem::take(&mut self.line);
+                self.push_ready(line);
+                self.push_ready(*b"\n");
+                self.echo(fx, b"\r\n");
+                fx.wake = true;
+            }
+            // 忽略其余的控制字符
+            byte if byte < 0x20 && byte != b'\t' => {}
+            byte => {
+                if self.line.len() < LINE_MAX {
+                    self.line.push(byte);
+                    self.echo(fx, &[byte]);
+                }
+            }
+        }
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> SyscallResult {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        if self.ready.is_empty() {
+            if self.eof {
+                self.eof = false;
+                return Ok(0);
+            }
+            return Err(Errno::EAGAIN);
+        }
+
+        let canonical = self.mode.contains(TtyMode::CANONICAL);
+        let mut len = 0;
+        while len < buf.len() {
+            let Some(byte) = self.ready.pop_front() else {
+                break;
+            };
+            buf[len] = byte;
+            len += 1;
+            if canonical && byte == b'\n' {
+                break;
+            }
+        }
+        Ok(len)
+    }
+
+    fn set_mode(&mut self, mode: TtyMode) {
+        // 离开规范模式时，未完成的行直接交给读者
+        if !mode.contains(TtyMode::CANONICAL) {
+            let line = core::mem::take(&mut self.line);
+            self.push_ready(line);
+        }
+        self.escape = EscapeState::Normal;
+        self.mode = mode;
+    }
+}
+
+/// Feed a byte received by the UART, called in the serial interrupt handler
+pub fn receive(byte: u8) {
+    let mut fx = Effects::default();
+    TTY.lock().receive(byte, &mut fx);
+
+    if !fx.echo.is_empty()
+        && let Some(mut serial) = get_serial()
+    {
+        for &byte in fx.echo.iter() {
+            serial.send(byte);
+        }
+    }
+
+    if let Some(pid) = fx.interrupt {
+        proc::interrupt(pid);
+    }
+
+    if fx.wake {
+        INPUT_WAITERS.wake_all();
+    }
+}
+
+/// Read input, returns `EAGAIN` if there is nothing to read yet
+pub fn read(buf: &mut [u8]) -> SyscallResult {
+    TTY.lock().read(buf)
+}
+
+/// Queue the current process until there is input
+pub fn wait_input() {
+    INPUT_WAITERS.wait_current();
+}
+
+/// Handle `Ioctl` on the terminal
+pub fn ioctl(request: IoctlRequest, arg: usize) -> SyscallResult {
+    let mut tty = TTY.lock();
+    match request {
+        IoctlRequest::TtyGetMode => Ok(tty.mode.bits()),
+        IoctlRequest::TtySetMode => {
+            let mode = TtyMode::from_bits(arg).ok_or(Errno::EINVAL)?;
+            tty.set_mode(mode);
+            drop(tty);
+            // 切换到原始模式后可能已有数据可读
+            INPUT_WAITERS.wake_all();
+            Ok(0)
+        }
+        IoctlRequest::TtySetForeground => {
+            tty.foreground = (arg != 0).then_some(ProcessId(arg as u16));
+            Ok(0)
+        }
+        IoctlRequest::Unknown => Err(Errno::EINVAL),
+    }
+}
@@ -32,6 +32,7 @@ pub unsafe fn register_idt(idt: &mut InterruptDescriptorTable) {
 
 // 003 新增的：利用as_handler宏重新定义中断处理函数
 pub extern "C" fn clock(mut context: ProcessContext) {
+    inc_counter();
     crate::proc::switch(&mut context);
     super::ack(); // 用于通知中断控制器中断处理已完成
 }
@@ -58,3 +59,15 @@ pub fn inc_counter() -> u64 {
     // 原子化先读取后 +1
     COUNTER.fetch_add(1, Ordering::SeqCst)
 }
+
+/// Interval of the clock interrupt in nanoseconds
+///
+/// the APIC timer counts down from 0x20000 with divide 1,
+/// the bus clock of the QEMU local APIC is 1 GHz
+pub const TICK_NANOS: u64 = 0x20000;
+
+/// Number of clock ticks in `ms` milliseconds, rounded up
+#[inline]
+pub fn ms_to_ticks(ms: u64) -> u64 {
+    ms.saturating_mul(1_000_000).div_ceil(TICK_NANOS)
+}
@@ -29,6 +29,7 @@ lazy_static! {
 /// init interrupts system
 pub fn init() {
     IDT.load();
+    syscall::init();
 
     // FIXME: check and init APIC
     if XApic::support() {
@@ -0,0 +1,121 @@
+//! Fast syscall entry via `syscall`/`sysret`
+//!
+//! The `syscall` instruction does not switch stack nor push an interrupt
+//! frame, so the entry below builds the same frame as `int 0x80` by hand:
+//! the `ProcessContext` seen by `dispatcher` is identical for both paths.
+//!
+//! On return, `sysretq` is only used when the frame still belongs to the
+//! caller (`rcx == rip` and `r11 == rflags`, which `syscall` guarantees),
+//! otherwise the context may be switched to another process and `iretq`
+//! is used to restore the full frame.
+
+use crate::memory::gdt::{get_selector, get_syscall_stack_top, get_user_selector};
+use x86_64::registers::model_specific::{Efer, EferFlags, LStar, SFMask, Star};
+use x86_64::registers::rflags::RFlags;
+use x86_64::VirtAddr;
+
+// 入口代码使用的临时变量，只在关中断时访问
+static mut USER_RSP: u64 = 0;
+static mut KERNEL_RSP: u64 = 0;
+static mut USER_CS: u64 = 0;
+static mut USER_SS: u64 = 0;
+
+unsafe extern "C" {
+    fn ysos_syscall_entry();
+}
+
+core::arch::global_asm!(
+    "
+    .global ysos_syscall_entry
+    ysos_syscall_entry:
+    mov [rip + {user_rsp}], rsp
+    mov rsp, [rip + {kernel_rsp}]
+
+    push qword ptr [rip + {user_ss}]
+    push qword ptr [rip + {user_rsp}]
+    push r11
+    push qword ptr [rip + {user_cs}]
+    push rcx
+
+    push rbp
+    push rax
+    push rbx
+    push rcx
+    push rdx
+    push rsi
+    push rdi
+    push r8
+    push r9
+    push r10
+    push r11
+    push r12
+    push r13
+    push r14
+    push r15
+    call {handler}
+    pop r15
+    pop r14
+    pop r13
+    pop r12
+    pop r11
+    pop r10
+    pop r9
+    pop r8
+    pop rdi
+    pop rsi
+    pop rdx
+    pop rcx
+    pop rbx
+    pop rax
+    pop rbp
+
+    cmp rcx, [rsp]
+    jne 2f
+    cmp r11, [rsp + 16]
+    jne 2f
+    push rax
+    mov rax, [rip + {user_cs}]
+    cmp rax, [rsp + 16]
+    pop rax
+    jne 2f
+    mov rsp, [rsp + 24]
+    sysretq
+2:
+    iretq
+    ",
+    user_rsp = sym USER_RSP,
+    kernel_rsp = sym KERNEL_RSP,
+    user_cs = sym USER_CS,
+    user_ss = sym USER_SS,
+    handler = sym super::syscall,
+);
+
+/// Program the MSRs for the `syscall` instruction
+pub fn init() {
+    let kernel = get_selector();
+    let user = get_user_selector();
+
+    unsafe {
+        USER_CS = user.user_code_selector.0 as u64;
+        USER_SS = user.user_data_selector.0 as u64;
+        // 按 16 字节对齐，保证调用 handler 时栈是对齐的
+        KERNEL_RSP = get_syscall_stack_top().align_down(16u64).as_u64();
+    }
+
+    Star::write(
+        user.user_code_selector,
+        user.user_data_selector,
+        kernel.code_selector,
+        kernel.data_selector,
+    )
+    .expect("Invalid GDT layout for SYSCALL/SYSRET");
+    LStar::write(VirtAddr::new(ysos_syscall_entry as *const () as u64));
+    // 进入内核时关中断，并清除方向标志与单步标志
+    SFMask::write(RFlags::INTERRUPT_FLAG | RFlags::DIRECTION_FLAG | RFlags::TRAP_FLAG);
+
+    unsafe {
+        Efer::update(|flags| flags.insert(EferFlags::SYSTEM_CALL_EXTENSIONS));
+    }
+
+    info!("Syscall Entry Initialized.");
+}
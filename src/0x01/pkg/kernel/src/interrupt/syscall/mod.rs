@@ -3,16 +3,19 @@ use alloc::format;
 use x86_64::structures::idt::{InterruptDescriptorTable, InterruptStackFrame};
 
 // NOTE: import `ysos_syscall` package as `syscall_def` in Cargo.toml
-use syscall_def::Syscall;
+use syscall_def::{Errno, Syscall, SyscallArg};
 
 use x86_64::PrivilegeLevel;
 
+mod entry;
 mod service;
 use super::consts;
 
 // FIXME: write syscall service handler in `service.rs`
 use service::*;
 
+pub use entry::init; // SYSCALL/SYSRET 快速入口
+
 pub unsafe fn register_idt(idt: &mut InterruptDescriptorTable) {
     // FIXME: register syscall handler to IDT
     //        - standalone syscall stack
@@ -39,110 +42,101 @@ pub struct SyscallArgs {
     pub arg0: usize,
     pub arg1: usize,
     pub arg2: usize,
+    pub arg3: usize,
+    pub arg4: usize,
+    pub arg5: usize,
+}
+
+/// Generate `dispatch` from the syscall table in `syscall_def`
+///
+/// arguments are decoded in order from `arg0` to `arg5` by `SyscallArg`,
+/// handlers live in `service.rs`
+macro_rules! define_dispatcher {
+    ($(
+        $(#[$meta:meta])*
+        $variant:ident = $nr:literal => $(@$kind:ident)? fn $name:ident ($($arg:ident : $ty:ty),*) -> $ret:ty;
+    )*) => {
+        fn dispatch(args: &SyscallArgs, context: &mut ProcessContext) {
+            match args.syscall {
+                $(
+                    Syscall::$variant => {
+                        let mut _regs = args.regs().into_iter();
+                        define_dispatcher!(
+                            @call $($kind)? context, $name,
+                            $(<$ty as SyscallArg>::from_arg(_regs.next().unwrap_or(0))),*
+                        )
+                    }
+                )*
+                // Unknown
+                Syscall::Unknown => {
+                    warn!("Unhandled syscall: {:x?}", context.regs.rax);
+                    context.set_rax(Errno::encode(Err(Errno::ENOSYS)))
+                }
+            }
+        }
+    };
+    // 需要操作进程上下文的系统调用（可能阻塞或切换进程），由处理函数自行设置返回值
+    (@call context $context:ident, $name:ident, $($arg:expr),*) => {
+        $name($context, $($arg),*)
+    };
+    (@call $context:ident, $name:ident, $($arg:expr),*) => {
+        $context.set_rax(Errno::encode($name($($arg),*).map(SyscallArg::into_arg)))
+    };
 }
 
+syscall_def::syscall_table!(define_dispatcher);
+
 pub fn dispatcher(context: &mut ProcessContext) {
     let args = super::syscall::SyscallArgs::new(
         Syscall::from(context.regs.rax),
-        context.regs.rdi,
-        context.regs.rsi,
-        context.regs.rdx,
+        [
+            context.regs.rdi,
+            context.regs.rsi,
+            context.regs.rdx,
+            context.regs.r10,
+            context.regs.r8,
+            context.regs.r9,
+        ],
     );
 
     // NOTE: you may want to trace syscall arguments
     // trace!("{}", args);
 
-    match args.syscall {
-        // fd: arg0 as u8, buf: &[u8] (ptr: arg1 as *const u8, len: arg2)
-        Syscall::Read => { /* FIXME: read from fd & return length */
-            context.set_rax(sys_read(&args))
-        },
-        // fd: arg0 as u8, buf: &[u8] (ptr: arg1 as *const u8, len: arg2)
-        Syscall::Write => { /* FIXME: write to fd & return length */
-            context.set_rax(sys_write(&args))
-        },
-
-        // None -> pid: u16
-        Syscall::GetPid => { /* FIXME: get current pid */ 
-            context.set_rax(sys_get_pid() as usize)
-        },
-
-        // path: &str (ptr: arg0 as *const u8, len: arg1) -> pid: u16
-        Syscall::Spawn => { /* FIXME: spawn process from name */
-            context.set_rax(spawn_process(&args) as usize)
-        },
-        // ret: arg0 as isize
-        Syscall::Exit => { /* FIXME: exit process with retcode */
-            exit_process(&args, context)
-        },
-        // pid: arg0 as u16 -> status: isize
-        // ? mislead explaination
-        Syscall::WaitPid => { /* FIXME: check if the process is running or get retcode */
-            sys_wait_pid(&args, context)
-        },
-
-        // None
-        Syscall::Stat => { /* FIXME: list processes */
-            list_process()
-        },
-        // None
-        Syscall::ListApp => { /* FIXME: list available apps */
-            list_app()
-        },
-
-        // 0x05: add Fork & Sem
-        // None -> pid: u16 or 0 or -1
-        Syscall::Fork => { /* FIXME: Fork the new process */
-            sys_fork(context)
-        },
-
-        // op: u8, key: u32, val: usize -> ret: any
-        Syscall::Sem => {
-            sys_sem(&args, context)
-        },
-
-        Syscall::Time => {
-            context.set_rax(sys_time() as usize)
-        },
-
-        // 0x07 add:
-        Syscall::Brk => {
-            context.set_rax(sys_brk(&args))
-        }
-
-        // ----------------------------------------------------
-        // NOTE: following syscall examples are implemented
-        // ----------------------------------------------------
-
-        // layout: arg0 as *const Layout -> ptr: *mut u8
-        Syscall::Allocate => context.set_rax(sys_allocate(&args)),
-        // ptr: arg0 as *mut u8
-        Syscall::Deallocate => sys_deallocate(&args),
-        // Unknown
-        Syscall::Unknown => warn!("Unhandled syscall: {:x?}", context.regs.rax),
-    }
+    dispatch(&args, context);
 }
 
 impl SyscallArgs {
-    pub fn new(syscall: Syscall, arg0: usize, arg1: usize, arg2: usize) -> Self {
+    pub fn new(syscall: Syscall, regs: [usize; 6]) -> Self {
+        let [arg0, arg1, arg2, arg3, arg4, arg5] = regs;
         Self {
             syscall,
             arg0,
             arg1,
             arg2,
+            arg3,
+            arg4,
+            arg5,
         }
     }
+
+    /// Arguments in the order of the syscall ABI
+    pub fn regs(&self) -> [usize; 6] {
+        [self.arg0, self.arg1, self.arg2, self.arg3, self.arg4, self.arg5]
+    }
 }
 
 impl core::fmt::Display for SyscallArgs {
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         write!(
             f,
-            "SYSCALL: {:<10} (0x{:016x}, 0x{:016x}, 0x{:016x})",
+            "SYSCALL: {:<10} (0x{:016x}, 0x{:016x}, 0x{:016x}, 0x{:016x}, 0x{:016x}, 0x{:016x})",
             format!("{:?}", self.syscall),
             self.arg0,
             self.arg1,
-            self.arg2
+            self.arg2,
+            self.arg3,
+            self.arg4,
+            self.arg5
         )
     }
 }
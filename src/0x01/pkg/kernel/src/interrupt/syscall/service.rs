@@ -1,144 +1,445 @@
+use alloc::vec;
 use core::alloc::Layout;
+use core::mem::size_of;
 
 use crate::proc::*;
 use crate::utils::*;
+use crate::utils::resource::WaitFor;
 use crate::memory::*;
 use crate::proc;
+use crate::filesystem;
+use crate::memory::user::{USER_HEAP_SIZE, USER_HEAP_START};
 
-use super::SyscallArgs;
+use crate::proc::msg::{Message, Receiver};
+use storage::FileSystem;
+use syscall_def::{
+    DirEntry, Errno, FcntlCmd, FileStat, FutexOp, IoctlRequest, OpenFlags, SeekWhence, SemFlags,
+    SemOp, SyscallResult, MSG_ANY_TYPE, MSG_MAX_SIZE, SEM_NAME_MAX,
+};
 use x86_64::VirtAddr;
 
+// 处理函数的签名与 syscall_def 中的系统调用表一一对应
+
 // path: &str (ptr: arg0 as *const u8, len: arg1) -> pid: u16
-pub fn spawn_process(args: &SyscallArgs) -> usize {
-    // FIXME: get app name by args
-    //       - core::str::from_utf8_unchecked
-    //       - core::slice::from_raw_parts
-    let name = unsafe {
-        core::str::from_utf8_unchecked(
-            core::slice::from_raw_parts(
-                args.arg0 as *const u8, args.arg1
-            )
-        )
-    };
-    // FIXME: spawn the process by name
-    // FIXME: handle spawn error, return 0 if failed
-    // FIXME: return pid as usize
-    match proc::spawn(name) {
-        Some(pid) => return pid.0 as usize,
-        _ => return 0,
+pub fn sys_spawn(path: *const u8, len: usize) -> SyscallResult<u16> {
+    // 从用户空间复制路径，检查地址范围并校验 UTF-8
+    let name = str_from_user(path as usize, len)?;
+    match proc::spawn(&name) {
+        Some(pid) => Ok(pid.0),
+        None => Err(Errno::ENOENT),
+    }
+}
+
+/// Max bytes copied between user and kernel at once
+const IO_CHUNK_SIZE: usize = PAGE_SIZE as usize;
+
+// fd: arg0, buf: &[u8] (ptr: arg1 as *const u8, len: arg2) -> len: usize
+pub fn sys_write(context: &mut ProcessContext, fd: usize, buf: *const u8, len: usize) {
+    match write_from_user(fd, buf as usize, len) {
+        // 管道已满时阻塞，被读者唤醒后重新执行该系统调用
+        Err(Errno::EAGAIN) => proc::wait_fd(fd, WaitFor::Write, context),
+        ret => context.set_rax(Errno::encode(ret)),
+    }
+}
+
+fn write_from_user(fd: usize, addr: usize, len: usize) -> SyscallResult {
+    check_user(addr, len, false)?;
+
+    // 分块复制到内核缓冲区后再写入，避免一次分配过大的内存
+    let mut buf = vec![0u8; len.min(IO_CHUNK_SIZE)];
+    let mut written = 0;
+    while written < len {
+        let chunk = (len - written).min(IO_CHUNK_SIZE);
+        copy_from_user(&mut buf[..chunk], addr + written)?;
+        let n = match proc::write(fd, &buf[..chunk]) {
+            Ok(n) => n,
+            // 已经写入了部分数据，返回写入的长度而不是阻塞
+            Err(Errno::EAGAIN) if written > 0 => break,
+            Err(err) => return Err(err),
+        };
+        written += n;
+        if n < chunk {
+            break;
+        }
+    }
+    Ok(written)
+}
+
+// fd: arg0, buf: &mut [u8] (ptr: arg1 as *mut u8, len: arg2) -> len: usize
+pub fn sys_read(context: &mut ProcessContext, fd: usize, buf: *mut u8, len: usize) {
+    match read_to_user(fd, buf as usize, len) {
+        // 暂无数据时阻塞，被串口中断或管道的写者唤醒后重新执行该系统调用
+        Err(Errno::EAGAIN) => proc::wait_fd(fd, WaitFor::Read, context),
+        ret => context.set_rax(Errno::encode(ret)),
+    }
+}
+
+fn read_to_user(fd: usize, addr: usize, len: usize) -> SyscallResult {
+    // 先检查用户缓冲区，避免读取数据后才发现无法写回
+    check_user(addr, len, true)?;
+
+    let mut buf = vec![0u8; len.min(IO_CHUNK_SIZE)];
+    let n = proc::read(fd, &mut buf)?;
+    copy_to_user(addr, &buf[..n])?;
+    Ok(n)
+}
+
+// fd: arg0, request: arg1, arg: arg2 -> ret: usize
+pub fn sys_ioctl(fd: usize, request: usize, arg: usize) -> SyscallResult {
+    proc::ioctl(fd, IoctlRequest::from(request), arg)
+}
+
+// fds: &mut [usize; 2] (ptr: arg0 as *mut usize), read fd in fds[0], write fd in fds[1]
+pub fn sys_pipe(fds: *mut usize) -> SyscallResult<()> {
+    // 先检查用户地址，避免创建管道后无法写回
+    check_user(fds as usize, size_of::<[usize; 2]>(), true)?;
+    let (read_fd, write_fd) = proc::pipe()?;
+    write_to_user(fds as usize, &[read_fd, write_fd])
+}
+
+// path: &str (ptr: arg0 as *const u8, len: arg1), flags: arg2 -> fd: usize
+pub fn sys_open(path: *const u8, len: usize, flags: usize) -> SyscallResult {
+    let path = str_from_user(path as usize, len)?;
+    let flags = OpenFlags::from_bits(flags).ok_or(Errno::EINVAL)?;
+    proc::open(&path, flags)
+}
+
+// fd: arg0, offset: arg1 as isize, whence: arg2 -> offset: usize
+pub fn sys_seek(fd: usize, offset: isize, whence: usize) -> SyscallResult {
+    proc::seek(fd, offset, SeekWhence::from(whence))
+}
+
+// path: &str (ptr: arg0 as *const u8, len: arg1), stat: arg2 as *mut FileStat
+pub fn sys_stat_file(path: *const u8, len: usize, stat: *mut FileStat) -> SyscallResult<()> {
+    let path = proc::resolve_path(&str_from_user(path as usize, len)?);
+    let meta = filesystem::get_vfs()
+        .metadata(&path)
+        .map_err(filesystem::errno)?;
+    write_to_user(stat as usize, &filesystem::file_stat(&meta))
+}
+
+// fd: arg0, stat: arg1 as *mut FileStat
+pub fn sys_fstat(fd: usize, stat: *mut FileStat) -> SyscallResult<()> {
+    let file_stat = proc::fstat(fd)?;
+    write_to_user(stat as usize, &file_stat)
+}
+
+// path: &str (ptr: arg0 as *const u8, len: arg1), buf: &mut [DirEntry] (ptr: arg2, len: arg3),
+// start: arg4 -> count: usize
+pub fn sys_read_dir(
+    path: *const u8,
+    len: usize,
+    buf: *mut DirEntry,
+    count: usize,
+    start: usize,
+) -> SyscallResult {
+    let path = proc::resolve_path(&str_from_user(path as usize, len)?);
+    let size = count.checked_mul(size_of::<DirEntry>()).ok_or(Errno::EINVAL)?;
+    check_user(buf as usize, size, true)?;
+
+    let entries = filesystem::get_vfs()
+        .read_dir(&path)
+        .map_err(filesystem::errno)?;
+    let mut written = 0;
+    for meta in entries.skip(start).take(count) {
+        let entry = DirEntry::new(&meta.name, filesystem::file_stat(&meta));
+        write_to_user(buf as usize + written * size_of::<DirEntry>(), &entry)?;
+        written += 1;
+    }
+    Ok(written)
+}
+
+// path: &str (ptr: arg0 as *const u8, len: arg1)
+pub fn sys_mkdir(path: *const u8, len: usize) -> SyscallResult<()> {
+    let path = proc::resolve_path(&str_from_user(path as usize, len)?);
+    filesystem::get_vfs()
+        .create_dir(&path)
+        .map_err(filesystem::errno)
+}
+
+// path: &str (ptr: arg0 as *const u8, len: arg1), dir: arg2 as bool
+pub fn sys_unlink(path: *const u8, len: usize, dir: bool) -> SyscallResult<()> {
+    let path = proc::resolve_path(&str_from_user(path as usize, len)?);
+    let vfs = filesystem::get_vfs();
+    let meta = vfs.metadata(&path).map_err(filesystem::errno)?;
+    match (dir, meta.is_dir()) {
+        (false, true) => Err(Errno::EISDIR),
+        (true, false) => Err(Errno::ENOTDIR),
+        (false, false) => vfs.remove_file(&path).map_err(filesystem::errno),
+        (true, true) => vfs.remove_dir(&path).map_err(filesystem::errno),
     }
-    
 }
 
-pub fn sys_write(args: &SyscallArgs) -> usize {
-    // FIXME: get buffer and fd by args
-    //       - core::slice::from_raw_parts
-    let fd = args.arg0 as u8;
-    let buf = unsafe{core::slice::from_raw_parts(args.arg1 as *const u8, args.arg2)};
-    // FIXME: call proc::write -> isize
-    // FIXME: return the result as usize
-    proc::write(fd, buf) as usize
+// src: &str (ptr: arg0 as *const u8, len: arg1), dst: &str (ptr: arg2 as *const u8, len: arg3)
+pub fn sys_rename(
+    src: *const u8,
+    src_len: usize,
+    dst: *const u8,
+    dst_len: usize,
+) -> SyscallResult<()> {
+    let src = proc::resolve_path(&str_from_user(src as usize, src_len)?);
+    let dst = proc::resolve_path(&str_from_user(dst as usize, dst_len)?);
+    let vfs = filesystem::get_vfs();
+    let meta = vfs.metadata(&src).map_err(filesystem::errno)?;
+    if meta.is_dir() {
+        vfs.move_dir(&src, &dst)
+    } else {
+        vfs.move_file(&src, &dst)
+    }
+    .map_err(filesystem::errno)
 }
 
-pub fn sys_read(args: &SyscallArgs) -> usize {
-    // FIXME: just like sys_write
-    let fd = args.arg0 as u8;
-    let buf = unsafe{core::slice::from_raw_parts_mut(args.arg1 as *mut u8, args.arg2)};
-    proc::read(fd, buf) as usize    
+// fd: arg0
+pub fn sys_close(fd: usize) -> SyscallResult<()> {
+    proc::close(fd)
+}
+
+// fd: arg0 -> new fd: usize
+pub fn sys_dup(fd: usize) -> SyscallResult<usize> {
+    proc::dup(fd)
+}
+
+// old: arg0, new: arg1 -> new fd: usize
+pub fn sys_dup2(old: usize, new: usize) -> SyscallResult<usize> {
+    proc::dup2(old, new)
+}
+
+// fd: arg0, cmd: arg1, arg: arg2 -> ret: usize
+pub fn sys_fcntl(fd: usize, cmd: usize, arg: usize) -> SyscallResult {
+    proc::fcntl(fd, FcntlCmd::from(cmd), arg)
+}
+
+// buf: &mut [u8] (ptr: arg0 as *mut u8, len: arg1) -> len: usize
+pub fn sys_getcwd(buf: *mut u8, len: usize) -> SyscallResult {
+    let cwd = proc::getcwd();
+    if cwd.len() > len {
+        return Err(Errno::ERANGE);
+    }
+    copy_to_user(buf as usize, cwd.as_bytes())?;
+    Ok(cwd.len())
+}
+
+// path: &str (ptr: arg0 as *const u8, len: arg1)
+pub fn sys_chdir(path: *const u8, len: usize) -> SyscallResult<()> {
+    let path = str_from_user(path as usize, len)?;
+    proc::chdir(&path)
+}
+
+// fstype: &str (ptr: arg0 as *const u8, len: arg1), target: &str (ptr: arg2 as *const u8, len: arg3)
+pub fn sys_mount(
+    fstype: *const u8,
+    fstype_len: usize,
+    target: *const u8,
+    target_len: usize,
+) -> SyscallResult<()> {
+    let fstype = str_from_user(fstype as usize, fstype_len)?;
+    let target = proc::resolve_path(&str_from_user(target as usize, target_len)?);
+    filesystem::mount(&fstype, &target).map_err(filesystem::errno)
+}
+
+// target: &str (ptr: arg0 as *const u8, len: arg1)
+pub fn sys_umount(target: *const u8, len: usize) -> SyscallResult<()> {
+    let target = proc::resolve_path(&str_from_user(target as usize, len)?);
+    filesystem::umount(&target).map_err(filesystem::errno)
 }
 
 // ret: arg0 as isize
-pub fn exit_process(args: &SyscallArgs, context: &mut ProcessContext) {
+pub fn sys_exit(context: &mut ProcessContext, code: isize) {
     // FIXME: exit process with retcode
-    proc::process_exit(args.arg0 as isize, context);
+    proc::process_exit(code, context);
 }
 
-pub fn list_process() {
+pub fn sys_stat() -> SyscallResult<()> {
     // FIXME: list all processes
     proc::print_process_list();
+    Ok(())
 }
 
-pub fn sys_allocate(args: &SyscallArgs) -> usize {
-    let layout = unsafe { (args.arg0 as *const Layout).as_ref().unwrap() };
+// size: arg0, align: arg1 -> ptr: *mut u8
+pub fn sys_allocate(size: usize, align: usize) -> SyscallResult<*mut u8> {
+    let layout = Layout::from_size_align(size, align).map_err(|_| Errno::EINVAL)?;
 
     if layout.size() == 0 {
-        return 0;
+        return Ok(core::ptr::null_mut());
     }
 
     let ret = crate::memory::user::USER_ALLOCATOR
         .lock()
-        .allocate_first_fit(*layout);
+        .allocate_first_fit(layout);
 
     match ret {
-        Ok(ptr) => ptr.as_ptr() as usize,
-        Err(_) => 0,
+        Ok(ptr) => Ok(ptr.as_ptr()),
+        Err(_) => Err(Errno::ENOMEM),
     }
 }
 
-pub fn sys_deallocate(args: &SyscallArgs) {
-    let layout = unsafe { (args.arg1 as *const Layout).as_ref().unwrap() };
+// ptr: arg0 as *mut u8, size: arg1, align: arg2
+pub fn sys_deallocate(ptr: *mut u8, size: usize, align: usize) -> SyscallResult<()> {
+    let layout = Layout::from_size_align(size, align).map_err(|_| Errno::EINVAL)?;
 
-    if args.arg0 == 0 || layout.size() == 0 {
-        return;
+    if ptr.is_null() || layout.size() == 0 {
+        return Ok(());
     }
 
-    let ptr = args.arg0 as *mut u8;
+    // 只允许释放用户堆内的内存
+    let heap = USER_HEAP_START..USER_HEAP_START + USER_HEAP_SIZE;
+    let end = (ptr as usize).checked_add(layout.size()).ok_or(Errno::EFAULT)?;
+    if !heap.contains(&(ptr as usize)) || end > heap.end {
+        return Err(Errno::EFAULT);
+    }
 
     unsafe {
         crate::memory::user::USER_ALLOCATOR
             .lock()
-            .deallocate(core::ptr::NonNull::new_unchecked(ptr), *layout);
+            .deallocate(core::ptr::NonNull::new_unchecked(ptr), layout);
     }
+
+    Ok(())
 }
 
 // None -> pid: u16
-pub fn sys_get_pid() -> u16 {
-    proc::processor::get_pid().0
+pub fn sys_get_pid() -> SyscallResult<u16> {
+    Ok(proc::processor::get_pid().0)
 }
 
-// pid: arg0 as u16 -> status: isize
-pub fn sys_wait_pid(args: &SyscallArgs, context: &mut ProcessContext) {
-    let pid = ProcessId(args.arg0 as u16);
-    proc::wait_pid(pid, context);
+// pid: arg0 as u16, status: arg1 as *mut isize -> pid: u16
+pub fn sys_wait_pid(context: &mut ProcessContext, pid: u16, status: *mut isize) {
+    proc::wait_pid(ProcessId(pid), VirtAddr::new_truncate(status as u64), context);
 }
 
-pub fn list_app() {
+pub fn sys_list_app() -> SyscallResult<()> {
     proc::list_app();
+    Ok(())
 }
 
 // None -> pid: u16 or 0 or -1
-pub fn sys_fork(context: &mut ProcessContext){
+pub fn sys_fork(context: &mut ProcessContext) {
     proc::fork(context);
 }
 
 // 0x05 add: 信号量的实现，根据args的值确定其不同操作
-pub fn sys_sem(args: &SyscallArgs, context: &mut ProcessContext) {
-    match args.arg0 {
-        0 => context.set_rax(sem_init(args.arg1 as u32, args.arg2)),
-        1 => context.set_rax(sem_remove(args.arg1 as u32)),
-        2 => sem_signal(args.arg1 as u32, context),
-        3 => sem_wait(args.arg1 as u32, context),
-        _ => context.set_rax(usize::MAX),
+// op: arg0, id: arg1, timeout: arg2 (ms)
+pub fn sys_sem(context: &mut ProcessContext, op: usize, id: u32, timeout: usize) {
+    match SemOp::from(op) {
+        SemOp::Close => context.set_rax(Errno::encode(sem_close(id))),
+        SemOp::Signal => sem_signal(id, context),
+        SemOp::Wait => sem_wait(id, timeout, false, context),
+        SemOp::WaitChecked => sem_wait(id, timeout, true, context),
+        SemOp::TryWait => context.set_rax(Errno::encode(sem_try_wait(id))),
+        SemOp::Unknown => context.set_rax(Errno::encode(Err(Errno::EINVAL))),
+    }
+}
+
+// name: &str (ptr: arg0 as *const u8, len: arg1), value: arg2, flags: arg3 -> id: u32
+pub fn sys_sem_open(name: *const u8, len: usize, value: usize, flags: usize) -> SyscallResult<u32> {
+    if len == 0 || len > SEM_NAME_MAX {
+        return Err(Errno::EINVAL);
+    }
+    let flags = SemFlags::from_bits(flags).ok_or(Errno::EINVAL)?;
+    let name = str_from_user(name as usize, len)?;
+    sem_open(&name, value, flags)
+}
+
+// key: arg0, capacity: arg1
+pub fn sys_msg_new(key: u32, capacity: usize) -> SyscallResult<()> {
+    msg_new(key, capacity)
+}
+
+// key: arg0
+pub fn sys_msg_remove(key: u32) -> SyscallResult<()> {
+    msg_remove(key)
+}
+
+// key: arg0, mtype: arg1, data: &[u8] (ptr: arg2 as *const u8, len: arg3), timeout: arg4 (ms)
+pub fn sys_msg_send(
+    context: &mut ProcessContext,
+    key: u32,
+    mtype: usize,
+    buf: *const u8,
+    len: usize,
+    timeout: usize,
+) {
+    match message_from_user(mtype, buf as usize, len) {
+        Ok(msg) => msg_send(key, msg, timeout, context),
+        Err(err) => context.set_rax(Errno::encode(Err(err))),
+    }
+}
+
+fn message_from_user(mtype: usize, addr: usize, len: usize) -> SyscallResult<Message> {
+    if mtype == MSG_ANY_TYPE || len > MSG_MAX_SIZE {
+        return Err(Errno::EINVAL);
+    }
+    let mut data = vec![0u8; len];
+    copy_from_user(&mut data, addr)?;
+    Ok(Message { mtype, data })
+}
+
+// key: arg0, mtype: arg1, buf: &mut [u8] (ptr: arg2 as *mut u8, len: arg3),
+// mtype_out: arg4 as *mut usize (may be null), timeout: arg5 (ms) -> len: usize
+pub fn sys_msg_recv(
+    context: &mut ProcessContext,
+    key: u32,
+    mtype: usize,
+    buf: *mut u8,
+    len: usize,
+    mtype_out: *mut usize,
+    timeout: usize,
+) {
+    // 消息可能在阻塞后由发送者写入，先检查缓冲区
+    let checked = check_user(buf as usize, len, true).and_then(|_| {
+        if mtype_out.is_null() {
+            Ok(())
+        } else {
+            check_user(mtype_out as usize, size_of::<usize>(), true)
+        }
+    });
+    if let Err(err) = checked {
+        context.set_rax(Errno::encode(Err(err)));
+        return;
+    }
+
+    let receiver = Receiver {
+        pid: processor::get_pid(),
+        mtype,
+        buf: buf as usize,
+        len,
+        mtype_out: mtype_out as usize,
+    };
+    msg_recv(key, receiver, timeout, context);
+}
+
+// addr: arg0 as *const u32, op: arg1, val: arg2, timeout: arg3 (ms) -> woken: usize
+pub fn sys_futex(
+    context: &mut ProcessContext,
+    addr: *const u32,
+    op: usize,
+    val: usize,
+    timeout: usize,
+) {
+    match FutexOp::from(op) {
+        FutexOp::Wait => futex_wait(addr as usize, val as u32, timeout, context),
+        FutexOp::Wake => context.set_rax(Errno::encode(futex_wake(addr as usize, val))),
+        FutexOp::Unknown => context.set_rax(Errno::encode(Err(Errno::EINVAL))),
     }
 }
 
 // 0x04 加分项, 0x05 add: sleep的实现
-pub fn sys_time() -> u64 {
-    let time = uefi::runtime::get_time().unwrap();
+pub fn sys_time() -> SyscallResult<u64> {
+    let time = uefi::runtime::get_time().map_err(|_| Errno::EIO)?;
     let secs = time.hour() as u64 * 3600 + time.minute() as u64 * 60 + time.second() as u64;
-    let msecs = time.nanosecond() / 1_000_000; 
-    secs * 1000 + msecs as u64
+    let msecs = time.nanosecond() / 1_000_000;
+    Ok(secs * 1000 + msecs as u64)
 }
 
 // 0x07 add: brk
-pub fn sys_brk(args: &SyscallArgs) -> usize {
-    let new_heap_end = if args.arg0 == 0 {
+pub fn sys_brk(addr: usize) -> SyscallResult {
+    let new_heap_end = if addr == 0 {
         None
     } else {
-        Some(VirtAddr::new(args.arg0 as u64))
+        Some(VirtAddr::try_new(addr as u64).map_err(|_| Errno::EINVAL)?)
     };
     match brk(new_heap_end) {
-        Some(new_heap_end) => new_heap_end.as_u64() as usize,
-        None => !0,
+        Some(new_heap_end) => Ok(new_heap_end.as_u64() as usize),
+        None => Err(Errno::ENOMEM),
     }
 }
\ No newline at end of file
@@ -48,6 +48,8 @@ pub fn init(boot_info: &'static BootInfo) {
     interrupt::init(); // init interrupts
     proc::init(boot_info); // init proc
     memory::init(boot_info); // init memory manager
+    devfs::init(); // register devices, including the disks
+    filesystem::init(); // mount the root filesystem
 
     x86_64::instructions::interrupts::enable();
     info!("Interrupts Enabled.");
@@ -64,6 +66,7 @@ pub fn init(boot_info: &'static BootInfo) {
 
 pub fn shutdown() -> ! {
     info!("YatSenOS shutting down.");
+    devfs::sync();
     uefi::runtime::reset(ResetType::SHUTDOWN, Status::SUCCESS, None);
 }
 
@@ -106,8 +106,9 @@ lazy_static! {
         let tss_selector = gdt.append(Descriptor::tss_segment(&TSS));
         
         // 0x04 add
-        let user_code_selector = gdt.append(Descriptor::user_code_segment());
+        // SYSRET 要求用户数据段紧接在用户代码段之前（见 STAR 寄存器）
         let user_data_selector = gdt.append(Descriptor::user_data_segment());
+        let user_code_selector = gdt.append(Descriptor::user_code_segment());
 
         (
             gdt,
@@ -174,6 +175,13 @@ pub fn get_user_selector() -> &'static UserSelectors {
     &GDT.2
 } // 0x04 add: 返回用户进程选择子
 
+/// Stack top used by syscall entries, shared by `int 0x80` and `syscall`
+///
+/// both paths run with interrupts disabled, so they never nest
+pub fn get_syscall_stack_top() -> VirtAddr {
+    TSS.interrupt_stack_table[SYSCALL_IST_INDEX as usize]
+}
+
 pub fn get_gdt() -> Option<&'static GlobalDescriptorTable> {
     Some(&GDT.0)
 }
\ No newline at end of file
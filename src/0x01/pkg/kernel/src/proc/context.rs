@@ -71,6 +71,13 @@ impl ProcessContext {
     pub fn update_rsp(&mut self, stack_top: u64) {
         self.value.stack_frame.stack_pointer = VirtAddr::new(stack_top);
     }
+
+    /// Re-execute the syscall instruction when this context is restored
+    ///
+    /// both `int 0x80` (`cd 80`) and `syscall` (`0f 05`) are 2 bytes long
+    pub fn restart_syscall(&mut self) {
+        self.value.stack_frame.instruction_pointer -= 2u64;
+    }
 }
 
 impl Default for ProcessContextValue {
@@ -6,14 +6,18 @@ use x86_64::structures::paging::{
 };
 
 use super::*;
-use crate::utils::resource::ResourceSet;
+use crate::utils::resource::{Resource, ResourceSet, WaitFor};
+use syscall_def::{FcntlCmd, FileStat, SeekWhence, SemFlags, SyscallResult};
 
 #[derive(Debug, Clone)]
 pub struct ProcessData {
     // shared data
     pub(super) env: Arc<RwLock<BTreeMap<String, String>>>,
     pub(super) resources: Arc<RwLock<ResourceSet>>, // 0x04 add
-    pub(super) semaphores: Arc<RwLock<SemaphoreSet>>, // 0x05 add
+    pub(super) semaphores: Arc<RwLock<SemaphoreHandles>>, // 0x05 add
+    pub(super) msg_queues: Arc<RwLock<MessageQueueSet>>,
+    /// The current working directory, always absolute and normalized
+    pub(super) cwd: Arc<RwLock<String>>,
 }
 
 impl Default for ProcessData {
@@ -21,7 +25,9 @@ impl Default for ProcessData {
         Self {
             env: Arc::new(RwLock::new(BTreeMap::new())),
             resources: Arc::new(RwLock::new(ResourceSet::default())), // 0x04 add
-            semaphores: Arc::new(RwLock::new(SemaphoreSet::default())),
+            semaphores: Arc::new(RwLock::new(SemaphoreHandles::default())),
+            msg_queues: Arc::new(RwLock::new(MessageQueueSet::default())),
+            cwd: Arc::new(RwLock::new(String::from("/"))),
         }
     }
 }
@@ -31,6 +37,29 @@ impl ProcessData {
         Self::default()
     }
 
+    /// Process data for a forked child
+    ///
+    /// the fd table, opened semaphores and cwd are copied, other data is still shared
+    pub fn fork(&self) -> Self {
+        Self {
+            resources: Arc::new(RwLock::new(self.resources.read().clone())),
+            semaphores: Arc::new(RwLock::new(self.semaphores.read().clone())),
+            cwd: Arc::new(RwLock::new(self.cwd())),
+            ..self.clone()
+        }
+    }
+
+    /// Process data for a spawned child, only fds without `FD_CLOEXEC` are inherited
+    ///
+    /// the child starts in the cwd of its parent
+    pub fn inherit(&self) -> Self {
+        Self {
+            resources: Arc::new(RwLock::new(self.resources.read().inherit())),
+            cwd: Arc::new(RwLock::new(self.cwd())),
+            ..Self::default()
+        }
+    }
+
     pub fn env(&self, key: &str) -> Option<String> {
         self.env.read().get(key).cloned()
     }
@@ -39,28 +68,83 @@ impl ProcessData {
         self.env.write().insert(key.into(), val.into());
     }
 
+    pub fn cwd(&self) -> String {
+        self.cwd.read().clone()
+    }
+
+    pub fn set_cwd(&self, cwd: String) {
+        *self.cwd.write() = cwd;
+    }
+
     // 0x04 add: write() && read()
-    pub fn read(&self, fd: u8, buf: &mut [u8]) -> isize {
+    pub fn read(&self, fd: usize, buf: &mut [u8]) -> SyscallResult {
         self.resources.read().read(fd, buf)
     }
 
-    pub fn write(&self, fd: u8, buf: &[u8]) -> isize {
+    pub fn write(&self, fd: usize, buf: &[u8]) -> SyscallResult {
         self.resources.read().write(fd, buf)
     }
 
-    // 0x05 add: 信号量的四种操作：
-    pub fn sem_init(&mut self, key: u32, value: usize) -> bool {
-        self.semaphores.write().insert(key, value)
+    pub fn pipe(&self) -> SyscallResult<(usize, usize)> {
+        self.resources.write().pipe()
+    }
+
+    pub fn wait(&self, fd: usize, on: WaitFor) -> bool {
+        self.resources.read().wait(fd, on)
+    }
+
+    pub fn ioctl(&self, fd: usize, request: IoctlRequest, arg: usize) -> SyscallResult {
+        self.resources.read().ioctl(fd, request, arg)
+    }
+
+    pub fn open(&self, res: Resource) -> SyscallResult<usize> {
+        self.resources.write().open(res)
     }
 
-    pub fn sem_remove(&mut self, key: u32) -> bool {
-        self.semaphores.write().remove(key)
+    pub fn seek(&self, fd: usize, offset: isize, whence: SeekWhence) -> SyscallResult {
+        self.resources.read().seek(fd, offset, whence)
     }
 
-    pub fn sem_wait(&mut self, key: u32, pid: ProcessId) -> SemaphoreResult {
-        self.semaphores.write().wait(key, pid)
+    pub fn fstat(&self, fd: usize) -> SyscallResult<FileStat> {
+        self.resources.read().fstat(fd)
     }
-    pub fn sem_signal(&mut self, key: u32) -> SemaphoreResult {
-        self.semaphores.write().signal(key)
+
+    pub fn close(&self, fd: usize) -> SyscallResult<()> {
+        self.resources.write().close(fd)
+    }
+
+    pub fn dup(&self, fd: usize) -> SyscallResult<usize> {
+        self.resources.write().dup(fd)
+    }
+
+    pub fn dup2(&self, old: usize, new: usize) -> SyscallResult<usize> {
+        self.resources.write().dup2(old, new)
+    }
+
+    pub fn fcntl(&self, fd: usize, cmd: FcntlCmd, arg: usize) -> SyscallResult {
+        self.resources.write().fcntl(fd, cmd, arg)
+    }
+
+    /// Message queues shared with forked processes
+    pub fn msg_queues(&self) -> Arc<RwLock<MessageQueueSet>> {
+        self.msg_queues.clone()
+    }
+
+    // 0x05 add: 信号量的操作，信号量本身位于全局的命名空间
+    pub fn sem_open(&self, name: &str, value: usize, flags: SemFlags) -> SyscallResult<SemaphoreId> {
+        self.semaphores.write().open(name, value, flags)
+    }
+
+    pub fn sem_close(&self, id: SemaphoreId) -> SyscallResult<()> {
+        self.semaphores.write().close(id)
+    }
+
+    pub fn sem_check(&self, id: SemaphoreId) -> SyscallResult<()> {
+        self.semaphores.read().check(id)
+    }
+
+    /// Remove `pid` from the holders of its opened semaphores, the mutexes stay locked
+    pub fn sem_remove_holder(&self, pid: ProcessId) {
+        self.semaphores.read().remove_holder(pid)
     }
 }
@@ -0,0 +1,72 @@
+//! Futexes
+//!
+//! A futex is a `u32` in user memory, waiters are queued by the physical
+//! address of the word, so processes sharing the memory (e.g. forked ones)
+//! agree on the same futex even if it is mapped at different addresses.
+//!
+//! The value is checked and the process is queued with interrupts disabled,
+//! so a wake up between the check and blocking can not be lost.
+
+use super::{PageTableContext, ProcessId};
+use alloc::collections::*;
+use alloc::vec::Vec;
+use core::mem::align_of;
+use spin::Mutex;
+use syscall_def::{Errno, SyscallResult};
+
+static FUTEXES: Mutex<BTreeMap<u64, VecDeque<ProcessId>>> = Mutex::new(BTreeMap::new());
+
+/// Physical address of the futex word at `addr`
+fn futex_key(page_table: &PageTableContext, addr: usize) -> SyscallResult<u64> {
+    if !addr.is_multiple_of(align_of::<u32>()) {
+        return Err(Errno::EINVAL);
+    }
+    Ok(page_table.translate_user(addr as u64, false)?.as_u64())
+}
+
+/// Queue `pid` on the futex if its value is still `expected`
+///
+/// returns the key of the futex, the caller should block the process on it
+pub fn wait(
+    page_table: &PageTableContext,
+    addr: usize,
+    expected: u32,
+    pid: ProcessId,
+) -> SyscallResult<u64> {
+    let key = futex_key(page_table, addr)?;
+    if page_table.read_from_user::<u32>(addr)? != expected {
+        return Err(Errno::EAGAIN);
+    }
+    FUTEXES.lock().entry(key).or_default().push_back(pid);
+    Ok(key)
+}
+
+/// Dequeue at most `count` waiters of the futex, the caller should wake them up
+pub fn wake(
+    page_table: &PageTableContext,
+    addr: usize,
+    count: usize,
+) -> SyscallResult<Vec<ProcessId>> {
+    let key = futex_key(page_table, addr)?;
+    let mut futexes = FUTEXES.lock();
+    let Some(waiters) = futexes.get_mut(&key) else {
+        return Ok(Vec::new());
+    };
+
+    let woken = waiters.drain(..count.min(waiters.len())).collect();
+    if waiters.is_empty() {
+        futexes.remove(&key);
+    }
+    Ok(woken)
+}
+
+/// Remove a blocked process, e.g. on timeout or when it is killed
+pub fn cancel(key: u64, pid: ProcessId) {
+    let mut futexes = FUTEXES.lock();
+    if let Some(waiters) = futexes.get_mut(&key) {
+        waiters.retain(|waiter| *waiter != pid);
+        if waiters.is_empty() {
+            futexes.remove(&key);
+        }
+    }
+}
@@ -4,7 +4,7 @@ use crate::memory::{
     allocator::{ALLOCATOR, HEAP_SIZE},
     get_frame_alloc_for_sure, PAGE_SIZE,
 };
-use alloc::{collections::*, format, sync::Arc, sync::Weak};
+use alloc::{collections::*, format, sync::Arc, sync::Weak, vec, vec::Vec};
 use spin::{Mutex, RwLock};
 use vm::*;
 use core::ops::DerefMut;
@@ -35,11 +35,24 @@ pub fn get_process_manager() -> &'static ProcessManager {
         .expect("Process Manager has not been initialized")
 }
 
+/// Kernel objects a process can be blocked on
+///
+/// the wait is cancelled when the process times out or is killed
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum WaitObject {
+    MsgQueue(u32),
+    /// 以物理地址为键的 futex
+    Futex(u64),
+    Semaphore(u32),
+}
+
 pub struct ProcessManager {
     processes: RwLock<BTreeMap<ProcessId, Arc<Process>>>, // 用读写锁保护的进程键值对
     ready_queue: Mutex<VecDeque<ProcessId>>, // 用于进程管理的双端队列
     app_list: boot::AppListRef, // 0x04: 采用boot/lib.rs中定义的Option<&AppList>
-    wait_queue: Mutex<BTreeMap<ProcessId, BTreeSet<ProcessId>>>, // 0x05: 等待队列
+    wait_queue: Mutex<BTreeMap<ProcessId, BTreeMap<ProcessId, VirtAddr>>>, // 0x05: 等待队列，记录等待者及其退出码的写回地址
+    interrupted: Mutex<Option<ProcessId>>, // 被 Ctrl-C 中断、待下次调度时结束的当前进程
+    blocked_on: Mutex<BTreeMap<ProcessId, (WaitObject, Option<u64>)>>, // 阻塞在内核对象上的进程，及其超时的时钟 tick
 }
 
 impl ProcessManager {
@@ -56,6 +69,8 @@ impl ProcessManager {
             ready_queue: Mutex::new(ready_queue),
             app_list: apps,
             wait_queue: Mutex::new(BTreeMap::new()), // 0x05 add
+            interrupted: Mutex::new(None),
+            blocked_on: Mutex::new(BTreeMap::new()),
         }
     }
 
@@ -77,7 +92,17 @@ impl ProcessManager {
     #[inline]
     pub fn get_proc(&self, pid: &ProcessId) -> Option<Arc<Process>> {
         self.processes.read().get(pid).cloned()
-    } 
+    }
+
+    /// Pids of the processes not dead yet, in ascending order
+    pub fn alive_pids(&self) -> Vec<ProcessId> {
+        self.processes
+            .read()
+            .iter()
+            .filter(|(_, proc)| !proc.read().is_dead())
+            .map(|(&pid, _)| pid)
+            .collect()
+    }
 
     #[inline]
     pub fn pop_ready(&self) -> ProcessId {
@@ -152,9 +177,13 @@ impl ProcessManager {
             return;
         }
         // 0x05 add
-        if let Some(pids) = self.wait_queue.lock().remove(&pid) {
-            for pid in pids {
-                self.wake_up(pid, Some(ret));
+        if let Some(waiters) = self.wait_queue.lock().remove(&pid) {
+            for (waiter, status) in waiters {
+                if let Some(proc) = self.get_proc(&waiter) {
+                    proc.read().write_exit_code(status, ret);
+                }
+                // 等待者的返回值为被等待进程的 pid
+                self.wake_up(waiter, Some(pid.0 as isize));
             }
         } // finish add
 
@@ -165,6 +194,10 @@ impl ProcessManager {
             return;
         }
 
+        self.cancel_wait(pid);
+        // 死锁检测不应再沿着已死亡的持有者查找
+        proc.read().sem_remove_holder(pid);
+
         trace!("Kill {:#?}", &proc);
         info!("ret = {}", ret);
         proc.kill(ret);
@@ -242,15 +275,20 @@ impl ProcessManager {
     }
 
     #[inline]
-    pub fn write(&self, fd: u8, buf: &[u8]) -> isize{
+    pub fn write(&self, fd: usize, buf: &[u8]) -> SyscallResult {
         self.current().write().write(fd, buf)
     }
 
     #[inline]
-    pub fn read(&self, fd: u8, buf: &mut [u8]) -> isize{
+    pub fn read(&self, fd: usize, buf: &mut [u8]) -> SyscallResult {
         self.current().read().read(fd, buf)
     }
 
+    #[inline]
+    pub fn ioctl(&self, fd: usize, request: IoctlRequest, arg: usize) -> SyscallResult {
+        self.current().read().ioctl(fd, request, arg)
+    }
+
     // 0x05 add:
     // 选择了同样返回一个子进程的Arc引用，因为Manager中需要获取它的pid
     pub fn fork(&self) -> Arc<Process> {
@@ -273,12 +311,141 @@ impl ProcessManager {
         }
     }
 
-    pub fn wait_pid(&self, pid: ProcessId) {
+    /// Block the process on a kernel object until it is woken up or `deadline` (in clock ticks)
+    pub fn block_on(&self, pid: ProcessId, on: WaitObject, deadline: Option<u64>) {
+        self.blocked_on.lock().insert(pid, (on, deadline));
+        self.block(&pid);
+    }
+
+    /// Remove the process from the kernel object it is blocked on
+    fn cancel_wait(&self, pid: ProcessId) {
+        let Some((on, _)) = self.blocked_on.lock().remove(&pid) else {
+            return;
+        };
+        let Some(proc) = self.get_proc(&pid) else {
+            return;
+        };
+        match on {
+            WaitObject::MsgQueue(key) => proc.read().msg_queues().write().cancel(key, pid),
+            WaitObject::Futex(key) => futex::cancel(key, pid),
+            WaitObject::Semaphore(id) => SEMAPHORES.lock().cancel(SemaphoreId::new(id), pid),
+        }
+    }
+
+    /// Check if blocking `pid` on the semaphore `id` closes a cycle in the wait-for graph
+    ///
+    /// a process waits for the holders of the semaphore it is blocked on, only mutexes
+    /// have holders, so waits on counting semaphores never close a cycle,
+    /// the cycle found is reported with the names of the processes and semaphores
+    pub fn check_sem_deadlock(&self, pid: ProcessId, id: SemaphoreId) -> bool {
+        let sems = SEMAPHORES.lock();
+        let blocked_on = self.blocked_on.lock();
+        let mut path = vec![(pid, id)];
+        let mut visited = BTreeSet::new();
+        if !find_wait_cycle(&sems, &blocked_on, pid, &mut path, &mut visited) {
+            return false;
+        }
+
+        let mut report = String::new();
+        for (pid, id) in path.iter() {
+            let name = self.get_proc(pid).map(|proc| String::from(proc.read().name()));
+            report += &format!(
+                "{}#{} waits for <{}> held by ",
+                name.as_deref().unwrap_or("?"),
+                pid,
+                sems.name(*id).unwrap_or("?")
+            );
+        }
+        report += &format!("#{}", pid);
+        warn!("Deadlock detected: {}", report);
+        true
+    }
+
+    /// Wake up the blocked processes whose deadline has passed with `ETIMEDOUT`
+    pub fn wake_up_expired(&self, now: u64) {
+        let expired: Vec<ProcessId> = self
+            .blocked_on
+            .lock()
+            .iter()
+            .filter(|(_, (_, deadline))| deadline.is_some_and(|deadline| deadline <= now))
+            .map(|(pid, _)| *pid)
+            .collect();
+
+        for pid in expired {
+            self.cancel_wait(pid);
+            self.wake_up(pid, Some(Errno::encode(Err(Errno::ETIMEDOUT)) as isize));
+        }
+    }
+
+    /// Wake up the processes unblocked by a message queue operation
+    pub fn apply_msg_effects(&self, fx: MsgEffects) {
+        for (receiver, msg) in fx.deliver {
+            let ret = msg.and_then(|msg| self.deliver_message(&receiver, msg));
+            self.wake_up(receiver.pid, Some(Errno::encode(ret) as isize));
+        }
+        for pid in fx.sent {
+            self.wake_up(pid, Some(0));
+        }
+        for (pid, errno) in fx.failed {
+            self.wake_up(pid, Some(Errno::encode(Err(errno)) as isize));
+        }
+    }
+
+    /// Copy the message to the buffers of the receiver, returns the data length
+    ///
+    /// the receiver may not be the current process, so its own page table is used
+    pub fn deliver_message(&self, receiver: &Receiver, msg: Message) -> SyscallResult {
+        let proc = self.get_proc(&receiver.pid).ok_or(Errno::ESRCH)?;
+        let inner = proc.read();
+        let page_table = &inner.vm().page_table;
+        page_table.copy_to_user(receiver.buf, &msg.data)?;
+        if receiver.mtype_out != 0 {
+            page_table.write_to_user(receiver.mtype_out, &msg.mtype)?;
+        }
+        Ok(msg.data.len())
+    }
+
+    pub fn wait_pid(&self, pid: ProcessId, status: VirtAddr) {
         let mut wait_queue = self.wait_queue.lock();
         // FIXME: push the current process to the wait queue
         //        `processor::get_pid()` is waiting for `pid`
         let entry = wait_queue.entry(pid).or_default();
-        entry.insert(processor::get_pid());
+        entry.insert(processor::get_pid(), status);
+    }
+
+    /// Wake up the process if it is still blocked
+    ///
+    /// used by `WaitQueue`, the process may have been killed while waiting
+    pub fn wake_up_blocked(&self, pid: ProcessId) {
+        let blocked = self
+            .get_proc(&pid)
+            .is_some_and(|proc| proc.read().status() == ProgramStatus::Blocked);
+        if blocked {
+            self.wake_up(pid, None);
+        }
+    }
+
+    /// Kill the process interrupted by the terminal
+    ///
+    /// the running process can not be killed in an interrupt handler
+    /// without its context, so it is killed on the next schedule
+    pub fn interrupt(&self, pid: ProcessId) {
+        if pid == processor::get_pid() {
+            *self.interrupted.lock() = Some(pid);
+        } else {
+            self.kill(pid, INTERRUPTED_EXIT_CODE);
+        }
+    }
+
+    /// Check if the current process should be killed by `interrupt`
+    pub fn take_interrupted(&self) -> bool {
+        let mut interrupted = self.interrupted.lock();
+        if *interrupted == Some(processor::get_pid()) {
+            *interrupted = None;
+            true
+        } else {
+            false
+        }
     }
 
     pub fn get_exit_code(&self, pid: ProcessId) -> Option<isize> {
@@ -299,6 +466,7 @@ impl ProcessManager {
     ///
     /// If `ret` is `Some`, set the return value of the process
     pub fn wake_up(&self, pid: ProcessId, ret: Option<isize>) {
+        self.blocked_on.lock().remove(&pid);
         if let Some(proc) = self.get_proc(&pid) {
             let mut inner = proc.write();
             if let Some(ret) = ret {
@@ -332,3 +500,34 @@ impl ProcessManager {
         )
     }
 }
+
+/// Depth-first search for a path of waits leading back to `target`
+///
+/// `path` starts with the last process and the semaphore it waits on,
+/// and holds the whole cycle if one is found
+fn find_wait_cycle(
+    sems: &SemaphoreSet,
+    blocked_on: &BTreeMap<ProcessId, (WaitObject, Option<u64>)>,
+    target: ProcessId,
+    path: &mut Vec<(ProcessId, SemaphoreId)>,
+    visited: &mut BTreeSet<ProcessId>,
+) -> bool {
+    let &(_, id) = path.last().unwrap();
+    for &holder in sems.holders(id) {
+        if holder == target {
+            return true;
+        }
+        if !visited.insert(holder) {
+            continue;
+        }
+        // 只有阻塞在信号量上的持有者才会继续等待
+        if let Some(&(WaitObject::Semaphore(next), _)) = blocked_on.get(&holder) {
+            path.push((holder, SemaphoreId::new(next)));
+            if find_wait_cycle(sems, blocked_on, target, path, visited) {
+                return true;
+            }
+            path.pop();
+        }
+    }
+    false
+}
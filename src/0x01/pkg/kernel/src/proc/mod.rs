@@ -5,15 +5,20 @@ mod paging;
 mod pid;
 mod process;
 pub mod processor;
+pub mod procfs;
+mod uaccess;
 mod vm;
 
 pub mod sync; // 0x05 add
+pub mod msg;
+mod futex;
 
 use manager::*;
 use process::*;
 use vm::*;
 use processor::*; // 在switch函数中使用了proceeor相关的函数
 use sync::*; // 0x05 add
+use msg::*;
 use crate::memory::PAGE_SIZE;
 
 use alloc::string::String;
@@ -22,16 +27,23 @@ pub use paging::PageTableContext;
 pub use data::ProcessData;
 pub use pid::ProcessId;
 pub use manager::ProcessManager;
+pub use uaccess::*;
+pub use sync::WaitQueue;
 
 use x86_64::structures::idt::PageFaultErrorCode;
 use x86_64::VirtAddr;
 pub const KERNEL_PID: ProcessId = ProcessId(1); // 常量定义：内核进程pid为1
+/// Exit code of processes killed by Ctrl-C (128 + SIGINT)
+pub const INTERRUPTED_EXIT_CODE: isize = 130;
 
-use alloc::vec::Vec;
 use alloc::format;
-use alloc::string::ToString;
 use alloc::sync::{Arc, Weak};
 use xmas_elf::ElfFile;
+use storage::FileSystem;
+use syscall_def::{Errno, FcntlCmd, FileStat, IoctlRequest, OpenFlags, SeekWhence, SyscallResult};
+use crate::utils::resource::{Resource, WaitFor};
+use crate::interrupt::clock;
+use syscall_def::{SemFlags, TIMEOUT_FOREVER};
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum ProgramStatus {
@@ -73,10 +85,16 @@ pub fn switch(context: &mut ProcessContext) {
         // FIXME: switch to the next process
         //      - save current process's context
         let manager = get_process_manager();
-        manager.save_current(context);
+        manager.wake_up_expired(clock::read_counter());
+        if manager.take_interrupted() {
+            // 当前进程已被 Ctrl-C 中断，直接结束它
+            manager.kill_current(INTERRUPTED_EXIT_CODE);
+        } else {
+            manager.save_current(context);
 
-        //      - handle ready queue update
-        manager.push_ready(get_pid());
+            //      - handle ready queue update
+            manager.push_ready(get_pid());
+        }
 
         //      - restore next process's context
         manager.switch_next(context);
@@ -147,21 +165,74 @@ pub fn list_app() {
 
 
 // 0x04 add: spawn && elf_spawn && read && write
-pub fn spawn(name: &str) -> Option<ProcessId> {
+/// Spawn an app by its path, e.g. `/APP/FAB` or `../FAB`
+///
+/// a bare name is looked up in `/APP`, the app list loaded by the
+/// bootloader is used as a fallback
+pub fn spawn(path: &str) -> Option<ProcessId> {
+    let name = path.rsplit('/').next().unwrap_or(path).to_lowercase();
+    let path = if path.contains('/') {
+        resolve_path(path)
+    } else {
+        format!("/APP/{path}")
+    };
+
+    // ELF 读入内核内存，各段在加载时复制到进程的地址空间
+    match crate::filesystem::read_file(&path) {
+        Ok(buf) => match ElfFile::new(&buf) {
+            Ok(elf) => return elf_spawn(name, &elf),
+            Err(err) => warn!("Invalid ELF file {}: {}", path, err),
+        },
+        Err(err) => trace!("Failed to read {}: {:?}", path, err),
+    }
+
     let app = x86_64::instructions::interrupts::without_interrupts(|| {
         let app_list = get_process_manager().app_list()?;
-        app_list.iter().find(|&app| app.name.eq(name))
+        app_list.iter().find(|&app| app.name.as_str() == name)
     })?;
 
-    elf_spawn(name.to_string(), &app.elf)
+    elf_spawn(name, &app.elf)
+}
+
+/// The current working directory of the current process
+pub fn getcwd() -> String {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().current().read().cwd()
+    })
+}
+
+/// Resolve `path` against the cwd of the current process into a normalized absolute path
+pub fn resolve_path(path: &str) -> String {
+    storage::join(&getcwd(), path)
+}
+
+/// Change the cwd of the current process, `path` must be a directory
+pub fn chdir(path: &str) -> SyscallResult<()> {
+    let path = resolve_path(path);
+    // 系统调用全程关中断（SFMask 清除 IF），VFS 与磁盘 I/O 同样在关中断时进行，
+    // 查询元数据时不持有进程管理器的锁
+    let meta = crate::filesystem::get_vfs()
+        .metadata(&path)
+        .map_err(crate::filesystem::errno)?;
+    if !meta.is_dir() {
+        return Err(Errno::ENOTDIR);
+    }
+
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().current().read().set_cwd(path);
+    });
+    Ok(())
 }
 
 pub fn elf_spawn(name: String, elf: &ElfFile) -> Option<ProcessId> {
     let pid = x86_64::instructions::interrupts::without_interrupts(|| {
         let manager = get_process_manager();
         let process_name = name.to_lowercase();
-        let parent = Arc::downgrade(&manager.current());
-        let pid = manager.spawn(elf, name, Some(parent), None);
+        let current = manager.current();
+        // 子进程继承未设置 FD_CLOEXEC 的描述符，便于重定向
+        let data = current.read().inherit();
+        let parent = Arc::downgrade(&current);
+        let pid = manager.spawn(elf, name, Some(parent), Some(data));
 
         debug!("Spawned process: {}#{}", process_name, pid);
         pid
@@ -170,14 +241,119 @@ pub fn elf_spawn(name: String, elf: &ElfFile) -> Option<ProcessId> {
     Some(pid)
 }
 
-pub fn read(fd: u8, buf: &mut [u8]) -> isize {
+pub fn read(fd: usize, buf: &mut [u8]) -> SyscallResult {
     x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().read(fd, buf))
 }
 
-pub fn write(fd: u8, buf: &[u8]) -> isize {
+pub fn write(fd: usize, buf: &[u8]) -> SyscallResult {
     x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().write(fd, buf))
 }
 
+/// Open the file at `path` for the current process, returns the new fd
+pub fn open(path: &str, flags: OpenFlags) -> SyscallResult<usize> {
+    if !flags.intersects(OpenFlags::READ | OpenFlags::WRITE) {
+        return Err(Errno::EINVAL);
+    }
+
+    let path = resolve_path(path);
+    // 同 chdir，打开文件时不持有进程管理器的锁，此时中断仍是关闭的
+    let file = crate::filesystem::open(&path, flags).map_err(crate::filesystem::errno)?;
+    if file.meta.is_dir() {
+        return Err(Errno::EISDIR);
+    }
+    // 字符设备需要支持阻塞和 ioctl，不经过文件读写
+    let res = match crate::drivers::devfs::char_device(&file.meta) {
+        Some(device) => Resource::Device(device, flags),
+        None => Resource::File(file, flags),
+    };
+
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().current().read().open(res)
+    })
+}
+
+/// Move the offset of fd, returns the new offset
+pub fn seek(fd: usize, offset: isize, whence: SeekWhence) -> SyscallResult {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().current().read().seek(fd, offset, whence)
+    })
+}
+
+pub fn fstat(fd: usize) -> SyscallResult<FileStat> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().current().read().fstat(fd)
+    })
+}
+
+pub fn close(fd: usize) -> SyscallResult<()> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().current().read().close(fd)
+    })
+}
+
+pub fn dup(fd: usize) -> SyscallResult<usize> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().current().read().dup(fd)
+    })
+}
+
+pub fn dup2(old: usize, new: usize) -> SyscallResult<usize> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().current().read().dup2(old, new)
+    })
+}
+
+pub fn fcntl(fd: usize, cmd: FcntlCmd, arg: usize) -> SyscallResult {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().current().read().fcntl(fd, cmd, arg)
+    })
+}
+
+/// Create a pipe, returns its read fd and write fd
+pub fn pipe() -> SyscallResult<(usize, usize)> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().current().read().pipe()
+    })
+}
+
+pub fn ioctl(fd: usize, request: IoctlRequest, arg: usize) -> SyscallResult {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().ioctl(fd, request, arg)
+    })
+}
+
+/// Kill the process `pid` on Ctrl-C
+pub fn interrupt(pid: ProcessId) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().interrupt(pid);
+    })
+}
+
+/// Block the current process until `fd` may be read or written
+///
+/// the syscall is restarted after the process is woken up
+pub fn wait_fd(fd: usize, on: WaitFor, context: &mut ProcessContext) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let manager = get_process_manager();
+        if !manager.current().read().wait(fd, on) {
+            // 没有可以唤醒该进程的事件，直接返回错误
+            context.set_rax(Errno::encode(Err(Errno::EAGAIN)));
+            return;
+        }
+        context.restart_syscall();
+        manager.save_current(context);
+        manager.block(&processor::get_pid());
+        manager.switch_next(context);
+    })
+}
+
+/// Wake up `pid` if it is still blocked
+pub fn wake_up_blocked(pid: ProcessId) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().wake_up_blocked(pid);
+    })
+}
+
 pub fn exit(ret: isize, context: &mut ProcessContext) {
     x86_64::instructions::interrupts::without_interrupts(|| {
         let manager = get_process_manager();
@@ -198,13 +374,20 @@ pub fn still_alive(pid: ProcessId) -> bool {
     })
 }
 
-pub fn wait_pid(pid: ProcessId, context: &mut ProcessContext) {
+/// Wait for the process `pid` to exit
+///
+/// on success, `rax` is set to `pid` and the exit code is written to `status`
+/// (ignored if `status` is null), otherwise `rax` is set to `-ECHILD`
+pub fn wait_pid(pid: ProcessId, status: VirtAddr, context: &mut ProcessContext) {
     x86_64::instructions::interrupts::without_interrupts(|| {
         let manager = get_process_manager();
-        if let Some(ret) = manager.get_exit_code(pid) {
-            context.set_rax(ret as usize);
+        if manager.get_proc(&pid).is_none() {
+            context.set_rax(Errno::encode(Err(Errno::ECHILD)));
+        } else if let Some(ret) = manager.get_exit_code(pid) {
+            manager.current().read().write_exit_code(status, ret);
+            context.set_rax(pid.0 as usize);
         } else {
-            manager.wait_pid(pid);
+            manager.wait_pid(pid, status);
             manager.save_current(context);
             manager.current().write().block();
             manager.switch_next(context);
@@ -228,36 +411,51 @@ pub fn fork(context: &mut ProcessContext) {
     })
 }
 
-pub fn sem_init(key: u32, val: usize) -> usize {
+/// Open the named semaphore, returns its id
+pub fn sem_open(name: &str, value: usize, flags: SemFlags) -> SyscallResult<u32> {
     x86_64::instructions::interrupts::without_interrupts(|| {
         let manager = get_process_manager();
-        let ret = manager.current().write().sem_init(key, val);
-        ret as usize
+        let id = manager.current().read().sem_open(name, value, flags)?;
+        Ok(id.0)
     })
 }
 
-pub fn sem_remove(key: u32) -> usize {
+pub fn sem_close(id: u32) -> SyscallResult {
     x86_64::instructions::interrupts::without_interrupts(|| {
         let manager = get_process_manager();
-        let ret = manager.current().write().sem_remove(key);
-        ret as usize
+        manager.current().read().sem_close(SemaphoreId::new(id))?;
+        Ok(0)
     })
 }
 
-
-pub fn sem_wait(key: u32, context: &mut ProcessContext) {
+/// Wait the semaphore, blocking up to `timeout` ms
+///
+/// a wait that would deadlock is reported, and fails with `EDEADLK`
+/// instead of blocking if `checked` is set
+pub fn sem_wait(id: u32, timeout: usize, checked: bool, context: &mut ProcessContext) {
     x86_64::instructions::interrupts::without_interrupts(|| {
         let manager = get_process_manager();
         let pid = processor::get_pid();
-        let ret = manager.current().write().sem_wait(key, pid);
+        let id = SemaphoreId::new(id);
+        if let Err(err) = manager.current().read().sem_check(id) {
+            context.set_rax(Errno::encode(Err(err)));
+            return;
+        }
+
+        let ret = SEMAPHORES.lock().wait(id, pid);
         match ret {
             SemaphoreResult::Ok => context.set_rax(0),
-            SemaphoreResult::NotExist => context.set_rax(1),
+            SemaphoreResult::NotExist => context.set_rax(Errno::encode(Err(Errno::ENOENT))),
             SemaphoreResult::Block(pid) => {
+                if manager.check_sem_deadlock(pid, id) && checked {
+                    SEMAPHORES.lock().cancel(id, pid);
+                    context.set_rax(Errno::encode(Err(Errno::EDEADLK)));
+                    return;
+                }
                 // FIXME: save, block it, then switch to next
                 //        use `save_current` and `switch_next`
                 manager.save_current(context);
-                manager.block(&pid);
+                manager.block_on(pid, WaitObject::Semaphore(id.0), deadline(timeout));
                 manager.switch_next(context);
             }
             _ => unreachable!(),
@@ -265,19 +463,156 @@ pub fn sem_wait(key: u32, context: &mut ProcessContext) {
     })
 }
 
-pub fn sem_signal(key: u32, context: &mut ProcessContext) {
+pub fn sem_try_wait(id: u32) -> SyscallResult {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let manager = get_process_manager();
+        let id = SemaphoreId::new(id);
+        manager.current().read().sem_check(id)?;
+        SEMAPHORES.lock().try_wait(id, processor::get_pid())?;
+        Ok(0)
+    })
+}
+
+pub fn sem_signal(id: u32, context: &mut ProcessContext) {
     x86_64::instructions::interrupts::without_interrupts(|| {
         let manager = get_process_manager();
-        let ret = manager.current().write().sem_signal(key);
+        let id = SemaphoreId::new(id);
+        if let Err(err) = manager.current().read().sem_check(id) {
+            context.set_rax(Errno::encode(Err(err)));
+            return;
+        }
+
+        let ret = SEMAPHORES.lock().signal(id, processor::get_pid());
         match ret {
             SemaphoreResult::Ok => context.set_rax(0),
-            SemaphoreResult::NotExist => context.set_rax(1),
-            SemaphoreResult::WakeUp(pid) => manager.wake_up(pid,None),
+            SemaphoreResult::NotExist => context.set_rax(Errno::encode(Err(Errno::ENOENT))),
+            SemaphoreResult::WakeUp(pid) => {
+                context.set_rax(0);
+                manager.wake_up(pid, Some(0))
+            }
+            SemaphoreResult::NotHolder => context.set_rax(Errno::encode(Err(Errno::EPERM))),
             _ => unreachable!(),
         };
     })
 }
 
+/// Deadline in clock ticks of a wait for `timeout` milliseconds
+fn deadline(timeout: usize) -> Option<u64> {
+    (timeout != TIMEOUT_FOREVER)
+        .then(|| clock::read_counter() + clock::ms_to_ticks(timeout as u64))
+}
+
+pub fn msg_new(key: u32, capacity: usize) -> SyscallResult<()> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let queues = get_process_manager().current().read().msg_queues();
+        queues.write().insert(key, capacity)
+    })
+}
+
+pub fn msg_remove(key: u32) -> SyscallResult<()> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let manager = get_process_manager();
+        let queues = manager.current().read().msg_queues();
+        let mut fx = MsgEffects::default();
+        let ret = queues.write().remove(key, &mut fx);
+        manager.apply_msg_effects(fx);
+        ret
+    })
+}
+
+/// Send a message, blocks up to `timeout` ms if the queue is full
+pub fn msg_send(key: u32, msg: Message, timeout: usize, context: &mut ProcessContext) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let manager = get_process_manager();
+        let pid = processor::get_pid();
+        let queues = manager.current().read().msg_queues();
+        let mut fx = MsgEffects::default();
+        // Ok(true) 表示队列已满，需要阻塞
+        let ret = queues.write().get_mut(key).and_then(|queue| match queue.send(msg, &mut fx) {
+            Ok(()) => Ok(false),
+            Err(_) if timeout == 0 => Err(Errno::EAGAIN),
+            Err(msg) => {
+                queue.wait_send(pid, msg);
+                Ok(true)
+            }
+        });
+        manager.apply_msg_effects(fx);
+
+        match ret {
+            Ok(true) => {
+                // 消息被接收者取走空间后放入队列，由接收者唤醒
+                manager.save_current(context);
+                manager.block_on(pid, WaitObject::MsgQueue(key), deadline(timeout));
+                manager.switch_next(context);
+            }
+            ret => context.set_rax(Errno::encode(ret.map(|_| 0))),
+        }
+    })
+}
+
+/// Receive a message, blocks up to `timeout` ms if there is none
+pub fn msg_recv(key: u32, receiver: Receiver, timeout: usize, context: &mut ProcessContext) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let manager = get_process_manager();
+        let queues = manager.current().read().msg_queues();
+        let mut fx = MsgEffects::default();
+        let ret = queues.write().get_mut(key).and_then(|queue| {
+            match queue.receive(receiver.mtype, receiver.len, &mut fx)? {
+                Some(msg) => Ok(Some(msg)),
+                None if timeout == 0 => Err(Errno::ENOMSG),
+                None => {
+                    queue.wait_receive(receiver);
+                    Ok(None)
+                }
+            }
+        });
+        manager.apply_msg_effects(fx);
+
+        match ret {
+            Ok(Some(msg)) => {
+                let ret = manager.deliver_message(&receiver, msg);
+                context.set_rax(Errno::encode(ret));
+            }
+            Ok(None) => {
+                // 由发送者直接把消息写入缓冲区并唤醒
+                manager.save_current(context);
+                manager.block_on(receiver.pid, WaitObject::MsgQueue(key), deadline(timeout));
+                manager.switch_next(context);
+            }
+            Err(err) => context.set_rax(Errno::encode(Err(err))),
+        }
+    })
+}
+
+/// Block the current process while the futex at `addr` equals `expected`
+pub fn futex_wait(addr: usize, expected: u32, timeout: usize, context: &mut ProcessContext) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let manager = get_process_manager();
+        let pid = processor::get_pid();
+        let ret = futex::wait(&manager.current().read().vm().page_table, addr, expected, pid);
+        match ret {
+            Ok(key) => {
+                // 被唤醒时返回 0，超时返回 ETIMEDOUT
+                manager.save_current(context);
+                manager.block_on(pid, WaitObject::Futex(key), deadline(timeout));
+                manager.switch_next(context);
+            }
+            Err(err) => context.set_rax(Errno::encode(Err(err))),
+        }
+    })
+}
+
+/// Wake up at most `count` processes waiting on the futex at `addr`
+pub fn futex_wake(addr: usize, count: usize) -> SyscallResult {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let manager = get_process_manager();
+        let woken = futex::wake(&manager.current().read().vm().page_table, addr, count)?;
+        for pid in woken.iter() {
+            manager.wake_up(*pid, Some(0));
+        }
+        Ok(woken.len())
+    })
+}
 
 // 0x07 add: brk
 pub fn brk(addr: Option<VirtAddr>) -> Option<VirtAddr> {
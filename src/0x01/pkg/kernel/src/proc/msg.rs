@@ -0,0 +1,179 @@
+//! Message queues
+//!
+//! Queues are keyed by `u32` like semaphores and shared by forked processes.
+//! A message has a positive type and at most `MSG_MAX_SIZE` bytes of data,
+//! receivers may ask for the first message of a given type.
+//!
+//! Blocked processes are recorded in the queue with what they are waiting
+//! for, and their operation is completed by the process unblocking them:
+//!
+//! - a receiver blocked on an empty queue is handed the message by the sender
+//! - a sender blocked on a full queue leaves its message here, which is moved
+//!   into the queue once a message is received
+
+use super::ProcessId;
+use alloc::collections::*;
+use alloc::vec::Vec;
+use syscall_def::{Errno, SyscallResult, MSG_ANY_TYPE, MSG_QUEUE_MAX_LEN};
+
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub mtype: usize,
+    pub data: Vec<u8>,
+}
+
+/// A receiver blocked on an empty queue, with the user buffers to fill
+#[derive(Debug, Clone, Copy)]
+pub struct Receiver {
+    pub pid: ProcessId,
+    /// 期望的消息类型，`MSG_ANY_TYPE` 表示任意类型
+    pub mtype: usize,
+    pub buf: usize,
+    pub len: usize,
+    pub mtype_out: usize,
+}
+
+impl Receiver {
+    fn accepts(&self, mtype: usize) -> bool {
+        self.mtype == MSG_ANY_TYPE || self.mtype == mtype
+    }
+}
+
+/// Blocked processes to wake up, applied after the queues are unlocked
+#[derive(Debug, Default)]
+pub struct MsgEffects {
+    /// receivers handed a message, or an error
+    pub deliver: Vec<(Receiver, SyscallResult<Message>)>,
+    /// senders whose message is now queued
+    pub sent: Vec<ProcessId>,
+    /// processes whose wait failed, e.g. the queue is removed
+    pub failed: Vec<(ProcessId, Errno)>,
+}
+
+#[derive(Debug)]
+pub struct MessageQueue {
+    capacity: usize,
+    messages: VecDeque<Message>,
+    senders: VecDeque<(ProcessId, Message)>,
+    receivers: VecDeque<Receiver>,
+}
+
+impl MessageQueue {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            messages: VecDeque::new(),
+            senders: VecDeque::new(),
+            receivers: VecDeque::new(),
+        }
+    }
+
+    /// Hand the message to a waiting receiver, or queue it
+    ///
+    /// returns the message back if the queue is full
+    pub fn send(&mut self, msg: Message, fx: &mut MsgEffects) -> Result<(), Message> {
+        while let Some(idx) = self.receivers.iter().position(|r| r.accepts(msg.mtype)) {
+            let receiver = self.receivers.remove(idx).unwrap();
+            if msg.data.len() > receiver.len {
+                // 与非阻塞接收一致：缓冲区不足时接收者失败，消息留给其他接收者
+                fx.deliver.push((receiver, Err(Errno::E2BIG)));
+                continue;
+            }
+            fx.deliver.push((receiver, Ok(msg)));
+            return Ok(());
+        }
+
+        if self.messages.len() >= self.capacity {
+            return Err(msg);
+        }
+        self.messages.push_back(msg);
+        Ok(())
+    }
+
+    /// Receive the first message accepted by `mtype`
+    ///
+    /// returns `None` if there is no such message, or `E2BIG` if it is larger than `len`
+    pub fn receive(
+        &mut self,
+        mtype: usize,
+        len: usize,
+        fx: &mut MsgEffects,
+    ) -> SyscallResult<Option<Message>> {
+        let accepts = |msg: &Message| mtype == MSG_ANY_TYPE || msg.mtype == mtype;
+        let Some(idx) = self.messages.iter().position(accepts) else {
+            return Ok(None);
+        };
+        if self.messages[idx].data.len() > len {
+            return Err(Errno::E2BIG);
+        }
+
+        let msg = self.messages.remove(idx).unwrap();
+
+        // 腾出了空间，依次放入阻塞的发送者的消息
+        while self.messages.len() < self.capacity {
+            let Some((pid, msg)) = self.senders.pop_front() else {
+                break;
+            };
+            // 队列未满，必然成功
+            let _ = self.send(msg, fx);
+            fx.sent.push(pid);
+        }
+
+        Ok(Some(msg))
+    }
+
+    pub fn wait_send(&mut self, pid: ProcessId, msg: Message) {
+        self.senders.push_back((pid, msg));
+    }
+
+    pub fn wait_receive(&mut self, receiver: Receiver) {
+        self.receivers.push_back(receiver);
+    }
+
+    /// Remove a blocked process, e.g. on timeout or when it is killed
+    pub fn cancel(&mut self, pid: ProcessId) {
+        self.senders.retain(|(sender, _)| *sender != pid);
+        self.receivers.retain(|receiver| receiver.pid != pid);
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct MessageQueueSet {
+    queues: BTreeMap<u32, MessageQueue>,
+}
+
+impl MessageQueueSet {
+    pub fn insert(&mut self, key: u32, capacity: usize) -> SyscallResult<()> {
+        trace!("MsgQueue Insert: <{:#x}>{}", key, capacity);
+
+        if capacity == 0 || capacity > MSG_QUEUE_MAX_LEN {
+            return Err(Errno::EINVAL);
+        }
+        if self.queues.contains_key(&key) {
+            return Err(Errno::EEXIST);
+        }
+        self.queues.insert(key, MessageQueue::new(capacity));
+        Ok(())
+    }
+
+    /// Remove the queue, all blocked processes fail with `EIDRM`
+    pub fn remove(&mut self, key: u32, fx: &mut MsgEffects) -> SyscallResult<()> {
+        trace!("MsgQueue Remove: <{:#x}>", key);
+
+        let queue = self.queues.remove(&key).ok_or(Errno::ENOENT)?;
+        let senders = queue.senders.into_iter().map(|(pid, _)| pid);
+        let receivers = queue.receivers.into_iter().map(|receiver| receiver.pid);
+        fx.failed.extend(senders.chain(receivers).map(|pid| (pid, Errno::EIDRM)));
+        Ok(())
+    }
+
+    pub fn get_mut(&mut self, key: u32) -> SyscallResult<&mut MessageQueue> {
+        self.queues.get_mut(&key).ok_or(Errno::ENOENT)
+    }
+
+    pub fn cancel(&mut self, key: u32, pid: ProcessId) {
+        if let Some(queue) = self.queues.get_mut(&key) {
+            queue.cancel(pid);
+        }
+    }
+}
@@ -90,7 +90,11 @@ impl Process {
             ret
         );
 
+        // 释放 fd 可能唤醒其他进程（例如管道的另一端），需要先释放锁
+        let data = inner.proc_data.take();
         inner.kill(ret);
+        drop(inner);
+        drop(data);
     }
 
     pub fn alloc_init_stack(&self) -> VirtAddr {
@@ -139,6 +143,14 @@ impl ProcessInner {
         self.ticks_passed += 1;
     }
 
+    pub fn ticks_passed(&self) -> usize {
+        self.ticks_passed
+    }
+
+    pub fn children(&self) -> Vec<ProcessId> {
+        self.children.iter().map(|child| child.pid).collect()
+    }
+
     pub fn status(&self) -> ProgramStatus {
         self.status
     }
@@ -243,7 +255,8 @@ impl ProcessInner {
         // FIXME: set the return value 0 for child with `context.set_rax`
         child_context.set_rax(0);
         // FIXME: clone the process data struct
-        let child_data = self.proc_data.clone();
+        // 描述符表被复制，而不是与父进程共享
+        let child_data = self.proc_data.as_ref().map(ProcessData::fork);
         // FIXME: construct the child process inner
         // NOTE: return inner because there's no pid record in inner
         Self {
@@ -267,27 +280,23 @@ impl ProcessInner {
         self.context.set_rax(ret);
     } // 添加一个方法便于manager.rs的wake_up中可以直接写
 
-    // 0x05: 信号量相关的接口
-    pub fn sem_init(&mut self, key: u32, value: usize) -> bool {
-        self.proc_data.as_mut().unwrap().sem_init(key, value)
-    }
-
-    pub fn sem_remove(&mut self, key: u32) -> bool {
-        self.proc_data.as_mut().unwrap().sem_remove(key)
-    }
-
-    pub fn sem_wait(&mut self, key: u32, pid: ProcessId) -> SemaphoreResult {
-        self.proc_data.as_mut().unwrap().sem_wait(key, pid)
-    }
-
-    pub fn sem_signal(&mut self, key: u32) -> SemaphoreResult {
-        self.proc_data.as_mut().unwrap().sem_signal(key)
-    }
-
     // 0x07 add: brk的逐层调用
     pub fn brk(&self, addr: Option<VirtAddr>) -> Option<VirtAddr>{
         self.proc_vm.as_ref().unwrap().brk(addr)
     }
+
+    /// Write the exit code of a waited process to `addr` of this process
+    pub fn write_exit_code(&self, addr: VirtAddr, code: isize) {
+        if addr.is_null() {
+            return;
+        }
+        if let Some(vm) = self.proc_vm.as_ref() {
+            // 等待者的页表此时可能并未加载，通过其页表转换后写入
+            if let Err(e) = vm.page_table.write_to_user(addr.as_u64() as usize, &code) {
+                warn!("Failed to write exit code to {:#x}: {}", addr, e);
+            }
+        }
+    }
 }
 
 impl core::ops::Deref for Process {
@@ -0,0 +1,379 @@
+//! A synthetic filesystem exposing the kernel state, mounted at `/proc`
+//!
+//! ```text
+//! /proc
+//! ├── <pid>
+//! │   ├── status
+//! │   └── maps
+//! ├── cpuinfo
+//! ├── meminfo
+//! └── uptime
+//! ```
+//!
+//! file contents are generated when opened, their length is reported as 0
+
+use super::*;
+use crate::interrupt::clock::{self, TICK_NANOS};
+use crate::memory::allocator::ALLOCATOR;
+use crate::memory::get_frame_alloc_for_sure;
+use alloc::boxed::Box;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::fmt::Write as _;
+use storage::{
+    FileHandle, FileSystem, FileType, FsError, FsResult, Metadata, Read, Seek, SeekFrom, Write,
+};
+use x86::cpuid::CpuId;
+
+/// Files directly under `/proc`
+const GLOBAL_FILES: [&str; 3] = ["cpuinfo", "meminfo", "uptime"];
+
+/// Files under `/proc/<pid>`
+const PROCESS_FILES: [&str; 2] = ["status", "maps"];
+
+#[derive(Debug, Default)]
+pub struct ProcFs;
+
+/// What a path of procfs refers to
+enum Node {
+    Root,
+    Process(ProcessId),
+    Global(&'static str),
+    ProcessFile(ProcessId, &'static str),
+}
+
+impl Node {
+    fn lookup(path: &str) -> FsResult<Self> {
+        let mut parts = path.split('/').filter(|part| !part.is_empty());
+        let node = match (parts.next(), parts.next()) {
+            (None, _) => Node::Root,
+            (Some(name), None) => match GLOBAL_FILES.iter().find(|&&file| file == name) {
+                Some(file) => Node::Global(file),
+                None => Node::Process(alive_pid(name)?),
+            },
+            (Some(pid), Some(name)) => {
+                let pid = alive_pid(pid)?;
+                let file = PROCESS_FILES
+                    .iter()
+                    .find(|&&file| file == name)
+                    .ok_or(FsError::FileNotFound)?;
+                Node::ProcessFile(pid, file)
+            }
+        };
+
+        if parts.next().is_some() {
+            return Err(FsError::FileNotFound);
+        }
+        Ok(node)
+    }
+
+    fn metadata(&self) -> Metadata {
+        match self {
+            Node::Root => dir_meta(String::from("/")),
+            Node::Process(pid) => dir_meta(pid.0.to_string()),
+            Node::Global(name) | Node::ProcessFile(_, name) => file_meta(name),
+        }
+    }
+
+    fn contents(&self) -> FsResult<String> {
+        match *self {
+            Node::Root | Node::Process(_) => Err(FsError::NotAFile),
+            Node::Global("cpuinfo") => Ok(cpuinfo()),
+            Node::Global("meminfo") => Ok(meminfo()),
+            Node::Global(_) => Ok(uptime()),
+            Node::ProcessFile(pid, "status") => status(pid),
+            Node::ProcessFile(pid, _) => maps(pid),
+        }
+    }
+}
+
+/// Parse `name` as the pid of a process not dead yet
+fn alive_pid(name: &str) -> FsResult<ProcessId> {
+    let pid = ProcessId(name.parse().map_err(|_| FsError::FileNotFound)?);
+    get_process_manager()
+        .get_proc(&pid)
+        .filter(|proc| !proc.read().is_dead())
+        .map(|_| pid)
+        .ok_or(FsError::FileNotFound)
+}
+
+fn dir_meta(name: String) -> Metadata {
+    Metadata::new(name, FileType::Directory, 0, None, None, None)
+}
+
+fn file_meta(name: &str) -> Metadata {
+    Metadata::new(String::from(name), FileType::File, 0, None, None, None)
+}
+
+impl FileSystem for ProcFs {
+    fn read_dir(&self, path: &str) -> FsResult<Box<dyn Iterator<Item = Metadata> + Send>> {
+        let entries: Vec<Metadata> = match Node::lookup(path)? {
+            Node::Root => GLOBAL_FILES
+                .iter()
+                .map(|name| file_meta(name))
+                .chain(
+                    get_process_manager()
+                        .alive_pids()
+                        .into_iter()
+                        .map(|pid| dir_meta(pid.0.to_string())),
+                )
+                .collect(),
+            Node::Process(_) => PROCESS_FILES.iter().map(|name| file_meta(name)).collect(),
+            Node::Global(_) | Node::ProcessFile(..) => return Err(FsError::NotADirectory),
+        };
+        Ok(Box::new(entries.into_iter()))
+    }
+
+    fn open_file(&self, path: &str) -> FsResult<FileHandle> {
+        let node = Node::lookup(path)?;
+        let data = node.contents()?.into_bytes();
+        Ok(FileHandle::new(
+            node.metadata(),
+            Box::new(ProcFile { data, offset: 0 }),
+        ))
+    }
+
+    fn metadata(&self, path: &str) -> FsResult<Metadata> {
+        Node::lookup(path).map(|node| node.metadata())
+    }
+
+    fn exists(&self, path: &str) -> FsResult<bool> {
+        Ok(Node::lookup(path).is_ok())
+    }
+
+    fn create_file(&self, _path: &str) -> FsResult<FileHandle> {
+        Err(FsError::ReadOnly)
+    }
+
+    fn append_file(&self, _path: &str) -> FsResult<FileHandle> {
+        Err(FsError::ReadOnly)
+    }
+
+    fn create_dir(&self, _path: &str) -> FsResult {
+        Err(FsError::ReadOnly)
+    }
+
+    fn remove_file(&self, _path: &str) -> FsResult {
+        Err(FsError::ReadOnly)
+    }
+
+    fn remove_dir(&self, _path: &str) -> FsResult {
+        Err(FsError::ReadOnly)
+    }
+
+    fn copy_file(&self, _src: &str, _dst: &str) -> FsResult {
+        Err(FsError::ReadOnly)
+    }
+
+    fn move_file(&self, _src: &str, _dst: &str) -> FsResult {
+        Err(FsError::ReadOnly)
+    }
+
+    fn move_dir(&self, _src: &str, _dst: &str) -> FsResult {
+        Err(FsError::ReadOnly)
+    }
+}
+
+/// A snapshot of a procfs file taken when it is opened
+struct ProcFile {
+    data: Vec<u8>,
+    offset: usize,
+}
+
+impl Read for ProcFile {
+    fn read(&mut self, buf: &mut [u8]) -> FsResult<usize> {
+        let remain = &self.data[self.offset..];
+        let len = remain.len().min(buf.len());
+        buf[..len].copy_from_slice(&remain[..len]);
+        self.offset += len;
+        Ok(len)
+    }
+}
+
+impl Write for ProcFile {
+    fn write(&mut self, _buf: &[u8]) -> FsResult<usize> {
+        Err(FsError::ReadOnly)
+    }
+
+    fn flush(&mut self) -> FsResult {
+        Ok(())
+    }
+}
+
+impl Seek for ProcFile {
+    fn seek(&mut self, pos: SeekFrom) -> FsResult<usize> {
+        let offset = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.data.len().checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.offset.checked_add_signed(offset),
+        };
+        match offset {
+            Some(offset) if offset <= self.data.len() => {
+                self.offset = offset;
+                Ok(offset)
+            }
+            _ => Err(FsError::InvalidOffset),
+        }
+    }
+}
+
+/// `ticks` of the clock interrupt as seconds, e.g. `12.34`
+fn ticks_to_secs(ticks: u64) -> String {
+    let centis = ticks * TICK_NANOS / 10_000_000;
+    format!("{}.{:02}", centis / 100, centis % 100)
+}
+
+/// Time since boot and time spent by the kernel process (idle), in seconds
+fn uptime() -> String {
+    let idle = get_process_manager()
+        .get_proc(&KERNEL_PID)
+        .map_or(0, |proc| proc.read().ticks_passed());
+    format!(
+        "{} {}\n",
+        ticks_to_secs(clock::read_counter()),
+        ticks_to_secs(idle as u64)
+    )
+}
+
+fn meminfo() -> String {
+    let frames = get_frame_alloc_for_sure();
+    let frames_total = frames.frames_total();
+    let frames_used = frames.frames_used() - frames.frames_recycled();
+    drop(frames);
+
+    // 格式化会分配内存，必须先释放堆分配器的锁
+    let heap = ALLOCATOR.lock();
+    let (heap_total, heap_used) = (heap.size(), heap.used());
+    drop(heap);
+
+    let kib = |bytes: usize| bytes / 1024;
+    let frame_kib = |frames: usize| kib(frames * PAGE_SIZE as usize);
+
+    let mut info = String::new();
+    let _ = writeln!(info, "MemTotal:       {:>8} kB", frame_kib(frames_total));
+    let _ = writeln!(info, "MemUsed:        {:>8} kB", frame_kib(frames_used));
+    let _ = writeln!(
+        info,
+        "MemFree:        {:>8} kB",
+        frame_kib(frames_total.saturating_sub(frames_used))
+    );
+    let _ = writeln!(info, "KernelHeap:     {:>8} kB", kib(heap_total));
+    let _ = writeln!(info, "KernelHeapUsed: {:>8} kB", kib(heap_used));
+    let _ = writeln!(info, "KernelHeapFree: {:>8} kB", kib(heap_total - heap_used));
+    info
+}
+
+fn cpuinfo() -> String {
+    let cpuid = CpuId::new();
+    let mut info = String::new();
+
+    if let Some(features) = cpuid.get_feature_info() {
+        let _ = writeln!(info, "processor\t: {}", features.initial_local_apic_id());
+    }
+    if let Some(vendor) = cpuid.get_vendor_info() {
+        let _ = writeln!(info, "vendor_id\t: {}", vendor.as_str());
+    }
+    if let Some(brand) = cpuid.get_processor_brand_string() {
+        let _ = writeln!(info, "model name\t: {}", brand.as_str().trim());
+    }
+    if let Some(features) = cpuid.get_feature_info() {
+        let _ = writeln!(info, "cpu family\t: {}", features.family_id());
+        let _ = writeln!(info, "model\t\t: {}", features.model_id());
+        let _ = writeln!(info, "stepping\t: {}", features.stepping_id());
+
+        let flags = [
+            ("fpu", features.has_fpu()),
+            ("tsc", features.has_tsc()),
+            ("msr", features.has_msr()),
+            ("pae", features.has_pae()),
+            ("apic", features.has_apic()),
+            ("pge", features.has_pge()),
+            ("sse", features.has_sse()),
+            ("sse2", features.has_sse2()),
+            ("sse3", features.has_sse3()),
+            ("ssse3", features.has_ssse3()),
+            ("sse4_1", features.has_sse41()),
+            ("sse4_2", features.has_sse42()),
+            ("x2apic", features.has_x2apic()),
+            ("popcnt", features.has_popcnt()),
+            ("avx", features.has_avx()),
+            ("rdrand", features.has_rdrand()),
+            ("hypervisor", features.has_hypervisor()),
+        ];
+        let flags: Vec<&str> = flags
+            .iter()
+            .filter(|(_, has)| *has)
+            .map(|(flag, _)| *flag)
+            .collect();
+        let _ = writeln!(info, "flags\t\t: {}", flags.join(" "));
+    }
+    info
+}
+
+fn status(pid: ProcessId) -> FsResult<String> {
+    let proc = get_process_manager()
+        .get_proc(&pid)
+        .ok_or(FsError::FileNotFound)?;
+    let inner = proc.read();
+    if inner.is_dead() {
+        return Err(FsError::FileNotFound);
+    }
+
+    let children: Vec<String> = inner
+        .children()
+        .iter()
+        .map(|child| child.0.to_string())
+        .collect();
+
+    let mut info = String::new();
+    let _ = writeln!(info, "Name:\t{}", inner.name());
+    let _ = writeln!(info, "State:\t{:?}", inner.status());
+    let _ = writeln!(info, "Pid:\t{}", pid.0);
+    let _ = writeln!(
+        info,
+        "PPid:\t{}",
+        inner.parent().map_or(0, |parent| parent.pid().0)
+    );
+    let _ = writeln!(info, "Children:\t{}", children.join(" "));
+    let _ = writeln!(info, "Ticks:\t{}", inner.ticks_passed());
+    let _ = writeln!(info, "Cwd:\t{}", inner.cwd());
+
+    // 各区域的大小，单位与 /proc/meminfo 一致
+    let regions = inner.vm().regions();
+    let size = |name: &str| -> u64 {
+        regions
+            .iter()
+            .filter(|region| name.is_empty() || region.name == name)
+            .map(|region| region.size())
+            .sum::<u64>()
+            / 1024
+    };
+    let _ = writeln!(info, "VmSize:\t{} kB", size(""));
+    let _ = writeln!(info, "VmCode:\t{} kB", size("code"));
+    let _ = writeln!(info, "VmHeap:\t{} kB", size("heap"));
+    let _ = writeln!(info, "VmStack:\t{} kB", size("stack"));
+    Ok(info)
+}
+
+/// One line per mapped region, `start-end name`
+fn maps(pid: ProcessId) -> FsResult<String> {
+    let proc = get_process_manager()
+        .get_proc(&pid)
+        .ok_or(FsError::FileNotFound)?;
+    let inner = proc.read();
+    if inner.is_dead() {
+        return Err(FsError::FileNotFound);
+    }
+
+    let mut info = String::new();
+    for region in inner.vm().regions() {
+        let _ = writeln!(
+            info,
+            "{:016x}-{:016x} {}",
+            region.start.as_u64(),
+            region.end.as_u64(),
+            region.name
+        );
+    }
+    Ok(info)
+}
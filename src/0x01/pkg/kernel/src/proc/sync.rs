@@ -1,9 +1,12 @@
 use super::ProcessId;
 use alloc::collections::*;
+use alloc::string::String;
+use alloc::vec::Vec;
 use spin::Mutex;
+use syscall_def::{Errno, SemFlags, SyscallResult};
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
-pub struct SemaphoreId(u32);
+pub struct SemaphoreId(pub u32);
 
 impl SemaphoreId {
     pub fn new(key: u32) -> Self {
@@ -16,6 +19,10 @@ impl SemaphoreId {
 pub struct Semaphore {
     count: usize,
     wait_queue: VecDeque<ProcessId>,
+    /// 以 `SemFlags::MUTEX` 创建的互斥锁，只有这种信号量记录持有者
+    mutex: bool,
+    /// 已获取（尚未释放）信号量的进程，用于死锁检测
+    holders: Vec<ProcessId>,
 }
 
 /// Semaphore result
@@ -25,14 +32,18 @@ pub enum SemaphoreResult {
     NotExist,
     Block(ProcessId),
     WakeUp(ProcessId),
+    /// The semaphore is a mutex not held by the signaling process
+    NotHolder,
 }
 
 impl Semaphore {
-    /// Create a new semaphore
-    pub fn new(value: usize) -> Self {
+    /// Create a new semaphore, a mutex if `mutex` is set
+    pub fn new(value: usize, mutex: bool) -> Self {
         Self {
             count: value,
             wait_queue: VecDeque::new(),
+            mutex,
+            holders: Vec::new(),
         }
     }
 
@@ -51,22 +62,76 @@ impl Semaphore {
         // FIXME: else decrease the count and return Ok
         else {
             self.count -= 1;
+            self.acquire(pid);
             return SemaphoreResult::Ok;
         } // 否则信号量--，满足对应需求
     }
 
+    /// Decrease the count if it is not 0, never blocks
+    pub fn try_wait(&mut self, pid: ProcessId) -> bool {
+        if self.count == 0 {
+            return false;
+        }
+        self.count -= 1;
+        self.acquire(pid);
+        true
+    }
+
+    /// Processes which acquired the semaphore and have not signaled it yet
+    ///
+    /// only known for mutexes: a counting semaphore is usually signaled by
+    /// other processes (e.g. a producer makes room for itself by waiting for
+    /// a consumer), so its waiters do not wait for the processes which acquired it
+    pub fn holders(&self) -> &[ProcessId] {
+        &self.holders
+    }
+
+    fn acquire(&mut self, pid: ProcessId) {
+        if self.mutex {
+            self.holders.push(pid);
+        }
+    }
+
+    /// Release one acquisition of `pid`, fails if `pid` does not hold the mutex
+    fn release(&mut self, pid: ProcessId) -> bool {
+        if !self.mutex {
+            return true;
+        }
+        match self.holders.iter().position(|holder| *holder == pid) {
+            Some(idx) => {
+                self.holders.remove(idx);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Forget all acquisitions of `pid`, e.g. when it is killed
+    pub fn remove_holder(&mut self, pid: ProcessId) {
+        self.holders.retain(|holder| *holder != pid);
+    }
+
+    /// Remove a process from the wait queue
+    pub fn cancel(&mut self, pid: ProcessId) {
+        self.wait_queue.retain(|waiter| *waiter != pid);
+    }
+
     /// Signal the semaphore (release/up/verhogen)
     ///
     /// if the wait queue is not empty, then pop a process from the wait queue
     /// else increase the count
-    pub fn signal(&mut self) -> SemaphoreResult {
+    pub fn signal(&mut self, pid: ProcessId) -> SemaphoreResult {
         // FIXME: if the wait queue is not empty
         //          pop a process from the wait queue
         //          return WakeUp(pid)
-        
+        if !self.release(pid) {
+            return SemaphoreResult::NotHolder;
+        }
 
         if !self.wait_queue.is_empty() {
             let pid = self.wait_queue.pop_front().unwrap();
+            // 信号量直接移交给被唤醒的进程
+            self.acquire(pid);
             return SemaphoreResult::WakeUp(pid);
         } // 唤醒等待队列第一个进程
         // FIXME: else increase the count and return Ok
@@ -77,65 +142,244 @@ impl Semaphore {
     }
 }
 
-#[derive(Debug, Default)]
+/// A semaphore in the global namespace
+#[derive(Debug)]
+struct NamedSemaphore {
+    name: String,
+    /// 打开该信号量的进程数，为 0 时删除
+    refs: usize,
+    sem: Semaphore,
+}
+
+/// The global namespace of named semaphores
+#[derive(Debug)]
 pub struct SemaphoreSet {
-    sems: BTreeMap<SemaphoreId, Mutex<Semaphore>>,
+    names: BTreeMap<String, SemaphoreId>,
+    sems: BTreeMap<SemaphoreId, NamedSemaphore>,
+    next_id: u32,
 }
 
+pub static SEMAPHORES: Mutex<SemaphoreSet> = Mutex::new(SemaphoreSet::new());
+
 impl SemaphoreSet {
-    pub fn insert(&mut self, key: u32, value: usize) -> bool {
-        trace!("Sem Insert: <{:#x}>{}", key, value);
+    const fn new() -> Self {
+        Self {
+            names: BTreeMap::new(),
+            sems: BTreeMap::new(),
+            // id 0 保留，用户库以此表示未打开
+            next_id: 1,
+        }
+    }
+
+    /// Open the semaphore `name` and take a reference
+    ///
+    /// it is created with `value` if `SemFlags::CREATE` is set,
+    /// as a mutex if `SemFlags::MUTEX` is also set
+    pub fn open(&mut self, name: &str, value: usize, flags: SemFlags) -> SyscallResult<SemaphoreId> {
+        if let Some(&id) = self.names.get(name) {
+            if flags.contains(SemFlags::CREATE | SemFlags::EXCL) {
+                return Err(Errno::EEXIST);
+            }
+            self.retain(id);
+            return Ok(id);
+        }
+
+        if !flags.contains(SemFlags::CREATE) {
+            return Err(Errno::ENOENT);
+        }
+
+        trace!("Sem Insert: <{}>{}", name, value);
 
-        // FIXME: insert a new semaphore into the sems
-        //          use `insert(/* ... */).is_none()`
-        return self.sems.insert(SemaphoreId::new(key), Mutex::new(Semaphore::new(value))).is_none();
+        let id = SemaphoreId::new(self.next_id);
+        self.next_id += 1;
+        self.names.insert(name.into(), id);
+        self.sems.insert(
+            id,
+            NamedSemaphore {
+                name: name.into(),
+                refs: 1,
+                sem: Semaphore::new(value, flags.contains(SemFlags::MUTEX)),
+            },
+        );
+        Ok(id)
     }
 
-    pub fn remove(&mut self, key: u32) -> bool {
-        trace!("Sem Remove: <{:#x}>", key);
+    fn retain(&mut self, id: SemaphoreId) {
+        if let Some(named) = self.sems.get_mut(&id) {
+            named.refs += 1;
+        }
+    }
 
-        // FIXME: remove the semaphore from the sems
-        //          use `remove(/* ... */).is_some()`
-        return self.sems.remove(&SemaphoreId::new(key)).is_some();
+    /// Drop a reference, the semaphore is removed after the last one
+    fn close(&mut self, id: SemaphoreId) {
+        let Some(named) = self.sems.get_mut(&id) else {
+            return;
+        };
+        named.refs -= 1;
+        if named.refs == 0 {
+            trace!("Sem Remove: <{}>", named.name);
+            let named = self.sems.remove(&id).unwrap();
+            self.names.remove(&named.name);
+        }
     }
 
     /// Wait the semaphore (acquire/down/proberen)
-    pub fn wait(&self, key: u32, pid: ProcessId) -> SemaphoreResult {
-        let sid = SemaphoreId::new(key);
-
-        // FIXME: try get the semaphore from the sems
-        //         then do it's operation
-        match self.sems.get(&sid) {
-            Some(sem) => {
-                sem.lock().wait(pid) // 调用上述的wait函数
-            },
-            None => {
-                SemaphoreResult::NotExist
-            }
+    pub fn wait(&mut self, id: SemaphoreId, pid: ProcessId) -> SemaphoreResult {
+        match self.sems.get_mut(&id) {
+            Some(named) => named.sem.wait(pid),
+            None => SemaphoreResult::NotExist,
+        }
+    }
+
+    /// Wait the semaphore without blocking, returns `EAGAIN` if the count is 0
+    pub fn try_wait(&mut self, id: SemaphoreId, pid: ProcessId) -> SyscallResult<()> {
+        let named = self.sems.get_mut(&id).ok_or(Errno::ENOENT)?;
+        if named.sem.try_wait(pid) {
+            Ok(())
+        } else {
+            Err(Errno::EAGAIN)
         }
-        // FIXME: return NotExist if the semaphore is not exist
     }
 
     /// Signal the semaphore (release/up/verhogen)
-    pub fn signal(&self, key: u32) -> SemaphoreResult {
-        let sid = SemaphoreId::new(key);
-
-        // FIXME: try get the semaphore from the sems
-        //         then do it's operation
-        match self.sems.get(&sid) {
-            Some(sem) => {
-                sem.lock().signal() // 调用上述的signal函数
-            },
-            None => {
-                SemaphoreResult::NotExist
-            }
+    pub fn signal(&mut self, id: SemaphoreId, pid: ProcessId) -> SemaphoreResult {
+        match self.sems.get_mut(&id) {
+            Some(named) => named.sem.signal(pid),
+            None => SemaphoreResult::NotExist,
+        }
+    }
+
+    /// Remove a waiting process, e.g. on timeout or when it is killed
+    pub fn cancel(&mut self, id: SemaphoreId, pid: ProcessId) {
+        if let Some(named) = self.sems.get_mut(&id) {
+            named.sem.cancel(pid);
+        }
+    }
+
+    /// Remove a process from the holders of the semaphore
+    pub fn remove_holder(&mut self, id: SemaphoreId, pid: ProcessId) {
+        if let Some(named) = self.sems.get_mut(&id) {
+            named.sem.remove_holder(pid);
+        }
+    }
+
+    pub fn name(&self, id: SemaphoreId) -> Option<&str> {
+        self.sems.get(&id).map(|named| named.name.as_str())
+    }
+
+    pub fn holders(&self, id: SemaphoreId) -> &[ProcessId] {
+        self.sems.get(&id).map_or(&[], |named| named.sem.holders())
+    }
+}
+
+/// Named semaphores opened by a process
+///
+/// a forked process takes its own references to the same semaphores,
+/// all of them are closed when the process exits
+#[derive(Debug, Default)]
+pub struct SemaphoreHandles {
+    ids: BTreeSet<SemaphoreId>,
+}
+
+impl SemaphoreHandles {
+    pub fn open(&mut self, name: &str, value: usize, flags: SemFlags) -> SyscallResult<SemaphoreId> {
+        let mut sems = SEMAPHORES.lock();
+        let id = sems.open(name, value, flags)?;
+        if !self.ids.insert(id) {
+            // 已经打开过，每个进程只持有一个引用
+            sems.close(id);
+        }
+        Ok(id)
+    }
+
+    pub fn close(&mut self, id: SemaphoreId) -> SyscallResult<()> {
+        if !self.ids.remove(&id) {
+            return Err(Errno::EINVAL);
+        }
+        SEMAPHORES.lock().close(id);
+        Ok(())
+    }
+
+    /// Remove `pid` from the holders of the opened semaphores
+    pub fn remove_holder(&self, pid: ProcessId) {
+        let mut sems = SEMAPHORES.lock();
+        for &id in self.ids.iter() {
+            sems.remove_holder(id, pid);
+        }
+    }
+
+    /// Check if the semaphore is opened by this process
+    pub fn check(&self, id: SemaphoreId) -> SyscallResult<()> {
+        if self.ids.contains(&id) {
+            Ok(())
+        } else {
+            Err(Errno::EINVAL)
+        }
+    }
+}
+
+impl Clone for SemaphoreHandles {
+    fn clone(&self) -> Self {
+        let mut sems = SEMAPHORES.lock();
+        for &id in self.ids.iter() {
+            sems.retain(id);
+        }
+        Self {
+            ids: self.ids.clone(),
+        }
+    }
+}
+
+impl Drop for SemaphoreHandles {
+    fn drop(&mut self) {
+        let mut sems = SEMAPHORES.lock();
+        for &id in self.ids.iter() {
+            sems.close(id);
         }
-        // FIXME: return NotExist if the semaphore is not exist
     }
 }
 
 impl core::fmt::Display for Semaphore {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        write!(f, "Semaphore({}) {:?}", self.count, self.wait_queue)
+        write!(
+            f,
+            "Semaphore({}) {:?} held by {:?}",
+            self.count, self.wait_queue, self.holders
+        )
+    }
+}
+
+/// Processes blocked until some event happens, e.g. input or pipe data
+///
+/// all waiters are woken up at once and should check the condition again,
+/// which is done by restarting the syscall
+#[derive(Debug, Default)]
+pub struct WaitQueue {
+    waiters: Mutex<VecDeque<ProcessId>>,
+}
+
+impl WaitQueue {
+    pub const fn new() -> Self {
+        Self {
+            waiters: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Queue the current process, the caller should block it afterwards
+    pub fn wait_current(&self) {
+        let pid = super::processor::get_pid();
+        let mut waiters = self.waiters.lock();
+        if !waiters.contains(&pid) {
+            waiters.push_back(pid);
+        }
+    }
+
+    /// Wake up all waiting processes
+    pub fn wake_all(&self) {
+        // 先释放锁再唤醒，唤醒过程中不会再访问该队列
+        let waiters: Vec<ProcessId> = self.waiters.lock().drain(..).collect();
+        for pid in waiters {
+            super::wake_up_blocked(pid);
+        }
     }
 }
@@ -0,0 +1,168 @@
+//! User memory access
+//!
+//! Pointers passed by syscalls come from user space and can not be trusted,
+//! every access goes through the page table of the target process:
+//!
+//! - the range must be below the user/kernel split
+//! - every page must be mapped with `USER_ACCESSIBLE` (and `WRITABLE` for writes)
+//!
+//! Stack pages of the current process that are not touched yet are mapped
+//! first, just like the page fault the process would take on them.
+//!
+//! The data is copied via the physical memory offset mapping, so it also works
+//! when the page table of the target process is not loaded.
+
+use super::*;
+use crate::memory::physical_to_virtual;
+use core::mem::{size_of, MaybeUninit};
+use x86_64::structures::paging::{mapper::TranslateResult, PageTableFlags, Translate};
+use x86_64::PhysAddr;
+
+/// The lower half of the virtual address space belongs to user processes
+pub const USER_SPACE_END: u64 = 0x0000_8000_0000_0000;
+
+impl PageTableContext {
+    /// Translate a user address, requiring it to be accessible from ring 3
+    pub fn translate_user(&self, addr: u64, write: bool) -> SyscallResult<PhysAddr> {
+        let mut required = PageTableFlags::PRESENT | PageTableFlags::USER_ACCESSIBLE;
+        if write {
+            required |= PageTableFlags::WRITABLE;
+        }
+
+        match self.mapper().translate(VirtAddr::new_truncate(addr)) {
+            TranslateResult::Mapped {
+                frame,
+                offset,
+                flags,
+            } if flags.contains(required) => Ok(frame.start_address() + offset),
+            _ => Err(Errno::EFAULT),
+        }
+    }
+
+    /// Walk through `[addr, addr + len)` page by page
+    ///
+    /// `f` is called with the kernel pointer of each chunk and its offset in the range
+    fn walk_user(
+        &self,
+        addr: usize,
+        len: usize,
+        write: bool,
+        mut f: impl FnMut(*mut u8, usize, usize),
+    ) -> SyscallResult<()> {
+        let end = addr.checked_add(len).ok_or(Errno::EFAULT)?;
+        if end as u64 > USER_SPACE_END {
+            return Err(Errno::EFAULT);
+        }
+
+        let mut cur = addr;
+        while cur < end {
+            // 每次最多处理到当前页的末尾，保证物理地址连续
+            let page_left = PAGE_SIZE as usize - cur % PAGE_SIZE as usize;
+            let chunk = page_left.min(end - cur);
+            let phys = self.translate_user(cur as u64, write)?;
+            f(physical_to_virtual(phys.as_u64()) as *mut u8, cur - addr, chunk);
+            cur += chunk;
+        }
+
+        Ok(())
+    }
+
+    /// Check if `[addr, addr + len)` is accessible by the user process
+    pub fn check_user(&self, addr: usize, len: usize, write: bool) -> SyscallResult<()> {
+        self.walk_user(addr, len, write, |_, _, _| {})
+    }
+
+    /// Copy `dst.len()` bytes from user address `src`
+    pub fn copy_from_user(&self, dst: &mut [u8], src: usize) -> SyscallResult<()> {
+        self.walk_user(src, dst.len(), false, |ptr, offset, len| unsafe {
+            core::ptr::copy_nonoverlapping(ptr, dst[offset..].as_mut_ptr(), len);
+        })
+    }
+
+    /// Copy `src` to user address `dst`
+    pub fn copy_to_user(&self, dst: usize, src: &[u8]) -> SyscallResult<()> {
+        // 先检查整个范围，避免写入一半后才失败
+        self.check_user(dst, src.len(), true)?;
+        self.walk_user(dst, src.len(), true, |ptr, offset, len| unsafe {
+            core::ptr::copy_nonoverlapping(src[offset..].as_ptr(), ptr, len);
+        })
+    }
+
+    /// Read a plain value from user address `src`
+    ///
+    /// `T` must be valid for any bit pattern
+    pub fn read_from_user<T: Copy>(&self, src: usize) -> SyscallResult<T> {
+        let mut val = MaybeUninit::<T>::uninit();
+        let buf = unsafe {
+            core::slice::from_raw_parts_mut(val.as_mut_ptr() as *mut u8, size_of::<T>())
+        };
+        self.copy_from_user(buf, src)?;
+        Ok(unsafe { val.assume_init() })
+    }
+
+    /// Write a plain value to user address `dst`
+    pub fn write_to_user<T: Copy>(&self, dst: usize, val: &T) -> SyscallResult<()> {
+        let buf =
+            unsafe { core::slice::from_raw_parts(val as *const T as *const u8, size_of::<T>()) };
+        self.copy_to_user(dst, buf)
+    }
+
+    /// Copy a UTF-8 string of `len` bytes from user address `src`
+    pub fn str_from_user(&self, src: usize, len: usize) -> SyscallResult<String> {
+        let mut buf = alloc::vec![0u8; len];
+        self.copy_from_user(&mut buf, src)?;
+        String::from_utf8(buf).map_err(|_| Errno::EINVAL)
+    }
+}
+
+/// Run `f` with the page table of the current process to access `[addr, addr + len)`
+fn with_current_page_table<T>(
+    addr: usize,
+    len: usize,
+    f: impl FnOnce(&PageTableContext) -> T,
+) -> T {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let proc = get_process_manager().current();
+        let mut inner = proc.write();
+
+        // 超出用户空间的范围留给 walk_user 报错
+        if let Some(end) = addr.checked_add(len)
+            && end as u64 <= USER_SPACE_END
+        {
+            let vm = inner.vm_mut();
+            vm.fault_in_stack(VirtAddr::new(addr as u64), VirtAddr::new(end as u64));
+        }
+
+        f(&inner.vm().page_table)
+    })
+}
+
+/// Check if `[addr, addr + len)` is accessible by the current process
+pub fn check_user(addr: usize, len: usize, write: bool) -> SyscallResult<()> {
+    with_current_page_table(addr, len, |pt| pt.check_user(addr, len, write))
+}
+
+/// Copy `dst.len()` bytes from the current process
+pub fn copy_from_user(dst: &mut [u8], src: usize) -> SyscallResult<()> {
+    with_current_page_table(src, dst.len(), |pt| pt.copy_from_user(dst, src))
+}
+
+/// Copy `src` to the current process
+pub fn copy_to_user(dst: usize, src: &[u8]) -> SyscallResult<()> {
+    with_current_page_table(dst, src.len(), |pt| pt.copy_to_user(dst, src))
+}
+
+/// Read a plain value from the current process
+pub fn read_from_user<T: Copy>(src: usize) -> SyscallResult<T> {
+    with_current_page_table(src, size_of::<T>(), |pt| pt.read_from_user(src))
+}
+
+/// Write a plain value to the current process
+pub fn write_to_user<T: Copy>(dst: usize, val: &T) -> SyscallResult<()> {
+    with_current_page_table(dst, size_of::<T>(), |pt| pt.write_to_user(dst, val))
+}
+
+/// Copy a UTF-8 string (e.g. a path) from the current process
+pub fn str_from_user(src: usize, len: usize) -> SyscallResult<String> {
+    with_current_page_table(src, len, |pt| pt.str_from_user(src, len))
+}
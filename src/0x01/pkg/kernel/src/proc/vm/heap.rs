@@ -159,6 +159,15 @@ impl Heap {
     pub fn memory_usage(&self) -> u64 {
         self.end.load(Ordering::Relaxed) - self.base.as_u64()
     }
+
+    pub fn base(&self) -> VirtAddr {
+        self.base
+    }
+
+    /// The current end address of the heap
+    pub fn end(&self) -> VirtAddr {
+        VirtAddr::new(self.end.load(Ordering::Relaxed))
+    }
 }
 
 impl core::fmt::Debug for Heap {
@@ -26,6 +26,21 @@ use super::{PageTableContext, ProcessId};
 type MapperRef<'a> = &'a mut OffsetPageTable<'static>;
 type FrameAllocatorRef<'a> = &'a mut BootInfoFrameAllocator;
 
+/// A mapped region of a process, the range is [start, end)
+#[derive(Clone, Copy, Debug)]
+pub struct VmRegion {
+    pub start: VirtAddr,
+    pub end: VirtAddr,
+    /// What the region holds, e.g. `stack`
+    pub name: &'static str,
+}
+
+impl VmRegion {
+    pub fn size(&self) -> u64 {
+        self.end - self.start
+    }
+}
+
 pub struct ProcessVm {
     // page table is shared by parent and child
     pub(super) page_table: PageTableContext, // 使用paging.rs中的结构体
@@ -105,10 +120,47 @@ impl ProcessVm {
         self.stack.handle_page_fault(addr, mapper, alloc)
     }
 
+    /// Map the stack pages in `[start, end)` the process has not touched yet
+    pub fn fault_in_stack(&mut self, start: VirtAddr, end: VirtAddr) -> bool {
+        let mapper = &mut self.page_table.mapper();
+        let alloc = &mut *get_frame_alloc_for_sure();
+
+        self.stack.fault_in(start, end, mapper, alloc)
+    }
+
     pub(super) fn memory_usage(&self) -> u64 {
         self.stack.memory_usage()
     }
 
+    /// The mapped regions sorted by address, empty regions are skipped
+    ///
+    /// code is only recorded by the process which loaded it, not by forked children
+    pub fn regions(&self) -> Vec<VmRegion> {
+        let mut regions: Vec<VmRegion> = self
+            .code
+            .iter()
+            .map(|range| VmRegion {
+                start: range.start.start_address(),
+                end: range.end.start_address() + range.end.size(),
+                name: "code",
+            })
+            .collect();
+        regions.push(VmRegion {
+            start: self.heap.base(),
+            end: self.heap.end(),
+            name: "heap",
+        });
+        regions.push(VmRegion {
+            start: self.stack.range.start.start_address(),
+            end: self.stack.range.end.start_address(),
+            name: "stack",
+        });
+
+        regions.retain(|region| region.size() > 0);
+        regions.sort_by_key(|region| region.start);
+        regions
+    }
+
     // 0x05 add:
     pub fn stack_start(&self) -> VirtAddr {
         self.stack.range.start.start_address()
@@ -103,6 +103,26 @@ impl Stack {
         true
     }
 
+    /// Grow the stack over the part of `[start, end)` below the mapped pages
+    ///
+    /// the kernel accesses user buffers through the page table and never faults on
+    /// them, so stack pages the process has not touched yet are mapped here instead
+    pub fn fault_in(
+        &mut self,
+        start: VirtAddr,
+        end: VirtAddr,
+        mapper: MapperRef,
+        alloc: FrameAllocatorRef,
+    ) -> bool {
+        // 栈向下连续增长，只需处理范围内位于栈空间中的最低地址
+        let cur_stack_bot = self.range.start.start_address();
+        let lowest = start.max(VirtAddr::new(cur_stack_bot.as_u64() & STACK_START_MASK));
+        if lowest >= end || lowest >= cur_stack_bot {
+            return true;
+        }
+        self.handle_page_fault(lowest, mapper, alloc)
+    }
+
     fn is_on_stack(&self, addr: VirtAddr) -> bool {
         let addr = addr.as_u64();
         let cur_stack_bot = self.range.start.start_address().as_u64();
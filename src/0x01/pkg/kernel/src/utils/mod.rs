@@ -6,6 +6,7 @@ pub mod regs; // 在clock.rs中调用了regs.rs，所以在这里把它设为公
 use alloc::format;
 pub mod func;
 pub mod logger;
+pub mod pipe;
 pub mod resource;
 
 pub use macros::*;
@@ -0,0 +1,129 @@
+//! Anonymous pipes
+//!
+//! A pipe is a bounded ring buffer with a read end and a write end, each end
+//! is a `Resource` which can be shared by several fds via `dup` or `fork`.
+//! An end is closed when the last fd referring to it is closed.
+//!
+//! - reading an empty pipe returns `EAGAIN` (the caller blocks and retries),
+//!   or 0 (EOF) if the write end is closed
+//! - writing a full pipe returns `EAGAIN`, or `EPIPE` if the read end is closed
+//! - a write only stores what fits in the buffer and returns the written length
+
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use spin::Mutex;
+use syscall_def::{Errno, SyscallResult};
+
+use crate::proc::WaitQueue;
+
+/// Capacity of the pipe buffer
+pub const PIPE_BUF_SIZE: usize = 4096;
+
+#[derive(Debug)]
+struct PipeState {
+    buf: VecDeque<u8>,
+    read_closed: bool,
+    write_closed: bool,
+}
+
+#[derive(Debug)]
+struct Pipe {
+    state: Mutex<PipeState>,
+    /// 等待数据的读者
+    readers: WaitQueue,
+    /// 等待缓冲区空间的写者
+    writers: WaitQueue,
+}
+
+/// The read end of a pipe
+#[derive(Debug)]
+pub struct PipeReader(Arc<Pipe>);
+
+/// The write end of a pipe
+#[derive(Debug)]
+pub struct PipeWriter(Arc<Pipe>);
+
+/// Create a pipe, returns its read end and write end
+pub fn pipe() -> (PipeReader, PipeWriter) {
+    let pipe = Arc::new(Pipe {
+        state: Mutex::new(PipeState {
+            buf: VecDeque::with_capacity(PIPE_BUF_SIZE),
+            read_closed: false,
+            write_closed: false,
+        }),
+        readers: WaitQueue::new(),
+        writers: WaitQueue::new(),
+    });
+    (PipeReader(pipe.clone()), PipeWriter(pipe))
+}
+
+impl PipeReader {
+    pub fn read(&self, buf: &mut [u8]) -> SyscallResult {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let mut state = self.0.state.lock();
+        if state.buf.is_empty() {
+            return if state.write_closed { Ok(0) } else { Err(Errno::EAGAIN) };
+        }
+
+        let len = buf.len().min(state.buf.len());
+        for (dst, src) in buf.iter_mut().zip(state.buf.drain(..len)) {
+            *dst = src;
+        }
+        drop(state);
+
+        self.0.writers.wake_all();
+        Ok(len)
+    }
+
+    /// Queue the current process until the pipe has data
+    pub fn wait(&self) {
+        self.0.readers.wait_current();
+    }
+}
+
+impl PipeWriter {
+    pub fn write(&self, buf: &[u8]) -> SyscallResult {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let mut state = self.0.state.lock();
+        if state.read_closed {
+            return Err(Errno::EPIPE);
+        }
+
+        let len = buf.len().min(PIPE_BUF_SIZE - state.buf.len());
+        if len == 0 {
+            return Err(Errno::EAGAIN);
+        }
+        state.buf.extend(&buf[..len]);
+        drop(state);
+
+        self.0.readers.wake_all();
+        Ok(len)
+    }
+
+    /// Queue the current process until the pipe has free space
+    pub fn wait(&self) {
+        self.0.writers.wait_current();
+    }
+}
+
+impl Drop for PipeReader {
+    fn drop(&mut self) {
+        self.0.state.lock().read_closed = true;
+        // 阻塞的写者将得到 EPIPE
+        self.0.writers.wake_all();
+    }
+}
+
+impl Drop for PipeWriter {
+    fn drop(&mut self) {
+        self.0.state.lock().write_closed = true;
+        // 阻塞的读者将读到 EOF
+        self.0.readers.wake_all();
+    }
+}
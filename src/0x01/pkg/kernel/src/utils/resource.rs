@@ -1,8 +1,16 @@
 use alloc::string::String;
 use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
 use spin::Mutex;
-use crate::drivers::input::try_pop_key;
-use pc_keyboard::DecodedKey;
+use crate::drivers::devfs::CharDevice;
+use crate::drivers::tty;
+use crate::utils::pipe::{self, PipeReader, PipeWriter};
+use crate::filesystem;
+use storage::{FileHandle, SeekFrom};
+use syscall_def::{
+    Errno, FcntlCmd, FileKind, FileStat, IoctlRequest, OpenFlags, SeekWhence, SyscallResult,
+    FD_CLOEXEC,
+};
 
 #[derive(Debug, Clone)]
 pub enum StdIO {
@@ -11,9 +19,38 @@ pub enum StdIO {
     Stderr,
 }
 
-#[derive(Debug)]
+/// What a process blocked on a resource is waiting for
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum WaitFor {
+    Read,
+    Write,
+}
+
+/// Max number of open fds of a process
+pub const MAX_FDS: usize = 1024;
+
+/// An entry of the fd table
+///
+/// fds duplicated by `dup` or inherited by `fork` share the same resource
+#[derive(Debug, Clone)]
+struct FileDescriptor {
+    resource: Arc<Mutex<Resource>>,
+    cloexec: bool,
+}
+
+impl FileDescriptor {
+    fn new(resource: Arc<Mutex<Resource>>) -> Self {
+        Self {
+            resource,
+            cloexec: false,
+        }
+    }
+}
+
+/// The fd table of a process
+#[derive(Debug, Clone)]
 pub struct ResourceSet {
-    pub handles: BTreeMap<u8, Mutex<Resource>>,
+    handles: BTreeMap<usize, FileDescriptor>,
 }
 
 impl Default for ResourceSet {
@@ -22,87 +59,273 @@ impl Default for ResourceSet {
             handles: BTreeMap::new(),
         };
 
-        res.open(Resource::Console(StdIO::Stdin));
-        res.open(Resource::Console(StdIO::Stdout));
-        res.open(Resource::Console(StdIO::Stderr));
+        // 新的描述符表为空，以下必然成功
+        let _ = res.open(Resource::Console(StdIO::Stdin));
+        let _ = res.open(Resource::Console(StdIO::Stdout));
+        let _ = res.open(Resource::Console(StdIO::Stderr));
 
         res
     }
 }
 
 impl ResourceSet {
-    pub fn open(&mut self, res: Resource) -> u8 {
-        let fd = self.handles.len() as u8;
-        self.handles.insert(fd, Mutex::new(res));
-        fd
+    /// The fd table for a spawned process, fds with `FD_CLOEXEC` are not inherited
+    pub fn inherit(&self) -> Self {
+        Self {
+            handles: self
+                .handles
+                .iter()
+                .filter(|(_, desc)| !desc.cloexec)
+                .map(|(&fd, desc)| (fd, desc.clone()))
+                .collect(),
+        }
+    }
+
+    /// Find the lowest free fd not less than `from`
+    fn lowest_free(&self, from: usize) -> SyscallResult<usize> {
+        // BTreeMap 的键有序，找到第一个空位即可
+        let mut fd = from;
+        for &used in self.handles.range(from..).map(|(fd, _)| fd) {
+            if used != fd {
+                break;
+            }
+            fd += 1;
+        }
+        if fd < MAX_FDS { Ok(fd) } else { Err(Errno::EMFILE) }
+    }
+
+    fn get(&self, fd: usize) -> SyscallResult<&FileDescriptor> {
+        self.handles.get(&fd).ok_or(Errno::EBADF)
     }
 
-    pub fn close(&mut self, fd: u8) -> bool {
-        self.handles.remove(&fd).is_some()
+    pub fn open(&mut self, res: Resource) -> SyscallResult<usize> {
+        let fd = self.lowest_free(0)?;
+        self.handles
+            .insert(fd, FileDescriptor::new(Arc::new(Mutex::new(res))));
+        Ok(fd)
     }
 
-    pub fn read(&self, fd: u8, buf: &mut [u8]) -> isize {
-        if let Some(count) = self.handles.get(&fd).and_then(|h| h.lock().read(buf)) {
-            count as isize
-        } else {
-            -1
+    /// Create a pipe, returns its read fd and write fd
+    pub fn pipe(&mut self) -> SyscallResult<(usize, usize)> {
+        let (reader, writer) = pipe::pipe();
+        let read_fd = self.open(Resource::PipeRead(reader))?;
+        match self.open(Resource::PipeWrite(writer)) {
+            Ok(write_fd) => Ok((read_fd, write_fd)),
+            Err(err) => {
+                let _ = self.close(read_fd);
+                Err(err)
+            }
+        }
+    }
+
+    pub fn close(&mut self, fd: usize) -> SyscallResult<()> {
+        self.handles.remove(&fd).map(|_| ()).ok_or(Errno::EBADF)
+    }
+
+    pub fn dup(&mut self, fd: usize) -> SyscallResult<usize> {
+        self.dup_from(fd, 0)
+    }
+
+    /// Duplicate `fd` to the lowest free fd not less than `from`
+    fn dup_from(&mut self, fd: usize, from: usize) -> SyscallResult<usize> {
+        let resource = self.get(fd)?.resource.clone();
+        let new = self.lowest_free(from)?;
+        self.handles.insert(new, FileDescriptor::new(resource));
+        Ok(new)
+    }
+
+    pub fn dup2(&mut self, old: usize, new: usize) -> SyscallResult<usize> {
+        let resource = self.get(old)?.resource.clone();
+        if new >= MAX_FDS {
+            return Err(Errno::EBADF);
+        }
+        if old != new {
+            // 旧的 new 被直接替换（即关闭）
+            self.handles.insert(new, FileDescriptor::new(resource));
         }
+        Ok(new)
     }
 
-    pub fn write(&self, fd: u8, buf: &[u8]) -> isize {
-        if let Some(count) = self.handles.get(&fd).and_then(|h| h.lock().write(buf)) {
-            count as isize
-        } else {
-            -1
+    pub fn fcntl(&mut self, fd: usize, cmd: FcntlCmd, arg: usize) -> SyscallResult {
+        match cmd {
+            FcntlCmd::DupFd => self.dup_from(fd, arg),
+            FcntlCmd::GetFd => Ok(if self.get(fd)?.cloexec { FD_CLOEXEC } else { 0 }),
+            FcntlCmd::SetFd => {
+                let desc = self.handles.get_mut(&fd).ok_or(Errno::EBADF)?;
+                desc.cloexec = arg & FD_CLOEXEC != 0;
+                Ok(0)
+            }
+            FcntlCmd::Unknown => Err(Errno::EINVAL),
         }
     }
+
+    pub fn read(&self, fd: usize, buf: &mut [u8]) -> SyscallResult {
+        self.get(fd)?.resource.lock().read(buf)
+    }
+
+    pub fn write(&self, fd: usize, buf: &[u8]) -> SyscallResult {
+        self.get(fd)?.resource.lock().write(buf)
+    }
+
+    /// Queue the current process on `fd`, returns false if it can not be woken up
+    pub fn wait(&self, fd: usize, on: WaitFor) -> bool {
+        self.get(fd).is_ok_and(|desc| desc.resource.lock().wait(on))
+    }
+
+    pub fn ioctl(&self, fd: usize, request: IoctlRequest, arg: usize) -> SyscallResult {
+        self.get(fd)?.resource.lock().ioctl(request, arg)
+    }
+
+    pub fn seek(&self, fd: usize, offset: isize, whence: SeekWhence) -> SyscallResult {
+        self.get(fd)?.resource.lock().seek(offset, whence)
+    }
+
+    pub fn fstat(&self, fd: usize) -> SyscallResult<FileStat> {
+        self.get(fd)?.resource.lock().stat()
+    }
 }
 
 #[derive(Debug)]
 pub enum Resource {
     Console(StdIO),
-    Null,
+    PipeRead(PipeReader),
+    PipeWrite(PipeWriter),
+    /// A file opened by the `Open` syscall, the flags decide if it is readable or writable
+    File(FileHandle, OpenFlags),
+    /// A character device opened from devfs
+    Device(Arc<dyn CharDevice>, OpenFlags),
 }
 
 impl Resource {
-    pub fn read(&mut self, buf: &mut [u8]) -> Option<usize> {
+    /// Read from the resource
+    ///
+    /// returns `EAGAIN` if there is no data yet, the caller should block and retry
+    pub fn read(&mut self, buf: &mut [u8]) -> SyscallResult {
         match self {
             Resource::Console(stdio) => match stdio {
-                StdIO::Stdin => {
-                    // FIXME: just read from kernel input buffer
-                    if buf.len() < 4 { // 保证缓冲区能够写入UTF-8
-                        Some(0) // UTF-8最大字节数为4
-                    } else {
-                        match try_pop_key() {
-                            Some(DecodedKey::Unicode(key)) => {
-                                // 排除特殊控制字符
-                                let s = key.encode_utf8(buf);
-                                Some(s.len())
-                            }
-                            _ => Some(0),
-                        }
-                    }
+                // 由 TTY 处理行编辑，输入为空时返回 EAGAIN，由调用者阻塞等待
+                StdIO::Stdin => tty::read(buf),
+                // 资源存在但不支持读（例如 stdout），视为无效的描述符
+                _ => Err(Errno::EBADF),
+            }
+            Resource::PipeRead(pipe) => pipe.read(buf),
+            Resource::PipeWrite(_) => Err(Errno::EBADF),
+            Resource::File(file, flags) => {
+                if !flags.contains(OpenFlags::READ) {
+                    return Err(Errno::EBADF);
+                }
+                file.read(buf).map_err(filesystem::errno)
+            }
+            Resource::Device(device, flags) => {
+                if !flags.contains(OpenFlags::READ) {
+                    return Err(Errno::EBADF);
                 }
-                _ => None,
+                device.read(buf)
             }
-            Resource::Null => Some(0),
         }
     }
 
-    pub fn write(&mut self, buf: &[u8]) -> Option<usize> {
+    /// Write to the resource
+    ///
+    /// returns `EAGAIN` if nothing can be written yet, the caller should block and retry
+    pub fn write(&mut self, buf: &[u8]) -> SyscallResult {
         match self {
             Resource::Console(stdio) => match *stdio {
-                StdIO::Stdin => None,
+                StdIO::Stdin => Err(Errno::EBADF),
                 StdIO::Stdout => {
                     print!("{}", String::from_utf8_lossy(buf));
-                    Some(buf.len())
+                    Ok(buf.len())
                 }
                 StdIO::Stderr => {
                     warn!("{}", String::from_utf8_lossy(buf));
-                    Some(buf.len())
+                    Ok(buf.len())
                 }
             },
-            Resource::Null => Some(buf.len()),
+            Resource::PipeRead(_) => Err(Errno::EBADF),
+            Resource::PipeWrite(pipe) => pipe.write(buf),
+            Resource::File(file, flags) => {
+                if !flags.contains(OpenFlags::WRITE) {
+                    return Err(Errno::EBADF);
+                }
+                if flags.contains(OpenFlags::APPEND) {
+                    file.seek(SeekFrom::End(0)).map_err(filesystem::errno)?;
+                }
+                let len = file.write(buf).map_err(filesystem::errno)?;
+                // 立即写回目录项，其他打开者才能看到新的长度
+                file.flush().map_err(filesystem::errno)?;
+                Ok(len)
+            }
+            Resource::Device(device, flags) => {
+                if !flags.contains(OpenFlags::WRITE) {
+                    return Err(Errno::EBADF);
+                }
+                device.write(buf)
+            }
+        }
+    }
+
+    /// Queue the current process until the resource is ready,
+    /// returns false if the resource never blocks for `on`
+    pub fn wait(&self, on: WaitFor) -> bool {
+        match (self, on) {
+            (Resource::Console(StdIO::Stdin), WaitFor::Read) => {
+                tty::wait_input();
+                true
+            }
+            (Resource::PipeRead(pipe), WaitFor::Read) => {
+                pipe.wait();
+                true
+            }
+            (Resource::PipeWrite(pipe), WaitFor::Write) => {
+                pipe.wait();
+                true
+            }
+            (Resource::Device(device, _), on) => device.wait(on),
+            _ => false,
+        }
+    }
+
+    pub fn ioctl(&mut self, request: IoctlRequest, arg: usize) -> SyscallResult {
+        match self {
+            // 标准输入输出都指向同一个终端
+            Resource::Console(_) => tty::ioctl(request, arg),
+            Resource::Device(device, _) => device.ioctl(request, arg),
+            Resource::PipeRead(_) | Resource::PipeWrite(_) | Resource::File(..) => {
+                Err(Errno::ENOTTY)
+            }
+        }
+    }
+
+    /// Move the offset of a file, returns the new offset
+    pub fn seek(&mut self, offset: isize, whence: SeekWhence) -> SyscallResult {
+        let Resource::File(file, _) = self else {
+            return Err(Errno::ESPIPE);
+        };
+        let pos = match whence {
+            SeekWhence::Set => {
+                SeekFrom::Start(usize::try_from(offset).map_err(|_| Errno::EINVAL)?)
+            }
+            SeekWhence::Current => SeekFrom::Current(offset),
+            SeekWhence::End => SeekFrom::End(offset),
+            SeekWhence::Unknown => return Err(Errno::EINVAL),
+        };
+        file.seek(pos).map_err(filesystem::errno)
+    }
+
+    pub fn stat(&mut self) -> SyscallResult<FileStat> {
+        match self {
+            Resource::Console(_) | Resource::Device(..) => {
+                Ok(FileStat::new(FileKind::CharDevice, 0))
+            }
+            Resource::PipeRead(_) | Resource::PipeWrite(_) => Ok(FileStat::new(FileKind::Pipe, 0)),
+            Resource::File(file, _) => {
+                let mut stat = filesystem::file_stat(&file.meta);
+                // 打开后的写入不会更新 meta，长度以文件末尾为准
+                let offset = file.seek(SeekFrom::Current(0)).map_err(filesystem::errno)?;
+                stat.size = file.seek(SeekFrom::End(0)).map_err(filesystem::errno)?;
+                file.seek(SeekFrom::Start(offset)).map_err(filesystem::errno)?;
+                Ok(stat)
+            }
         }
     }
 }
@@ -2,10 +2,10 @@ pub struct KernelAllocator;
 
 unsafe impl alloc::alloc::GlobalAlloc for KernelAllocator {
     unsafe fn alloc(&self, layout: core::alloc::Layout) -> *mut u8 {
-        crate::sys_allocate(&layout)
+        crate::sys_allocate(&layout).unwrap_or(core::ptr::null_mut())
     }
     unsafe fn dealloc(&self, ptr: *mut u8, layout: core::alloc::Layout) {
-        crate::sys_deallocate(ptr, &layout);
+        let _ = crate::sys_deallocate(ptr, &layout);
     }
 }
 
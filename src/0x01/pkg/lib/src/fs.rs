@@ -0,0 +1,339 @@
+//! Filesystem access, modelled on `std::fs`
+//!
+//! relative paths start from the current working directory, see `sys_chdir`
+
+use crate::*;
+use alloc::string::String;
+use alloc::vec::Vec;
+use chrono::{DateTime, Utc};
+use syscall_def::{DirEntry as RawDirEntry, FileKind, FileStat, OpenFlags, SeekWhence, TIME_UNKNOWN};
+
+/// Number of entries fetched by each `ReadDir` syscall
+const READ_DIR_BATCH: usize = 16;
+
+/// Where `File::seek` starts from
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SeekFrom {
+    Start(usize),
+    End(isize),
+    Current(isize),
+}
+
+/// Options deciding how a file is opened
+#[derive(Clone, Copy, Debug)]
+pub struct OpenOptions {
+    flags: OpenFlags,
+}
+
+impl Default for OpenOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OpenOptions {
+    /// All options are off
+    pub fn new() -> Self {
+        Self {
+            flags: OpenFlags::empty(),
+        }
+    }
+
+    fn set(&mut self, flags: OpenFlags, value: bool) -> &mut Self {
+        self.flags.set(flags, value);
+        self
+    }
+
+    pub fn read(&mut self, read: bool) -> &mut Self {
+        self.set(OpenFlags::READ, read)
+    }
+
+    pub fn write(&mut self, write: bool) -> &mut Self {
+        self.set(OpenFlags::WRITE, write)
+    }
+
+    /// Every write goes to the end of the file, implies `write`
+    pub fn append(&mut self, append: bool) -> &mut Self {
+        if append {
+            self.write(true);
+        }
+        self.set(OpenFlags::APPEND, append)
+    }
+
+    pub fn truncate(&mut self, truncate: bool) -> &mut Self {
+        self.set(OpenFlags::TRUNCATE, truncate)
+    }
+
+    /// Create the file if it does not exist
+    pub fn create(&mut self, create: bool) -> &mut Self {
+        self.set(OpenFlags::CREATE, create)
+    }
+
+    /// Create the file, fails with `EEXIST` if it exists
+    pub fn create_new(&mut self, create_new: bool) -> &mut Self {
+        self.set(OpenFlags::CREATE | OpenFlags::EXCL, create_new)
+    }
+
+    pub fn open(&self, path: &str) -> SyscallResult<File> {
+        sys_open(path, self.flags).map(|fd| File { fd })
+    }
+}
+
+/// An opened file, closed on drop
+#[derive(Debug)]
+pub struct File {
+    fd: usize,
+}
+
+impl File {
+    /// Open the file at `path` for reading
+    pub fn open(path: &str) -> SyscallResult<Self> {
+        OpenOptions::new().read(true).open(path)
+    }
+
+    /// Open the file at `path` for writing, it is created or truncated
+    pub fn create(path: &str) -> SyscallResult<Self> {
+        OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)
+    }
+
+    pub fn options() -> OpenOptions {
+        OpenOptions::new()
+    }
+
+    /// The fd of the file, e.g. for `sys_dup2`
+    pub fn fd(&self) -> usize {
+        self.fd
+    }
+
+    /// Read into `buf`, returns the length read, 0 at the end of file
+    pub fn read(&mut self, buf: &mut [u8]) -> SyscallResult<usize> {
+        sys_read(self.fd, buf)
+    }
+
+    /// Read until the end of file, returns the length read
+    pub fn read_to_end(&mut self, buf: &mut Vec<u8>) -> SyscallResult<usize> {
+        let mut chunk = [0u8; 512];
+        let mut total = 0;
+        loop {
+            match self.read(&mut chunk)? {
+                0 => return Ok(total),
+                len => {
+                    buf.extend_from_slice(&chunk[..len]);
+                    total += len;
+                }
+            }
+        }
+    }
+
+    /// Read until the end of file, fails with `EINVAL` if it is not UTF-8
+    pub fn read_to_string(&mut self, buf: &mut String) -> SyscallResult<usize> {
+        let mut bytes = Vec::new();
+        let len = self.read_to_end(&mut bytes)?;
+        buf.push_str(core::str::from_utf8(&bytes).map_err(|_| Errno::EINVAL)?);
+        Ok(len)
+    }
+
+    /// Write `buf`, returns the length written
+    pub fn write(&mut self, buf: &[u8]) -> SyscallResult<usize> {
+        sys_write(self.fd, buf)
+    }
+
+    /// Write the whole `buf`, fails with `ENOSPC` if nothing more can be written
+    pub fn write_all(&mut self, buf: &[u8]) -> SyscallResult<()> {
+        io::write_all(self.fd, buf)
+    }
+
+    /// Move the offset, returns the new offset from the start of file
+    pub fn seek(&mut self, pos: SeekFrom) -> SyscallResult<usize> {
+        let (offset, whence) = match pos {
+            SeekFrom::Start(offset) => (offset as isize, SeekWhence::Set),
+            SeekFrom::End(offset) => (offset, SeekWhence::End),
+            SeekFrom::Current(offset) => (offset, SeekWhence::Current),
+        };
+        sys_seek(self.fd, offset, whence)
+    }
+
+    pub fn metadata(&self) -> SyscallResult<Metadata> {
+        sys_fstat(self.fd).map(Metadata)
+    }
+}
+
+impl Drop for File {
+    fn drop(&mut self) {
+        let _ = sys_close(self.fd);
+    }
+}
+
+/// Metadata of a file or directory
+#[derive(Clone, Copy, Debug)]
+pub struct Metadata(FileStat);
+
+impl Metadata {
+    pub fn file_type(&self) -> FileKind {
+        self.0.kind()
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.file_type() == FileKind::File
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.file_type() == FileKind::Directory
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.file_type() == FileKind::Symlink
+    }
+
+    /// Length in bytes, 0 for directories
+    pub fn len(&self) -> usize {
+        self.0.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// `None` if the filesystem does not record it
+    pub fn created(&self) -> Option<DateTime<Utc>> {
+        time(self.0.created)
+    }
+
+    /// `None` if the filesystem does not record it
+    pub fn modified(&self) -> Option<DateTime<Utc>> {
+        time(self.0.modified)
+    }
+
+    /// `None` if the filesystem does not record it
+    pub fn accessed(&self) -> Option<DateTime<Utc>> {
+        time(self.0.accessed)
+    }
+}
+
+fn time(millis: i64) -> Option<DateTime<Utc>> {
+    if millis == TIME_UNKNOWN {
+        None
+    } else {
+        DateTime::from_timestamp_millis(millis)
+    }
+}
+
+/// An entry returned by `read_dir`
+#[derive(Clone, Debug)]
+pub struct DirEntry {
+    name: String,
+    meta: Metadata,
+}
+
+impl DirEntry {
+    pub fn file_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn metadata(&self) -> Metadata {
+        self.meta
+    }
+}
+
+/// Iterator over the entries of a directory, fetched from the kernel in batches
+#[derive(Debug)]
+pub struct ReadDir {
+    path: String,
+    batch: Vec<RawDirEntry>,
+    /// Number of entries fetched so far
+    fetched: usize,
+    /// Next entry of `batch` to return
+    next: usize,
+}
+
+impl ReadDir {
+    fn fetch(&mut self) -> SyscallResult<()> {
+        self.batch.resize(READ_DIR_BATCH, RawDirEntry::default());
+        let len = sys_read_dir(&self.path, &mut self.batch, self.fetched)?;
+        self.batch.truncate(len);
+        self.fetched += len;
+        self.next = 0;
+        Ok(())
+    }
+}
+
+impl Iterator for ReadDir {
+    type Item = SyscallResult<DirEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // 上一批未取满说明已经到达末尾
+        if self.next == self.batch.len() {
+            if self.batch.len() < READ_DIR_BATCH {
+                return None;
+            }
+            if let Err(err) = self.fetch() {
+                self.batch.clear();
+                return Some(Err(err));
+            }
+        }
+
+        let entry = self.batch.get(self.next)?;
+        self.next += 1;
+        Some(Ok(DirEntry {
+            name: String::from(entry.name()),
+            meta: Metadata(entry.stat),
+        }))
+    }
+}
+
+/// Iterate over the entries of the directory at `path`
+pub fn read_dir(path: &str) -> SyscallResult<ReadDir> {
+    let mut dir = ReadDir {
+        path: String::from(path),
+        batch: Vec::new(),
+        fetched: 0,
+        next: 0,
+    };
+    dir.fetch()?;
+    Ok(dir)
+}
+
+pub fn metadata(path: &str) -> SyscallResult<Metadata> {
+    sys_stat_file(path).map(Metadata)
+}
+
+/// Read the whole file at `path`
+pub fn read(path: &str) -> SyscallResult<Vec<u8>> {
+    let mut buf = Vec::new();
+    File::open(path)?.read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+/// Read the whole file at `path`, fails with `EINVAL` if it is not UTF-8
+pub fn read_to_string(path: &str) -> SyscallResult<String> {
+    let mut buf = String::new();
+    File::open(path)?.read_to_string(&mut buf)?;
+    Ok(buf)
+}
+
+/// Write `contents` to the file at `path`, it is created or truncated
+pub fn write(path: &str, contents: &[u8]) -> SyscallResult<()> {
+    File::create(path)?.write_all(contents)
+}
+
+pub fn create_dir(path: &str) -> SyscallResult<()> {
+    sys_mkdir(path)
+}
+
+pub fn remove_file(path: &str) -> SyscallResult<()> {
+    sys_unlink(path, false)
+}
+
+/// Remove the directory at `path` and all of its contents
+pub fn remove_dir_all(path: &str) -> SyscallResult<()> {
+    sys_unlink(path, true)
+}
+
+/// Rename a file or directory, an existing file at `to` is replaced
+pub fn rename(from: &str, to: &str) -> SyscallResult<()> {
+    sys_rename(from, to)
+}
@@ -11,40 +11,50 @@ impl Stdin {
         Self
     }
 
+    /// Read a line without the trailing newline
+    ///
+    /// line editing (echo, backspace, Ctrl-U...) is done by the kernel tty
+    /// in canonical mode, returns what has been read on end of file
     pub fn read_line(&self) -> String {
-        // FIXME: allocate string
-        let mut line = String::new();
-        // FIXME: read from input buffer
-        //       - maybe char by char?
-        // FIXME: handle backspace / enter...
-        // FIXME: return string
+        let mut line = vec::Vec::new();
+        let mut buf = [0u8; 256];
 
+        // sys_read 在没有输入时由内核阻塞，不会忙等
         loop {
-            let buf: &mut [u8] = &mut [0u8; 256];
-            let ret = sys_read(0, buf);
-
-            if ret.is_none() {
-                continue;
-            } else {
-                for i in 0..ret.unwrap() {
-                    let ch = buf[i];
-                    match ch {
-                        b'\r' => {
-                            sys_write(1, "\n".as_bytes()); // 写入一个换行符
-                            return line;
-                        }
-                        b'\x08' | b'\x7f' => {
-                            line.pop();
-                            sys_write(1, "\x08\x20\x08".as_bytes()); // 写入一个退格
-                        }
-                        _ => {
-                            line.push(ch as char);
-                            sys_write(1, &mut[ch]); // 写入一个字符ch
-                        }
-                    };
+            match sys_read(STDIN, &mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(len) => {
+                    line.extend_from_slice(&buf[..len]);
+                    if line.last() == Some(&b'\n') {
+                        line.pop();
+                        break;
+                    }
                 }
             }
         }
+
+        String::from_utf8_lossy(&line).into_owned()
+    }
+
+    /// Read raw bytes, returns 0 on end of file
+    pub fn read(&self, buf: &mut [u8]) -> SyscallResult {
+        sys_read(STDIN, buf)
+    }
+
+    /// Get the terminal mode
+    pub fn mode(&self) -> SyscallResult<TtyMode> {
+        let bits = sys_ioctl(STDIN, IoctlRequest::TtyGetMode, 0)?;
+        Ok(TtyMode::from_bits_truncate(bits))
+    }
+
+    /// Set the terminal mode, e.g. clear `TtyMode::CANONICAL` to read raw keys
+    pub fn set_mode(&self, mode: TtyMode) -> SyscallResult<()> {
+        sys_ioctl(STDIN, IoctlRequest::TtySetMode, mode.bits()).map(|_| ())
+    }
+
+    /// Set the process killed by Ctrl-C, `None` to clear it
+    pub fn set_foreground(&self, pid: Option<u16>) -> SyscallResult<()> {
+        sys_ioctl(STDIN, IoctlRequest::TtySetForeground, pid.unwrap_or(0) as usize).map(|_| ())
     }
 }
 
@@ -53,8 +63,9 @@ impl Stdout {
         Self
     }
 
+    /// Write the whole `s`, a write to a full pipe blocks until it is read
     pub fn write(&self, s: &str) {
-        sys_write(1, s.as_bytes());
+        let _ = write_all(STDOUT, s.as_bytes());
     }
 }
 
@@ -63,9 +74,23 @@ impl Stderr {
         Self
     }
 
+    /// Write the whole `s`, a write to a full pipe blocks until it is read
     pub fn write(&self, s: &str) {
-        sys_write(2, s.as_bytes());
+        let _ = write_all(STDERR, s.as_bytes());
+    }
+}
+
+/// Write the whole `buf` to `fd`, fails with `ENOSPC` if nothing more can be written
+///
+/// a write may be partial, e.g. to a pipe with little free space
+pub fn write_all(fd: usize, mut buf: &[u8]) -> SyscallResult<()> {
+    while !buf.is_empty() {
+        match sys_write(fd, buf)? {
+            0 => return Err(Errno::ENOSPC),
+            len => buf = &buf[len..],
+        }
     }
+    Ok(())
 }
 
 pub fn stdin() -> Stdin {
@@ -13,6 +13,7 @@ pub mod io;
 #[cfg(any(feature = "brk_alloc", feature = "kernel_alloc"))]
 pub mod allocator;
 pub mod sync;
+pub mod fs;
 pub extern crate alloc;
 
 mod syscall;
@@ -24,6 +25,10 @@ pub use chrono::*;
 pub use io::*;
 pub use sync::*;
 pub use syscall::*;
+pub use syscall_def::{
+    Errno, FcntlCmd, FileKind, IoctlRequest, OpenFlags, SemFlags, SyscallResult, TtyMode,
+    FD_CLOEXEC, MSG_ANY_TYPE, MSG_MAX_SIZE, STDERR, STDIN, STDOUT,
+};
 
 pub fn init() {
     #[cfg(feature = "brk_alloc")]
@@ -1,6 +1,9 @@
 use core::{
+    cell::UnsafeCell,
     hint::spin_loop,
-    sync::atomic::{AtomicBool, Ordering},
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicBool, AtomicU32, Ordering},
+    time::Duration,
 };
 
 use crate::*;
@@ -31,40 +34,155 @@ impl SpinLock {
 
 unsafe impl Sync for SpinLock {} // Why? Check reflection question 5
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+/// A named semaphore in the kernel, visible to all processes
+///
+/// the id of the opened semaphore is cached here, so forked processes
+/// sharing the memory can use it directly
+#[derive(Debug)]
 pub struct Semaphore {
     /* FIXME: record the sem key */
-    key: u32,
+    name: &'static str,
+    /// 内核分配的 id，0 表示尚未打开
+    id: AtomicU32,
 }
 
 impl Semaphore {
-    pub const fn new(key: u32) -> Self {
-        Semaphore { key }
+    pub const fn new(name: &'static str) -> Self {
+        Semaphore {
+            name,
+            id: AtomicU32::new(0),
+        }
     }
 
+    /// Create the semaphore with `value` if it does not exist, and open it
     #[inline(always)]
-    pub fn init(&self, value: usize) -> bool {
-        sys_new_sem(self.key, value)
+    pub fn init(&self, value: usize) -> SyscallResult<()> {
+        self.open_with(value, SemFlags::CREATE)
     } // new操作
 
+    /// Create the semaphore as a mutex with value 1 if it does not exist, and open it
+    ///
+    /// a mutex is owned by the processes which acquired it: only they may
+    /// signal it, and it is tracked by the kernel to detect deadlocks
+    #[inline(always)]
+    pub fn init_mutex(&self) -> SyscallResult<()> {
+        self.open_with(1, SemFlags::CREATE | SemFlags::MUTEX)
+    }
+
+    /// Open an existing semaphore, e.g. created by another app
+    #[inline(always)]
+    pub fn open(&self) -> SyscallResult<()> {
+        self.open_with(0, SemFlags::empty())
+    }
+
+    fn open_with(&self, value: usize, flags: SemFlags) -> SyscallResult<()> {
+        let id = sys_sem_open(self.name, value, flags)?;
+        self.id.store(id, Ordering::Release);
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn id(&self) -> u32 {
+        self.id.load(Ordering::Acquire)
+    }
+
     /* FIXME: other functions with syscall... */
-    // 添加信号量所需的其他三种操作
+    // 添加信号量所需的其他操作
+    /// Close the semaphore, it is removed after all processes close it
     #[inline(always)]
-    pub fn remove(&self) -> bool {
-        sys_remove_sem(self.key)
-    } // remove操作
+    pub fn close(&self) -> SyscallResult<()> {
+        sys_sem_close(self.id.swap(0, Ordering::AcqRel))
+    } // close操作，进程退出时也会自动关闭
 
     #[inline(always)]
-    pub fn wait(&self) -> bool {
-        sys_sem_wait(self.key)
+    pub fn wait(&self) -> SyscallResult<()> {
+        sys_sem_wait(self.id(), None)
     } // P操作
 
-    pub fn signal(&self) -> bool {
-        sys_sem_signal(self.key)
+    /// Wait the semaphore, fails with `ETIMEDOUT` after `timeout`
+    #[inline(always)]
+    pub fn wait_timeout(&self, timeout: Duration) -> SyscallResult<()> {
+        sys_sem_wait(self.id(), Some(timeout))
+    }
+
+    /// Wait the semaphore, fails with `EDEADLK` instead of deadlocking
+    ///
+    /// only semaphores created by `init_mutex` are tracked,
+    /// waits on counting semaphores are never reported
+    #[inline(always)]
+    pub fn wait_checked(&self) -> SyscallResult<()> {
+        sys_sem_wait_checked(self.id(), None)
+    }
+
+    /// Wait the semaphore without blocking, fails with `EAGAIN`
+    #[inline(always)]
+    pub fn try_wait(&self) -> SyscallResult<()> {
+        sys_sem_try_wait(self.id())
+    }
+
+    /// Signal the semaphore, fails with `EPERM` on a mutex not held by this process
+    #[inline(always)]
+    pub fn signal(&self) -> SyscallResult<()> {
+        sys_sem_signal(self.id())
     } // V操作
 }
 
-unsafe impl Sync for Semaphore {}
+/// A kernel message queue, shared by forked processes with the same key
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MessageQueue {
+    key: u32,
+}
+
+impl MessageQueue {
+    pub const fn new(key: u32) -> Self {
+        MessageQueue { key }
+    }
+
+    /// Create the queue holding at most `capacity` messages
+    #[inline(always)]
+    pub fn init(&self, capacity: usize) -> SyscallResult<()> {
+        sys_msg_new(self.key, capacity)
+    }
+
+    #[inline(always)]
+    pub fn remove(&self) -> SyscallResult<()> {
+        sys_msg_remove(self.key)
+    }
+
+    /// Send a message of type `mtype` (> 0), blocks while the queue is full
+    #[inline(always)]
+    pub fn send(&self, mtype: usize, data: &[u8]) -> SyscallResult<()> {
+        sys_msg_send(self.key, mtype, data, None)
+    }
+
+    /// Send a message, fails with `ETIMEDOUT` if the queue is still full after `timeout`
+    ///
+    /// a zero timeout does not block and fails with `EAGAIN`
+    #[inline(always)]
+    pub fn send_timeout(&self, mtype: usize, data: &[u8], timeout: Duration) -> SyscallResult<()> {
+        sys_msg_send(self.key, mtype, data, Some(timeout))
+    }
+
+    /// Receive the first message of type `mtype` (`MSG_ANY_TYPE` for any),
+    /// blocks until there is one, returns `(type, length)` of the message
+    #[inline(always)]
+    pub fn receive(&self, mtype: usize, buf: &mut [u8]) -> SyscallResult<(usize, usize)> {
+        sys_msg_recv(self.key, mtype, buf, None)
+    }
+
+    /// Receive a message, fails with `ETIMEDOUT` if there is none after `timeout`
+    ///
+    /// a zero timeout does not block and fails with `ENOMSG`
+    #[inline(always)]
+    pub fn receive_timeout(
+        &self,
+        mtype: usize,
+        buf: &mut [u8],
+        timeout: Duration,
+    ) -> SyscallResult<(usize, usize)> {
+        sys_msg_recv(self.key, mtype, buf, Some(timeout))
+    }
+}
 
 #[macro_export]
 macro_rules! semaphore_array {
@@ -72,3 +190,285 @@ macro_rules! semaphore_array {
         [ $($crate::Semaphore::new($x),)* ]
     }
 }
+
+/// Times to spin before sleeping in the kernel, the lock is likely to be
+/// released soon if it is only held for a short critical section
+const SPIN_LIMIT: usize = 100;
+
+/// Spin until `f` returns true or `SPIN_LIMIT` is reached
+fn spin_until(mut f: impl FnMut() -> bool) -> bool {
+    for _ in 0..SPIN_LIMIT {
+        if f() {
+            return true;
+        }
+        spin_loop();
+    }
+    false
+}
+
+const UNLOCKED: u32 = 0;
+const LOCKED: u32 = 1;
+/// 锁被持有，且可能有进程在 futex 上等待
+const CONTENDED: u32 = 2;
+
+/// A mutex blocking in the kernel via futex when contended
+pub struct Mutex<T> {
+    state: AtomicU32,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: Send> Send for Mutex<T> {}
+unsafe impl<T: Send> Sync for Mutex<T> {}
+
+impl<T> Mutex<T> {
+    pub const fn new(data: T) -> Self {
+        Self {
+            state: AtomicU32::new(UNLOCKED),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    pub fn lock(&self) -> MutexGuard<'_, T> {
+        if self
+            .state
+            .compare_exchange(UNLOCKED, LOCKED, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            self.lock_contended();
+        }
+        MutexGuard { mutex: self }
+    }
+
+    pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+        self.state
+            .compare_exchange(UNLOCKED, LOCKED, Ordering::Acquire, Ordering::Relaxed)
+            .ok()
+            .map(|_| MutexGuard { mutex: self })
+    }
+
+    fn lock_contended(&self) {
+        let acquired = spin_until(|| {
+            self.state.load(Ordering::Relaxed) == UNLOCKED
+                && self
+                    .state
+                    .compare_exchange(UNLOCKED, LOCKED, Ordering::Acquire, Ordering::Relaxed)
+                    .is_ok()
+        });
+        if acquired {
+            return;
+        }
+
+        // 标记为有等待者，持有者解锁时会唤醒一个等待者
+        while self.state.swap(CONTENDED, Ordering::Acquire) != UNLOCKED {
+            let _ = sys_futex_wait(&self.state, CONTENDED, None);
+        }
+    }
+
+    fn unlock(&self) {
+        if self.state.swap(UNLOCKED, Ordering::Release) == CONTENDED {
+            let _ = sys_futex_wake(&self.state, 1);
+        }
+    }
+}
+
+pub struct MutexGuard<'a, T> {
+    mutex: &'a Mutex<T>,
+}
+
+impl<T> Deref for MutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.mutex.data.get() }
+    }
+}
+
+impl<T> DerefMut for MutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.mutex.data.get() }
+    }
+}
+
+impl<T> Drop for MutexGuard<'_, T> {
+    fn drop(&mut self) {
+        self.mutex.unlock();
+    }
+}
+
+/// A condition variable used with `Mutex`
+///
+/// waiters sleep on a sequence number, which is increased by every notification
+pub struct Condvar {
+    seq: AtomicU32,
+}
+
+impl Condvar {
+    pub const fn new() -> Self {
+        Self {
+            seq: AtomicU32::new(0),
+        }
+    }
+
+    /// Unlock the mutex and wait for a notification, the mutex is locked again before returning
+    ///
+    /// spurious wake ups are possible, the condition should be checked in a loop
+    pub fn wait<'a, T>(&self, guard: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+        self.wait_timeout(guard, None).0
+    }
+
+    /// Like `wait`, but returns `true` as the second value if `timeout` has passed
+    pub fn wait_timeout<'a, T>(
+        &self,
+        guard: MutexGuard<'a, T>,
+        timeout: Option<Duration>,
+    ) -> (MutexGuard<'a, T>, bool) {
+        // 在解锁前读取序号，解锁后的通知会改变序号，使 futex 等待立即返回
+        let seq = self.seq.load(Ordering::Relaxed);
+        let mutex = guard.mutex;
+        drop(guard);
+        let timed_out = sys_futex_wait(&self.seq, seq, timeout) == Err(Errno::ETIMEDOUT);
+        (mutex.lock(), timed_out)
+    }
+
+    pub fn notify_one(&self) {
+        self.seq.fetch_add(1, Ordering::Release);
+        let _ = sys_futex_wake(&self.seq, 1);
+    }
+
+    pub fn notify_all(&self) {
+        self.seq.fetch_add(1, Ordering::Release);
+        let _ = sys_futex_wake(&self.seq, usize::MAX);
+    }
+}
+
+impl Default for Condvar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 写者持有锁时的状态，其余值为读者的数量
+const WRITE_LOCKED: u32 = u32::MAX;
+
+/// A readers-writer lock blocking in the kernel via futex when contended
+pub struct RwLock<T> {
+    state: AtomicU32,
+    /// 在 futex 上等待的进程数，为 0 时解锁不需要进入内核
+    waiters: AtomicU32,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: Send> Send for RwLock<T> {}
+unsafe impl<T: Send + Sync> Sync for RwLock<T> {}
+
+impl<T> RwLock<T> {
+    pub const fn new(data: T) -> Self {
+        Self {
+            state: AtomicU32::new(0),
+            waiters: AtomicU32::new(0),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    fn try_read_state(&self) -> core::result::Result<(), u32> {
+        let state = self.state.load(Ordering::Relaxed);
+        if state >= WRITE_LOCKED - 1 {
+            return Err(state);
+        }
+        self.state
+            .compare_exchange(state, state + 1, Ordering::Acquire, Ordering::Relaxed)
+            .map(|_| ())
+    }
+
+    fn try_write_state(&self) -> core::result::Result<(), u32> {
+        self.state
+            .compare_exchange(0, WRITE_LOCKED, Ordering::Acquire, Ordering::Relaxed)
+            .map(|_| ())
+    }
+
+    /// Lock with `try_lock`, spin first and then sleep on the futex
+    fn lock_with(&self, try_lock: impl Fn(&Self) -> core::result::Result<(), u32>) {
+        if spin_until(|| try_lock(self).is_ok()) {
+            return;
+        }
+        loop {
+            let state = match try_lock(self) {
+                Ok(()) => return,
+                Err(state) => state,
+            };
+            self.waiters.fetch_add(1, Ordering::SeqCst);
+            let _ = sys_futex_wait(&self.state, state, None);
+            self.waiters.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    fn wake_waiters(&self) {
+        if self.waiters.load(Ordering::SeqCst) != 0 {
+            let _ = sys_futex_wake(&self.state, usize::MAX);
+        }
+    }
+
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        self.lock_with(Self::try_read_state);
+        RwLockReadGuard { lock: self }
+    }
+
+    pub fn write(&self) -> RwLockWriteGuard<'_, T> {
+        self.lock_with(Self::try_write_state);
+        RwLockWriteGuard { lock: self }
+    }
+
+    pub fn try_read(&self) -> Option<RwLockReadGuard<'_, T>> {
+        self.try_read_state().ok().map(|_| RwLockReadGuard { lock: self })
+    }
+
+    pub fn try_write(&self) -> Option<RwLockWriteGuard<'_, T>> {
+        self.try_write_state().ok().map(|_| RwLockWriteGuard { lock: self })
+    }
+}
+
+pub struct RwLockReadGuard<'a, T> {
+    lock: &'a RwLock<T>,
+}
+
+impl<T> Deref for RwLockReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T> Drop for RwLockReadGuard<'_, T> {
+    fn drop(&mut self) {
+        // 最后一个读者离开时唤醒等待的写者
+        if self.lock.state.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.lock.wake_waiters();
+        }
+    }
+}
+
+pub struct RwLockWriteGuard<'a, T> {
+    lock: &'a RwLock<T>,
+}
+
+impl<T> Deref for RwLockWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T> DerefMut for RwLockWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<T> Drop for RwLockWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.state.store(0, Ordering::SeqCst);
+        self.lock.wake_waiters();
+    }
+}
@@ -1,127 +1,355 @@
-use syscall_def::Syscall;
+use syscall_def::{
+    DirEntry, Errno, FcntlCmd, FileStat, FutexOp, IoctlRequest, OpenFlags, SeekWhence, SemFlags,
+    SemOp, Syscall, SyscallResult, FD_CLOEXEC, TIMEOUT_FOREVER,
+};
+use alloc::string::String;
+use alloc::vec;
+use core::sync::atomic::AtomicU32;
 use core::time::Duration;
 
-#[inline(always)]
-pub fn sys_write(fd: u8, buf: &[u8]) -> Option<usize> {
-    let ret = syscall!(
-        Syscall::Write,
-        fd as u64,
-        buf.as_ptr() as u64,
-        buf.len() as u64
-    ) as isize;
-    if ret.is_negative() {
-        None
-    } else {
-        Some(ret as usize)
+/// Raw syscall wrappers generated from the syscall table in `syscall_def`
+///
+/// arguments are passed as-is, prefer the wrappers below which take safe types
+pub mod raw {
+    use syscall_def::{Errno, Syscall, SyscallArg, SyscallResult};
+
+    macro_rules! define_wrappers {
+        ($(
+            $(#[$meta:meta])*
+            $variant:ident = $nr:literal => $(@$kind:ident)? fn $name:ident ($($arg:ident : $ty:ty),*) -> $ret:ty;
+        )*) => {
+            $(
+                $(#[$meta])*
+                #[inline(always)]
+                pub fn $name($($arg: $ty),*) -> SyscallResult<$ret> {
+                    Errno::decode(syscall!(Syscall::$variant $(, SyscallArg::into_arg($arg))*))
+                        .map(<$ret as SyscallArg>::from_arg)
+                }
+            )*
+        };
     }
+
+    syscall_def::syscall_table!(define_wrappers);
 }
 
 #[inline(always)]
-pub fn sys_read(fd: u8, buf: &mut [u8]) -> Option<usize> {
-    let ret = syscall!(
-        Syscall::Read,
-        fd as u64,
-        buf.as_ptr() as u64,
-        buf.len() as u64
-    ) as isize;
-    if ret.is_negative() {
-        None
-    } else {
-        Some(ret as usize)
-    }
+pub fn sys_write(fd: usize, buf: &[u8]) -> SyscallResult {
+    raw::sys_write(fd, buf.as_ptr(), buf.len())
+}
+
+#[inline(always)]
+pub fn sys_read(fd: usize, buf: &mut [u8]) -> SyscallResult {
+    raw::sys_read(fd, buf.as_mut_ptr(), buf.len())
+}
+
+#[inline(always)]
+pub fn sys_close(fd: usize) -> SyscallResult<()> {
+    raw::sys_close(fd)
+}
+
+#[inline(always)]
+pub fn sys_dup(fd: usize) -> SyscallResult<usize> {
+    raw::sys_dup(fd)
+}
+
+#[inline(always)]
+pub fn sys_dup2(old: usize, new: usize) -> SyscallResult<usize> {
+    raw::sys_dup2(old, new)
+}
+
+/// Create a pipe, returns `(read fd, write fd)`
+#[inline(always)]
+pub fn sys_pipe() -> SyscallResult<(usize, usize)> {
+    let mut fds = [0usize; 2];
+    raw::sys_pipe(fds.as_mut_ptr())?;
+    Ok((fds[0], fds[1]))
+}
+
+#[inline(always)]
+pub fn sys_fcntl(fd: usize, cmd: FcntlCmd, arg: usize) -> SyscallResult {
+    raw::sys_fcntl(fd, cmd as usize, arg)
+}
+
+/// Set or clear `FD_CLOEXEC` of the fd
+#[inline(always)]
+pub fn sys_set_cloexec(fd: usize, cloexec: bool) -> SyscallResult<()> {
+    let flags = if cloexec { FD_CLOEXEC } else { 0 };
+    sys_fcntl(fd, FcntlCmd::SetFd, flags).map(|_| ())
 }
 
 #[inline(always)]
-pub fn sys_wait_pid(pid: u16) -> isize {
-    // FIXME: try to get the return value for process
-    //        loop until the process is finished
-    syscall!(Syscall::WaitPid, pid as u64) as isize
+pub fn sys_ioctl(fd: usize, request: IoctlRequest, arg: usize) -> SyscallResult {
+    raw::sys_ioctl(fd, request as usize, arg)
+}
+
+/// Wait for the process `pid` to exit and return its exit code
+#[inline(always)]
+pub fn sys_wait_pid(pid: u16) -> SyscallResult<isize> {
+    // 退出码由内核写回 status，返回值只用于传递错误
+    let mut status: isize = 0;
+    raw::sys_wait_pid(pid, &mut status)?;
+    Ok(status)
 }
 
 #[inline(always)]
 pub fn sys_list_app() {
-    syscall!(Syscall::ListApp);
+    let _ = raw::sys_list_app();
 }
 
 #[inline(always)]
 pub fn sys_stat() {
-    syscall!(Syscall::Stat);
+    let _ = raw::sys_stat();
+}
+
+#[inline(always)]
+pub fn sys_allocate(layout: &core::alloc::Layout) -> SyscallResult<*mut u8> {
+    raw::sys_allocate(layout.size(), layout.align())
+}
+
+#[inline(always)]
+pub fn sys_deallocate(ptr: *mut u8, layout: &core::alloc::Layout) -> SyscallResult<()> {
+    raw::sys_deallocate(ptr, layout.size(), layout.align())
+}
+
+#[inline(always)]
+pub fn sys_spawn(path: &str) -> SyscallResult<u16> {
+    raw::sys_spawn(path.as_ptr(), path.len())
+}
+
+/// The current working directory of this process
+pub fn sys_getcwd() -> SyscallResult<String> {
+    let mut buf = vec![0u8; 64];
+    loop {
+        match raw::sys_getcwd(buf.as_mut_ptr(), buf.len()) {
+            Ok(len) => {
+                buf.truncate(len);
+                return String::from_utf8(buf).map_err(|_| Errno::EINVAL);
+            }
+            // 缓冲区不够时扩大后重试
+            Err(Errno::ERANGE) => buf.resize(buf.len() * 2, 0),
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Change the current working directory, `path` may be relative
+#[inline(always)]
+pub fn sys_chdir(path: &str) -> SyscallResult<()> {
+    raw::sys_chdir(path.as_ptr(), path.len())
+}
+
+/// Mount a new filesystem of type `fstype` (e.g. `tmpfs`) at `target`
+#[inline(always)]
+pub fn sys_mount(fstype: &str, target: &str) -> SyscallResult<()> {
+    raw::sys_mount(fstype.as_ptr(), fstype.len(), target.as_ptr(), target.len())
+}
+
+/// Unmount the filesystem at `target`
+#[inline(always)]
+pub fn sys_umount(target: &str) -> SyscallResult<()> {
+    raw::sys_umount(target.as_ptr(), target.len())
 }
 
+/// Open the file at `path`, see `OpenFlags`, returns the new fd
 #[inline(always)]
-pub fn sys_allocate(layout: &core::alloc::Layout) -> *mut u8 {
-    syscall!(Syscall::Allocate, layout as *const _) as *mut u8
+pub fn sys_open(path: &str, flags: OpenFlags) -> SyscallResult<usize> {
+    raw::sys_open(path.as_ptr(), path.len(), flags.bits())
 }
 
+/// Move the offset of fd, returns the new offset
 #[inline(always)]
-pub fn sys_deallocate(ptr: *mut u8, layout: &core::alloc::Layout) -> usize {
-    syscall!(Syscall::Deallocate, ptr, layout as *const _)
+pub fn sys_seek(fd: usize, offset: isize, whence: SeekWhence) -> SyscallResult<usize> {
+    raw::sys_seek(fd, offset, whence as usize)
 }
 
+/// The metadata of the file at `path`
 #[inline(always)]
-pub fn sys_spawn(path: &str) -> u16 {
-    syscall!(Syscall::Spawn, path.as_ptr() as u64, path.len() as u64) as u16
+pub fn sys_stat_file(path: &str) -> SyscallResult<FileStat> {
+    let mut stat = FileStat::default();
+    raw::sys_stat_file(path.as_ptr(), path.len(), &mut stat)?;
+    Ok(stat)
+}
+
+/// The metadata of the file opened as fd
+#[inline(always)]
+pub fn sys_fstat(fd: usize) -> SyscallResult<FileStat> {
+    let mut stat = FileStat::default();
+    raw::sys_fstat(fd, &mut stat)?;
+    Ok(stat)
+}
+
+/// Read the entries of the directory at `path` into `buf`, skipping the first `start` ones,
+/// returns the number read
+#[inline(always)]
+pub fn sys_read_dir(path: &str, buf: &mut [DirEntry], start: usize) -> SyscallResult<usize> {
+    raw::sys_read_dir(path.as_ptr(), path.len(), buf.as_mut_ptr(), buf.len(), start)
+}
+
+#[inline(always)]
+pub fn sys_mkdir(path: &str) -> SyscallResult<()> {
+    raw::sys_mkdir(path.as_ptr(), path.len())
+}
+
+/// Remove the file at `path`, or the directory and all of its contents if `dir` is set
+#[inline(always)]
+pub fn sys_unlink(path: &str, dir: bool) -> SyscallResult<()> {
+    raw::sys_unlink(path.as_ptr(), path.len(), dir)
+}
+
+#[inline(always)]
+pub fn sys_rename(src: &str, dst: &str) -> SyscallResult<()> {
+    raw::sys_rename(src.as_ptr(), src.len(), dst.as_ptr(), dst.len())
 }
 
 #[inline(always)]
 pub fn sys_get_pid() -> u16 {
-    syscall!(Syscall::GetPid) as u16
+    raw::sys_get_pid().unwrap_or(0)
+}
+
+/// `GetPid` through `int 0x80`, regardless of the `fast_syscall` feature
+#[inline(always)]
+pub fn sys_get_pid_int80() -> u16 {
+    syscall_def::macros::int80_syscall0(Syscall::GetPid) as u16
+}
+
+/// `GetPid` through the `syscall` instruction, regardless of the `fast_syscall` feature
+#[inline(always)]
+pub fn sys_get_pid_fast() -> u16 {
+    syscall_def::macros::fast_syscall0(Syscall::GetPid) as u16
 }
 
 #[inline(always)]
 pub fn sys_exit(code: isize) -> ! {
-    syscall!(Syscall::Exit, code as u64);
+    let _ = raw::sys_exit(code);
     unreachable!("This process should be terminated by now.")
 }
 
 // 0x05 add
 #[inline(always)]
-pub fn sys_fork() -> u16 {
-    syscall!(Syscall::Fork) as u16
+pub fn sys_fork() -> SyscallResult<u16> {
+    raw::sys_fork()
+}
+
+// 0x05 add: 为信号量操作分配系统调用
+/// Open the named semaphore, see `SemFlags`, returns its id
+#[inline(always)]
+pub fn sys_sem_open(name: &str, value: usize, flags: SemFlags) -> SyscallResult<u32> {
+    raw::sys_sem_open(name.as_ptr(), name.len(), value, flags.bits())
 }
 
-// 0x05 add: 为四个信号操作分配系统调用
 #[inline(always)]
-pub fn sys_new_sem(key: u32, value: usize) -> bool {
-    syscall!(Syscall::Sem, 0, key as usize, value) == 0
+pub fn sys_sem_close(id: u32) -> SyscallResult<()> {
+    raw::sys_sem(SemOp::Close as usize, id, 0)
 }
 
 #[inline(always)]
-pub fn sys_remove_sem(key: u32) -> bool {
-    syscall!(Syscall::Sem, 1, key as usize) == 0
+pub fn sys_sem_signal(id: u32) -> SyscallResult<()> {
+    raw::sys_sem(SemOp::Signal as usize, id, 0)
 }
 
+/// Wait the semaphore up to `timeout` (forever if `None`), fails with `ETIMEDOUT` on timeout
 #[inline(always)]
-pub fn sys_sem_signal(key: u32) -> bool {
-    syscall!(Syscall::Sem, 2, key as usize) == 0
+pub fn sys_sem_wait(id: u32, timeout: Option<Duration>) -> SyscallResult<()> {
+    raw::sys_sem(SemOp::Wait as usize, id, timeout_ms(timeout))
 }
 
+/// Like `sys_sem_wait`, but fails with `EDEADLK` if the wait would deadlock
 #[inline(always)]
-pub fn sys_sem_wait(key: u32) -> bool {
-    syscall!(Syscall::Sem, 3, key as usize) == 0
+pub fn sys_sem_wait_checked(id: u32, timeout: Option<Duration>) -> SyscallResult<()> {
+    raw::sys_sem(SemOp::WaitChecked as usize, id, timeout_ms(timeout))
+}
+
+#[inline(always)]
+pub fn sys_sem_try_wait(id: u32) -> SyscallResult<()> {
+    raw::sys_sem(SemOp::TryWait as usize, id, 0)
+}
+
+/// Timeout in milliseconds passed to the kernel, `None` to wait forever
+fn timeout_ms(timeout: Option<Duration>) -> usize {
+    timeout.map_or(TIMEOUT_FOREVER, |timeout| {
+        (timeout.as_millis() as usize).min(TIMEOUT_FOREVER - 1)
+    })
+}
+
+#[inline(always)]
+pub fn sys_msg_new(key: u32, capacity: usize) -> SyscallResult<()> {
+    raw::sys_msg_new(key, capacity)
+}
+
+#[inline(always)]
+pub fn sys_msg_remove(key: u32) -> SyscallResult<()> {
+    raw::sys_msg_remove(key)
+}
+
+/// Send a message of type `mtype`, waits up to `timeout` if the queue is full
+#[inline(always)]
+pub fn sys_msg_send(
+    key: u32,
+    mtype: usize,
+    data: &[u8],
+    timeout: Option<Duration>,
+) -> SyscallResult<()> {
+    raw::sys_msg_send(key, mtype, data.as_ptr(), data.len(), timeout_ms(timeout))
+}
+
+/// Receive the first message of type `mtype` (`MSG_ANY_TYPE` for any) into `buf`,
+/// waits up to `timeout` if there is none, returns `(type, length)` of the message
+#[inline(always)]
+pub fn sys_msg_recv(
+    key: u32,
+    mtype: usize,
+    buf: &mut [u8],
+    timeout: Option<Duration>,
+) -> SyscallResult<(usize, usize)> {
+    let mut mtype_out = 0;
+    let len = raw::sys_msg_recv(
+        key,
+        mtype,
+        buf.as_mut_ptr(),
+        buf.len(),
+        &mut mtype_out,
+        timeout_ms(timeout),
+    )?;
+    Ok((mtype_out, len))
+}
+
+/// Block while `futex` equals `expected`, up to `timeout` (forever if `None`)
+///
+/// fails with `EAGAIN` if the value has changed, or `ETIMEDOUT` on timeout
+#[inline(always)]
+pub fn sys_futex_wait(
+    futex: &AtomicU32,
+    expected: u32,
+    timeout: Option<Duration>,
+) -> SyscallResult<()> {
+    let op = FutexOp::Wait as usize;
+    raw::sys_futex(futex.as_ptr(), op, expected as usize, timeout_ms(timeout)).map(|_| ())
+}
+
+/// Wake up at most `count` processes waiting on `futex`, returns the number woken
+#[inline(always)]
+pub fn sys_futex_wake(futex: &AtomicU32, count: usize) -> SyscallResult<usize> {
+    raw::sys_futex(futex.as_ptr(), FutexOp::Wake as usize, count, 0)
 }
 
 // 0x04 加分项，0x05 add：sleep的实现
 #[inline(always)]
-pub fn sys_time() -> u64 {
-    syscall!(Syscall::Time) as u64
+pub fn sys_time() -> SyscallResult<u64> {
+    raw::sys_time()
 }
 
 pub fn sleep(millisecs: u64) {
-    let start = Duration::from_millis(sys_time());
+    let now = || Duration::from_millis(sys_time().unwrap_or(0));
+    let start = now();
     let dur = Duration::from_millis(millisecs as u64);
     let mut current = start;
     while current.saturating_sub(start) < dur {
-        current = Duration::from_millis(sys_time());
+        current = now();
     }
 }
 
 // 0x07 add: brk的系统调用
 #[inline(always)]
-pub fn sys_brk(addr: Option<usize>) -> Option<usize> {
-    const BRK_FAILED: usize = !0;
-    match syscall!(Syscall::Brk, addr.unwrap_or(0)) {
-        BRK_FAILED => None,
-        ret => Some(ret),
-    }
+pub fn sys_brk(addr: Option<usize>) -> SyscallResult {
+    raw::sys_brk(addr.unwrap_or(0))
 }
\ No newline at end of file
@@ -0,0 +1,182 @@
+use super::*;
+use core::marker::PhantomData;
+
+/// A whole block device accessed as a file, e.g. `/dev/hda`
+///
+/// the length is fixed to the size of the device,
+/// blocks written partially are read first and merged
+pub struct BlockFile<T, B>
+where
+    T: BlockDevice<B>,
+    B: BlockTrait,
+{
+    inner: T,
+    offset: usize,
+    len: usize,
+    _block: PhantomData<B>,
+}
+
+impl<T, B> BlockFile<T, B>
+where
+    T: BlockDevice<B>,
+    B: BlockTrait,
+{
+    pub fn new(inner: T) -> FsResult<Self> {
+        let len = inner.block_count()? * B::size();
+        Ok(Self {
+            inner,
+            offset: 0,
+            len,
+            _block: PhantomData,
+        })
+    }
+
+    /// Size of the device in bytes
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Split `pos` into the index of its block and the offset in the block
+    fn locate(pos: usize) -> (usize, usize) {
+        (pos / B::size(), pos % B::size())
+    }
+}
+
+impl<T, B> Read for BlockFile<T, B>
+where
+    T: BlockDevice<B>,
+    B: BlockTrait,
+{
+    fn read(&mut self, buf: &mut [u8]) -> FsResult<usize> {
+        let len = buf.len().min(self.len - self.offset);
+        let mut block = B::default();
+        let mut done = 0;
+
+        while done < len {
+            let (index, start) = Self::locate(self.offset + done);
+            let count = (B::size() - start).min(len - done);
+            self.inner.read_block(index, &mut block)?;
+            buf[done..done + count].copy_from_slice(&block.as_ref()[start..start + count]);
+            done += count;
+        }
+
+        self.offset += done;
+        Ok(done)
+    }
+}
+
+impl<T, B> Write for BlockFile<T, B>
+where
+    T: BlockDevice<B>,
+    B: BlockTrait,
+{
+    fn write(&mut self, buf: &[u8]) -> FsResult<usize> {
+        if !buf.is_empty() && self.offset == self.len {
+            return Err(FsError::WriteZero);
+        }
+
+        let len = buf.len().min(self.len - self.offset);
+        let mut block = B::default();
+        let mut done = 0;
+
+        while done < len {
+            let (index, start) = Self::locate(self.offset + done);
+            let count = (B::size() - start).min(len - done);
+            // 只写入块的一部分时，保留其余内容
+            if count < B::size() {
+                self.inner.read_block(index, &mut block)?;
+            }
+            block.as_mut()[start..start + count].copy_from_slice(&buf[done..done + count]);
+            self.inner.write_block(index, &block)?;
+            done += count;
+        }
+
+        self.offset += done;
+        Ok(done)
+    }
+
+    fn flush(&mut self) -> FsResult {
+        Ok(())
+    }
+}
+
+impl<T, B> Seek for BlockFile<T, B>
+where
+    T: BlockDevice<B>,
+    B: BlockTrait,
+{
+    fn seek(&mut self, pos: SeekFrom) -> FsResult<usize> {
+        let offset = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.len.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.offset.checked_add_signed(offset),
+        };
+
+        match offset {
+            Some(offset) if offset <= self.len => {
+                self.offset = offset;
+                Ok(offset)
+            }
+            _ => Err(FsError::InvalidOffset),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_file() {
+        let disk = RamDisk::new(4);
+        let mut file = BlockFile::new(disk.clone()).unwrap();
+        assert_eq!(file.len(), 2048);
+
+        // 跨越块边界的写入只修改对应的字节
+        disk.blocks()[1].as_mut().fill(0xAA);
+        assert_eq!(file.seek(SeekFrom::Start(500)), Ok(500));
+        assert_eq!(file.write(&[1; 20]), Ok(20));
+        assert_eq!(&disk.blocks()[0].as_ref()[500..], [1; 12]);
+        assert_eq!(disk.blocks()[1].as_ref()[..8], [1; 8]);
+        assert_eq!(disk.blocks()[1].as_ref()[8], 0xAA);
+
+        let mut buf = [0; 24];
+        file.seek(SeekFrom::Current(-22)).unwrap();
+        assert_eq!(file.read(&mut buf), Ok(24));
+        assert_eq!(buf[..2], [0, 0]);
+        assert_eq!(buf[2..22], [1; 20]);
+        assert_eq!(buf[22..], [0xAA, 0xAA]);
+
+        // 长度固定为设备大小
+        assert_eq!(file.seek(SeekFrom::End(-4)), Ok(2044));
+        assert_eq!(file.write(&[2; 8]), Ok(4));
+        assert_eq!(file.write(&[2; 8]), Err(FsError::WriteZero));
+        assert_eq!(file.read(&mut buf), Ok(0));
+        assert_eq!(file.seek(SeekFrom::End(1)), Err(FsError::InvalidOffset));
+        assert_eq!(
+            file.seek(SeekFrom::Current(-2049)),
+            Err(FsError::InvalidOffset)
+        );
+    }
+
+    #[test]
+    fn test_shared_device() {
+        let disk: Arc<dyn BlockDevice<Block512>> = Arc::new(RamDisk::from_bytes(b"shared"));
+        let part = Partition::new(disk.clone(), 0, 1);
+
+        let mut file = BlockFile::new(part).unwrap();
+        let mut buf = Vec::new();
+        assert_eq!(file.read_all(&mut buf), Ok(512));
+        assert_eq!(&buf[..6], b"shared");
+
+        file.seek(SeekFrom::Start(0)).unwrap();
+        file.write_all(b"SH").unwrap();
+        let mut block = Block512::default();
+        disk.read_block(0, &mut block).unwrap();
+        assert_eq!(&block.as_ref()[..6], b"SHared");
+    }
+}
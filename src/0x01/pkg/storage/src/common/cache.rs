@@ -0,0 +1,373 @@
+use super::*;
+use core::num::NonZeroUsize;
+use lru::LruCache;
+
+/// Hit and miss counters of a `BlockCache`
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CacheStats {
+    /// Accesses served from the cache
+    pub hits: usize,
+    /// Accesses that had to load or insert a block
+    pub misses: usize,
+    /// Dirty blocks written back to the device
+    pub writebacks: usize,
+}
+
+struct CachedBlock<B> {
+    block: B,
+    dirty: bool,
+}
+
+struct CacheState<B> {
+    blocks: LruCache<usize, CachedBlock<B>>,
+    stats: CacheStats,
+}
+
+/// A write-back cache of the blocks of `inner`
+///
+/// the least recently used block is evicted when the cache is full,
+/// dirty blocks reach the device only when evicted or on `sync`
+pub struct BlockCache<T, B>
+where
+    T: BlockDevice<B>,
+    B: BlockTrait,
+{
+    inner: T,
+    state: spin::Mutex<CacheState<B>>,
+}
+
+impl<T, B> BlockCache<T, B>
+where
+    T: BlockDevice<B>,
+    B: BlockTrait,
+{
+    /// Cache at most `capacity` blocks of `inner`
+    pub fn new(inner: T, capacity: NonZeroUsize) -> Self {
+        Self {
+            inner,
+            state: spin::Mutex::new(CacheState {
+                blocks: LruCache::new(capacity),
+                stats: CacheStats::default(),
+            }),
+        }
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.state.lock().blocks.cap().get()
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.state.lock().stats
+    }
+
+    /// Number of cached blocks not written to the device yet
+    pub fn dirty_count(&self) -> usize {
+        let state = self.state.lock();
+        state
+            .blocks
+            .iter()
+            .filter(|(_, cached)| cached.dirty)
+            .count()
+    }
+
+    /// Write all dirty blocks back to the device, they stay cached
+    pub fn sync(&self) -> FsResult {
+        let mut state = self.state.lock();
+        let CacheState { blocks, stats } = &mut *state;
+        for (&offset, cached) in blocks.iter_mut().filter(|(_, cached)| cached.dirty) {
+            self.inner.write_block(offset, &cached.block)?;
+            cached.dirty = false;
+            stats.writebacks += 1;
+        }
+        Ok(())
+    }
+
+    /// Make room for a new block, the evicted block is written back first if dirty
+    ///
+    /// if the write back fails, the block stays cached and nothing is lost
+    fn evict(&self, state: &mut CacheState<B>) -> FsResult {
+        if state.blocks.len() < state.blocks.cap().get() {
+            return Ok(());
+        }
+        if let Some((&offset, cached)) = state.blocks.peek_lru()
+            && cached.dirty
+        {
+            self.inner.write_block(offset, &cached.block)?;
+            state.stats.writebacks += 1;
+        }
+        state.blocks.pop_lru();
+        Ok(())
+    }
+}
+
+impl<T, B> BlockDevice<B> for BlockCache<T, B>
+where
+    T: BlockDevice<B>,
+    B: BlockTrait,
+{
+    fn block_count(&self) -> FsResult<usize> {
+        self.inner.block_count()
+    }
+
+    fn read_block(&self, offset: usize, block: &mut B) -> FsResult {
+        let mut guard = self.state.lock();
+        let state = &mut *guard;
+        if let Some(cached) = state.blocks.get(&offset) {
+            *block = cached.block.clone();
+            state.stats.hits += 1;
+            return Ok(());
+        }
+
+        state.stats.misses += 1;
+        self.inner.read_block(offset, block)?;
+        self.evict(state)?;
+        state.blocks.push(
+            offset,
+            CachedBlock {
+                block: block.clone(),
+                dirty: false,
+            },
+        );
+        Ok(())
+    }
+
+    fn write_block(&self, offset: usize, block: &B) -> FsResult {
+        let mut guard = self.state.lock();
+        let state = &mut *guard;
+        if let Some(cached) = state.blocks.get_mut(&offset) {
+            cached.block = block.clone();
+            cached.dirty = true;
+            state.stats.hits += 1;
+            return Ok(());
+        }
+
+        // 整块写入，无需先从设备读取
+        state.stats.misses += 1;
+        if offset >= self.inner.block_count()? {
+            return Err(FsError::InvalidOffset);
+        }
+        self.evict(state)?;
+        state.blocks.push(
+            offset,
+            CachedBlock {
+                block: block.clone(),
+                dirty: true,
+            },
+        );
+        Ok(())
+    }
+
+    fn block_size(&self) -> usize {
+        self.inner.block_size()
+    }
+}
+
+impl<T, B> Drop for BlockCache<T, B>
+where
+    T: BlockDevice<B>,
+    B: BlockTrait,
+{
+    fn drop(&mut self) {
+        if let Err(err) = self.sync() {
+            warn!("Failed to write back the block cache: {:?}", err);
+        }
+    }
+}
+
+impl<T, B> core::fmt::Debug for BlockCache<T, B>
+where
+    T: BlockDevice<B>,
+    B: BlockTrait,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let state = self.state.lock();
+        f.debug_struct("BlockCache")
+            .field("capacity", &state.blocks.cap())
+            .field("cached", &state.blocks.len())
+            .field("stats", &state.stats)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    /// A `RamDisk` counting the accesses reaching it
+    #[derive(Clone)]
+    struct CountingDisk {
+        disk: RamDisk,
+        reads: Arc<AtomicUsize>,
+        writes: Arc<AtomicUsize>,
+    }
+
+    impl CountingDisk {
+        fn new(count: usize) -> Self {
+            Self {
+                disk: RamDisk::new(count),
+                reads: Arc::new(AtomicUsize::new(0)),
+                writes: Arc::new(AtomicUsize::new(0)),
+            }
+        }
+
+        fn reads(&self) -> usize {
+            self.reads.load(Ordering::Relaxed)
+        }
+
+        fn writes(&self) -> usize {
+            self.writes.load(Ordering::Relaxed)
+        }
+    }
+
+    impl BlockDevice<Block512> for CountingDisk {
+        fn block_count(&self) -> FsResult<usize> {
+            self.disk.block_count()
+        }
+
+        fn read_block(&self, offset: usize, block: &mut Block512) -> FsResult {
+            self.reads.fetch_add(1, Ordering::Relaxed);
+            self.disk.read_block(offset, block)
+        }
+
+        fn write_block(&self, offset: usize, block: &Block512) -> FsResult {
+            self.writes.fetch_add(1, Ordering::Relaxed);
+            self.disk.write_block(offset, block)
+        }
+    }
+
+    fn block(byte: u8) -> Block512 {
+        Block::new(&[byte; 512])
+    }
+
+    fn cache(disk: &CountingDisk, capacity: usize) -> BlockCache<CountingDisk, Block512> {
+        BlockCache::new(disk.clone(), NonZeroUsize::new(capacity).unwrap())
+    }
+
+    #[test]
+    fn test_read_hit() {
+        let disk = CountingDisk::new(8);
+        disk.disk.blocks()[3] = block(3);
+        let cache = cache(&disk, 4);
+
+        let mut buf = Block512::default();
+        for _ in 0..5 {
+            cache.read_block(3, &mut buf).unwrap();
+            assert_eq!(buf.as_ref(), block(3).as_ref());
+        }
+
+        assert_eq!(disk.reads(), 1);
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 4,
+                misses: 1,
+                writebacks: 0
+            }
+        );
+        assert_eq!(cache.read_block(8, &mut buf), Err(FsError::InvalidOffset));
+    }
+
+    #[test]
+    fn test_lru_eviction() {
+        let disk = CountingDisk::new(8);
+        let cache = cache(&disk, 2);
+        let mut buf = Block512::default();
+
+        cache.read_block(0, &mut buf).unwrap();
+        cache.read_block(1, &mut buf).unwrap();
+        // 访问 0 后，1 成为最久未使用的块
+        cache.read_block(0, &mut buf).unwrap();
+        cache.read_block(2, &mut buf).unwrap();
+        assert_eq!(disk.reads(), 3);
+
+        cache.read_block(0, &mut buf).unwrap();
+        assert_eq!(disk.reads(), 3);
+        cache.read_block(1, &mut buf).unwrap();
+        assert_eq!(disk.reads(), 4);
+        assert_eq!(cache.stats().hits, 2);
+        assert_eq!(cache.stats().misses, 4);
+    }
+
+    #[test]
+    fn test_write_back() {
+        let disk = CountingDisk::new(8);
+        let cache = cache(&disk, 2);
+        let mut buf = Block512::default();
+
+        // 写入只修改缓存，整块写入也不读取设备
+        cache.write_block(0, &block(1)).unwrap();
+        cache.write_block(0, &block(2)).unwrap();
+        assert_eq!((disk.reads(), disk.writes()), (0, 0));
+        assert_eq!(cache.dirty_count(), 1);
+        cache.read_block(0, &mut buf).unwrap();
+        assert_eq!(buf.as_ref(), block(2).as_ref());
+        assert_eq!(disk.disk.blocks()[0].as_ref(), [0; 512]);
+
+        // 淘汰脏块时写回设备
+        cache.read_block(1, &mut buf).unwrap();
+        cache.read_block(2, &mut buf).unwrap();
+        assert_eq!(disk.writes(), 1);
+        assert_eq!(disk.disk.blocks()[0].as_ref(), block(2).as_ref());
+        assert_eq!(cache.dirty_count(), 0);
+
+        // 淘汰干净的块不写入
+        cache.read_block(3, &mut buf).unwrap();
+        assert_eq!(disk.writes(), 1);
+        assert_eq!(cache.stats().writebacks, 1);
+
+        assert_eq!(cache.write_block(8, &block(8)), Err(FsError::InvalidOffset));
+    }
+
+    #[test]
+    fn test_sync() {
+        let disk = CountingDisk::new(8);
+        let cache = cache(&disk, 4);
+
+        for i in 0..3 {
+            cache.write_block(i, &block(i as u8 + 1)).unwrap();
+        }
+        assert_eq!(cache.dirty_count(), 3);
+        cache.sync().unwrap();
+        assert_eq!(disk.writes(), 3);
+        assert_eq!(cache.dirty_count(), 0);
+        for i in 0..3 {
+            assert_eq!(disk.disk.blocks()[i].as_ref(), block(i as u8 + 1).as_ref());
+        }
+
+        // 已同步的块仍在缓存中，再次同步不写入
+        cache.sync().unwrap();
+        assert_eq!(disk.writes(), 3);
+        let mut buf = Block512::default();
+        cache.read_block(1, &mut buf).unwrap();
+        assert_eq!(disk.reads(), 0);
+
+        // 释放缓存时写回剩余的脏块
+        cache.write_block(4, &block(5)).unwrap();
+        drop(cache);
+        assert_eq!(disk.writes(), 4);
+        assert_eq!(disk.disk.blocks()[4].as_ref(), block(5).as_ref());
+    }
+
+    #[test]
+    fn test_filesystem_on_cache() {
+        let disk = CountingDisk::new(8);
+        let cache = Arc::new(cache(&disk, 4));
+        let mut file = BlockFile::new(cache.clone()).unwrap();
+
+        file.write_all(&[7; 1000]).unwrap();
+        file.seek(SeekFrom::Start(0)).unwrap();
+        let mut buf = vec![0; 1000];
+        assert_eq!(file.read(&mut buf), Ok(1000));
+        assert_eq!(buf, [7; 1000]);
+
+        // 仅第二块的部分写入需要读取设备
+        assert_eq!((disk.reads(), disk.writes()), (1, 0));
+        cache.sync().unwrap();
+        assert_eq!(disk.writes(), 2);
+    }
+}
@@ -0,0 +1,81 @@
+use crate::*;
+
+/// The `Read` trait allows for reading bytes from a source.
+pub trait Read {
+    /// Pull some bytes from this source into the specified buffer, returning
+    /// how many bytes were read.
+    fn read(&mut self, buf: &mut [u8]) -> FsResult<usize>;
+
+    /// Read all bytes until EOF in this source, placing them into `buf`.
+    fn read_all(&mut self, buf: &mut Vec<u8>) -> FsResult<usize> {
+        let start_len = buf.len();
+        let mut len = start_len;
+        loop {
+            // 缓冲区已满时扩容
+            if len == buf.len() {
+                buf.resize(len + 512, 0);
+            }
+            match self.read(&mut buf[len..]) {
+                Ok(0) => break,
+                Ok(n) => len += n,
+                Err(e) => {
+                    buf.truncate(len);
+                    return Err(e);
+                }
+            }
+        }
+        buf.truncate(len);
+        Ok(len - start_len)
+    }
+}
+
+/// The `Write` trait allows for writing bytes to a source.
+///
+/// NOTE: Leave here to ensure flexibility for the optional lab task.
+pub trait Write {
+    /// Write a buffer into this writer, returning how many bytes were written.
+    fn write(&mut self, buf: &[u8]) -> FsResult<usize>;
+
+    /// Flush this output stream, ensuring that all intermediately buffered
+    /// contents reach their destination.
+    fn flush(&mut self) -> FsResult;
+
+    /// Truncate or extend (with zeros) the underlying file to `len` bytes.
+    fn set_len(&mut self, _len: usize) -> FsResult {
+        Err(FsError::NotSupported)
+    }
+
+    /// Attempts to write an entire buffer into this writer.
+    fn write_all(&mut self, mut buf: &[u8]) -> FsResult {
+        while !buf.is_empty() {
+            match self.write(buf)? {
+                0 => return Err(FsError::WriteZero),
+                n => buf = &buf[n..],
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Enumeration of possible methods to seek within an I/O object.
+#[derive(Copy, PartialEq, Eq, Clone, Debug)]
+pub enum SeekFrom {
+    /// Sets the offset to the provided number of bytes.
+    Start(usize),
+
+    /// Sets the offset to the size of this object plus the offset.
+    End(isize),
+
+    /// Sets the offset to the current position plus the offset.
+    Current(isize),
+}
+
+/// The `Seek` trait provides a cursor within byte stream.
+pub trait Seek {
+    /// Seek to an offset, in bytes, in a stream.
+    fn seek(&mut self, pos: SeekFrom) -> FsResult<usize>;
+}
+
+pub trait FileIO: Read + Write + Seek {}
+
+impl<T: Read + Write + Seek> FileIO for T {}
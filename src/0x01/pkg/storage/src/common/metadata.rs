@@ -0,0 +1,99 @@
+use crate::*;
+use chrono::{DateTime, Utc};
+
+pub type FsTime = DateTime<Utc>;
+
+static CLOCK: spin::Once<fn() -> FsTime> = spin::Once::new();
+
+/// Set the source of the current time, used to stamp created and modified entries
+pub fn set_clock(clock: fn() -> FsTime) {
+    CLOCK.call_once(|| clock);
+}
+
+/// The current time, or the unix epoch if no clock is set
+pub fn now() -> FsTime {
+    match CLOCK.get() {
+        Some(clock) => clock(),
+        None => DateTime::from_timestamp_millis(0).unwrap(),
+    }
+}
+
+/// Type of file entry
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FileType {
+    /// A plain file
+    File,
+    /// A Directory
+    Directory,
+    /// A symbolic link
+    Symlink,
+    /// A character device, e.g. a serial port
+    CharDevice,
+    /// A block device, e.g. a disk or a partition
+    BlockDevice,
+}
+
+#[derive(Debug)]
+/// File entry metadata
+pub struct Metadata {
+    /// Name of the entry
+    pub name: String,
+    /// The type of entry
+    pub entry_type: FileType,
+    /// Length of the file in bytes, 0 for directories
+    pub len: usize,
+    /// Creation time of the file
+    pub created: Option<FsTime>,
+    /// Modification time of the file
+    pub modified: Option<FsTime>,
+    /// Access time of the file
+    pub accessed: Option<FsTime>,
+}
+
+impl Metadata {
+    /// Create a new metadata object
+    pub fn new(
+        name: String,
+        entry_type: FileType,
+        len: usize,
+        created: Option<FsTime>,
+        modified: Option<FsTime>,
+        accessed: Option<FsTime>,
+    ) -> Self {
+        Self {
+            len,
+            name,
+            created,
+            modified,
+            accessed,
+            entry_type,
+        }
+    }
+
+    /// Return `true` if the entry is a file
+    #[inline]
+    pub fn is_file(&self) -> bool {
+        self.entry_type == FileType::File
+    }
+
+    /// Return `true` if the entry is a directory
+    #[inline]
+    pub fn is_dir(&self) -> bool {
+        self.entry_type == FileType::Directory
+    }
+
+    /// Return `true` if the entry is a symbolic link
+    #[inline]
+    pub fn is_symlink(&self) -> bool {
+        self.entry_type == FileType::Symlink
+    }
+
+    /// Return `true` if the entry is a character or block device
+    #[inline]
+    pub fn is_device(&self) -> bool {
+        matches!(
+            self.entry_type,
+            FileType::CharDevice | FileType::BlockDevice
+        )
+    }
+}
@@ -0,0 +1,32 @@
+#[macro_use]
+mod macros;
+
+mod block;
+mod blockfile;
+mod cache;
+mod device;
+mod error;
+mod filehandle;
+mod filesystem;
+mod io;
+mod metadata;
+mod mount;
+mod ramdisk;
+mod vfs;
+
+use super::*;
+
+pub use block::*;
+pub use blockfile::*;
+pub use cache::*;
+pub use device::*;
+pub use error::*;
+pub use filehandle::*;
+pub use filesystem::*;
+pub use io::*;
+pub use metadata::*;
+pub use mount::*;
+pub use ramdisk::*;
+pub use vfs::*;
+
+pub const PATH_SEPARATOR: char = '/';
@@ -0,0 +1,107 @@
+use super::*;
+
+/// Mount a file system to a specific path
+///
+/// NOTE: strip the mount point from the path before calling the underlying file system
+pub struct Mount {
+    pub fs: Box<dyn FileSystem>,
+    pub mount_point: Box<str>,
+}
+
+impl Mount {
+    #[inline]
+    pub fn new(fs: Box<dyn FileSystem>, mount_point: Box<str>) -> Self {
+        Self { fs, mount_point }
+    }
+
+    /// The path relative to the mount point, `None` if `path` is not under it
+    ///
+    /// only whole components are matched, `/tmp` does not contain `/tmpfile`
+    pub fn relative<'a>(&self, path: &'a str) -> Option<&'a str> {
+        let mount_point = self.mount_point.trim_end_matches(PATH_SEPARATOR);
+        match path.strip_prefix(mount_point)? {
+            "" => Some("/"),
+            rest if rest.starts_with(PATH_SEPARATOR) => Some(rest),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    fn trim_mount_point<'a>(&self, path: &'a str) -> &'a str {
+        self.relative(path).unwrap_or(path)
+    }
+}
+
+impl FileSystem for Mount {
+    #[inline]
+    fn read_dir(&self, path: &str) -> FsResult<Box<dyn Iterator<Item = Metadata> + Send>> {
+        self.fs.read_dir(self.trim_mount_point(path))
+    }
+
+    #[inline]
+    fn open_file(&self, path: &str) -> FsResult<FileHandle> {
+        self.fs.open_file(self.trim_mount_point(path))
+    }
+
+    #[inline]
+    fn metadata(&self, path: &str) -> FsResult<Metadata> {
+        self.fs.metadata(self.trim_mount_point(path))
+    }
+
+    #[inline]
+    fn exists(&self, path: &str) -> FsResult<bool> {
+        self.fs.exists(self.trim_mount_point(path))
+    }
+
+    #[inline]
+    fn create_file(&self, path: &str) -> FsResult<FileHandle> {
+        self.fs.create_file(self.trim_mount_point(path))
+    }
+
+    #[inline]
+    fn append_file(&self, path: &str) -> FsResult<FileHandle> {
+        self.fs.append_file(self.trim_mount_point(path))
+    }
+
+    #[inline]
+    fn create_dir(&self, path: &str) -> FsResult {
+        self.fs.create_dir(self.trim_mount_point(path))
+    }
+
+    #[inline]
+    fn remove_file(&self, path: &str) -> FsResult {
+        self.fs.remove_file(self.trim_mount_point(path))
+    }
+
+    #[inline]
+    fn remove_dir(&self, path: &str) -> FsResult {
+        self.fs.remove_dir(self.trim_mount_point(path))
+    }
+
+    #[inline]
+    fn copy_file(&self, src: &str, dst: &str) -> FsResult {
+        self.fs
+            .copy_file(self.trim_mount_point(src), self.trim_mount_point(dst))
+    }
+
+    #[inline]
+    fn move_file(&self, src: &str, dst: &str) -> FsResult {
+        self.fs
+            .move_file(self.trim_mount_point(src), self.trim_mount_point(dst))
+    }
+
+    #[inline]
+    fn move_dir(&self, src: &str, dst: &str) -> FsResult {
+        self.fs
+            .move_dir(self.trim_mount_point(src), self.trim_mount_point(dst))
+    }
+}
+
+impl core::fmt::Debug for Mount {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Mount")
+            .field("mount_point", &self.mount_point)
+            .field("fs", &self.fs)
+            .finish()
+    }
+}
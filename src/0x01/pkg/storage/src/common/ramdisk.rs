@@ -0,0 +1,67 @@
+use super::*;
+
+/// A block device in memory
+///
+/// clones share the same blocks, so a filesystem can be built on one clone
+/// while another one is used to inspect or reuse the content
+#[derive(Clone)]
+pub struct RamDisk {
+    blocks: Arc<spin::Mutex<Vec<Block512>>>,
+}
+
+impl RamDisk {
+    /// A zeroed disk of `count` blocks
+    pub fn new(count: usize) -> Self {
+        Self::from_blocks(vec![Block::default(); count])
+    }
+
+    pub fn from_blocks(blocks: Vec<Block512>) -> Self {
+        Self {
+            blocks: Arc::new(spin::Mutex::new(blocks)),
+        }
+    }
+
+    /// A disk holding `data`, the last block is padded with zeros
+    pub fn from_bytes(data: &[u8]) -> Self {
+        let blocks = data
+            .chunks(Block512::size())
+            .map(|chunk| {
+                let mut block = Block::default();
+                block.as_mut()[..chunk.len()].copy_from_slice(chunk);
+                block
+            })
+            .collect();
+        Self::from_blocks(blocks)
+    }
+
+    /// Direct access to the blocks of the disk
+    pub fn blocks(&self) -> spin::MutexGuard<'_, Vec<Block512>> {
+        self.blocks.lock()
+    }
+}
+
+impl BlockDevice<Block512> for RamDisk {
+    fn block_count(&self) -> FsResult<usize> {
+        Ok(self.blocks.lock().len())
+    }
+
+    fn read_block(&self, offset: usize, block: &mut Block512) -> FsResult {
+        let blocks = self.blocks.lock();
+        *block = blocks.get(offset).ok_or(FsError::InvalidOffset)?.clone();
+        Ok(())
+    }
+
+    fn write_block(&self, offset: usize, block: &Block512) -> FsResult {
+        let mut blocks = self.blocks.lock();
+        *blocks.get_mut(offset).ok_or(FsError::InvalidOffset)? = block.clone();
+        Ok(())
+    }
+}
+
+impl core::fmt::Debug for RamDisk {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("RamDisk")
+            .field("blocks", &self.blocks.lock().len())
+            .finish()
+    }
+}
@@ -0,0 +1,283 @@
+//! The virtual filesystem, joining mounted filesystems into one tree
+
+#[cfg(test)]
+mod tests;
+
+use super::*;
+
+/// Normalize `path` into an absolute path
+///
+/// duplicate separators and `.` are removed, `..` is resolved lexically and
+/// stays at the root, relative paths are taken from the root
+pub fn normalize(path: &str) -> String {
+    let mut components = Vec::new();
+    for name in path.split(PATH_SEPARATOR) {
+        match name {
+            "" | "." => {}
+            ".." => {
+                components.pop();
+            }
+            name => components.push(name),
+        }
+    }
+
+    let mut normalized = String::with_capacity(path.len() + 1);
+    for name in components {
+        normalized.push(PATH_SEPARATOR);
+        normalized.push_str(name);
+    }
+    if normalized.is_empty() {
+        normalized.push(PATH_SEPARATOR);
+    }
+    normalized
+}
+
+/// Resolve `path` against the directory `cwd` and normalize it
+pub fn join(cwd: &str, path: &str) -> String {
+    if path.starts_with(PATH_SEPARATOR) {
+        normalize(path)
+    } else {
+        normalize(&format!("{cwd}{PATH_SEPARATOR}{path}"))
+    }
+}
+
+/// The mount table, a path is served by the mount with the longest
+/// matching mount point
+///
+/// Paths are normalized before resolution, so `.`, `..` and duplicate
+/// separators never reach the mounted filesystems. Mount points are shown in
+/// the listing of their parent directory, even if the parent filesystem has
+/// no such directory.
+pub struct Vfs {
+    mounts: spin::RwLock<Vec<Arc<Mount>>>,
+}
+
+impl Vfs {
+    pub const fn new() -> Self {
+        Self {
+            mounts: spin::RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Mount `fs` at `mount_point`
+    ///
+    /// fails if something is already mounted there, or it is a file in the
+    /// parent filesystem
+    pub fn mount(&self, mount_point: &str, fs: Box<dyn FileSystem>) -> FsResult {
+        let mount_point = normalize(mount_point);
+        if self.mount_point(&mount_point) {
+            return Err(FsError::AlreadyExists);
+        }
+        match self.metadata(&mount_point) {
+            Ok(meta) if !meta.is_dir() => return Err(FsError::NotADirectory),
+            _ => {}
+        }
+
+        self.mounts
+            .write()
+            .push(Arc::new(Mount::new(fs, mount_point.into())));
+        Ok(())
+    }
+
+    /// Unmount the filesystem at `mount_point`
+    ///
+    /// fails with `Busy` if other filesystems are mounted below it, files
+    /// opened on it stay usable
+    pub fn umount(&self, mount_point: &str) -> FsResult {
+        let mount_point = normalize(mount_point);
+        let mut mounts = self.mounts.write();
+        let index = mounts
+            .iter()
+            .position(|mount| *mount.mount_point == *mount_point)
+            .ok_or(FsError::InvalidPath(mount_point.clone()))?;
+        if mounts
+            .iter()
+            .any(|mount| is_below(&mount.mount_point, &mount_point))
+        {
+            return Err(FsError::Busy);
+        }
+        mounts.remove(index);
+        Ok(())
+    }
+
+    /// Mount points in the order they were mounted
+    pub fn mount_points(&self) -> Vec<String> {
+        self.mounts
+            .read()
+            .iter()
+            .map(|mount| mount.mount_point.as_ref().into())
+            .collect()
+    }
+
+    /// The mount serving `path` and the path relative to it
+    pub fn resolve(&self, path: &str) -> FsResult<(Arc<Mount>, String)> {
+        let path = normalize(path);
+        let mounts = self.mounts.read();
+        let (mount, relative) = mounts
+            .iter()
+            .filter_map(|mount| Some((mount, mount.relative(&path)?)))
+            .max_by_key(|(mount, _)| mount.mount_point.len())
+            .ok_or(FsError::FileNotFound)?;
+        Ok((mount.clone(), relative.into()))
+    }
+
+    /// Whether `path` is a mount point
+    fn mount_point(&self, path: &str) -> bool {
+        self.mounts
+            .read()
+            .iter()
+            .any(|mount| *mount.mount_point == *path)
+    }
+
+    /// Fails with `Busy` if `path` is a mount point or contains one
+    fn check_busy(&self, path: &str) -> FsResult {
+        let path = normalize(path);
+        let mounts = self.mounts.read();
+        if mounts
+            .iter()
+            .any(|mount| *mount.mount_point == *path || is_below(&mount.mount_point, &path))
+        {
+            return Err(FsError::Busy);
+        }
+        Ok(())
+    }
+
+    /// Resolve `src` and `dst`, which must be on the same mount
+    fn resolve_pair(&self, src: &str, dst: &str) -> FsResult<(Arc<Mount>, String, String)> {
+        let (mount, src) = self.resolve(src)?;
+        let (dst_mount, dst) = self.resolve(dst)?;
+        if !Arc::ptr_eq(&mount, &dst_mount) {
+            return Err(FsError::CrossDevice);
+        }
+        Ok((mount, src, dst))
+    }
+}
+
+impl Default for Vfs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether `path` is strictly below the directory `dir`
+fn is_below(path: &str, dir: &str) -> bool {
+    let dir = dir.trim_end_matches(PATH_SEPARATOR);
+    path.strip_prefix(dir)
+        .is_some_and(|rest| rest.len() > 1 && rest.starts_with(PATH_SEPARATOR))
+}
+
+impl FileSystem for Vfs {
+    fn read_dir(&self, path: &str) -> FsResult<Box<dyn Iterator<Item = Metadata> + Send>> {
+        let path = normalize(path);
+        let (mount, relative) = self.resolve(&path)?;
+        let mut entries: Vec<Metadata> = mount.fs.read_dir(&relative)?.collect();
+
+        // 挂载点显示为父目录中的目录
+        for mount in self.mounts.read().iter() {
+            let Some((parent, name)) = mount.mount_point.rsplit_once(PATH_SEPARATOR) else {
+                continue;
+            };
+            let parent = if parent.is_empty() { "/" } else { parent };
+            if parent == path && !name.is_empty() && !entries.iter().any(|meta| meta.name == name) {
+                entries.push(Metadata::new(
+                    name.into(),
+                    FileType::Directory,
+                    0,
+                    None,
+                    None,
+                    None,
+                ));
+            }
+        }
+
+        Ok(Box::new(entries.into_iter()))
+    }
+
+    fn open_file(&self, path: &str) -> FsResult<FileHandle> {
+        let (mount, path) = self.resolve(path)?;
+        mount.fs.open_file(&path)
+    }
+
+    fn metadata(&self, path: &str) -> FsResult<Metadata> {
+        let path = normalize(path);
+        let (mount, relative) = self.resolve(&path)?;
+        let mut meta = mount.fs.metadata(&relative)?;
+        // 挂载的文件系统的根目录以挂载点命名
+        if relative == "/" {
+            meta.name = match path.rsplit(PATH_SEPARATOR).next() {
+                Some(name) if !name.is_empty() => name.into(),
+                _ => path,
+            };
+        }
+        Ok(meta)
+    }
+
+    fn exists(&self, path: &str) -> FsResult<bool> {
+        let (mount, path) = self.resolve(path)?;
+        mount.fs.exists(&path)
+    }
+
+    fn create_file(&self, path: &str) -> FsResult<FileHandle> {
+        let (mount, path) = self.resolve(path)?;
+        mount.fs.create_file(&path)
+    }
+
+    fn append_file(&self, path: &str) -> FsResult<FileHandle> {
+        let (mount, path) = self.resolve(path)?;
+        mount.fs.append_file(&path)
+    }
+
+    fn create_dir(&self, path: &str) -> FsResult {
+        if self.mount_point(&normalize(path)) {
+            return Err(FsError::AlreadyExists);
+        }
+        let (mount, path) = self.resolve(path)?;
+        mount.fs.create_dir(&path)
+    }
+
+    fn remove_file(&self, path: &str) -> FsResult {
+        let (mount, path) = self.resolve(path)?;
+        mount.fs.remove_file(&path)
+    }
+
+    fn remove_dir(&self, path: &str) -> FsResult {
+        self.check_busy(path)?;
+        let (mount, path) = self.resolve(path)?;
+        mount.fs.remove_dir(&path)
+    }
+
+    /// Copies the file, which may be on another mounted filesystem
+    fn copy_file(&self, src: &str, dst: &str) -> FsResult {
+        match self.resolve_pair(src, dst) {
+            Ok((mount, src, dst)) => mount.fs.copy_file(&src, &dst),
+            Err(FsError::CrossDevice) => {
+                let mut data = Vec::new();
+                self.open_file(src)?.read_all(&mut data)?;
+                let mut file = self.create_file(dst)?;
+                file.write_all(&data)?;
+                file.flush()
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    fn move_file(&self, src: &str, dst: &str) -> FsResult {
+        let (mount, src, dst) = self.resolve_pair(src, dst)?;
+        mount.fs.move_file(&src, &dst)
+    }
+
+    fn move_dir(&self, src: &str, dst: &str) -> FsResult {
+        self.check_busy(src)?;
+        self.check_busy(dst)?;
+        let (mount, src, dst) = self.resolve_pair(src, dst)?;
+        mount.fs.move_dir(&src, &dst)
+    }
+}
+
+impl core::fmt::Debug for Vfs {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Vfs")
+            .field("mounts", &*self.mounts.read())
+            .finish()
+    }
+}
@@ -0,0 +1,138 @@
+//! Tests of the mount table and path resolution
+
+use super::*;
+use crate::fs::fat16::Fat16;
+use crate::fs::fat16::tests::{names, read_to_end, sample_image, write_file};
+use crate::fs::tmpfs::TmpFs;
+
+/// A FAT16 root with a tmpfs at `/tmp` and another one at `/tmp/deep`
+fn sample() -> Vfs {
+    let vfs = Vfs::new();
+    vfs.mount("/", Box::new(Fat16::new(sample_image().disk()).unwrap()))
+        .unwrap();
+    vfs.mount("/tmp", Box::new(TmpFs::new())).unwrap();
+    vfs.mount("/tmp//deep/", Box::new(TmpFs::new())).unwrap();
+    vfs
+}
+
+#[test]
+fn test_normalize() {
+    assert_eq!(normalize("/"), "/");
+    assert_eq!(normalize(""), "/");
+    assert_eq!(normalize("//a///b/"), "/a/b");
+    assert_eq!(normalize("/a/./b/../c"), "/a/c");
+    assert_eq!(normalize("/../../a/.."), "/");
+    assert_eq!(normalize("a/b"), "/a/b");
+
+    assert_eq!(join("/app", "sub/note.md"), "/app/sub/note.md");
+    assert_eq!(join("/app/sub", ".."), "/app");
+    assert_eq!(join("/app", "/tmp/./x"), "/tmp/x");
+    assert_eq!(join("/", "../.."), "/");
+}
+
+#[test]
+fn test_resolve() {
+    let vfs = sample();
+    assert_eq!(vfs.mount_points(), ["/", "/tmp", "/tmp/deep"]);
+
+    let resolve = |path| {
+        let (mount, relative) = vfs.resolve(path).unwrap();
+        (String::from(&*mount.mount_point), relative)
+    };
+    assert_eq!(resolve("/APP/FAB"), ("/".into(), "/APP/FAB".into()));
+    assert_eq!(resolve("/tmp"), ("/tmp".into(), "/".into()));
+    assert_eq!(
+        resolve("/tmp/a/../deep/x"),
+        ("/tmp/deep".into(), "/x".into())
+    );
+    assert_eq!(resolve("/tmpfile"), ("/".into(), "/tmpfile".into()));
+    assert_eq!(resolve("/tmp/deeper"), ("/tmp".into(), "/deeper".into()));
+
+    // 每个挂载点各自独立
+    write_file(&vfs, "/tmp/a.txt", b"tmp");
+    write_file(&vfs, "/tmp/deep/a.txt", b"deep");
+    assert_eq!(read_to_end(&vfs, "/tmp/a.txt"), b"tmp");
+    assert_eq!(read_to_end(&vfs, "/tmp/deep/../deep//a.txt"), b"deep");
+    assert_eq!(read_to_end(&vfs, "/APP/../HELLO.TXT"), b"Hello, FAT16!");
+
+    // 挂载点出现在父目录的列表中
+    assert_eq!(
+        names(&vfs, "/"),
+        ["HELLO.TXT", "BIG.BIN", "EMPTY", "APP", "tmp"]
+    );
+    assert_eq!(names(&vfs, "/tmp"), ["a.txt", "deep"]);
+    let meta = vfs.metadata("/tmp/deep").unwrap();
+    assert!(meta.is_dir());
+    assert_eq!(meta.name, "deep");
+    assert_eq!(vfs.metadata("/").unwrap().name, "/");
+    assert_eq!(vfs.exists("/tmp/deep/a.txt"), Ok(true));
+
+    assert_eq!(
+        Vfs::new().open_file("/a").unwrap_err(),
+        FsError::FileNotFound
+    );
+}
+
+#[test]
+fn test_mount_and_umount() {
+    let vfs = sample();
+
+    assert_eq!(
+        vfs.mount("/tmp/", Box::new(TmpFs::new())),
+        Err(FsError::AlreadyExists)
+    );
+    assert_eq!(
+        vfs.mount("/HELLO.TXT", Box::new(TmpFs::new())),
+        Err(FsError::NotADirectory)
+    );
+    assert_eq!(vfs.umount("/tmp"), Err(FsError::Busy));
+    assert!(matches!(vfs.umount("/APP"), Err(FsError::InvalidPath(_))));
+
+    // 卸载后仍能使用已打开的文件，路径回到父文件系统
+    write_file(&vfs, "/tmp/deep/a.txt", b"deep");
+    let mut file = vfs.open_file("/tmp/deep/a.txt").unwrap();
+    vfs.umount("/tmp/./deep").unwrap();
+    let mut buf = Vec::new();
+    file.read_all(&mut buf).unwrap();
+    assert_eq!(buf, b"deep");
+    assert_eq!(vfs.exists("/tmp/deep/a.txt"), Ok(false));
+    assert_eq!(names(&vfs, "/tmp"), Vec::<String>::new());
+
+    vfs.umount("/tmp").unwrap();
+    assert_eq!(vfs.exists("/tmp"), Ok(false));
+    assert_eq!(vfs.mount_points(), ["/"]);
+    vfs.mount("/APP/SUB", Box::new(TmpFs::new())).unwrap();
+    assert_eq!(names(&vfs, "/APP/SUB"), Vec::<String>::new());
+}
+
+#[test]
+fn test_operations() {
+    let vfs = sample();
+
+    vfs.create_dir("/tmp/dir").unwrap();
+    write_file(&vfs, "/tmp/dir/a", b"data");
+    vfs.move_file("/tmp/dir/a", "/tmp/b").unwrap();
+    assert_eq!(read_to_end(&vfs, "/tmp/b"), b"data");
+    vfs.move_dir("/tmp/dir", "/tmp/dir2").unwrap();
+    assert_eq!(vfs.create_dir("/tmp/deep"), Err(FsError::AlreadyExists));
+
+    // 跨文件系统只能复制，不能移动
+    vfs.copy_file("/HELLO.TXT", "/tmp/hello").unwrap();
+    assert_eq!(read_to_end(&vfs, "/tmp/hello"), b"Hello, FAT16!");
+    assert_eq!(
+        vfs.move_file("/tmp/hello", "/tmp/deep/hello"),
+        Err(FsError::CrossDevice)
+    );
+    assert_eq!(
+        vfs.move_dir("/tmp/dir2", "/APP/dir2"),
+        Err(FsError::CrossDevice)
+    );
+
+    // 挂载点及包含挂载点的目录不能删除或移动
+    assert_eq!(vfs.remove_dir("/tmp/deep"), Err(FsError::Busy));
+    assert_eq!(vfs.remove_dir("/tmp"), Err(FsError::Busy));
+    assert_eq!(vfs.move_dir("/tmp/deep", "/tmp/x"), Err(FsError::Busy));
+    vfs.remove_dir("/tmp/dir2").unwrap();
+    vfs.remove_file("/tmp/b").unwrap();
+    assert_eq!(names(&vfs, "/tmp"), ["hello", "deep"]);
+}
@@ -0,0 +1,142 @@
+//! Ext2 Directory Entry
+//!
+//! reference: <https://www.nongnu.org/ext2-doc/ext2.html#linked-directories>
+
+use super::*;
+
+pub const FT_UNKNOWN: u8 = 0;
+pub const FT_REG_FILE: u8 = 1;
+pub const FT_DIR: u8 = 2;
+pub const FT_CHRDEV: u8 = 3;
+pub const FT_BLKDEV: u8 = 4;
+pub const FT_FIFO: u8 = 5;
+pub const FT_SOCK: u8 = 6;
+pub const FT_SYMLINK: u8 = 7;
+
+/// Maximum length of a name in bytes
+pub const MAX_NAME_LEN: usize = 255;
+
+/// An entry of a linked directory
+///
+/// entries never cross a block, the last entry of a block covers the rest of it
+#[derive(Debug, Clone)]
+pub struct DirEntry {
+    /// The inode of the entry, 0 if the entry is unused
+    pub inode: u32,
+    /// Distance to the next entry in bytes
+    pub rec_len: u16,
+    /// One of the `FT_*` values, always `FT_UNKNOWN` without the filetype feature
+    pub file_type: u8,
+    pub name: String,
+}
+
+/// Location of a directory entry on the disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntryLocation {
+    /// The block holding the entry
+    pub block: u32,
+    /// Byte offset of the entry in the block
+    pub offset: usize,
+    /// Byte offset of the previous entry in the same block
+    pub prev: Option<usize>,
+}
+
+impl DirEntry {
+    pub const HEADER_LEN: usize = 8;
+
+    pub fn new(inode: u32, name: &str, file_type: u8) -> DirEntry {
+        DirEntry {
+            inode,
+            rec_len: Self::entry_len(name.len()) as u16,
+            file_type,
+            name: String::from(name),
+        }
+    }
+
+    /// Parse the entry at the start of `data`, which extends to the end of the block
+    pub fn parse(data: &[u8], has_filetype: bool) -> FsResult<DirEntry> {
+        if data.len() < Self::HEADER_LEN {
+            return Err(FsError::InvalidOperation);
+        }
+
+        let inode = u32::from_le_bytes(data[0..4].try_into().unwrap());
+        let rec_len = u16::from_le_bytes([data[4], data[5]]);
+        // 没有 filetype 特性时名字长度占 2 字节
+        let (name_len, file_type) = match has_filetype {
+            true => (data[6] as usize, data[7]),
+            false => (u16::from_le_bytes([data[6], data[7]]) as usize, FT_UNKNOWN),
+        };
+
+        if (rec_len as usize) < Self::HEADER_LEN
+            || rec_len as usize > data.len()
+            || !rec_len.is_multiple_of(4)
+            || Self::HEADER_LEN + name_len > rec_len as usize
+        {
+            return Err(FsError::InvalidOperation);
+        }
+
+        let name = &data[Self::HEADER_LEN..Self::HEADER_LEN + name_len];
+        Ok(DirEntry {
+            inode,
+            rec_len,
+            file_type,
+            name: String::from_utf8_lossy(name).into_owned(),
+        })
+    }
+
+    /// Bytes needed by an entry with a name of `name_len` bytes
+    pub fn entry_len(name_len: usize) -> usize {
+        (Self::HEADER_LEN + name_len).next_multiple_of(4)
+    }
+
+    /// Bytes actually used by this entry, the rest of `rec_len` can hold new entries
+    pub fn used_len(&self) -> usize {
+        match self.inode {
+            0 => 0,
+            _ => Self::entry_len(self.name.len()),
+        }
+    }
+
+    /// Write the entry at the start of `data`
+    pub fn write(&self, data: &mut [u8], has_filetype: bool) {
+        let name = self.name.as_bytes();
+        data[0..4].copy_from_slice(&self.inode.to_le_bytes());
+        data[4..6].copy_from_slice(&self.rec_len.to_le_bytes());
+        match has_filetype {
+            true => {
+                data[6] = name.len() as u8;
+                data[7] = self.file_type;
+            }
+            false => data[6..8].copy_from_slice(&(name.len() as u16).to_le_bytes()),
+        }
+        data[Self::HEADER_LEN..Self::HEADER_LEN + name.len()].copy_from_slice(name);
+    }
+
+    /// The directory entry file type of an inode
+    pub fn file_type_of(inode: &Inode) -> u8 {
+        match inode.mode() & S_IFMT {
+            S_IFREG => FT_REG_FILE,
+            S_IFDIR => FT_DIR,
+            S_IFCHR => FT_CHRDEV,
+            S_IFBLK => FT_BLKDEV,
+            S_IFIFO => FT_FIFO,
+            S_IFSOCK => FT_SOCK,
+            S_IFLNK => FT_SYMLINK,
+            _ => FT_UNKNOWN,
+        }
+    }
+
+    /// Check that `name` can be used as an entry name
+    pub fn validate(name: &str) -> FsResult {
+        if name.is_empty() {
+            return Err(FilenameError::FilenameEmpty.into());
+        }
+        if name.len() > MAX_NAME_LEN {
+            return Err(FilenameError::NameTooLong.into());
+        }
+        if name.contains(['/', '\0']) {
+            return Err(FilenameError::InvalidCharacter.into());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,126 @@
+//! File
+//!
+//! reference: <https://www.nongnu.org/ext2-doc/ext2.html#i-block>
+
+use super::*;
+
+pub struct File {
+    /// The current offset in the file
+    offset: usize,
+    /// The inode number of this file
+    ino: u32,
+    /// The inode of this file
+    inode: Inode,
+    /// The file system handle that contains this file
+    handle: Ext2Handle,
+    /// Whether the inode needs to be written back
+    dirty: bool,
+}
+
+impl File {
+    pub fn new(handle: Ext2Handle, ino: u32, inode: Inode) -> Self {
+        Self {
+            offset: 0,
+            ino,
+            inode,
+            handle,
+            dirty: false,
+        }
+    }
+
+    pub fn length(&self) -> usize {
+        self.inode.size()
+    }
+}
+
+impl Read for File {
+    fn read(&mut self, buf: &mut [u8]) -> FsResult<usize> {
+        let read = self.handle.read_at(&self.inode, self.offset, buf)?;
+        self.offset += read;
+        Ok(read)
+    }
+}
+
+impl Seek for File {
+    /// Seek within the file, the offset can not go beyond the end of file
+    fn seek(&mut self, pos: SeekFrom) -> FsResult<usize> {
+        let offset = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(delta) => self.length().checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.offset.checked_add_signed(delta),
+        };
+        let offset = match offset {
+            Some(offset) if offset <= self.length() => offset,
+            _ => return Err(FsError::InvalidOffset),
+        };
+
+        self.offset = offset;
+        Ok(offset)
+    }
+}
+
+impl Write for File {
+    /// Write at the current offset, blocks are allocated as the file grows
+    fn write(&mut self, buf: &[u8]) -> FsResult<usize> {
+        self.handle.check_writable()?;
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let written = self
+            .handle
+            .write_at(self.ino, &mut self.inode, self.offset, buf)?;
+        self.offset += written;
+        self.inode.touch();
+        self.dirty = true;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> FsResult {
+        if self.dirty {
+            self.handle.write_inode(self.ino, &self.inode)?;
+            self.dirty = false;
+        }
+        Ok(())
+    }
+
+    /// Truncate or extend the file, the extended part is a hole read as zeros
+    fn set_len(&mut self, len: usize) -> FsResult {
+        self.handle.check_writable()?;
+        let length = self.length();
+        let block_size = self.handle.block_size;
+
+        if len < length {
+            // 清零最后一块中被截去的部分，之后扩展文件时读到的是零
+            let tail = len % block_size;
+            if tail != 0 {
+                let block = self.handle.bmap(&self.inode, len / block_size)?;
+                if block != 0 {
+                    let mut buf = vec![0u8; block_size];
+                    self.handle.read_block(block, &mut buf)?;
+                    buf[tail..].fill(0);
+                    self.handle.write_block(block, &buf)?;
+                }
+            }
+            self.handle
+                .truncate_blocks(&mut self.inode, len.div_ceil(block_size))?;
+        }
+
+        if len != length {
+            self.inode.set_size(len);
+            self.inode.touch();
+            self.offset = self.offset.min(len);
+            self.dirty = true;
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for File {
+    fn drop(&mut self) {
+        if let Err(err) = self.flush() {
+            warn!("Failed to flush inode {}: {:?}", self.ino, err);
+        }
+    }
+}
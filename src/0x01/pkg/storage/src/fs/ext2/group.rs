@@ -0,0 +1,58 @@
+//! Ext2 Block Group Descriptor
+//!
+//! reference: <https://wiki.osdev.org/Ext2#Block_Group_Descriptor_Table>
+
+use crate::*;
+
+/// Describes where the bitmaps and the inode table of a block group are
+/// and how many blocks and inodes are still free in it.
+#[derive(Clone)]
+pub struct GroupDesc {
+    data: [u8; 32],
+}
+
+impl GroupDesc {
+    pub const LEN: usize = 32;
+
+    pub fn new(data: &[u8]) -> GroupDesc {
+        GroupDesc {
+            data: data.try_into().unwrap(),
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.data
+    }
+
+    pub fn set_free_blocks_count(&mut self, count: u16) {
+        self.data[0x0C..0x0E].copy_from_slice(&count.to_le_bytes());
+    }
+
+    pub fn set_free_inodes_count(&mut self, count: u16) {
+        self.data[0x0E..0x10].copy_from_slice(&count.to_le_bytes());
+    }
+
+    pub fn set_used_dirs_count(&mut self, count: u16) {
+        self.data[0x10..0x12].copy_from_slice(&count.to_le_bytes());
+    }
+
+    define_field!(u32, 0x00, block_bitmap);
+    define_field!(u32, 0x04, inode_bitmap);
+    define_field!(u32, 0x08, inode_table);
+    define_field!(u16, 0x0C, free_blocks_count);
+    define_field!(u16, 0x0E, free_inodes_count);
+    define_field!(u16, 0x10, used_dirs_count);
+}
+
+impl core::fmt::Debug for GroupDesc {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("GroupDesc")
+            .field("Block Bitmap", &self.block_bitmap())
+            .field("Inode Bitmap", &self.inode_bitmap())
+            .field("Inode Table", &self.inode_table())
+            .field("Free Blocks", &self.free_blocks_count())
+            .field("Free Inodes", &self.free_inodes_count())
+            .field("Used Dirs", &self.used_dirs_count())
+            .finish()
+    }
+}
@@ -0,0 +1,1086 @@
+use super::*;
+use crate::fs::split_path;
+
+/// Maximum number of symlinks followed when resolving a path
+const MAX_SYMLINKS: usize = 8;
+/// Maximum number of hard links to an inode
+const MAX_LINKS: u16 = 65000;
+
+/// Read `buf.len()` bytes at byte `offset` of the device
+fn read_bytes(device: &dyn BlockDevice<Block512>, offset: usize, buf: &mut [u8]) -> FsResult {
+    let mut block = Block512::default();
+    let mut done = 0;
+    while done < buf.len() {
+        let pos = offset + done;
+        let start = pos % SECTOR_SIZE;
+        let len = (SECTOR_SIZE - start).min(buf.len() - done);
+        device.read_block(pos / SECTOR_SIZE, &mut block)?;
+        buf[done..done + len].copy_from_slice(&block[start..start + len]);
+        done += len;
+    }
+    Ok(())
+}
+
+/// Write `data` at byte `offset` of the device, partial sectors are read first
+fn write_bytes(device: &dyn BlockDevice<Block512>, offset: usize, data: &[u8]) -> FsResult {
+    let mut block = Block512::default();
+    let mut done = 0;
+    while done < data.len() {
+        let pos = offset + done;
+        let start = pos % SECTOR_SIZE;
+        let len = (SECTOR_SIZE - start).min(data.len() - done);
+        if len < SECTOR_SIZE {
+            device.read_block(pos / SECTOR_SIZE, &mut block)?;
+        }
+        block.as_mut()[start..start + len].copy_from_slice(&data[done..done + len]);
+        device.write_block(pos / SECTOR_SIZE, &block)?;
+        done += len;
+    }
+    Ok(())
+}
+
+impl Ext2Impl {
+    pub fn new(inner: impl BlockDevice<Block512>) -> FsResult<Self> {
+        let mut data = [0u8; SuperBlock::LEN];
+        read_bytes(&inner, SuperBlock::OFFSET, &mut data)?;
+        let sb = SuperBlock::new(&data)?;
+
+        trace!("Loading Ext2 Volume: {:#?}", sb);
+
+        if !sb.is_supported() {
+            warn!(
+                "Unsupported ext2 features: {:#x}",
+                sb.feature_incompat() & !superblock::SUPPORTED_INCOMPAT
+            );
+            return Err(FsError::NotSupported);
+        }
+
+        let read_only = sb.is_read_only();
+        if read_only {
+            warn!(
+                "Unsupported ext2 read-only features: {:#x}, mounted read-only",
+                sb.feature_ro_compat() & !superblock::SUPPORTED_RO_COMPAT
+            );
+        }
+
+        // 块组描述符表位于超级块所在块之后
+        let block_size = sb.block_size();
+        let group_table = (sb.first_data_block() as usize + 1) * block_size;
+        let mut table = vec![0u8; sb.group_count() * GroupDesc::LEN];
+        read_bytes(&inner, group_table, &mut table)?;
+        let groups = table.chunks(GroupDesc::LEN).map(GroupDesc::new).collect();
+
+        Ok(Self {
+            inner: Box::new(inner),
+            block_size,
+            inode_size: sb.inode_size(),
+            blocks_per_group: sb.blocks_per_group() as usize,
+            inodes_per_group: sb.inodes_per_group() as usize,
+            first_data_block: sb.first_data_block() as usize,
+            group_table,
+            has_filetype: sb.has_filetype(),
+            read_only,
+            state: spin::Mutex::new(AllocState { sb, groups }),
+        })
+    }
+
+    pub fn check_writable(&self) -> FsResult {
+        match self.read_only {
+            true => Err(FsError::ReadOnly),
+            false => Ok(()),
+        }
+    }
+
+    pub fn read_block(&self, block: u32, buf: &mut [u8]) -> FsResult {
+        read_bytes(&*self.inner, block as usize * self.block_size, buf)
+    }
+
+    pub fn write_block(&self, block: u32, data: &[u8]) -> FsResult {
+        write_bytes(&*self.inner, block as usize * self.block_size, data)
+    }
+
+    /// Number of 512-byte sectors in a block, the unit of `i_blocks`
+    fn sectors_per_block(&self) -> u32 {
+        (self.block_size / SECTOR_SIZE) as u32
+    }
+
+    /// Number of block pointers in an indirect block
+    fn pointers_per_block(&self) -> usize {
+        self.block_size / 4
+    }
+
+    /// The group holding the inode, new blocks of the inode are allocated near it
+    fn group_of(&self, ino: u32) -> usize {
+        (ino as usize - 1) / self.inodes_per_group
+    }
+
+    /// Byte offset of the inode on the device
+    fn inode_offset(&self, ino: u32) -> FsResult<usize> {
+        let state = self.state.lock();
+        if ino == 0 || ino > state.sb.inodes_count() {
+            return Err(FsError::InvalidOperation);
+        }
+        let index = (ino as usize - 1) % self.inodes_per_group;
+        let table = state.groups[self.group_of(ino)].inode_table() as usize;
+        Ok(table * self.block_size + index * self.inode_size)
+    }
+
+    pub fn read_inode(&self, ino: u32) -> FsResult<Inode> {
+        let mut data = [0u8; Inode::LEN];
+        read_bytes(&*self.inner, self.inode_offset(ino)?, &mut data)?;
+        Ok(Inode::new(&data))
+    }
+
+    /// Write the inode, the extra fields of larger inodes are kept
+    pub fn write_inode(&self, ino: u32, inode: &Inode) -> FsResult {
+        write_bytes(&*self.inner, self.inode_offset(ino)?, inode.as_bytes())
+    }
+
+    /// Write back the superblock and the descriptor of `group`
+    fn write_state(&self, state: &AllocState, group: usize) -> FsResult {
+        write_bytes(&*self.inner, SuperBlock::OFFSET, state.sb.as_bytes())?;
+        write_bytes(
+            &*self.inner,
+            self.group_table + group * GroupDesc::LEN,
+            state.groups[group].as_bytes(),
+        )
+    }
+
+    /// Number of blocks in the group, the last group may be smaller
+    fn blocks_in_group(&self, state: &AllocState, group: usize) -> usize {
+        let blocks = state.sb.blocks_count() as usize - self.first_data_block;
+        (blocks - group * self.blocks_per_group).min(self.blocks_per_group)
+    }
+
+    /// Find a clear bit from `start` in the first `limit` bits of the bitmap, then set it
+    fn alloc_bit(&self, bitmap: u32, start: usize, limit: usize) -> FsResult<Option<usize>> {
+        let mut buf = vec![0u8; self.block_size];
+        self.read_block(bitmap, &mut buf)?;
+
+        let free = (start..limit).find(|&bit| buf[bit / 8] & (1 << (bit % 8)) == 0);
+        if let Some(bit) = free {
+            buf[bit / 8] |= 1 << (bit % 8);
+            self.write_block(bitmap, &buf)?;
+        }
+        Ok(free)
+    }
+
+    /// Clear the bit of the bitmap, fails if it is already clear
+    fn free_bit(&self, bitmap: u32, bit: usize) -> FsResult {
+        let mut buf = vec![0u8; self.block_size];
+        self.read_block(bitmap, &mut buf)?;
+
+        if buf[bit / 8] & (1 << (bit % 8)) == 0 {
+            return Err(FsError::InvalidOperation);
+        }
+        buf[bit / 8] &= !(1 << (bit % 8));
+        self.write_block(bitmap, &buf)
+    }
+
+    /// Allocate a zeroed block, looking in the groups from `goal` on
+    pub fn alloc_block(&self, goal: usize) -> FsResult<u32> {
+        let mut state = self.state.lock();
+        let count = state.groups.len();
+
+        for group in (goal..count).chain(0..goal) {
+            if state.groups[group].free_blocks_count() == 0 {
+                continue;
+            }
+            let bitmap = state.groups[group].block_bitmap();
+            let limit = self.blocks_in_group(&state, group);
+            let Some(bit) = self.alloc_bit(bitmap, 0, limit)? else {
+                continue;
+            };
+
+            let desc = &mut state.groups[group];
+            desc.set_free_blocks_count(desc.free_blocks_count() - 1);
+            let free = state.sb.free_blocks_count();
+            state.sb.set_free_blocks_count(free.saturating_sub(1));
+            self.write_state(&state, group)?;
+
+            // 目录与间接块依赖全零的内容，文件也不应读到旧数据
+            let block = (self.first_data_block + group * self.blocks_per_group + bit) as u32;
+            self.write_block(block, &vec![0u8; self.block_size])?;
+            return Ok(block);
+        }
+
+        Err(FsError::WriteZero)
+    }
+
+    pub fn free_block(&self, block: u32) -> FsResult {
+        let mut state = self.state.lock();
+        if (block as usize) < self.first_data_block || block >= state.sb.blocks_count() {
+            return Err(FsError::InvalidOperation);
+        }
+
+        let index = block as usize - self.first_data_block;
+        let group = index / self.blocks_per_group;
+        self.free_bit(
+            state.groups[group].block_bitmap(),
+            index % self.blocks_per_group,
+        )?;
+
+        let desc = &mut state.groups[group];
+        desc.set_free_blocks_count(desc.free_blocks_count() + 1);
+        let free = state.sb.free_blocks_count();
+        state.sb.set_free_blocks_count(free + 1);
+        self.write_state(&state, group)
+    }
+
+    /// Allocate an inode, looking in the groups from `goal` on
+    fn alloc_inode(&self, goal: usize, is_dir: bool) -> FsResult<u32> {
+        let mut state = self.state.lock();
+        let count = state.groups.len();
+        let first = state.sb.first_inode() as usize;
+
+        for group in (goal..count).chain(0..goal) {
+            if state.groups[group].free_inodes_count() == 0 {
+                continue;
+            }
+            // 保留的 inode 不参与分配
+            let start = (first - 1).saturating_sub(group * self.inodes_per_group);
+            let bitmap = state.groups[group].inode_bitmap();
+            let Some(bit) = self.alloc_bit(bitmap, start, self.inodes_per_group)? else {
+                continue;
+            };
+
+            let desc = &mut state.groups[group];
+            desc.set_free_inodes_count(desc.free_inodes_count() - 1);
+            if is_dir {
+                desc.set_used_dirs_count(desc.used_dirs_count() + 1);
+            }
+            let free = state.sb.free_inodes_count();
+            state.sb.set_free_inodes_count(free.saturating_sub(1));
+            self.write_state(&state, group)?;
+
+            return Ok((group * self.inodes_per_group + bit + 1) as u32);
+        }
+
+        Err(FsError::WriteZero)
+    }
+
+    fn free_inode(&self, ino: u32, is_dir: bool) -> FsResult {
+        let mut state = self.state.lock();
+        let group = self.group_of(ino);
+        let index = (ino as usize - 1) % self.inodes_per_group;
+        self.free_bit(state.groups[group].inode_bitmap(), index)?;
+
+        let desc = &mut state.groups[group];
+        desc.set_free_inodes_count(desc.free_inodes_count() + 1);
+        if is_dir {
+            desc.set_used_dirs_count(desc.used_dirs_count().saturating_sub(1));
+        }
+        let free = state.sb.free_inodes_count();
+        state.sb.set_free_inodes_count(free + 1);
+        self.write_state(&state, group)
+    }
+
+    /// Where the `index`-th block pointer of a file is stored
+    ///
+    /// returns the slot in the inode, the levels of indirect blocks
+    /// below it and the index among the data blocks of that slot
+    fn block_path(&self, index: usize) -> FsResult<(usize, u32, usize)> {
+        if index < DIRECT_BLOCKS {
+            return Ok((index, 0, 0));
+        }
+
+        // 依次为一级、二级、三级间接块
+        let mut rest = index - DIRECT_BLOCKS;
+        let mut span = self.pointers_per_block();
+        for depth in 1..=3 {
+            if rest < span {
+                return Ok((DIRECT_BLOCKS + depth as usize - 1, depth, rest));
+            }
+            rest -= span;
+            span *= self.pointers_per_block();
+        }
+        Err(FsError::InvalidOffset)
+    }
+
+    /// The block holding the `index`-th block of the file, 0 for a hole
+    pub fn bmap(&self, inode: &Inode, index: usize) -> FsResult<u32> {
+        let (slot, depth, mut rest) = self.block_path(index)?;
+        let mut block = inode.block(slot);
+        let mut buf = vec![0u8; self.block_size];
+
+        for level in (0..depth).rev() {
+            if block == 0 {
+                break;
+            }
+            let span = self.pointers_per_block().pow(level);
+            let idx = rest / span;
+            rest %= span;
+            self.read_block(block, &mut buf)?;
+            block = u32::from_le_bytes(buf[idx * 4..idx * 4 + 4].try_into().unwrap());
+        }
+
+        Ok(block)
+    }
+
+    /// The block holding the `index`-th block of the file,
+    /// missing data and indirect blocks are allocated
+    pub fn bmap_alloc(&self, ino: u32, inode: &mut Inode, index: usize) -> FsResult<u32> {
+        let goal = self.group_of(ino);
+        let (slot, depth, mut rest) = self.block_path(index)?;
+
+        let mut block = inode.block(slot);
+        if block == 0 {
+            block = self.alloc_block(goal)?;
+            inode.set_block(slot, block);
+            inode.set_blocks(inode.blocks() + self.sectors_per_block());
+        }
+
+        let mut buf = vec![0u8; self.block_size];
+        for level in (0..depth).rev() {
+            let span = self.pointers_per_block().pow(level);
+            let idx = rest / span;
+            rest %= span;
+            self.read_block(block, &mut buf)?;
+
+            let mut next = u32::from_le_bytes(buf[idx * 4..idx * 4 + 4].try_into().unwrap());
+            if next == 0 {
+                next = self.alloc_block(goal)?;
+                inode.set_blocks(inode.blocks() + self.sectors_per_block());
+                buf[idx * 4..idx * 4 + 4].copy_from_slice(&next.to_le_bytes());
+                self.write_block(block, &buf)?;
+            }
+            block = next;
+        }
+
+        Ok(block)
+    }
+
+    /// Keep the first `keep` blocks of the file and free the rest,
+    /// including the indirect blocks no longer needed
+    pub fn truncate_blocks(&self, inode: &mut Inode, keep: usize) -> FsResult {
+        let mut freed = 0;
+
+        for slot in keep.min(DIRECT_BLOCKS)..DIRECT_BLOCKS {
+            if inode.block(slot) != 0 {
+                self.free_block(inode.block(slot))?;
+                inode.set_block(slot, 0);
+                freed += 1;
+            }
+        }
+
+        let mut start = DIRECT_BLOCKS;
+        let mut span = self.pointers_per_block();
+        for depth in 1..=3 {
+            let slot = DIRECT_BLOCKS + depth as usize - 1;
+            let block = inode.block(slot);
+            if block != 0
+                && keep < start + span
+                && self.truncate_tree(block, depth, keep.saturating_sub(start), &mut freed)?
+            {
+                inode.set_block(slot, 0);
+            }
+            start += span;
+            span *= self.pointers_per_block();
+        }
+
+        let sectors = freed * self.sectors_per_block();
+        inode.set_blocks(inode.blocks().saturating_sub(sectors));
+        Ok(())
+    }
+
+    /// Free the data blocks from the `keep`-th on under the indirect block
+    /// at `depth` levels above them, returns true if `block` itself is freed
+    fn truncate_tree(
+        &self,
+        block: u32,
+        depth: u32,
+        keep: usize,
+        freed: &mut u32,
+    ) -> FsResult<bool> {
+        if depth == 0 {
+            if keep > 0 {
+                return Ok(false);
+            }
+            self.free_block(block)?;
+            *freed += 1;
+            return Ok(true);
+        }
+
+        let span = self.pointers_per_block().pow(depth - 1);
+        let mut buf = vec![0u8; self.block_size];
+        self.read_block(block, &mut buf)?;
+
+        // 只有包含 keep 的子树需要部分保留，其后的子树全部释放
+        let mut changed = false;
+        for idx in keep / span..self.pointers_per_block() {
+            let child = u32::from_le_bytes(buf[idx * 4..idx * 4 + 4].try_into().unwrap());
+            if child != 0
+                && self.truncate_tree(child, depth - 1, keep.saturating_sub(idx * span), freed)?
+            {
+                buf[idx * 4..idx * 4 + 4].fill(0);
+                changed = true;
+            }
+        }
+
+        if keep == 0 {
+            self.free_block(block)?;
+            *freed += 1;
+            return Ok(true);
+        }
+        if changed {
+            self.write_block(block, &buf)?;
+        }
+        Ok(false)
+    }
+
+    /// Read the content of the inode at `offset`, holes are read as zeros
+    pub fn read_at(&self, inode: &Inode, offset: usize, buf: &mut [u8]) -> FsResult<usize> {
+        let size = inode.size();
+        if offset >= size {
+            return Ok(0);
+        }
+
+        let total = buf.len().min(size - offset);
+        let mut block_buf = vec![0u8; self.block_size];
+        let mut read = 0;
+
+        while read < total {
+            let pos = offset + read;
+            let start = pos % self.block_size;
+            let len = (self.block_size - start).min(total - read);
+
+            match self.bmap(inode, pos / self.block_size)? {
+                0 => buf[read..read + len].fill(0),
+                block => {
+                    self.read_block(block, &mut block_buf)?;
+                    buf[read..read + len].copy_from_slice(&block_buf[start..start + len]);
+                }
+            }
+            read += len;
+        }
+
+        Ok(read)
+    }
+
+    /// Write `data` into the inode at `offset`, allocating blocks as needed
+    ///
+    /// the size grows to cover the data, the inode is not written back
+    pub fn write_at(
+        &self,
+        ino: u32,
+        inode: &mut Inode,
+        offset: usize,
+        data: &[u8],
+    ) -> FsResult<usize> {
+        let mut block_buf = vec![0u8; self.block_size];
+        let mut written = 0;
+
+        while written < data.len() {
+            let pos = offset + written;
+            let start = pos % self.block_size;
+            let len = (self.block_size - start).min(data.len() - written);
+
+            let block = match self.bmap_alloc(ino, inode, pos / self.block_size) {
+                Ok(block) => block,
+                // 已写入部分数据时返回实际写入的长度
+                Err(_) if written > 0 => break,
+                Err(err) => return Err(err),
+            };
+            if len < self.block_size {
+                self.read_block(block, &mut block_buf)?;
+            }
+            block_buf[start..start + len].copy_from_slice(&data[written..written + len]);
+            self.write_block(block, &block_buf)?;
+
+            written += len;
+            if pos + len > inode.size() {
+                inode.set_size(pos + len);
+            }
+        }
+
+        Ok(written)
+    }
+
+    /// The target of the symlink
+    pub fn read_link(&self, inode: &Inode) -> FsResult<String> {
+        if !inode.is_symlink() {
+            return Err(FsError::InvalidOperation);
+        }
+
+        let target = if inode.is_fast_symlink(self.block_size) {
+            inode.block_bytes()[..inode.size().min(FAST_SYMLINK_LEN)].to_vec()
+        } else {
+            let mut buf = vec![0u8; inode.size()];
+            let len = self.read_at(inode, 0, &mut buf)?;
+            buf.truncate(len);
+            buf
+        };
+
+        String::from_utf8(target).map_err(|_| FilenameError::Utf8Error.into())
+    }
+
+    /// Call `f` on each entry of the directory with its location until it returns `false`
+    ///
+    /// unused entries are included, they are the free space of the directory
+    fn iterate_dir<F>(&self, dir: &Inode, mut f: F) -> FsResult
+    where
+        F: FnMut(&DirEntry, EntryLocation) -> bool,
+    {
+        if !dir.is_dir() {
+            return Err(FsError::NotADirectory);
+        }
+
+        let mut buf = vec![0u8; self.block_size];
+        for index in 0..dir.size().div_ceil(self.block_size) {
+            let block = self.bmap(dir, index)?;
+            if block == 0 {
+                continue;
+            }
+            self.read_block(block, &mut buf)?;
+
+            let mut offset = 0;
+            let mut prev = None;
+            while offset < self.block_size {
+                let entry = DirEntry::parse(&buf[offset..], self.has_filetype)?;
+                if !f(
+                    &entry,
+                    EntryLocation {
+                        block,
+                        offset,
+                        prev,
+                    },
+                ) {
+                    return Ok(());
+                }
+                prev = Some(offset);
+                offset += entry.rec_len as usize;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Find the entry named `name` in the directory
+    fn find_entry(&self, dir: &Inode, name: &str) -> FsResult<(DirEntry, EntryLocation)> {
+        DirEntry::validate(name)?;
+        let mut found = None;
+        self.iterate_dir(dir, |entry, location| {
+            if entry.inode != 0 && entry.name == name {
+                found = Some((entry.clone(), location));
+            }
+            found.is_none()
+        })?;
+        found.ok_or(FsError::FileNotFound)
+    }
+
+    /// All entries of the directory except `.` and `..`
+    fn list_dir(&self, dir: &Inode) -> FsResult<Vec<DirEntry>> {
+        let mut entries = Vec::new();
+        self.iterate_dir(dir, |entry, _| {
+            if entry.inode != 0 && entry.name != "." && entry.name != ".." {
+                entries.push(entry.clone());
+            }
+            true
+        })?;
+        Ok(entries)
+    }
+
+    /// Resolve `path` to an inode number, following the symlinks in it
+    /// and, if `follow` is set, a symlink at the end of it
+    pub fn lookup(&self, path: &str, follow: bool) -> FsResult<u32> {
+        let components = |path: &str| -> Vec<String> {
+            path.split(PATH_SEPARATOR)
+                .filter(|name| !name.is_empty() && *name != ".")
+                .rev()
+                .map(String::from)
+                .collect()
+        };
+
+        let mut pending = components(path);
+        let mut ino = ROOT_INO;
+        let mut links = 0;
+
+        while let Some(name) = pending.pop() {
+            // `..` 由目录中的项解析，根目录的 `..` 指向自身
+            let (entry, _) = self.find_entry(&self.read_inode(ino)?, &name)?;
+            let inode = self.read_inode(entry.inode)?;
+
+            if inode.is_symlink() && (follow || !pending.is_empty()) {
+                links += 1;
+                if links > MAX_SYMLINKS {
+                    return Err(FsError::InvalidPath(path.into()));
+                }
+                // 相对路径的符号链接从其所在目录开始解析
+                let target = self.read_link(&inode)?;
+                if target.starts_with(PATH_SEPARATOR) {
+                    ino = ROOT_INO;
+                }
+                pending.extend(components(&target));
+                continue;
+            }
+
+            ino = entry.inode;
+        }
+
+        Ok(ino)
+    }
+
+    /// The directory holding the last component of `path` and its name
+    fn lookup_parent<'a>(&self, path: &'a str) -> FsResult<(u32, Inode, &'a str)> {
+        let (parent, name) = split_path(path)?;
+        DirEntry::validate(name)?;
+        let ino = self.lookup(parent, true)?;
+        let dir = self.read_inode(ino)?;
+        if !dir.is_dir() {
+            return Err(FsError::NotADirectory);
+        }
+        Ok((ino, dir, name))
+    }
+
+    /// Put the entry into the first free space large enough in the directory,
+    /// the directory grows by a block if there is none, and is written back
+    fn add_entry(&self, dir_ino: u32, dir: &mut Inode, mut entry: DirEntry) -> FsResult {
+        let needed = DirEntry::entry_len(entry.name.len());
+        let mut slot = None;
+        self.iterate_dir(dir, |existing, location| {
+            if existing.rec_len as usize - existing.used_len() >= needed {
+                slot = Some((existing.clone(), location));
+            }
+            slot.is_none()
+        })?;
+
+        let mut buf = vec![0u8; self.block_size];
+        match slot {
+            Some((existing, location)) => {
+                // 拆分已有项末尾的空闲空间
+                self.read_block(location.block, &mut buf)?;
+                let used = existing.used_len();
+                let offset = location.offset + used;
+                if used > 0 {
+                    buf[location.offset + 4..location.offset + 6]
+                        .copy_from_slice(&(used as u16).to_le_bytes());
+                }
+                entry.rec_len = existing.rec_len - used as u16;
+                entry.write(&mut buf[offset..], self.has_filetype);
+                self.write_block(location.block, &buf)?;
+            }
+            None => {
+                let size = dir.size();
+                let block = self.bmap_alloc(dir_ino, dir, size / self.block_size)?;
+                entry.rec_len = self.block_size as u16;
+                entry.write(&mut buf, self.has_filetype);
+                self.write_block(block, &buf)?;
+                dir.set_size(size + self.block_size);
+            }
+        }
+
+        self.dir_modified(dir_ino, dir)
+    }
+
+    /// Remove the entry at `location` from the directory, which is written back
+    fn remove_entry(&self, dir_ino: u32, dir: &mut Inode, location: EntryLocation) -> FsResult {
+        let mut buf = vec![0u8; self.block_size];
+        self.read_block(location.block, &mut buf)?;
+
+        // 与前一项合并，块中的第一项只清除 inode
+        let offset = location.offset;
+        match location.prev {
+            Some(prev) => {
+                let rec_len = u16::from_le_bytes([buf[offset + 4], buf[offset + 5]]);
+                let prev_len = u16::from_le_bytes([buf[prev + 4], buf[prev + 5]]);
+                buf[prev + 4..prev + 6].copy_from_slice(&(prev_len + rec_len).to_le_bytes());
+            }
+            None => buf[offset..offset + 4].fill(0),
+        }
+        self.write_block(location.block, &buf)?;
+
+        self.dir_modified(dir_ino, dir)
+    }
+
+    fn dir_modified(&self, dir_ino: u32, dir: &mut Inode) -> FsResult {
+        // 散列索引在目录修改后失效，退回线性目录
+        dir.set_flags(dir.flags() & !INDEX_FL);
+        dir.touch();
+        self.write_inode(dir_ino, dir)
+    }
+
+    /// Allocate and write a fresh inode of `mode` near its parent directory
+    fn new_inode(&self, parent: u32, mode: u16) -> FsResult<(u32, Inode)> {
+        let inode = Inode::create(mode);
+        let ino = self.alloc_inode(self.group_of(parent), inode.is_dir())?;
+
+        // 较大的 inode 中的扩展字段也要清除
+        let extra = vec![0u8; self.inode_size - Inode::LEN];
+        write_bytes(&*self.inner, self.inode_offset(ino)? + Inode::LEN, &extra)?;
+        self.write_inode(ino, &inode)?;
+        Ok((ino, inode))
+    }
+
+    /// Create an inode of `mode` at `path`, which must not exist yet
+    pub fn create(&self, path: &str, mode: u16) -> FsResult<(u32, Inode)> {
+        self.check_writable()?;
+        let (dir_ino, mut dir, name) = self.lookup_parent(path)?;
+        match self.find_entry(&dir, name) {
+            Ok(_) => return Err(FsError::AlreadyExists),
+            Err(FsError::FileNotFound) => {}
+            Err(err) => return Err(err),
+        }
+
+        let (ino, mut inode) = self.new_inode(dir_ino, mode)?;
+        inode.set_links_count(1);
+
+        let result = (|| {
+            if inode.is_dir() {
+                // 新目录的前两项为 `.` 与 `..`，`..` 也是父目录的一个链接
+                let block = self.bmap_alloc(ino, &mut inode, 0)?;
+                let mut buf = vec![0u8; self.block_size];
+                let dot = DirEntry::new(ino, ".", FT_DIR);
+                let mut dotdot = DirEntry::new(dir_ino, "..", FT_DIR);
+                dotdot.rec_len = (self.block_size - dot.rec_len as usize) as u16;
+                dot.write(&mut buf, self.has_filetype);
+                dotdot.write(&mut buf[dot.rec_len as usize..], self.has_filetype);
+                self.write_block(block, &buf)?;
+
+                inode.set_size(self.block_size);
+                inode.set_links_count(2);
+                dir.set_links_count(dir.links_count() + 1);
+            }
+            self.write_inode(ino, &inode)?;
+
+            let entry = DirEntry::new(ino, name, DirEntry::file_type_of(&inode));
+            self.add_entry(dir_ino, &mut dir, entry)
+        })();
+
+        if let Err(err) = result {
+            self.release(ino, &mut inode)?;
+            return Err(err);
+        }
+        Ok((ino, inode))
+    }
+
+    /// Free the inode and its blocks
+    fn release(&self, ino: u32, inode: &mut Inode) -> FsResult {
+        if !inode.is_fast_symlink(self.block_size) {
+            self.truncate_blocks(inode, 0)?;
+        }
+        inode.set_links_count(0);
+        inode.set_delete_time(now().timestamp() as u32);
+        self.write_inode(ino, inode)?;
+        self.free_inode(ino, inode.is_dir())
+    }
+
+    /// Drop a link to the file, which is freed once no links are left
+    fn unlink(&self, ino: u32, inode: &mut Inode) -> FsResult {
+        match inode.links_count() {
+            0 | 1 => self.release(ino, inode),
+            links => {
+                inode.set_links_count(links - 1);
+                inode.set_change_time(now().timestamp() as u32);
+                self.write_inode(ino, inode)
+            }
+        }
+    }
+
+    /// Remove the file at `path`, or the symlink itself
+    pub fn remove_file(&self, path: &str) -> FsResult {
+        self.check_writable()?;
+        let (dir_ino, mut dir, name) = self.lookup_parent(path)?;
+        let (entry, location) = self.find_entry(&dir, name)?;
+        let mut inode = self.read_inode(entry.inode)?;
+        if inode.is_dir() {
+            return Err(FsError::NotAFile);
+        }
+
+        self.remove_entry(dir_ino, &mut dir, location)?;
+        self.unlink(entry.inode, &mut inode)
+    }
+
+    /// Remove the directory at `path` and everything in it
+    pub fn remove_dir(&self, path: &str) -> FsResult {
+        self.check_writable()?;
+        if self.lookup(path, false)? == ROOT_INO {
+            return Err(FsError::InvalidOperation);
+        }
+
+        let (dir_ino, mut dir, name) = self.lookup_parent(path)?;
+        let (entry, location) = self.find_entry(&dir, name)?;
+        let mut inode = self.read_inode(entry.inode)?;
+        if !inode.is_dir() {
+            return Err(FsError::NotADirectory);
+        }
+
+        dir.set_links_count(dir.links_count().saturating_sub(1));
+        self.remove_entry(dir_ino, &mut dir, location)?;
+        self.remove_tree(entry.inode, &mut inode)
+    }
+
+    /// Free the directory and everything in it, files with other links are kept
+    fn remove_tree(&self, ino: u32, inode: &mut Inode) -> FsResult {
+        for child in self.list_dir(inode)? {
+            let mut child_inode = self.read_inode(child.inode)?;
+            if child_inode.is_dir() {
+                self.remove_tree(child.inode, &mut child_inode)?;
+            } else {
+                self.unlink(child.inode, &mut child_inode)?;
+            }
+        }
+        self.release(ino, inode)
+    }
+
+    /// Move the entry at `src` to `dst`, an existing file at `dst` is replaced
+    pub fn rename(&self, src: &str, dst: &str, is_dir: bool) -> FsResult {
+        self.check_writable()?;
+        let (src_dir_ino, src_dir, src_name) = self.lookup_parent(src)?;
+        let (entry, _) = self.find_entry(&src_dir, src_name)?;
+        let inode = self.read_inode(entry.inode)?;
+        match (inode.is_dir(), is_dir) {
+            (false, true) => return Err(FsError::NotADirectory),
+            (true, false) => return Err(FsError::NotAFile),
+            _ => {}
+        }
+
+        let (dst_dir_ino, mut dst_dir, dst_name) = self.lookup_parent(dst)?;
+
+        if is_dir {
+            // 不能把目录移动到它自身之下
+            let mut ino = dst_dir_ino;
+            while ino != ROOT_INO {
+                if ino == entry.inode {
+                    return Err(FsError::InvalidOperation);
+                }
+                ino = self.find_entry(&self.read_inode(ino)?, "..")?.0.inode;
+            }
+        }
+
+        match self.find_entry(&dst_dir, dst_name) {
+            // 目标与源是同一个文件
+            Ok((existing, _)) if existing.inode == entry.inode => return Ok(()),
+            Ok((existing, location)) => {
+                let mut existing_inode = self.read_inode(existing.inode)?;
+                if is_dir || existing_inode.is_dir() {
+                    return Err(FsError::AlreadyExists);
+                }
+                self.remove_entry(dst_dir_ino, &mut dst_dir, location)?;
+                self.unlink(existing.inode, &mut existing_inode)?;
+            }
+            Err(FsError::FileNotFound) => {}
+            Err(err) => return Err(err),
+        }
+
+        let moved = DirEntry::new(entry.inode, dst_name, DirEntry::file_type_of(&inode));
+        self.add_entry(dst_dir_ino, &mut dst_dir, moved)?;
+
+        let reparent = is_dir && src_dir_ino != dst_dir_ino;
+        if reparent {
+            dst_dir.set_links_count(dst_dir.links_count() + 1);
+            self.write_inode(dst_dir_ino, &dst_dir)?;
+        }
+
+        // 两个目录可能相同，重新读取源目录与源项的位置
+        let mut src_dir = self.read_inode(src_dir_ino)?;
+        let (_, location) = self.find_entry(&src_dir, src_name)?;
+        if reparent {
+            src_dir.set_links_count(src_dir.links_count().saturating_sub(1));
+        }
+        self.remove_entry(src_dir_ino, &mut src_dir, location)?;
+
+        if reparent {
+            // `..` 指向新的父目录
+            let (_, location) = self.find_entry(&inode, "..")?;
+            let mut buf = vec![0u8; self.block_size];
+            self.read_block(location.block, &mut buf)?;
+            buf[location.offset..location.offset + 4].copy_from_slice(&dst_dir_ino.to_le_bytes());
+            self.write_block(location.block, &buf)?;
+        }
+
+        Ok(())
+    }
+
+    /// Create a hard link at `dst` to the file at `src`
+    pub fn link(&self, src: &str, dst: &str) -> FsResult {
+        self.check_writable()?;
+        let ino = self.lookup(src, false)?;
+        let mut inode = self.read_inode(ino)?;
+        if inode.is_dir() {
+            return Err(FsError::NotAFile);
+        }
+        if inode.links_count() >= MAX_LINKS {
+            return Err(FsError::InvalidOperation);
+        }
+
+        let (dir_ino, mut dir, name) = self.lookup_parent(dst)?;
+        match self.find_entry(&dir, name) {
+            Ok(_) => return Err(FsError::AlreadyExists),
+            Err(FsError::FileNotFound) => {}
+            Err(err) => return Err(err),
+        }
+
+        let entry = DirEntry::new(ino, name, DirEntry::file_type_of(&inode));
+        self.add_entry(dir_ino, &mut dir, entry)?;
+
+        inode.set_links_count(inode.links_count() + 1);
+        inode.set_change_time(now().timestamp() as u32);
+        self.write_inode(ino, &inode)
+    }
+
+    /// Create a symlink at `path` pointing to `target`
+    pub fn symlink(&self, target: &str, path: &str) -> FsResult {
+        if target.is_empty() || target.len() >= self.block_size {
+            return Err(FsError::InvalidPath(target.into()));
+        }
+
+        let (ino, mut inode) = self.create(path, S_IFLNK | 0o777)?;
+        // 较短的目标直接存放在块指针中
+        if target.len() < FAST_SYMLINK_LEN {
+            inode.block_bytes_mut()[..target.len()].copy_from_slice(target.as_bytes());
+            inode.set_size(target.len());
+        } else {
+            self.write_at(ino, &mut inode, 0, target.as_bytes())?;
+        }
+        self.write_inode(ino, &inode)
+    }
+
+    /// Set the permission bits of the file or directory at `path`
+    pub fn set_permissions(&self, path: &str, permissions: u16) -> FsResult {
+        self.check_writable()?;
+        let ino = self.lookup(path, true)?;
+        let mut inode = self.read_inode(ino)?;
+        inode.set_permissions(permissions);
+        inode.set_change_time(now().timestamp() as u32);
+        self.write_inode(ino, &inode)
+    }
+}
+
+/// The name of the last component of `path`, `/` for the root directory
+fn file_name(path: &str) -> &str {
+    match path
+        .trim_end_matches(PATH_SEPARATOR)
+        .rsplit(PATH_SEPARATOR)
+        .next()
+    {
+        Some(name) if !name.is_empty() => name,
+        _ => "/",
+    }
+}
+
+impl Ext2 {
+    fn open(&self, path: &str, ino: u32, inode: Inode) -> FileHandle {
+        let meta = inode.as_meta(file_name(path));
+        let file = File::new(self.handle.clone(), ino, inode);
+        FileHandle::new(meta, Box::new(file))
+    }
+
+    /// The inode of the file at `path`, a symlink at the end is followed if `follow` is set
+    pub fn stat(&self, path: &str, follow: bool) -> FsResult<Inode> {
+        let ino = self.handle.lookup(path, follow)?;
+        self.handle.read_inode(ino)
+    }
+
+    /// The permission bits of the file or directory at `path`
+    pub fn permissions(&self, path: &str) -> FsResult<u16> {
+        Ok(self.stat(path, true)?.permissions())
+    }
+
+    pub fn set_permissions(&self, path: &str, permissions: u16) -> FsResult {
+        self.handle.set_permissions(path, permissions)
+    }
+
+    /// Create a hard link at `dst` to the file at `src`
+    pub fn link(&self, src: &str, dst: &str) -> FsResult {
+        self.handle.link(src, dst)
+    }
+
+    /// Create a symlink at `path` pointing to `target`
+    pub fn symlink(&self, target: &str, path: &str) -> FsResult {
+        self.handle.symlink(target, path)
+    }
+
+    /// The target of the symlink at `path`
+    pub fn read_link(&self, path: &str) -> FsResult<String> {
+        self.handle.read_link(&self.stat(path, false)?)
+    }
+}
+
+impl FileSystem for Ext2 {
+    fn read_dir(&self, path: &str) -> FsResult<Box<dyn Iterator<Item = Metadata> + Send>> {
+        let dir = self.stat(path, true)?;
+        let entries = self
+            .handle
+            .list_dir(&dir)?
+            .into_iter()
+            .map(|entry| Ok(self.handle.read_inode(entry.inode)?.as_meta(&entry.name)))
+            .collect::<FsResult<Vec<_>>>()?;
+        Ok(Box::new(entries.into_iter()))
+    }
+
+    fn open_file(&self, path: &str) -> FsResult<FileHandle> {
+        let ino = self.handle.lookup(path, true)?;
+        let inode = self.handle.read_inode(ino)?;
+        if inode.is_dir() {
+            return Err(FsError::NotAFile);
+        }
+        Ok(self.open(path, ino, inode))
+    }
+
+    fn metadata(&self, path: &str) -> FsResult<Metadata> {
+        Ok(self.stat(path, true)?.as_meta(file_name(path)))
+    }
+
+    fn exists(&self, path: &str) -> FsResult<bool> {
+        match self.handle.lookup(path, true) {
+            Ok(_) => Ok(true),
+            Err(FsError::FileNotFound) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn create_file(&self, path: &str) -> FsResult<FileHandle> {
+        match self.handle.lookup(path, true) {
+            Ok(ino) => {
+                let inode = self.handle.read_inode(ino)?;
+                if inode.is_dir() {
+                    return Err(FsError::NotAFile);
+                }
+                let mut file = self.open(path, ino, inode);
+                file.set_len(0)?;
+                Ok(file)
+            }
+            Err(FsError::FileNotFound) => {
+                let (ino, inode) = self.handle.create(path, S_IFREG | 0o644)?;
+                Ok(self.open(path, ino, inode))
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    fn append_file(&self, path: &str) -> FsResult<FileHandle> {
+        let mut file = self.open_file(path)?;
+        file.seek(SeekFrom::End(0))?;
+        Ok(file)
+    }
+
+    fn create_dir(&self, path: &str) -> FsResult {
+        self.handle.create(path, S_IFDIR | 0o755).map(|_| ())
+    }
+
+    fn remove_file(&self, path: &str) -> FsResult {
+        self.handle.remove_file(path)
+    }
+
+    fn remove_dir(&self, path: &str) -> FsResult {
+        self.handle.remove_dir(path)
+    }
+
+    fn copy_file(&self, src: &str, dst: &str) -> FsResult {
+        // 先读出全部内容，复制到自身时也不会丢失数据
+        let mut data = Vec::new();
+        self.open_file(src)?.read_all(&mut data)?;
+
+        let mut file = self.create_file(dst)?;
+        file.write_all(&data)?;
+        file.flush()
+    }
+
+    fn move_file(&self, src: &str, dst: &str) -> FsResult {
+        self.handle.rename(src, dst, false)
+    }
+
+    fn move_dir(&self, src: &str, dst: &str) -> FsResult {
+        self.handle.rename(src, dst, true)
+    }
+}
@@ -0,0 +1,221 @@
+//! Ext2 Inode
+//!
+//! reference:
+//! - <https://wiki.osdev.org/Ext2#Inodes>
+//! - <https://www.nongnu.org/ext2-doc/ext2.html#inode-table>
+
+use super::*;
+use chrono::DateTime;
+
+/// The inode of the root directory
+pub const ROOT_INO: u32 = 2;
+/// Number of block pointers stored directly in the inode
+pub const DIRECT_BLOCKS: usize = 12;
+/// Direct, single, double and triple indirect block pointers
+pub const BLOCK_POINTERS: usize = DIRECT_BLOCKS + 3;
+/// Targets shorter than this are stored in the block pointers of the symlink
+pub const FAST_SYMLINK_LEN: usize = BLOCK_POINTERS * 4;
+
+pub const S_IFMT: u16 = 0xF000;
+pub const S_IFSOCK: u16 = 0xC000;
+pub const S_IFLNK: u16 = 0xA000;
+pub const S_IFREG: u16 = 0x8000;
+pub const S_IFBLK: u16 = 0x6000;
+pub const S_IFDIR: u16 = 0x4000;
+pub const S_IFCHR: u16 = 0x2000;
+pub const S_IFIFO: u16 = 0x1000;
+
+/// The directory uses a hashed b-tree index, which is dropped once it is modified
+pub const INDEX_FL: u32 = 0x1000;
+
+/// The first 128 bytes of an inode, later revisions may use larger inodes
+/// whose extra fields are kept as they are on the disk.
+#[derive(Clone)]
+pub struct Inode {
+    data: [u8; 128],
+}
+
+impl Inode {
+    pub const LEN: usize = 128;
+
+    pub fn new(data: &[u8]) -> Inode {
+        Inode {
+            data: data.try_into().unwrap(),
+        }
+    }
+
+    /// A fresh inode of `mode` with all timestamps set to now
+    pub fn create(mode: u16) -> Inode {
+        let mut inode = Inode { data: [0; 128] };
+        let time = now().timestamp() as u32;
+        inode.set_mode(mode);
+        inode.set_access_time(time);
+        inode.set_change_time(time);
+        inode.set_modify_time(time);
+        inode
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 128] {
+        &self.data
+    }
+
+    fn set_u16(&mut self, offset: usize, value: u16) {
+        self.data[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn set_u32(&mut self, offset: usize, value: u32) {
+        self.data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.mode() & S_IFMT == S_IFDIR
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.mode() & S_IFMT == S_IFREG
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.mode() & S_IFMT == S_IFLNK
+    }
+
+    /// Whether the target of the symlink is stored in the block pointers
+    pub fn is_fast_symlink(&self, block_size: usize) -> bool {
+        // 扩展属性块也计入 i_blocks
+        let acl_sectors = match self.file_acl() {
+            0 => 0,
+            _ => block_size / 512,
+        };
+        self.is_symlink() && self.blocks() as usize == acl_sectors
+    }
+
+    /// Permission bits, including setuid, setgid and sticky
+    pub fn permissions(&self) -> u16 {
+        self.mode() & !S_IFMT
+    }
+
+    pub fn set_permissions(&mut self, permissions: u16) {
+        self.set_mode(self.mode() & S_IFMT | permissions & !S_IFMT);
+    }
+
+    /// Size in bytes, the upper 32 bits are only used by regular files
+    pub fn size(&self) -> usize {
+        match self.is_file() {
+            true => (self.size_high() as usize) << 32 | self.size_low() as usize,
+            false => self.size_low() as usize,
+        }
+    }
+
+    pub fn set_size(&mut self, size: usize) {
+        self.set_u32(0x04, size as u32);
+        if self.is_file() {
+            self.set_u32(0x6C, (size as u64 >> 32) as u32);
+        }
+    }
+
+    /// The `index`-th block pointer
+    pub fn block(&self, index: usize) -> u32 {
+        let offset = 0x28 + index * 4;
+        u32::from_le_bytes(self.data[offset..offset + 4].try_into().unwrap())
+    }
+
+    pub fn set_block(&mut self, index: usize, block: u32) {
+        self.set_u32(0x28 + index * 4, block);
+    }
+
+    /// The block pointers as raw bytes, where fast symlinks keep their target
+    pub fn block_bytes(&self) -> &[u8] {
+        &self.data[0x28..0x28 + FAST_SYMLINK_LEN]
+    }
+
+    pub fn block_bytes_mut(&mut self) -> &mut [u8] {
+        &mut self.data[0x28..0x28 + FAST_SYMLINK_LEN]
+    }
+
+    pub fn set_mode(&mut self, mode: u16) {
+        self.set_u16(0x00, mode);
+    }
+
+    pub fn set_access_time(&mut self, time: u32) {
+        self.set_u32(0x08, time);
+    }
+
+    pub fn set_change_time(&mut self, time: u32) {
+        self.set_u32(0x0C, time);
+    }
+
+    pub fn set_modify_time(&mut self, time: u32) {
+        self.set_u32(0x10, time);
+    }
+
+    pub fn set_delete_time(&mut self, time: u32) {
+        self.set_u32(0x14, time);
+    }
+
+    pub fn set_links_count(&mut self, count: u16) {
+        self.set_u16(0x1A, count);
+    }
+
+    pub fn set_blocks(&mut self, sectors: u32) {
+        self.set_u32(0x1C, sectors);
+    }
+
+    pub fn set_flags(&mut self, flags: u32) {
+        self.set_u32(0x20, flags);
+    }
+
+    /// Mark the inode as modified now
+    pub fn touch(&mut self) {
+        let time = now().timestamp() as u32;
+        self.set_change_time(time);
+        self.set_modify_time(time);
+    }
+
+    /// The metadata of the inode for an entry named `name`
+    pub fn as_meta(&self, name: &str) -> Metadata {
+        let time = |secs: u32| DateTime::from_timestamp(secs as i64, 0);
+        let (entry_type, len) = if self.is_dir() {
+            (FileType::Directory, 0)
+        } else if self.is_symlink() {
+            (FileType::Symlink, self.size())
+        } else {
+            (FileType::File, self.size())
+        };
+
+        // ext2 不记录创建时间，ctime 是状态修改的时间
+        Metadata::new(
+            String::from(name),
+            entry_type,
+            len,
+            None,
+            time(self.modify_time()),
+            time(self.access_time()),
+        )
+    }
+
+    define_field!(u16, 0x00, mode);
+    define_field!(u16, 0x02, uid);
+    define_field!(u32, 0x04, size_low);
+    define_field!(u32, 0x08, access_time);
+    define_field!(u32, 0x0C, change_time);
+    define_field!(u32, 0x10, modify_time);
+    define_field!(u32, 0x14, delete_time);
+    define_field!(u16, 0x18, gid);
+    define_field!(u16, 0x1A, links_count);
+    define_field!(u32, 0x1C, blocks);
+    define_field!(u32, 0x20, flags);
+    define_field!(u32, 0x68, file_acl);
+    define_field!(u32, 0x6C, size_high);
+}
+
+impl core::fmt::Debug for Inode {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Inode")
+            .field("Mode", &format_args!("{:#o}", self.mode()))
+            .field("Size", &self.size())
+            .field("Links", &self.links_count())
+            .field("Blocks", &self.blocks())
+            .field("Flags", &format_args!("{:#x}", self.flags()))
+            .finish()
+    }
+}
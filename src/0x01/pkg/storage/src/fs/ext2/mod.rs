@@ -0,0 +1,92 @@
+pub mod dirent;
+pub mod file;
+pub mod group;
+pub mod impls;
+pub mod inode;
+pub mod superblock;
+
+#[cfg(test)]
+mod tests;
+
+use crate::*;
+use dirent::*;
+use file::File;
+use group::GroupDesc;
+use inode::*;
+use superblock::SuperBlock;
+
+const SECTOR_SIZE: usize = 512;
+
+/// Identifies an ext2 filesystem on the disk.
+///
+/// Besides files and directories, ext2 keeps permissions, hard links and
+/// symlinks, which are available through the inherent methods of `Ext2`.
+pub struct Ext2 {
+    handle: Ext2Handle,
+}
+
+impl Ext2 {
+    /// Mount the ext2 filesystem on `inner`
+    ///
+    /// fails if there is no ext2 superblock or the volume uses features not
+    /// understood by the driver, unknown read-only features make it read-only
+    pub fn new(inner: impl BlockDevice<Block512>) -> FsResult<Self> {
+        Ok(Self {
+            handle: Arc::new(Ext2Impl::new(inner)?),
+        })
+    }
+}
+
+pub(crate) type Ext2Handle = Arc<Ext2Impl>;
+
+/// The allocation state, written back whenever a block or an inode is allocated or freed
+struct AllocState {
+    sb: SuperBlock,
+    groups: Vec<GroupDesc>,
+}
+
+/// The ext2 filesystem.
+///
+/// The volume is divided into block groups of `blocks_per_group` blocks.
+/// The superblock is at byte 1024, followed by the group descriptor table.
+///
+/// [ Boot ] [ Superblock ] [ Group Descriptors ] [ Block Group 0 ] [ Block Group 1 ] ...
+///
+/// Each group has its own bitmaps and inode table, some groups also keep
+/// backups of the superblock and the group descriptors.
+///
+/// [ (Backups) ] [ Block Bitmap ] [ Inode Bitmap ] [ Inode Table ] [ Data ]
+pub struct Ext2Impl {
+    pub(crate) inner: Box<dyn BlockDevice<Block512>>,
+    pub block_size: usize,
+    pub inode_size: usize,
+    pub blocks_per_group: usize,
+    pub inodes_per_group: usize,
+    pub first_data_block: usize,
+    /// Byte offset of the group descriptor table
+    pub group_table: usize,
+    /// Whether directory entries record the file type
+    pub has_filetype: bool,
+    /// Whether the volume uses read-only compatible features not understood by the driver
+    pub read_only: bool,
+    state: spin::Mutex<AllocState>,
+}
+
+impl core::fmt::Debug for Ext2 {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Ext2")
+            .field("superblock", &self.handle.state.lock().sb)
+            .finish()
+    }
+}
+
+impl core::fmt::Debug for Ext2Impl {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let state = self.state.lock();
+        f.debug_struct("Ext2Impl")
+            .field("superblock", &state.sb)
+            .field("groups", &state.groups)
+            .field("read_only", &self.read_only)
+            .finish()
+    }
+}
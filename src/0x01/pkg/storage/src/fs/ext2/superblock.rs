@@ -0,0 +1,148 @@
+//! Ext2 Superblock
+//!
+//! reference:
+//! - <https://wiki.osdev.org/Ext2#Superblock>
+//! - <https://www.nongnu.org/ext2-doc/ext2.html#superblock>
+
+use crate::*;
+
+const EXT2_MAGIC: u16 = 0xEF53;
+
+/// Directory entries record the file type
+pub const FEATURE_INCOMPAT_FILETYPE: u32 = 0x0002;
+/// Backups of the superblock are only kept in groups 0, 1 and powers of 3, 5 and 7
+pub const FEATURE_RO_COMPAT_SPARSE_SUPER: u32 = 0x0001;
+/// Regular files may be larger than 2 GiB
+pub const FEATURE_RO_COMPAT_LARGE_FILE: u32 = 0x0002;
+
+/// Incompatible features understood by the driver, the volume can not be mounted otherwise
+pub const SUPPORTED_INCOMPAT: u32 = FEATURE_INCOMPAT_FILETYPE;
+/// Read-only compatible features understood by the driver, the volume is read-only otherwise
+pub const SUPPORTED_RO_COMPAT: u32 = FEATURE_RO_COMPAT_SPARSE_SUPER | FEATURE_RO_COMPAT_LARGE_FILE;
+
+/// Represents the superblock of an ext2 filesystem.
+///
+/// It is always located at byte 1024 of the volume and is 1024 bytes long.
+pub struct SuperBlock {
+    data: [u8; 1024],
+}
+
+impl SuperBlock {
+    /// Byte offset of the superblock on the volume
+    pub const OFFSET: usize = 1024;
+    pub const LEN: usize = 1024;
+
+    /// Attempt to parse the superblock from its 1024 bytes.
+    pub fn new(data: &[u8]) -> FsResult<SuperBlock> {
+        let data = data.try_into().unwrap();
+        let sb = SuperBlock { data };
+
+        if sb.magic() != EXT2_MAGIC
+            || sb.blocks_per_group() == 0
+            || sb.inodes_per_group() == 0
+            || sb.log_block_size() > 6
+        {
+            return Err(FsError::InvalidOperation);
+        }
+
+        Ok(sb)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 1024] {
+        &self.data
+    }
+
+    /// Size of a block in bytes
+    pub fn block_size(&self) -> usize {
+        1024 << self.log_block_size()
+    }
+
+    /// Number of block groups
+    pub fn group_count(&self) -> usize {
+        (self.blocks_count() - self.first_data_block()).div_ceil(self.blocks_per_group()) as usize
+    }
+
+    /// Size of an inode on the disk, revision 0 always uses 128 bytes
+    pub fn inode_size(&self) -> usize {
+        match self.rev_level() {
+            0 => 128,
+            _ => self.rev1_inode_size() as usize,
+        }
+    }
+
+    /// The first inode that is not reserved, revision 0 always uses 11
+    pub fn first_inode(&self) -> u32 {
+        match self.rev_level() {
+            0 => 11,
+            _ => self.rev1_first_inode(),
+        }
+    }
+
+    /// Whether the features of the volume are all understood by the driver
+    pub fn is_supported(&self) -> bool {
+        self.rev_level() == 0 || self.feature_incompat() & !SUPPORTED_INCOMPAT == 0
+    }
+
+    /// Whether the volume can only be mounted read-only
+    pub fn is_read_only(&self) -> bool {
+        self.rev_level() != 0 && self.feature_ro_compat() & !SUPPORTED_RO_COMPAT != 0
+    }
+
+    /// Whether directory entries record the file type
+    pub fn has_filetype(&self) -> bool {
+        self.rev_level() != 0 && self.feature_incompat() & FEATURE_INCOMPAT_FILETYPE != 0
+    }
+
+    pub fn set_free_blocks_count(&mut self, count: u32) {
+        self.data[0x0C..0x10].copy_from_slice(&count.to_le_bytes());
+    }
+
+    pub fn set_free_inodes_count(&mut self, count: u32) {
+        self.data[0x10..0x14].copy_from_slice(&count.to_le_bytes());
+    }
+
+    define_field!(u32, 0x00, inodes_count);
+    define_field!(u32, 0x04, blocks_count);
+    define_field!(u32, 0x08, reserved_blocks_count);
+    define_field!(u32, 0x0C, free_blocks_count);
+    define_field!(u32, 0x10, free_inodes_count);
+    define_field!(u32, 0x14, first_data_block);
+    define_field!(u32, 0x18, log_block_size);
+    define_field!(u32, 0x20, blocks_per_group);
+    define_field!(u32, 0x28, inodes_per_group);
+    define_field!(u32, 0x2C, mount_time);
+    define_field!(u32, 0x30, write_time);
+    define_field!(u16, 0x38, magic);
+    define_field!(u16, 0x3A, state);
+    define_field!(u32, 0x4C, rev_level);
+    define_field!(u32, 0x54, rev1_first_inode);
+    define_field!(u16, 0x58, rev1_inode_size);
+    define_field!(u32, 0x5C, feature_compat);
+    define_field!(u32, 0x60, feature_incompat);
+    define_field!(u32, 0x64, feature_ro_compat);
+    define_field!([u8; 16], 0x68, uuid);
+    define_field!([u8; 16], 0x78, volume_name);
+}
+
+impl core::fmt::Debug for SuperBlock {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Ext2 SuperBlock")
+            .field("Inodes Count", &self.inodes_count())
+            .field("Blocks Count", &self.blocks_count())
+            .field("Free Blocks Count", &self.free_blocks_count())
+            .field("Free Inodes Count", &self.free_inodes_count())
+            .field("First Data Block", &self.first_data_block())
+            .field("Block Size", &self.block_size())
+            .field("Blocks per Group", &self.blocks_per_group())
+            .field("Inodes per Group", &self.inodes_per_group())
+            .field("Revision", &self.rev_level())
+            .field("Inode Size", &self.inode_size())
+            .field("Incompatible Features", &self.feature_incompat())
+            .field("Read-only Features", &self.feature_ro_compat())
+            .field(
+                "Volume Name",
+                &self.volume_name_str().trim_end_matches('\0'),
+            )
+            .finish()
+    }
+}
@@ -0,0 +1,695 @@
+//! Tests of the Ext2 filesystem on an image generated in memory
+
+use super::*;
+use crate::fs::fat16::tests::{names, pattern, read_to_end, write_file};
+
+const BLOCK_SIZE: usize = 1024;
+const BLOCKS: usize = 2048;
+const BLOCKS_PER_GROUP: usize = 1024;
+const GROUPS: usize = 2;
+const INODES_PER_GROUP: usize = 64;
+const INODE_SIZE: usize = 128;
+/// Superblock, group descriptors, bitmaps and the inode table at the start of each group
+const META_BLOCKS: usize = 4 + INODES_PER_GROUP * INODE_SIZE / BLOCK_SIZE;
+
+fn u16_at(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(data[offset..offset + 2].try_into().unwrap())
+}
+
+fn u32_at(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn put(data: &mut [u8], offset: usize, value: &[u8]) {
+    data[offset..offset + value.len()].copy_from_slice(value);
+}
+
+/// A minimal mke2fs
+///
+/// 1 KiB blocks in 2 groups, both holding a copy of the superblock and the
+/// group descriptors, 64 inodes of 128 bytes per group and the filetype feature
+struct ImageBuilder {
+    data: Vec<u8>,
+    used_blocks: Vec<usize>,
+    used_inodes: Vec<u32>,
+    dirs: Vec<u32>,
+    next_block: usize,
+    ro_compat: u32,
+    incompat: u32,
+}
+
+impl ImageBuilder {
+    fn new() -> Self {
+        let mut used_blocks = Vec::new();
+        for group in 0..GROUPS {
+            let start = 1 + group * BLOCKS_PER_GROUP;
+            used_blocks.extend(start..start + META_BLOCKS);
+        }
+        Self {
+            data: vec![0; BLOCKS * BLOCK_SIZE],
+            used_blocks,
+            used_inodes: (1..11).collect(),
+            dirs: Vec::new(),
+            next_block: 1 + META_BLOCKS,
+            ro_compat: superblock::FEATURE_RO_COMPAT_SPARSE_SUPER,
+            incompat: superblock::FEATURE_INCOMPAT_FILETYPE,
+        }
+    }
+
+    fn block(&mut self, block: usize) -> &mut [u8] {
+        &mut self.data[block * BLOCK_SIZE..(block + 1) * BLOCK_SIZE]
+    }
+
+    fn alloc(&mut self) -> usize {
+        let block = self.next_block;
+        self.next_block += 1;
+        self.used_blocks.push(block);
+        block
+    }
+
+    /// Write the inode, its blocks beyond the direct ones go to a single indirect block
+    fn inode(&mut self, ino: u32, mode: u16, size: usize, links: u16, blocks: &[usize]) {
+        let mut inode = [0u8; INODE_SIZE];
+        put(&mut inode, 0x00, &mode.to_le_bytes());
+        put(&mut inode, 0x04, &(size as u32).to_le_bytes());
+        put(&mut inode, 0x1A, &links.to_le_bytes());
+
+        let mut sectors = blocks.len() * 2;
+        for (idx, &block) in blocks.iter().take(DIRECT_BLOCKS).enumerate() {
+            put(&mut inode, 0x28 + idx * 4, &(block as u32).to_le_bytes());
+        }
+        if blocks.len() > DIRECT_BLOCKS {
+            let indirect = self.alloc();
+            sectors += 2;
+            put(
+                &mut inode,
+                0x28 + DIRECT_BLOCKS * 4,
+                &(indirect as u32).to_le_bytes(),
+            );
+            for (idx, &block) in blocks[DIRECT_BLOCKS..].iter().enumerate() {
+                put(self.block(indirect), idx * 4, &(block as u32).to_le_bytes());
+            }
+        }
+        put(&mut inode, 0x1C, &(sectors as u32).to_le_bytes());
+
+        let index = ino as usize - 1;
+        let table = 1 + index / INODES_PER_GROUP * BLOCKS_PER_GROUP + 4;
+        let offset = table * BLOCK_SIZE + index % INODES_PER_GROUP * INODE_SIZE;
+        put(&mut self.data, offset, &inode);
+        if !self.used_inodes.contains(&ino) {
+            self.used_inodes.push(ino);
+        }
+    }
+
+    fn file(&mut self, ino: u32, permissions: u16, links: u16, content: &[u8]) {
+        let mut blocks = Vec::new();
+        for chunk in content.chunks(BLOCK_SIZE) {
+            let block = self.alloc();
+            put(self.block(block), 0, chunk);
+            blocks.push(block);
+        }
+        self.inode(ino, S_IFREG | permissions, content.len(), links, &blocks);
+    }
+
+    /// A fast symlink, the target is kept in the block pointers
+    fn symlink(&mut self, ino: u32, target: &str) {
+        self.inode(ino, S_IFLNK | 0o777, target.len(), 1, &[]);
+        let index = ino as usize - 1;
+        let table = 1 + index / INODES_PER_GROUP * BLOCKS_PER_GROUP + 4;
+        let offset = table * BLOCK_SIZE + index % INODES_PER_GROUP * INODE_SIZE;
+        put(&mut self.data, offset + 0x28, target.as_bytes());
+    }
+
+    /// A directory of one block, the links are `.`, the entry in the
+    /// parent and the `..` of each subdirectory
+    fn dir(&mut self, ino: u32, parent: u32, entries: &[(&str, u32, u8)]) {
+        let block = self.alloc();
+        let mut all = vec![(".", ino, FT_DIR), ("..", parent, FT_DIR)];
+        all.extend_from_slice(entries);
+
+        let mut offset = 0;
+        for (idx, &(name, inode, file_type)) in all.iter().enumerate() {
+            let rec_len = match idx == all.len() - 1 {
+                true => BLOCK_SIZE - offset,
+                false => (8 + name.len()).next_multiple_of(4),
+            };
+            let data = self.block(block);
+            put(data, offset, &inode.to_le_bytes());
+            put(data, offset + 4, &(rec_len as u16).to_le_bytes());
+            data[offset + 6] = name.len() as u8;
+            data[offset + 7] = file_type;
+            put(data, offset + 8, name.as_bytes());
+            offset += rec_len;
+        }
+
+        let subdirs = entries.iter().filter(|entry| entry.2 == FT_DIR).count();
+        self.inode(
+            ino,
+            S_IFDIR | 0o755,
+            BLOCK_SIZE,
+            2 + subdirs as u16,
+            &[block],
+        );
+        self.dirs.push(ino);
+    }
+
+    /// Write the bitmaps, group descriptors and superblocks
+    fn disk(mut self) -> RamDisk {
+        let mut free_blocks = 0;
+        let mut free_inodes = 0;
+
+        for group in 0..GROUPS {
+            let start = 1 + group * BLOCKS_PER_GROUP;
+            let count = (BLOCKS - start).min(BLOCKS_PER_GROUP);
+
+            // 最后一个组中超出卷的位也要置位
+            let mut blocks = vec![0u8; BLOCK_SIZE];
+            for bit in 0..BLOCK_SIZE * 8 {
+                if bit >= count || self.used_blocks.contains(&(start + bit)) {
+                    blocks[bit / 8] |= 1 << (bit % 8);
+                }
+            }
+            let mut inodes = vec![0u8; BLOCK_SIZE];
+            for bit in 0..BLOCK_SIZE * 8 {
+                let ino = (group * INODES_PER_GROUP + bit + 1) as u32;
+                if bit >= INODES_PER_GROUP || self.used_inodes.contains(&ino) {
+                    inodes[bit / 8] |= 1 << (bit % 8);
+                }
+            }
+            let group_free_blocks = count - blocks_used(&blocks, count);
+            let group_free_inodes = INODES_PER_GROUP - blocks_used(&inodes, INODES_PER_GROUP);
+            let group_dirs = self
+                .dirs
+                .iter()
+                .filter(|&&ino| (ino as usize - 1) / INODES_PER_GROUP == group)
+                .count();
+            put(self.block(start + 2), 0, &blocks);
+            put(self.block(start + 3), 0, &inodes);
+            free_blocks += group_free_blocks;
+            free_inodes += group_free_inodes;
+
+            let mut desc = [0u8; 32];
+            put(&mut desc, 0x00, &((start + 2) as u32).to_le_bytes());
+            put(&mut desc, 0x04, &((start + 3) as u32).to_le_bytes());
+            put(&mut desc, 0x08, &((start + 4) as u32).to_le_bytes());
+            put(&mut desc, 0x0C, &(group_free_blocks as u16).to_le_bytes());
+            put(&mut desc, 0x0E, &(group_free_inodes as u16).to_le_bytes());
+            put(&mut desc, 0x10, &(group_dirs as u16).to_le_bytes());
+            for copy in 0..GROUPS {
+                let table = 1 + copy * BLOCKS_PER_GROUP + 1;
+                put(self.block(table), group * 32, &desc);
+            }
+        }
+
+        let mut sb = [0u8; 1024];
+        put(
+            &mut sb,
+            0x00,
+            &((GROUPS * INODES_PER_GROUP) as u32).to_le_bytes(),
+        );
+        put(&mut sb, 0x04, &(BLOCKS as u32).to_le_bytes());
+        put(&mut sb, 0x0C, &(free_blocks as u32).to_le_bytes());
+        put(&mut sb, 0x10, &(free_inodes as u32).to_le_bytes());
+        put(&mut sb, 0x14, &1u32.to_le_bytes());
+        put(&mut sb, 0x20, &(BLOCKS_PER_GROUP as u32).to_le_bytes());
+        put(&mut sb, 0x24, &(BLOCKS_PER_GROUP as u32).to_le_bytes());
+        put(&mut sb, 0x28, &(INODES_PER_GROUP as u32).to_le_bytes());
+        put(&mut sb, 0x36, &0xFFFFu16.to_le_bytes());
+        put(&mut sb, 0x38, &0xEF53u16.to_le_bytes());
+        put(&mut sb, 0x3A, &1u16.to_le_bytes());
+        put(&mut sb, 0x3C, &1u16.to_le_bytes());
+        put(&mut sb, 0x4C, &1u32.to_le_bytes());
+        put(&mut sb, 0x54, &11u32.to_le_bytes());
+        put(&mut sb, 0x58, &(INODE_SIZE as u16).to_le_bytes());
+        put(&mut sb, 0x60, &self.incompat.to_le_bytes());
+        put(&mut sb, 0x64, &self.ro_compat.to_le_bytes());
+        put(&mut sb, 0x68, b"ysos-ext2-tests!");
+        put(&mut sb, 0x78, b"TEST");
+        for group in 0..GROUPS {
+            put(&mut sb, 0x5A, &(group as u16).to_le_bytes());
+            put(self.block(1 + group * BLOCKS_PER_GROUP), 0, &sb);
+        }
+
+        let blocks = self
+            .data
+            .chunks(512)
+            .map(|sector| Block::new(sector.try_into().unwrap()))
+            .collect();
+        RamDisk::from_blocks(blocks)
+    }
+}
+
+/// Number of set bits among the first `count` bits of the bitmap
+fn blocks_used(bitmap: &[u8], count: usize) -> usize {
+    (0..count)
+        .filter(|bit| bitmap[bit / 8] & (1 << (bit % 8)) != 0)
+        .count()
+}
+
+/// The image used by most tests:
+///
+/// ```text
+/// /lost+found/
+/// /hello.txt          "Hello, ext2!", also linked as /docs/hello
+/// /docs/
+/// /docs/big.bin       20 KiB, the last 8 blocks through the single indirect block
+/// /docs/hello         hard link to /hello.txt
+/// /docs/top           symlink to "/"
+/// /link               symlink to "docs/big.bin"
+/// ```
+fn sample_image() -> ImageBuilder {
+    let mut image = ImageBuilder::new();
+    image.dir(
+        ROOT_INO,
+        ROOT_INO,
+        &[
+            ("lost+found", 11, FT_DIR),
+            ("hello.txt", 12, FT_REG_FILE),
+            ("docs", 13, FT_DIR),
+            ("link", 15, FT_SYMLINK),
+        ],
+    );
+    image.dir(11, ROOT_INO, &[]);
+    image.file(12, 0o644, 2, b"Hello, ext2!");
+    image.dir(
+        13,
+        ROOT_INO,
+        &[
+            ("big.bin", 14, FT_REG_FILE),
+            ("hello", 12, FT_REG_FILE),
+            ("top", 16, FT_SYMLINK),
+        ],
+    );
+    image.file(14, 0o600, 1, &pattern(20 * BLOCK_SIZE));
+    image.symlink(15, "docs/big.bin");
+    image.symlink(16, "/");
+    image
+}
+
+fn sample() -> (Ext2, RamDisk) {
+    let disk = sample_image().disk();
+    (Ext2::new(disk.clone()).unwrap(), disk)
+}
+
+/// Check that the free counts in the superblock and the group descriptors match the bitmaps
+fn check_counts(disk: &RamDisk) -> (u32, u32) {
+    let blocks = disk.blocks();
+    let block = |index: usize| -> Vec<u8> {
+        (index * 2..index * 2 + 2)
+            .flat_map(|sector| blocks[sector].iter().copied())
+            .collect()
+    };
+
+    let sb = block(1);
+    let table = block(2);
+    let (mut free_blocks, mut free_inodes) = (0, 0);
+    for group in 0..GROUPS {
+        let desc = &table[group * 32..group * 32 + 32];
+        let count = (BLOCKS - 1 - group * BLOCKS_PER_GROUP).min(BLOCKS_PER_GROUP);
+        let group_blocks = count - blocks_used(&block(u32_at(desc, 0) as usize), count);
+        let group_inodes =
+            INODES_PER_GROUP - blocks_used(&block(u32_at(desc, 4) as usize), INODES_PER_GROUP);
+        assert_eq!(u16_at(desc, 0x0C) as usize, group_blocks);
+        assert_eq!(u16_at(desc, 0x0E) as usize, group_inodes);
+        free_blocks += group_blocks as u32;
+        free_inodes += group_inodes as u32;
+    }
+    assert_eq!(u32_at(&sb, 0x0C), free_blocks);
+    assert_eq!(u32_at(&sb, 0x10), free_inodes);
+    (free_blocks, free_inodes)
+}
+
+#[test]
+fn test_mount() {
+    let (fs, disk) = sample();
+    let (free_blocks, free_inodes) = check_counts(&disk);
+    assert_eq!(free_inodes as usize, GROUPS * INODES_PER_GROUP - 16);
+    assert_eq!(
+        free_blocks as usize,
+        // 3 个目录、hello.txt、big.bin 及其一级间接块
+        BLOCKS - 1 - GROUPS * META_BLOCKS - 3 - 1 - 20 - 1
+    );
+
+    let handle = &fs.handle;
+    assert_eq!(handle.block_size, BLOCK_SIZE);
+    assert_eq!(handle.state.lock().groups.len(), GROUPS);
+    assert!(handle.has_filetype);
+    assert!(!handle.read_only);
+    assert!(format!("{:?}", fs).starts_with("Ext2"));
+
+    // 不认识的只读兼容特性：只读挂载
+    let mut image = sample_image();
+    image.ro_compat |= 0x0400;
+    let fs = Ext2::new(image.disk()).unwrap();
+    assert_eq!(read_to_end(&fs, "/hello.txt"), b"Hello, ext2!");
+    assert_eq!(fs.create_dir("/new").unwrap_err(), FsError::ReadOnly);
+    assert_eq!(fs.remove_file("/hello.txt").unwrap_err(), FsError::ReadOnly);
+    let mut file = fs.open_file("/hello.txt").unwrap();
+    assert_eq!(file.write(b"x"), Err(FsError::ReadOnly));
+
+    // 不认识的不兼容特性（如 extents）：拒绝挂载
+    let mut image = sample_image();
+    image.incompat |= 0x0040;
+    assert_eq!(Ext2::new(image.disk()).unwrap_err(), FsError::NotSupported);
+
+    let blank = RamDisk::new(16);
+    assert!(Ext2::new(blank).is_err());
+}
+
+#[test]
+fn test_read() {
+    let (fs, _) = sample();
+
+    assert_eq!(names(&fs, "/"), ["lost+found", "hello.txt", "docs", "link"]);
+    assert_eq!(names(&fs, "/docs"), ["big.bin", "hello", "top"]);
+    assert!(names(&fs, "/lost+found").is_empty());
+
+    assert_eq!(read_to_end(&fs, "/hello.txt"), b"Hello, ext2!");
+    assert_eq!(read_to_end(&fs, "/docs/hello"), b"Hello, ext2!");
+    assert_eq!(read_to_end(&fs, "/docs/big.bin"), pattern(20 * BLOCK_SIZE));
+    assert_eq!(
+        read_to_end(&fs, "/docs/../docs/./big.bin"),
+        pattern(20 * BLOCK_SIZE)
+    );
+
+    // 跨越直接块与间接块的边界读取
+    let mut file = fs.open_file("/docs/big.bin").unwrap();
+    let mut buf = [0u8; 100];
+    file.seek(SeekFrom::Start(12 * BLOCK_SIZE - 50)).unwrap();
+    assert_eq!(file.read(&mut buf), Ok(100));
+    assert_eq!(
+        buf,
+        pattern(20 * BLOCK_SIZE)[12 * BLOCK_SIZE - 50..12 * BLOCK_SIZE + 50]
+    );
+    assert_eq!(file.seek(SeekFrom::End(1)), Err(FsError::InvalidOffset));
+
+    let meta = fs.metadata("/docs/big.bin").unwrap();
+    assert!(meta.is_file());
+    assert_eq!((meta.name.as_str(), meta.len), ("big.bin", 20 * BLOCK_SIZE));
+    assert!(fs.metadata("/docs").unwrap().is_dir());
+    assert!(fs.metadata("/").unwrap().is_dir());
+    assert_eq!(fs.permissions("/docs/big.bin"), Ok(0o600));
+    assert_eq!(fs.stat("/hello.txt", true).unwrap().links_count(), 2);
+
+    assert_eq!(fs.exists("/docs/hello"), Ok(true));
+    assert_eq!(fs.exists("/docs/missing"), Ok(false));
+    assert_eq!(fs.open_file("/docs").unwrap_err(), FsError::NotAFile);
+    assert!(fs.read_dir("/hello.txt").is_err());
+    assert_eq!(
+        fs.open_file("/HELLO.TXT").unwrap_err(),
+        FsError::FileNotFound
+    );
+}
+
+#[test]
+fn test_read_symlinks() {
+    let (fs, _) = sample();
+
+    // 符号链接在路径中间与末尾都会被解析
+    assert_eq!(fs.read_link("/link"), Ok(String::from("docs/big.bin")));
+    assert_eq!(read_to_end(&fs, "/link"), pattern(20 * BLOCK_SIZE));
+    assert_eq!(read_to_end(&fs, "/docs/top/hello.txt"), b"Hello, ext2!");
+    assert_eq!(
+        names(&fs, "/docs/top/docs/top/docs"),
+        ["big.bin", "hello", "top"]
+    );
+
+    let entries: Vec<_> = fs.read_dir("/").unwrap().collect();
+    assert!(entries[3].is_symlink());
+    assert_eq!(entries[3].len, "docs/big.bin".len());
+    assert!(fs.metadata("/link").unwrap().is_file());
+    assert!(fs.stat("/link", false).unwrap().is_symlink());
+    assert_eq!(
+        fs.read_link("/hello.txt").unwrap_err(),
+        FsError::InvalidOperation
+    );
+}
+
+#[test]
+fn test_create_and_reread() {
+    let (fs, disk) = sample();
+    let (free_blocks, free_inodes) = check_counts(&disk);
+
+    write_file(&fs, "/new.txt", b"fresh");
+    fs.create_dir("/docs/sub").unwrap();
+    write_file(&fs, "/docs/sub/a file with spaces", b"spaces");
+    // 超过一级间接块，用到二级间接块
+    let size = (DIRECT_BLOCKS + BLOCK_SIZE / 4 + 30) * BLOCK_SIZE + 123;
+    write_file(&fs, "/docs/sub/huge.bin", &pattern(size));
+
+    let fs = Ext2::new(disk.clone()).unwrap();
+    assert_eq!(
+        names(&fs, "/"),
+        ["lost+found", "hello.txt", "docs", "link", "new.txt"]
+    );
+    assert_eq!(names(&fs, "/docs/sub"), ["a file with spaces", "huge.bin"]);
+    assert_eq!(read_to_end(&fs, "/new.txt"), b"fresh");
+    assert_eq!(read_to_end(&fs, "/docs/sub/huge.bin"), pattern(size));
+    assert_eq!(fs.permissions("/new.txt"), Ok(0o644));
+    assert_eq!(fs.permissions("/docs/sub"), Ok(0o755));
+
+    // 数据块、一级间接块、二级间接块及其下的 1 个一级间接块
+    let data_blocks = size.div_ceil(BLOCK_SIZE);
+    let huge = fs.stat("/docs/sub/huge.bin", true).unwrap();
+    assert_eq!(huge.blocks() as usize, (data_blocks + 3) * 2);
+
+    let dirs = fs.stat("/docs", true).unwrap();
+    assert_eq!(dirs.links_count(), 3);
+    let (now_blocks, now_inodes) = check_counts(&disk);
+    assert_eq!(free_inodes - now_inodes, 4);
+    assert_eq!(free_blocks - now_blocks, 1 + 1 + 1 + data_blocks as u32 + 3);
+
+    // 覆盖与追加
+    let mut file = fs.open_file("/new.txt").unwrap();
+    file.write_all(b"FR").unwrap();
+    drop(file);
+    let mut file = fs.append_file("/new.txt").unwrap();
+    file.write_all(b" and appended").unwrap();
+    drop(file);
+    assert_eq!(read_to_end(&fs, "/new.txt"), b"FResh and appended");
+
+    // 已存在的文件被截断
+    write_file(&fs, "/docs/sub/huge.bin", b"small");
+    assert_eq!(read_to_end(&fs, "/docs/sub/huge.bin"), b"small");
+    assert_eq!(check_counts(&disk).0, now_blocks + data_blocks as u32 + 2);
+
+    assert_eq!(fs.create_dir("/docs/sub"), Err(FsError::AlreadyExists));
+    assert_eq!(fs.create_file("/docs").unwrap_err(), FsError::NotAFile);
+    assert_eq!(
+        fs.create_file("/missing/file").unwrap_err(),
+        FsError::FileNotFound
+    );
+    assert_eq!(
+        fs.create_file("/hello.txt/file").unwrap_err(),
+        FsError::NotADirectory
+    );
+    assert_eq!(
+        fs.create_dir(&format!("/{}", "x".repeat(256))),
+        Err(FsError::FileNameError(FilenameError::NameTooLong))
+    );
+}
+
+#[test]
+fn test_set_len() {
+    let (fs, disk) = sample();
+    let (free_blocks, _) = check_counts(&disk);
+
+    // 截断到直接块以内，释放间接块
+    let mut file = fs.open_file("/docs/big.bin").unwrap();
+    file.seek(SeekFrom::End(0)).unwrap();
+    file.set_len(5 * BLOCK_SIZE + 10).unwrap();
+    assert_eq!(file.seek(SeekFrom::Current(0)), Ok(5 * BLOCK_SIZE + 10));
+    drop(file);
+    assert_eq!(
+        read_to_end(&fs, "/docs/big.bin"),
+        pattern(5 * BLOCK_SIZE + 10)
+    );
+    assert_eq!(check_counts(&disk).0, free_blocks + 14 + 1);
+
+    // 扩展的部分是空洞，读出为零且不占用块
+    let mut file = fs.open_file("/docs/big.bin").unwrap();
+    file.set_len(40 * BLOCK_SIZE).unwrap();
+    drop(file);
+    let mut expected = pattern(5 * BLOCK_SIZE + 10);
+    expected.resize(40 * BLOCK_SIZE, 0);
+    assert_eq!(read_to_end(&fs, "/docs/big.bin"), expected);
+    assert_eq!(check_counts(&disk).0, free_blocks + 15);
+
+    // 写入空洞中间时才分配块
+    let mut file = fs.open_file("/docs/big.bin").unwrap();
+    file.seek(SeekFrom::Start(30 * BLOCK_SIZE)).unwrap();
+    file.write_all(b"in the hole").unwrap();
+    drop(file);
+    expected[30 * BLOCK_SIZE..30 * BLOCK_SIZE + 11].copy_from_slice(b"in the hole");
+    assert_eq!(read_to_end(&fs, "/docs/big.bin"), expected);
+    assert_eq!(check_counts(&disk).0, free_blocks + 15 - 2);
+
+    let mut file = fs.open_file("/docs/big.bin").unwrap();
+    file.set_len(0).unwrap();
+    drop(file);
+    assert_eq!(fs.metadata("/docs/big.bin").unwrap().len, 0);
+    assert_eq!(fs.stat("/docs/big.bin", true).unwrap().blocks(), 0);
+    assert_eq!(check_counts(&disk).0, free_blocks + 21);
+}
+
+#[test]
+fn test_links() {
+    let (fs, disk) = sample();
+    let (free_blocks, free_inodes) = check_counts(&disk);
+
+    // 删除一个硬链接后，文件仍可由另一个链接访问
+    fs.remove_file("/hello.txt").unwrap();
+    assert_eq!(read_to_end(&fs, "/docs/hello"), b"Hello, ext2!");
+    assert_eq!(fs.stat("/docs/hello", true).unwrap().links_count(), 1);
+    assert_eq!(check_counts(&disk), (free_blocks, free_inodes));
+
+    fs.link("/docs/hello", "/again").unwrap();
+    assert_eq!(fs.stat("/again", true).unwrap().links_count(), 2);
+    let mut file = fs.append_file("/again").unwrap();
+    file.write_all(b" Linked.").unwrap();
+    drop(file);
+    assert_eq!(read_to_end(&fs, "/docs/hello"), b"Hello, ext2! Linked.");
+    assert_eq!(fs.link("/docs", "/docs2"), Err(FsError::NotAFile));
+    assert_eq!(
+        fs.link("/again", "/docs/hello"),
+        Err(FsError::AlreadyExists)
+    );
+
+    // 删除符号链接不影响目标，悬空的链接无法打开
+    fs.symlink("again", "/short").unwrap();
+    let target = format!("/{}again", "docs/top/".repeat(7));
+    fs.symlink(&target, "/long").unwrap();
+    assert_eq!(fs.stat("/short", false).unwrap().blocks(), 0);
+    assert_eq!(fs.stat("/long", false).unwrap().blocks(), 2);
+    assert_eq!(fs.read_link("/long"), Ok(target));
+    assert_eq!(read_to_end(&fs, "/short"), b"Hello, ext2! Linked.");
+    assert_eq!(read_to_end(&fs, "/long"), b"Hello, ext2! Linked.");
+    fs.remove_file("/short").unwrap();
+    assert_eq!(fs.exists("/again"), Ok(true));
+    fs.remove_file("/again").unwrap();
+    fs.remove_file("/docs/hello").unwrap();
+    assert_eq!(fs.open_file("/long").unwrap_err(), FsError::FileNotFound);
+    fs.remove_file("/long").unwrap();
+    assert_eq!(check_counts(&disk), (free_blocks + 1, free_inodes + 1));
+
+    // 循环的符号链接
+    fs.symlink("/loop2", "/loop1").unwrap();
+    fs.symlink("/loop1", "/loop2").unwrap();
+    assert!(matches!(
+        fs.open_file("/loop1"),
+        Err(FsError::InvalidPath(_))
+    ));
+
+    fs.set_permissions("/docs/big.bin", 0o4751).unwrap();
+    assert_eq!(fs.permissions("/link"), Ok(0o4751));
+    assert!(fs.stat("/docs/big.bin", true).unwrap().is_file());
+}
+
+#[test]
+fn test_directories() {
+    let (fs, disk) = sample();
+    let (free_blocks, free_inodes) = check_counts(&disk);
+
+    // 目录写满一块后继续增长，删除后的空间被复用
+    fs.create_dir("/many").unwrap();
+    let files: Vec<String> = (0..100).map(|i| format!("file number {i}")).collect();
+    for name in &files {
+        write_file(&fs, &format!("/many/{name}"), name.as_bytes());
+    }
+    assert_eq!(names(&fs, "/many"), files);
+    assert!(fs.stat("/many", true).unwrap().size() > BLOCK_SIZE);
+    for name in files.iter().step_by(2) {
+        fs.remove_file(&format!("/many/{name}")).unwrap();
+    }
+    let size = fs.stat("/many", true).unwrap().size();
+    write_file(&fs, "/many/reused", b"");
+    assert_eq!(fs.stat("/many", true).unwrap().size(), size);
+    for name in files.iter().skip(1).step_by(2) {
+        assert_eq!(read_to_end(&fs, &format!("/many/{name}")), name.as_bytes());
+    }
+
+    // 递归删除目录，其中文件的其它硬链接保留
+    fs.create_dir("/many/sub").unwrap();
+    fs.link("/many/file number 1", "/kept").unwrap();
+    assert_eq!(fs.stat("/", true).unwrap().links_count(), 5);
+    fs.remove_dir("/many").unwrap();
+    assert_eq!(fs.exists("/many"), Ok(false));
+    assert_eq!(read_to_end(&fs, "/kept"), b"file number 1");
+    assert_eq!(fs.stat("/", true).unwrap().links_count(), 4);
+    fs.remove_file("/kept").unwrap();
+    assert_eq!(check_counts(&disk), (free_blocks, free_inodes));
+
+    assert_eq!(fs.remove_dir("/hello.txt"), Err(FsError::NotADirectory));
+    assert_eq!(fs.remove_file("/docs"), Err(FsError::NotAFile));
+    assert_eq!(fs.remove_dir("/"), Err(FsError::InvalidOperation));
+    assert_eq!(fs.remove_file("/missing"), Err(FsError::FileNotFound));
+}
+
+#[test]
+fn test_move() {
+    let (fs, disk) = sample();
+    let (free_blocks, free_inodes) = check_counts(&disk);
+
+    fs.move_file("/hello.txt", "/docs/renamed").unwrap();
+    assert_eq!(names(&fs, "/"), ["lost+found", "docs", "link"]);
+    assert_eq!(read_to_end(&fs, "/docs/renamed"), b"Hello, ext2!");
+
+    // 目标文件被替换，同一文件的两个链接之间移动什么也不做
+    fs.move_file("/docs/hello", "/docs/renamed").unwrap();
+    assert_eq!(names(&fs, "/docs"), ["big.bin", "hello", "top", "renamed"]);
+    fs.move_file("/docs/renamed", "/docs/big.bin").unwrap();
+    assert_eq!(read_to_end(&fs, "/docs/big.bin"), b"Hello, ext2!");
+    assert_eq!(fs.stat("/docs/hello", true).unwrap().links_count(), 2);
+    assert_eq!(check_counts(&disk).0, free_blocks + 21);
+
+    // 移动目录时更新 `..` 与父目录的链接数
+    fs.create_dir("/docs/sub").unwrap();
+    write_file(&fs, "/docs/sub/note", b"note");
+    fs.move_dir("/docs/sub", "/lost+found/moved").unwrap();
+    assert_eq!(read_to_end(&fs, "/lost+found/moved/../moved/note"), b"note");
+    assert_eq!(names(&fs, "/lost+found/moved/../.."), names(&fs, "/"));
+    assert_eq!(fs.stat("/docs", true).unwrap().links_count(), 2);
+    assert_eq!(fs.stat("/lost+found", true).unwrap().links_count(), 3);
+
+    assert_eq!(
+        fs.move_dir("/lost+found", "/lost+found/moved/inner"),
+        Err(FsError::InvalidOperation)
+    );
+    assert_eq!(
+        fs.move_dir("/docs/hello", "/x"),
+        Err(FsError::NotADirectory)
+    );
+    assert_eq!(fs.move_file("/docs", "/x"), Err(FsError::NotAFile));
+    assert_eq!(
+        fs.move_dir("/docs", "/lost+found"),
+        Err(FsError::AlreadyExists)
+    );
+    fs.move_dir("/lost+found/moved", "/moved").unwrap();
+    assert_eq!(fs.stat("/", true).unwrap().links_count(), 5);
+
+    fs.copy_file("/docs/hello", "/moved/copy").unwrap();
+    assert_eq!(read_to_end(&fs, "/moved/copy"), b"Hello, ext2!");
+    assert_eq!(fs.stat("/moved/copy", true).unwrap().links_count(), 1);
+
+    fs.remove_dir("/moved").unwrap();
+    assert_eq!(check_counts(&disk), (free_blocks + 21, free_inodes + 1));
+}
+
+#[test]
+fn test_full() {
+    let (fs, disk) = sample();
+    let (free_blocks, _) = check_counts(&disk);
+
+    // 空间不足时返回已写入的长度
+    let mut file = fs.create_file("/fill").unwrap();
+    let data = pattern(BLOCKS * BLOCK_SIZE);
+    let written = file.write(&data).unwrap();
+    assert!(written < data.len());
+    assert_eq!(file.write(b"more"), Err(FsError::WriteZero));
+    drop(file);
+    assert_eq!(check_counts(&disk).0, 0);
+    assert_eq!(read_to_end(&fs, "/fill"), data[..written]);
+
+    fs.remove_file("/fill").unwrap();
+    assert_eq!(check_counts(&disk).0, free_blocks);
+}
@@ -0,0 +1,458 @@
+//! Directory Entry
+//!
+//! reference: <https://wiki.osdev.org/FAT#Directories_on_FAT12.2F16.2F32>
+
+use crate::*;
+use bitflags::bitflags;
+use chrono::LocalResult::Single;
+use chrono::{DateTime, Datelike, TimeZone, Timelike, Utc};
+use core::fmt::{Debug, Display};
+use core::ops::*;
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct DirEntry {
+    pub filename: ShortFileName,
+    pub modified_time: FsTime,
+    pub created_time: FsTime,
+    pub accessed_time: FsTime,
+    pub cluster: Cluster,
+    pub attributes: Attributes,
+    pub size: u32,
+    /// The VFAT long file name, `None` for a plain 8.3 name
+    pub long_name: Option<String>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct Cluster(pub u32);
+
+bitflags! {
+    /// File Attributes
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub struct Attributes: u8 {
+        const READ_ONLY = 0x01;
+        const HIDDEN    = 0x02;
+        const SYSTEM    = 0x04;
+        const VOLUME_ID = 0x08;
+        const DIRECTORY = 0x10;
+        const ARCHIVE   = 0x20;
+        const LFN       = 0x0f; // Long File Name
+    }
+}
+
+impl DirEntry {
+    pub const LEN: usize = 0x20;
+
+    pub fn filename(&self) -> String {
+        if let Some(long_name) = &self.long_name {
+            long_name.clone()
+        } else if self.is_valid() && !self.is_long_name() {
+            format!("{}", self.filename)
+        } else {
+            String::from("unknown")
+        }
+    }
+
+    /// Keep `name` as the long file name unless it is exactly the short name
+    pub fn set_long_name(&mut self, name: &str) {
+        self.long_name = (format!("{}", self.filename) != name).then(|| name.into());
+    }
+
+    /// Whether the entry is called `name`, ignoring case
+    pub fn matches(&self, name: &str) -> bool {
+        self.long_name
+            .as_deref()
+            .is_some_and(|long_name| super::lfn::name_eq(long_name, name))
+            || ShortFileName::parse(name).is_ok_and(|sfn| self.filename.matches(&sfn))
+    }
+
+    /// For Standard 8.3 format
+    ///
+    /// reference: https://osdev.org/FAT#Standard_8.3_format
+    pub fn parse(data: &[u8]) -> FsResult<DirEntry> {
+        let filename = ShortFileName::new(&data[..11]);
+        let attributes = Attributes::from_bits_truncate(data[11]);
+
+        let u16_at = |offset: usize| u16::from_le_bytes([data[offset], data[offset + 1]]) as u32;
+        let u32_at =
+            |offset: usize| u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+
+        // 时间与日期各占 16 位，日期在高位；访问时间只记录日期
+        let created_time = prase_datetime(u32_at(0x0E));
+        let accessed_time = prase_datetime(u16_at(0x12) << 16);
+        let modified_time = prase_datetime(u32_at(0x16));
+
+        let cluster = (u16_at(0x14) << 16) | u16_at(0x1A);
+        let size = u32_at(0x1C);
+
+        Ok(DirEntry {
+            filename,
+            modified_time,
+            created_time,
+            accessed_time,
+            cluster: Cluster(cluster),
+            attributes,
+            size,
+            long_name: None,
+        })
+    }
+
+    /// Create an entry stamped with the current time
+    pub fn new(filename: ShortFileName, attributes: Attributes, cluster: Cluster) -> Self {
+        let time = now();
+        DirEntry {
+            filename,
+            modified_time: time,
+            created_time: time,
+            accessed_time: time,
+            cluster,
+            attributes,
+            size: 0,
+            long_name: None,
+        }
+    }
+
+    /// Encode the entry in the on-disk 8.3 format
+    pub fn as_bytes(&self) -> [u8; Self::LEN] {
+        let mut data = [0u8; Self::LEN];
+        data[..8].copy_from_slice(&self.filename.name);
+        data[8..11].copy_from_slice(&self.filename.ext);
+        data[0x0B] = self.attributes.bits();
+
+        let accessed_date = (encode_datetime(&self.accessed_time) >> 16) as u16;
+        data[0x0E..0x12].copy_from_slice(&encode_datetime(&self.created_time).to_le_bytes());
+        data[0x12..0x14].copy_from_slice(&accessed_date.to_le_bytes());
+        data[0x14..0x16].copy_from_slice(&((self.cluster.0 >> 16) as u16).to_le_bytes());
+        data[0x16..0x1A].copy_from_slice(&encode_datetime(&self.modified_time).to_le_bytes());
+        data[0x1A..0x1C].copy_from_slice(&(self.cluster.0 as u16).to_le_bytes());
+        data[0x1C..0x20].copy_from_slice(&self.size.to_le_bytes());
+        data
+    }
+
+    pub fn as_meta(&self) -> Metadata {
+        self.into()
+    }
+
+    /// The entry marks the end of the directory
+    pub fn is_eod(&self) -> bool {
+        self.filename.is_eod()
+    }
+
+    /// The entry is deleted
+    pub fn is_unused(&self) -> bool {
+        self.filename.is_unused()
+    }
+
+    /// The entry is neither the end of the directory nor deleted
+    pub fn is_valid(&self) -> bool {
+        !self.is_eod() && !self.is_unused()
+    }
+
+    pub fn is_readonly(&self) -> bool {
+        self.attributes.contains(Attributes::READ_ONLY)
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.attributes.contains(Attributes::HIDDEN)
+    }
+
+    pub fn is_system(&self) -> bool {
+        self.attributes.contains(Attributes::SYSTEM)
+    }
+
+    pub fn is_volume_id(&self) -> bool {
+        self.attributes.contains(Attributes::VOLUME_ID)
+    }
+
+    pub fn is_directory(&self) -> bool {
+        self.attributes.contains(Attributes::DIRECTORY)
+    }
+
+    pub fn is_archive(&self) -> bool {
+        self.attributes.contains(Attributes::ARCHIVE)
+    }
+
+    pub fn is_long_name(&self) -> bool {
+        self.attributes.contains(Attributes::LFN)
+    }
+}
+
+fn prase_datetime(time: u32) -> FsTime {
+    // 日期：年（自 1980 起）7 位、月 4 位、日 5 位
+    // 时间：时 5 位、分 6 位、秒 5 位（以 2 秒为单位）
+    let year = ((time >> 25) & 0x7F) as i32 + 1980;
+    let month = (time >> 21) & 0x0F;
+    let day = (time >> 16) & 0x1F;
+    let hour = (time >> 11) & 0x1F;
+    let min = (time >> 5) & 0x3F;
+    let sec = (time & 0x1F) * 2;
+
+    if let Single(time) = Utc.with_ymd_and_hms(year, month, day, hour, min, sec) {
+        time
+    } else {
+        DateTime::from_timestamp_millis(0).unwrap()
+    }
+}
+
+/// The inverse of `prase_datetime`, times before 1980 are clamped to 1980-01-01
+fn encode_datetime(time: &FsTime) -> u32 {
+    if time.year() < 1980 {
+        return (1 << 21) | (1 << 16);
+    }
+    let date = ((time.year() as u32 - 1980) << 9) | (time.month() << 5) | time.day();
+    let time = (time.hour() << 11) | (time.minute() << 5) | (time.second() / 2);
+    (date << 16) | time
+}
+
+#[derive(PartialEq, Eq, Clone)]
+pub struct ShortFileName {
+    pub name: [u8; 8],
+    pub ext: [u8; 3],
+}
+
+impl ShortFileName {
+    pub fn new(buf: &[u8]) -> Self {
+        Self {
+            name: buf[..8].try_into().unwrap(),
+            ext: buf[8..11].try_into().unwrap(),
+        }
+    }
+
+    pub fn basename(&self) -> &str {
+        core::str::from_utf8(&self.name).unwrap()
+    }
+
+    pub fn extension(&self) -> &str {
+        core::str::from_utf8(&self.ext).unwrap()
+    }
+
+    pub fn is_eod(&self) -> bool {
+        self.name[0] == 0x00 && self.ext[0] == 0x00
+    }
+
+    pub fn is_unused(&self) -> bool {
+        self.name[0] == 0xE5
+    }
+
+    pub fn matches(&self, sfn: &ShortFileName) -> bool {
+        self.name == sfn.name && self.ext == sfn.ext
+    }
+
+    /// Parse a short file name from a string
+    ///
+    /// the name is converted to uppercase as FAT names are case-insensitive
+    pub fn parse(name: &str) -> FsResult<ShortFileName> {
+        if name.is_empty() {
+            return Err(FilenameError::FilenameEmpty.into());
+        }
+
+        let mut sfn = ShortFileName {
+            name: [0x20; 8],
+            ext: [0x20; 3],
+        };
+
+        // 子目录中的 `.` 与 `..` 项
+        if name == "." || name == ".." {
+            sfn.name[..name.len()].copy_from_slice(name.as_bytes());
+            return Ok(sfn);
+        }
+        let mut idx = 0;
+        let mut in_ext = false;
+
+        for ch in name.bytes() {
+            match ch {
+                0x00..=0x1F
+                | 0x20
+                | 0x22
+                | 0x2A
+                | 0x2B
+                | 0x2C
+                | 0x2F
+                | 0x3A
+                | 0x3B
+                | 0x3C
+                | 0x3D
+                | 0x3E
+                | 0x3F
+                | 0x5B
+                | 0x5C
+                | 0x5D
+                | 0x7C => return Err(FilenameError::InvalidCharacter.into()),
+                b'.' => {
+                    // 扩展名只能有一个，且主文件名不能为空
+                    if in_ext || idx == 0 {
+                        return Err(FilenameError::MisplacedPeriod.into());
+                    }
+                    in_ext = true;
+                    idx = 0;
+                }
+                _ if in_ext => {
+                    if idx >= 3 {
+                        return Err(FilenameError::NameTooLong.into());
+                    }
+                    sfn.ext[idx] = ch.to_ascii_uppercase();
+                    idx += 1;
+                }
+                _ => {
+                    if idx >= 8 {
+                        return Err(FilenameError::NameTooLong.into());
+                    }
+                    sfn.name[idx] = ch.to_ascii_uppercase();
+                    idx += 1;
+                }
+            }
+        }
+
+        if in_ext && idx == 0 {
+            return Err(FilenameError::MisplacedPeriod.into());
+        }
+
+        Ok(sfn)
+    }
+}
+
+impl Debug for ShortFileName {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl Display for ShortFileName {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        if self.ext[0] == 0x20 {
+            write!(f, "{}", self.basename().trim_end())
+        } else {
+            write!(
+                f,
+                "{}.{}",
+                self.basename().trim_end(),
+                self.extension().trim_end()
+            )
+        }
+    }
+}
+
+impl Cluster {
+    /// Magic value indicating an invalid cluster value.
+    pub const INVALID: Cluster = Cluster(0xFFFF_FFF6);
+    /// Magic value indicating a bad cluster.
+    pub const BAD: Cluster = Cluster(0xFFFF_FFF7);
+    /// Magic value indicating a empty cluster.
+    pub const EMPTY: Cluster = Cluster(0x0000_0000);
+    /// Magic value indicating the cluster holding the root directory
+    /// (which doesn't have a number in Fat16 as there's a reserved region).
+    pub const ROOT_DIR: Cluster = Cluster(0xFFFF_FFFC);
+    /// Magic value indicating that the cluster is allocated and is the final cluster for the file
+    pub const END_OF_FILE: Cluster = Cluster(0xFFFF_FFFF);
+}
+
+impl Add<u32> for Cluster {
+    type Output = Cluster;
+    fn add(self, rhs: u32) -> Cluster {
+        Cluster(self.0 + rhs)
+    }
+}
+
+impl AddAssign<u32> for Cluster {
+    fn add_assign(&mut self, rhs: u32) {
+        self.0 += rhs;
+    }
+}
+
+impl Add<Cluster> for Cluster {
+    type Output = Cluster;
+    fn add(self, rhs: Cluster) -> Cluster {
+        Cluster(self.0 + rhs.0)
+    }
+}
+
+impl AddAssign<Cluster> for Cluster {
+    fn add_assign(&mut self, rhs: Cluster) {
+        self.0 += rhs.0;
+    }
+}
+
+impl From<&DirEntry> for Metadata {
+    fn from(entry: &DirEntry) -> Metadata {
+        Metadata {
+            entry_type: if entry.is_directory() {
+                FileType::Directory
+            } else {
+                FileType::File
+            },
+            name: entry.filename(),
+            len: entry.size as usize,
+            created: Some(entry.created_time),
+            accessed: Some(entry.accessed_time),
+            modified: Some(entry.modified_time),
+        }
+    }
+}
+
+impl Display for Cluster {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "0x{:08X}", self.0)
+    }
+}
+
+impl Debug for Cluster {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "0x{:08X}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dir_entry() {
+        let data = hex_literal::hex!(
+            "4b 45 52 4e 45 4c 20 20 45 4c 46 20 00 00 0f be
+             d0 50 d0 50 00 00 0f be d0 50 02 00 f0 e4 0e 00"
+        );
+
+        let res = DirEntry::parse(&data).unwrap();
+
+        assert_eq!(&res.filename.name, b"KERNEL  ");
+        assert_eq!(&res.filename.ext, b"ELF");
+        assert_eq!(res.attributes, Attributes::ARCHIVE);
+        assert_eq!(res.cluster, Cluster(2));
+        assert_eq!(res.size, 0xee4f0);
+        assert_eq!(
+            res.created_time,
+            Utc.with_ymd_and_hms(2020, 6, 16, 23, 48, 30).unwrap()
+        );
+        assert_eq!(
+            res.modified_time,
+            Utc.with_ymd_and_hms(2020, 6, 16, 23, 48, 30).unwrap()
+        );
+        assert_eq!(
+            res.accessed_time,
+            Utc.with_ymd_and_hms(2020, 6, 16, 0, 0, 0).unwrap()
+        );
+
+        println!("{:#?}", res);
+
+        assert_eq!(res.as_bytes(), data);
+    }
+
+    #[test]
+    fn test_short_file_name() {
+        let sfn = ShortFileName::parse("kernel.elf").unwrap();
+        assert_eq!(&sfn.name, b"KERNEL  ");
+        assert_eq!(&sfn.ext, b"ELF");
+
+        let sfn = ShortFileName::parse("fab").unwrap();
+        assert_eq!(format!("{}", sfn), "FAB");
+
+        assert_eq!(&ShortFileName::parse("..").unwrap().name, b"..      ");
+
+        let err = |name| ShortFileName::parse(name).unwrap_err();
+        assert_eq!(err(""), FilenameError::FilenameEmpty.into());
+        assert_eq!(err("toolongname"), FilenameError::NameTooLong.into());
+        assert_eq!(err("a.long"), FilenameError::NameTooLong.into());
+        assert_eq!(err(".hidden"), FilenameError::MisplacedPeriod.into());
+        assert_eq!(err("a.b.c"), FilenameError::MisplacedPeriod.into());
+        assert_eq!(err("a*b"), FilenameError::InvalidCharacter.into());
+    }
+}
@@ -0,0 +1,250 @@
+//! File
+//!
+//! reference: <https://wiki.osdev.org/FAT#Directories_on_FAT12.2F16.2F32>
+
+use super::*;
+
+/// A sector of the file kept in memory until another sector is accessed
+struct CachedBlock {
+    sector: usize,
+    block: Block512,
+    dirty: bool,
+}
+
+pub struct File {
+    /// The current offset in the file
+    offset: usize,
+    /// The cluster at `cluster_index` in the chain of this file
+    current_cluster: Cluster,
+    /// The index of `current_cluster` in the chain
+    cluster_index: usize,
+    /// DirEntry of this file
+    entry: DirEntry,
+    /// Where the DirEntry is stored on the disk
+    location: EntryLocation,
+    /// The file system handle that contains this file
+    handle: FatHandle,
+    /// The last accessed sector
+    cache: Option<CachedBlock>,
+    /// Whether the DirEntry needs to be written back
+    dirty: bool,
+}
+
+impl File {
+    pub fn new(handle: FatHandle, entry: DirEntry, location: EntryLocation) -> Self {
+        Self {
+            offset: 0,
+            current_cluster: entry.cluster,
+            cluster_index: 0,
+            entry,
+            location,
+            handle,
+            cache: None,
+            dirty: false,
+        }
+    }
+
+    pub fn length(&self) -> usize {
+        self.entry.size as usize
+    }
+
+    /// Walk the chain to the `index`-th cluster of the file
+    ///
+    /// if `alloc` is set, missing clusters are allocated instead of failing
+    fn seek_cluster(&mut self, index: usize, alloc: bool) -> FsResult<Cluster> {
+        if self.entry.cluster == Cluster::EMPTY {
+            if !alloc {
+                return Err(FsError::BadCluster);
+            }
+            self.entry.cluster = self.handle.alloc_cluster(None)?;
+            self.dirty = true;
+        }
+
+        // 簇链只能向后遍历，回退时从首簇重新开始
+        if index < self.cluster_index || self.current_cluster == Cluster::EMPTY {
+            self.current_cluster = self.entry.cluster;
+            self.cluster_index = 0;
+        }
+
+        while self.cluster_index < index {
+            let next = match self.handle.next_cluster(&self.current_cluster)? {
+                // 簇链比文件长度短
+                Cluster::END_OF_FILE if !alloc => return Err(FsError::BadCluster),
+                Cluster::END_OF_FILE => self.handle.alloc_cluster(Some(self.current_cluster))?,
+                next => next,
+            };
+            self.current_cluster = next;
+            self.cluster_index += 1;
+        }
+
+        Ok(self.current_cluster)
+    }
+
+    /// The sector that holds the byte at `offset`
+    fn sector_at(&mut self, offset: usize, alloc: bool) -> FsResult<usize> {
+        let cluster_size = self.handle.bytes_per_cluster();
+        let cluster = self.seek_cluster(offset / cluster_size, alloc)?;
+        Ok(self.handle.cluster_to_sector(&cluster) + offset % cluster_size / BLOCK_SIZE)
+    }
+
+    /// Load the sector into the cache, writing back the previous one if needed
+    fn load(&mut self, sector: usize) -> FsResult<&mut CachedBlock> {
+        if self.cache.as_ref().map(|cached| cached.sector) != Some(sector) {
+            self.flush_block()?;
+            let mut block = Block::default();
+            self.handle.inner.read_block(sector, &mut block)?;
+            self.cache = Some(CachedBlock {
+                sector,
+                block,
+                dirty: false,
+            });
+        }
+        Ok(self.cache.as_mut().unwrap())
+    }
+
+    fn flush_block(&mut self) -> FsResult {
+        if let Some(cached) = self.cache.as_mut().filter(|cached| cached.dirty) {
+            self.handle
+                .inner
+                .write_block(cached.sector, &cached.block)?;
+            cached.dirty = false;
+        }
+        Ok(())
+    }
+
+    /// Write as much of `buf` as fits in the sector at the current offset
+    fn write_sector(&mut self, buf: &[u8]) -> FsResult<usize> {
+        let sector = self.sector_at(self.offset, true)?;
+        let block_offset = self.offset % BLOCK_SIZE;
+        let len = (BLOCK_SIZE - block_offset).min(buf.len());
+
+        let cached = self.load(sector)?;
+        cached.block.as_mut()[block_offset..block_offset + len].copy_from_slice(&buf[..len]);
+        cached.dirty = true;
+
+        self.offset += len;
+        if self.offset > self.length() {
+            self.entry.size = self.offset as u32;
+        }
+        Ok(len)
+    }
+}
+
+impl Read for File {
+    fn read(&mut self, buf: &mut [u8]) -> FsResult<usize> {
+        let length = self.length();
+        if self.offset >= length {
+            return Ok(0);
+        }
+
+        let total = buf.len().min(length - self.offset);
+        let mut read = 0;
+
+        while read < total {
+            let sector = self.sector_at(self.offset, false)?;
+            let block_offset = self.offset % BLOCK_SIZE;
+            let len = (BLOCK_SIZE - block_offset).min(total - read);
+
+            let cached = self.load(sector)?;
+            buf[read..read + len].copy_from_slice(&cached.block[block_offset..block_offset + len]);
+            read += len;
+            self.offset += len;
+        }
+
+        Ok(read)
+    }
+}
+
+impl Seek for File {
+    /// Seek within the file, the offset can not go beyond the end of file
+    fn seek(&mut self, pos: SeekFrom) -> FsResult<usize> {
+        let offset = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(delta) => self.length().checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.offset.checked_add_signed(delta),
+        };
+        let offset = match offset {
+            Some(offset) if offset <= self.length() => offset,
+            _ => return Err(FsError::InvalidOffset),
+        };
+
+        // 簇在下次读写时再沿 FAT 查找
+        self.offset = offset;
+        Ok(offset)
+    }
+}
+
+impl Write for File {
+    /// Write at the current offset, clusters are allocated as the file grows
+    fn write(&mut self, buf: &[u8]) -> FsResult<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        self.entry.modified_time = now();
+        self.dirty = true;
+
+        let mut written = 0;
+        while written < buf.len() {
+            match self.write_sector(&buf[written..]) {
+                Ok(len) => written += len,
+                // 已写入部分数据时返回实际写入的长度
+                Err(_) if written > 0 => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> FsResult {
+        self.flush_block()?;
+        if self.dirty {
+            self.handle.write_entry(self.location, &self.entry)?;
+            self.dirty = false;
+        }
+        Ok(())
+    }
+
+    fn set_len(&mut self, len: usize) -> FsResult {
+        let length = self.length();
+
+        if len > length {
+            // 在文件末尾补零，保持当前偏移不变
+            let offset = self.offset;
+            let zeros = [0u8; BLOCK_SIZE];
+            self.offset = length;
+            while self.offset < len {
+                self.write_all(&zeros[..(len - self.offset).min(BLOCK_SIZE)])?;
+            }
+            self.offset = offset;
+        } else if len < length {
+            // 被释放的扇区可能再次分配，丢弃缓存
+            self.flush_block()?;
+            self.cache = None;
+
+            let clusters = len.div_ceil(self.handle.bytes_per_cluster());
+            self.handle.truncate_chain(self.entry.cluster, clusters)?;
+            if clusters == 0 {
+                self.entry.cluster = Cluster::EMPTY;
+            }
+
+            self.current_cluster = self.entry.cluster;
+            self.cluster_index = 0;
+            self.entry.size = len as u32;
+            self.entry.modified_time = now();
+            self.offset = self.offset.min(len);
+            self.dirty = true;
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for File {
+    fn drop(&mut self) {
+        if let Err(err) = self.flush() {
+            warn!("Failed to flush file {}: {:?}", self.entry.filename(), err);
+        }
+    }
+}
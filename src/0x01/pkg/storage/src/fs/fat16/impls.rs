@@ -0,0 +1,758 @@
+use super::*;
+use crate::fs::split_path;
+
+/// First byte of a deleted directory entry
+const DELETED_ENTRY: u8 = 0xE5;
+
+impl FatImpl {
+    /// Load the volume on `inner`, fails if the BPB does not describe a usable FAT volume
+    pub fn new(inner: impl BlockDevice<Block512>) -> FsResult<Self> {
+        let mut block = Block::default();
+        let block_size = Block512::size();
+
+        inner.read_block(0, &mut block)?;
+        let bpb = Fat16Bpb::new(block.as_ref())?;
+
+        // 只支持 512 字节的扇区，每簇扇区数为 2 的幂且不为 0
+        if bpb.bytes_per_sector() as usize != block_size
+            || !bpb.sectors_per_cluster().is_power_of_two()
+            || bpb.fat_count() == 0
+        {
+            return Err(FsError::InvalidOperation);
+        }
+
+        let fat_type = FatType::detect(&bpb);
+        let bpb32 = match fat_type {
+            FatType::Fat16 => None,
+            FatType::Fat32 => Some(Fat32Bpb::new(block.as_ref())?),
+        };
+
+        trace!("Loading {:?} Volume: {:#?} {:#?}", fat_type, bpb, bpb32);
+
+        // HINT: FirstDataSector = BPB_ResvdSecCnt + (BPB_NumFATs * FATSz) + RootDirSectors;
+        let fat_start = bpb.reserved_sector_count() as usize;
+        let sectors_per_fat = match &bpb32 {
+            Some(bpb32) => bpb32.sectors_per_fat() as usize,
+            None => bpb.sectors_per_fat() as usize,
+        };
+        let root_dir_size =
+            (bpb.root_entries_count() as usize * DirEntry::LEN).div_ceil(block_size);
+        let first_root_dir_sector = fat_start + bpb.fat_count() as usize * sectors_per_fat;
+        let first_data_sector = first_root_dir_sector + root_dir_size;
+
+        let root_cluster = match &bpb32 {
+            Some(bpb32) => Cluster(bpb32.root_cluster()),
+            None => Cluster::ROOT_DIR,
+        };
+
+        // FSInfo 无效时忽略，不影响读写
+        let fs_info = bpb32.as_ref().and_then(|bpb32| {
+            let sector = bpb32.fs_info_sector() as usize;
+            inner.read_block(sector, &mut block).ok()?;
+            let info = FsInfo::new(block.as_ref()).ok()?;
+            Some((sector, spin::Mutex::new(info)))
+        });
+
+        Ok(Self {
+            bpb,
+            bpb32,
+            inner: Box::new(inner),
+            fat_type,
+            fat_start,
+            sectors_per_fat,
+            first_data_sector,
+            first_root_dir_sector,
+            root_cluster,
+            fs_info,
+        })
+    }
+
+    /// The first cluster of the directory, the FAT32 root directory is a cluster chain
+    fn dir_cluster(&self, cluster: Cluster) -> Cluster {
+        match cluster {
+            Cluster::ROOT_DIR => self.root_cluster,
+            cluster => cluster,
+        }
+    }
+
+    pub fn cluster_to_sector(&self, cluster: &Cluster) -> usize {
+        match self.dir_cluster(*cluster) {
+            Cluster::ROOT_DIR => self.first_root_dir_sector,
+            Cluster(c) => {
+                (c as usize - 2) * self.bpb.sectors_per_cluster() as usize + self.first_data_sector
+            }
+        }
+    }
+
+    pub fn bytes_per_cluster(&self) -> usize {
+        self.bpb.sectors_per_cluster() as usize * BLOCK_SIZE
+    }
+
+    /// Number of clusters in the data region
+    fn cluster_count(&self) -> usize {
+        (self.bpb.total_sectors() as usize - self.first_data_sector)
+            / self.bpb.sectors_per_cluster() as usize
+    }
+
+    /// The FATs that are kept up to date, all of them unless FAT32 disables mirroring
+    fn fats(&self) -> core::ops::Range<usize> {
+        match self.bpb32.as_ref().and_then(|bpb32| bpb32.active_fat()) {
+            Some(active) => active..active + 1,
+            None => 0..self.bpb.fat_count() as usize,
+        }
+    }
+
+    /// Sector and byte offset of the FAT entry of `cluster` in the `fat`-th FAT
+    fn fat_position(&self, fat: usize, cluster: u32) -> (usize, usize) {
+        // FAT16 每个表项占 2 字节，FAT32 占 4 字节
+        let fat_offset = cluster as usize * self.fat_type.entry_size();
+        (
+            self.fat_start + fat * self.sectors_per_fat + fat_offset / BLOCK_SIZE,
+            fat_offset % BLOCK_SIZE,
+        )
+    }
+
+    /// Decode the FAT entry at `offset` of a FAT sector
+    fn fat_entry(&self, block: &Block512, offset: usize) -> u32 {
+        match self.fat_type {
+            FatType::Fat16 => u16::from_le_bytes([block[offset], block[offset + 1]]) as u32,
+            FatType::Fat32 => {
+                u32::from_le_bytes(block[offset..offset + 4].try_into().unwrap())
+                    & self.fat_type.entry_mask()
+            }
+        }
+    }
+
+    /// Set the FAT entry of `cluster` in all copies of the FAT
+    fn write_fat(&self, cluster: u32, value: u32) -> FsResult {
+        let mut block = Block::default();
+        for fat in self.fats() {
+            let (sector, offset) = self.fat_position(fat, cluster);
+            self.inner.read_block(sector, &mut block)?;
+            let entry = &mut block.as_mut()[offset..offset + self.fat_type.entry_size()];
+            match self.fat_type {
+                FatType::Fat16 => entry.copy_from_slice(&(value as u16).to_le_bytes()),
+                FatType::Fat32 => {
+                    // FAT32 表项的高 4 位保留，需要保持原值
+                    let old = u32::from_le_bytes((&*entry).try_into().unwrap());
+                    let mask = self.fat_type.entry_mask();
+                    entry.copy_from_slice(&((old & !mask) | (value & mask)).to_le_bytes());
+                }
+            }
+            self.inner.write_block(sector, &block)?;
+        }
+        Ok(())
+    }
+
+    /// Read the FAT and get the next cluster of the chain
+    pub fn next_cluster(&self, cluster: &Cluster) -> FsResult<Cluster> {
+        let (sector, offset) = self.fat_position(self.fats().start, cluster.0);
+
+        let mut block = Block::default();
+        self.inner.read_block(sector, &mut block)?;
+
+        let mask = self.fat_type.entry_mask();
+        match self.fat_entry(&block, offset) {
+            // 0xFFF7 / 0x0FFFFFF7 为坏簇，之后的值均表示簇链结束
+            0x0000 | 0x0001 => Err(FsError::BadCluster),
+            next if next == mask - 8 => Err(FsError::BadCluster),
+            next if next > mask - 8 => Ok(Cluster::END_OF_FILE),
+            next => Ok(Cluster(next)),
+        }
+    }
+
+    /// Update the cached free cluster count and next free hint of FAT32
+    fn update_fs_info(&self, allocated: Option<u32>, freed: u32) -> FsResult {
+        let Some((sector, info)) = &self.fs_info else {
+            return Ok(());
+        };
+
+        let mut info = info.lock();
+        let free_count = info.free_count();
+        if free_count != fsinfo::UNKNOWN {
+            let allocated = allocated.is_some() as u32;
+            info.set_free_count((free_count + freed).saturating_sub(allocated));
+        }
+        if let Some(cluster) = allocated {
+            info.set_next_free(cluster + 1);
+        }
+        self.inner
+            .write_block(*sector, &Block::new(info.as_bytes()))
+    }
+
+    /// Allocate a zeroed cluster and append it to the chain ending with `prev`
+    pub fn alloc_cluster(&self, prev: Option<Cluster>) -> FsResult<Cluster> {
+        let count = self.cluster_count() as u32;
+        // FAT32 从 FSInfo 记录的位置开始查找空闲簇
+        let hint = match &self.fs_info {
+            Some((_, info)) => info.lock().next_free(),
+            None => 2,
+        };
+        let start = if (2..count + 2).contains(&hint) {
+            hint
+        } else {
+            2
+        };
+
+        let mut block = Block::default();
+        let mut loaded = None;
+        let mut free = None;
+
+        for cluster in (start..count + 2).chain(2..start) {
+            let (sector, offset) = self.fat_position(self.fats().start, cluster);
+            if loaded != Some(sector) {
+                self.inner.read_block(sector, &mut block)?;
+                loaded = Some(sector);
+            }
+            if self.fat_entry(&block, offset) == 0 {
+                free = Some(Cluster(cluster));
+                break;
+            }
+        }
+
+        let cluster = free.ok_or(FsError::WriteZero)?;
+        self.write_fat(cluster.0, self.fat_type.entry_mask())?;
+        if let Some(prev) = prev {
+            self.write_fat(prev.0, cluster.0)?;
+        }
+        self.update_fs_info(Some(cluster.0), 0)?;
+
+        // 目录依赖全零的目录项标记结束，文件也不应读到旧数据
+        let zero = Block::default();
+        let start = self.cluster_to_sector(&cluster);
+        for sector in start..start + self.bpb.sectors_per_cluster() as usize {
+            self.inner.write_block(sector, &zero)?;
+        }
+
+        Ok(cluster)
+    }
+
+    /// Free all clusters of the chain starting at `first`
+    pub fn free_chain(&self, first: Cluster) -> FsResult {
+        let mut cluster = first;
+        let mut freed = 0;
+        while cluster != Cluster::EMPTY && cluster != Cluster::END_OF_FILE {
+            let next = self.next_cluster(&cluster)?;
+            self.write_fat(cluster.0, 0)?;
+            freed += 1;
+            cluster = next;
+        }
+        if freed > 0 {
+            self.update_fs_info(None, freed)?;
+        }
+        Ok(())
+    }
+
+    /// Keep the first `count` clusters of the chain and free the rest
+    pub fn truncate_chain(&self, first: Cluster, count: usize) -> FsResult {
+        if count == 0 {
+            return self.free_chain(first);
+        }
+
+        let mut last = first;
+        for _ in 1..count {
+            last = self.next_cluster(&last)?;
+            if last == Cluster::END_OF_FILE {
+                return Ok(());
+            }
+        }
+
+        let rest = self.next_cluster(&last)?;
+        if rest != Cluster::END_OF_FILE {
+            self.write_fat(last.0, self.fat_type.entry_mask())?;
+            self.free_chain(rest)?;
+        }
+        Ok(())
+    }
+
+    /// Call `f` on each slot of the directory with its location until it returns `false`
+    ///
+    /// returns the last cluster of the directory if all slots are visited
+    fn visit_slots<F>(&self, dir: &Directory, mut f: F) -> FsResult<Option<Cluster>>
+    where
+        F: FnMut(EntryLocation, &[u8]) -> bool,
+    {
+        let mut cluster = self.dir_cluster(dir.cluster);
+        let mut block = Block::default();
+
+        loop {
+            // 根目录位于固定的区域，其余目录按簇链存放
+            let (start, sectors) = if cluster == Cluster::ROOT_DIR {
+                (
+                    self.first_root_dir_sector,
+                    self.first_data_sector - self.first_root_dir_sector,
+                )
+            } else {
+                (
+                    self.cluster_to_sector(&cluster),
+                    self.bpb.sectors_per_cluster() as usize,
+                )
+            };
+
+            for sector in start..start + sectors {
+                self.inner.read_block(sector, &mut block)?;
+                for (idx, data) in block.chunks(DirEntry::LEN).enumerate() {
+                    let offset = idx * DirEntry::LEN;
+                    if !f(EntryLocation { sector, offset }, data) {
+                        return Ok(None);
+                    }
+                }
+            }
+
+            if cluster == Cluster::ROOT_DIR {
+                return Ok(Some(cluster));
+            }
+            let next = self.next_cluster(&cluster)?;
+            if next == Cluster::END_OF_FILE {
+                return Ok(Some(cluster));
+            }
+            cluster = next;
+        }
+    }
+
+    /// Call `f` on each entry of the directory until it returns `false`
+    ///
+    /// deleted entries and volume labels are skipped, long file name entries
+    /// are collected into the `long_name` of the short entry following them
+    fn iterate_dir<F>(&self, dir: &Directory, mut f: F) -> FsResult
+    where
+        F: FnMut(&DirEntry, EntryLocation) -> bool,
+    {
+        let mut result = Ok(());
+        let mut long_name = LongNameBuilder::default();
+        self.visit_slots(dir, |location, data| match DirEntry::parse(data) {
+            Ok(entry) if entry.is_eod() => false,
+            Ok(entry) if entry.is_unused() => {
+                long_name.reset();
+                true
+            }
+            Ok(entry) if entry.is_long_name() => {
+                long_name.push(data);
+                true
+            }
+            Ok(entry) if entry.is_volume_id() => {
+                long_name.reset();
+                true
+            }
+            Ok(mut entry) => {
+                entry.long_name = long_name.finish(&entry.filename);
+                f(&entry, location)
+            }
+            Err(err) => {
+                result = Err(err);
+                false
+            }
+        })?;
+        result
+    }
+
+    /// Find the entry named `name` in the directory, by its long or short name
+    fn find_entry(&self, dir: &Directory, name: &str) -> FsResult<(DirEntry, EntryLocation)> {
+        lfn::validate(name)?;
+        let mut found = None;
+        self.iterate_dir(dir, |entry, location| {
+            if entry.matches(name) {
+                found = Some((entry.clone(), location));
+            }
+            found.is_none()
+        })?;
+        found.ok_or(FsError::FileNotFound)
+    }
+
+    /// Find the entry at `path` and the directory holding it, `None` for the root directory
+    fn lookup(&self, path: &str) -> FsResult<Option<(Directory, DirEntry, EntryLocation)>> {
+        let mut found: Option<(Directory, DirEntry, EntryLocation)> = None;
+
+        for name in path.split(PATH_SEPARATOR).filter(|name| !name.is_empty()) {
+            let dir = match found {
+                None => Directory::root(),
+                Some((_, entry, _)) if entry.is_directory() => Directory::from_entry(entry),
+                Some(_) => return Err(FsError::NotADirectory),
+            };
+            let (entry, location) = self.find_entry(&dir, name)?;
+            found = Some((dir, entry, location));
+        }
+
+        Ok(found)
+    }
+
+    /// Open the directory at `path`
+    fn open_dir(&self, path: &str) -> FsResult<Directory> {
+        match self.lookup(path)? {
+            None => Ok(Directory::root()),
+            Some((_, entry, _)) if entry.is_directory() => Ok(Directory::from_entry(entry)),
+            Some(_) => Err(FsError::NotADirectory),
+        }
+    }
+
+    /// All entries of the directory except `.` and `..`
+    fn list_dir(&self, dir: &Directory) -> FsResult<Vec<DirEntry>> {
+        let mut entries = Vec::new();
+        self.iterate_dir(dir, |entry, _| {
+            if entry.filename.name[0] != b'.' {
+                entries.push(entry.clone());
+            }
+            true
+        })?;
+        Ok(entries)
+    }
+
+    /// Write the entry at `location`
+    pub fn write_entry(&self, location: EntryLocation, entry: &DirEntry) -> FsResult {
+        self.write_slot(location, &entry.as_bytes())
+    }
+
+    fn write_slot(&self, location: EntryLocation, data: &[u8]) -> FsResult {
+        let mut block = Block::default();
+        self.inner.read_block(location.sector, &mut block)?;
+        block.as_mut()[location.offset..location.offset + data.len()].copy_from_slice(data);
+        self.inner.write_block(location.sector, &block)
+    }
+
+    /// Delete the entry at `location` and the long file name entries before it
+    fn remove_entry(&self, dir: &Directory, location: EntryLocation) -> FsResult {
+        let mut slots = Vec::new();
+        self.visit_slots(dir, |slot, data| {
+            if slot == location {
+                slots.push(slot);
+                return false;
+            }
+            if lfn::is_long_entry(data) && data[0] != DELETED_ENTRY {
+                slots.push(slot);
+            } else {
+                slots.clear();
+            }
+            true
+        })?;
+
+        for slot in slots {
+            self.write_slot(slot, &[DELETED_ENTRY])?;
+        }
+        Ok(())
+    }
+
+    /// A short name for `name` that is not used in the directory
+    ///
+    /// a valid 8.3 name is only uppercased, other names get a `~N` alias
+    fn short_name(&self, dir: &Directory, name: &str) -> FsResult<ShortFileName> {
+        let mut used = Vec::new();
+        self.iterate_dir(dir, |entry, _| {
+            used.push(entry.filename.clone());
+            true
+        })?;
+
+        if let Ok(sfn) = ShortFileName::parse(name)
+            && !used.contains(&sfn)
+        {
+            return Ok(sfn);
+        }
+
+        (1..1_000_000)
+            .map(|n| lfn::short_alias(name, n))
+            .find(|sfn| !used.contains(sfn))
+            .ok_or(FsError::AlreadyExists)
+    }
+
+    /// Create a new entry called `name` in the directory
+    fn new_entry(
+        &self,
+        dir: &Directory,
+        name: &str,
+        attributes: Attributes,
+        cluster: Cluster,
+    ) -> FsResult<(DirEntry, EntryLocation)> {
+        lfn::validate(name)?;
+        let mut entry = DirEntry::new(self.short_name(dir, name)?, attributes, cluster);
+        entry.set_long_name(name);
+        let location = self.add_entry(dir, &entry)?;
+        Ok((entry, location))
+    }
+
+    /// Put the entry and its long file name entries into the first run of
+    /// free slots large enough in the directory, returns the location of the short entry
+    ///
+    /// a full subdirectory grows by clusters, the root directory has a fixed size
+    fn add_entry(&self, dir: &Directory, entry: &DirEntry) -> FsResult<EntryLocation> {
+        let mut data = match &entry.long_name {
+            Some(long_name) => lfn::encode(long_name, &entry.filename),
+            None => Vec::new(),
+        };
+        data.push(entry.as_bytes());
+
+        let mut free = Vec::new();
+        let mut last = self.visit_slots(dir, |location, slot| {
+            if slot[0] == 0 || slot[0] == DELETED_ENTRY {
+                free.push(location);
+            } else {
+                free.clear();
+            }
+            free.len() < data.len()
+        })?;
+
+        // 长文件名项必须与短文件名项连续存放，空位不足时扩展目录
+        while free.len() < data.len() {
+            if last == Some(Cluster::ROOT_DIR) {
+                return Err(FsError::WriteZero);
+            }
+            let cluster = self.alloc_cluster(last)?;
+            let sector = self.cluster_to_sector(&cluster);
+            for sector in sector..sector + self.bpb.sectors_per_cluster() as usize {
+                for offset in (0..BLOCK_SIZE).step_by(DirEntry::LEN) {
+                    free.push(EntryLocation { sector, offset });
+                }
+            }
+            last = Some(cluster);
+        }
+
+        for (location, slot) in free.iter().zip(&data) {
+            self.write_slot(*location, slot)?;
+        }
+        Ok(free[data.len() - 1])
+    }
+
+    /// Free the clusters of the directory and everything in it
+    fn remove_tree(&self, entry: &DirEntry) -> FsResult {
+        for child in self.list_dir(&Directory::from_entry(entry.clone()))? {
+            if child.is_directory() {
+                self.remove_tree(&child)?;
+            } else {
+                self.free_chain(child.cluster)?;
+            }
+        }
+        self.free_chain(entry.cluster)
+    }
+
+    /// The cluster of the parent directory, read from the `..` entry
+    fn parent_cluster(&self, dir: Cluster) -> FsResult<Cluster> {
+        let (entry, _) = self.find_entry(&Directory::new(dir), "..")?;
+        Ok(Directory::from_entry(entry).cluster)
+    }
+
+    /// Move the entry at `src` to `dst`, an existing file at `dst` is replaced
+    fn rename(&self, src: &str, dst: &str, is_dir: bool) -> FsResult {
+        let (src_dir, entry, location) = self.lookup(src)?.ok_or(FsError::InvalidOperation)?;
+        match (entry.is_directory(), is_dir) {
+            (false, true) => return Err(FsError::NotADirectory),
+            (true, false) => return Err(FsError::NotAFile),
+            _ => {}
+        }
+
+        let (parent, name) = split_path(dst)?;
+        let dir = self.open_dir(parent)?;
+
+        if is_dir {
+            // 不能把目录移动到它自身之下
+            let mut cluster = dir.cluster;
+            while cluster != Cluster::ROOT_DIR {
+                if cluster == entry.cluster {
+                    return Err(FsError::InvalidOperation);
+                }
+                cluster = self.parent_cluster(cluster)?;
+            }
+        }
+
+        match self.find_entry(&dir, name) {
+            Ok((_, existing)) if existing == location => return Ok(()),
+            Ok((existing, existing_location)) => {
+                if is_dir || existing.is_directory() {
+                    return Err(FsError::AlreadyExists);
+                }
+                self.free_chain(existing.cluster)?;
+                self.remove_entry(&dir, existing_location)?;
+            }
+            Err(FsError::FileNotFound) => {}
+            Err(err) => return Err(err),
+        }
+
+        lfn::validate(name)?;
+        let mut moved = entry.clone();
+        moved.filename = self.short_name(&dir, name)?;
+        moved.set_long_name(name);
+        self.add_entry(&dir, &moved)?;
+        self.remove_entry(&src_dir, location)?;
+
+        if is_dir {
+            // `..` 指向新的父目录，根目录记为 0
+            let (mut dotdot, dotdot_location) =
+                self.find_entry(&Directory::from_entry(entry), "..")?;
+            dotdot.cluster = match dir.cluster {
+                Cluster::ROOT_DIR => Cluster::EMPTY,
+                cluster => cluster,
+            };
+            self.write_entry(dotdot_location, &dotdot)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Implement `FileSystem` for a FAT filesystem holding a `FatHandle` in `handle`
+macro_rules! impl_fat_filesystem {
+    ($fs:ty) => {
+        impl $fs {
+            fn open(&self, entry: DirEntry, location: EntryLocation) -> FileHandle {
+                let meta = entry.as_meta();
+                let file = File::new(self.handle.clone(), entry, location);
+                FileHandle::new(meta, Box::new(file))
+            }
+        }
+
+        impl FileSystem for $fs {
+            fn read_dir(&self, path: &str) -> FsResult<Box<dyn Iterator<Item = Metadata> + Send>> {
+                let dir = self.handle.open_dir(path)?;
+                let entries = self.handle.list_dir(&dir)?;
+                Ok(Box::new(entries.into_iter().map(|entry| entry.as_meta())))
+            }
+
+            fn open_file(&self, path: &str) -> FsResult<FileHandle> {
+                match self.handle.lookup(path)? {
+                    Some((_, entry, location)) if !entry.is_directory() => {
+                        Ok(self.open(entry, location))
+                    }
+                    _ => Err(FsError::NotAFile),
+                }
+            }
+
+            fn metadata(&self, path: &str) -> FsResult<Metadata> {
+                match self.handle.lookup(path)? {
+                    Some((_, entry, _)) => Ok(entry.as_meta()),
+                    // 根目录没有目录项
+                    None => Ok(Metadata::new(
+                        String::from("/"),
+                        FileType::Directory,
+                        0,
+                        None,
+                        None,
+                        None,
+                    )),
+                }
+            }
+
+            fn exists(&self, path: &str) -> FsResult<bool> {
+                match self.handle.lookup(path) {
+                    Ok(_) => Ok(true),
+                    Err(FsError::FileNotFound) => Ok(false),
+                    Err(err) => Err(err),
+                }
+            }
+
+            fn create_file(&self, path: &str) -> FsResult<FileHandle> {
+                let (parent, name) = split_path(path)?;
+                let dir = self.handle.open_dir(parent)?;
+
+                match self.handle.find_entry(&dir, name) {
+                    Ok((entry, _)) if entry.is_directory() => Err(FsError::NotAFile),
+                    Ok((entry, location)) => {
+                        let mut file = self.open(entry, location);
+                        file.set_len(0)?;
+                        Ok(file)
+                    }
+                    Err(FsError::FileNotFound) => {
+                        let (entry, location) = self.handle.new_entry(
+                            &dir,
+                            name,
+                            Attributes::ARCHIVE,
+                            Cluster::EMPTY,
+                        )?;
+                        Ok(self.open(entry, location))
+                    }
+                    Err(err) => Err(err),
+                }
+            }
+
+            fn append_file(&self, path: &str) -> FsResult<FileHandle> {
+                let mut file = self.open_file(path)?;
+                file.seek(SeekFrom::End(0))?;
+                Ok(file)
+            }
+
+            fn create_dir(&self, path: &str) -> FsResult {
+                let (parent, name) = split_path(path)?;
+                let dir = self.handle.open_dir(parent)?;
+
+                match self.handle.find_entry(&dir, name) {
+                    Ok(_) => return Err(FsError::AlreadyExists),
+                    Err(FsError::FileNotFound) => {}
+                    Err(err) => return Err(err),
+                }
+
+                lfn::validate(name)?;
+                let cluster = self.handle.alloc_cluster(None)?;
+
+                // 新目录的前两项为 `.` 与 `..`，指向根目录的 `..` 簇号为 0
+                let parent_cluster = match dir.cluster {
+                    Cluster::ROOT_DIR => Cluster::EMPTY,
+                    cluster => cluster,
+                };
+                let sector = self.handle.cluster_to_sector(&cluster);
+                let dot = DirEntry::new(ShortFileName::parse(".")?, Attributes::DIRECTORY, cluster);
+                let dotdot = DirEntry::new(
+                    ShortFileName::parse("..")?,
+                    Attributes::DIRECTORY,
+                    parent_cluster,
+                );
+                self.handle
+                    .write_entry(EntryLocation { sector, offset: 0 }, &dot)?;
+                self.handle.write_entry(
+                    EntryLocation {
+                        sector,
+                        offset: DirEntry::LEN,
+                    },
+                    &dotdot,
+                )?;
+
+                if let Err(err) = self
+                    .handle
+                    .new_entry(&dir, name, Attributes::DIRECTORY, cluster)
+                {
+                    self.handle.free_chain(cluster)?;
+                    return Err(err);
+                }
+                Ok(())
+            }
+
+            fn remove_file(&self, path: &str) -> FsResult {
+                match self.handle.lookup(path)? {
+                    Some((dir, entry, location)) if !entry.is_directory() => {
+                        self.handle.free_chain(entry.cluster)?;
+                        self.handle.remove_entry(&dir, location)
+                    }
+                    _ => Err(FsError::NotAFile),
+                }
+            }
+
+            fn remove_dir(&self, path: &str) -> FsResult {
+                match self.handle.lookup(path)? {
+                    Some((dir, entry, location)) if entry.is_directory() => {
+                        self.handle.remove_tree(&entry)?;
+                        self.handle.remove_entry(&dir, location)
+                    }
+                    Some(_) => Err(FsError::NotADirectory),
+                    // 根目录不能删除
+                    None => Err(FsError::InvalidOperation),
+                }
+            }
+
+            fn copy_file(&self, src: &str, dst: &str) -> FsResult {
+                // 先读出全部内容，复制到自身时也不会丢失数据
+                let mut data = Vec::new();
+                self.open_file(src)?.read_all(&mut data)?;
+
+                let mut file = self.create_file(dst)?;
+                file.write_all(&data)?;
+                file.flush()
+            }
+
+            fn move_file(&self, src: &str, dst: &str) -> FsResult {
+                self.handle.rename(src, dst, false)
+            }
+
+            fn move_dir(&self, src: &str, dst: &str) -> FsResult {
+                self.handle.rename(src, dst, true)
+            }
+        }
+    };
+}
+
+impl_fat_filesystem!(Fat16);
+impl_fat_filesystem!(super::fat32::Fat32);
@@ -0,0 +1,260 @@
+//! VFAT Long File Name
+//!
+//! reference: <https://wiki.osdev.org/FAT#Long_File_Names>
+
+use super::*;
+
+/// Number of UCS-2 characters in one long file name entry
+const CHARS_PER_ENTRY: usize = 13;
+/// Byte offsets of the characters in a long file name entry
+const CHAR_OFFSETS: [usize; CHARS_PER_ENTRY] = [1, 3, 5, 7, 9, 14, 16, 18, 20, 22, 24, 28, 30];
+/// Set in the ordinal of the last entry, which is stored first on the disk
+const LAST_LONG_ENTRY: u8 = 0x40;
+/// Offset of the checksum of the short name
+const CHECKSUM_OFFSET: usize = 13;
+/// Maximum length of a long file name in UCS-2 characters
+const MAX_LEN: usize = 255;
+
+/// The checksum of the short name stored in each of its long file name entries
+pub fn checksum(sfn: &ShortFileName) -> u8 {
+    sfn.name
+        .iter()
+        .chain(sfn.ext.iter())
+        .fold(0u8, |sum, &ch| sum.rotate_right(1).wrapping_add(ch))
+}
+
+/// Whether the raw entry is a long file name entry
+pub fn is_long_entry(data: &[u8]) -> bool {
+    data[0x0B] == Attributes::LFN.bits()
+}
+
+/// Check that `name` can be stored as a long file name
+pub fn validate(name: &str) -> FsResult {
+    if name.is_empty() {
+        return Err(FilenameError::FilenameEmpty.into());
+    }
+    if name.encode_utf16().count() > MAX_LEN {
+        return Err(FilenameError::NameTooLong.into());
+    }
+    if name
+        .chars()
+        .any(|ch| ch < ' ' || "\"*/:<>?\\|".contains(ch))
+    {
+        return Err(FilenameError::InvalidCharacter.into());
+    }
+    Ok(())
+}
+
+/// Case-insensitive comparison of file names
+pub fn name_eq(a: &str, b: &str) -> bool {
+    a.chars()
+        .flat_map(char::to_lowercase)
+        .eq(b.chars().flat_map(char::to_lowercase))
+}
+
+/// The `~n` short name alias of a long file name
+pub fn short_alias(name: &str, n: usize) -> ShortFileName {
+    // 去掉空格与开头的 `.`，最后一个 `.` 之后为扩展名
+    let name: String = name
+        .trim_start_matches('.')
+        .chars()
+        .filter(|&ch| ch != ' ')
+        .collect();
+    let (base, ext) = match name.rsplit_once('.') {
+        Some((base, ext)) => (base, ext),
+        None => (name.as_str(), ""),
+    };
+
+    let convert = |ch: char| match ch {
+        'a'..='z' | 'A'..='Z' | '0'..='9' => ch.to_ascii_uppercase() as u8,
+        '$' | '%' | '\'' | '-' | '_' | '@' | '~' | '`' | '!' | '(' | ')' | '{' | '}' | '^'
+        | '#' | '&' => ch as u8,
+        _ => b'_',
+    };
+
+    let mut sfn = ShortFileName {
+        name: [b' '; 8],
+        ext: [b' '; 3],
+    };
+
+    let tail = format!("~{n}");
+    let mut base: Vec<u8> = base.chars().filter(|&ch| ch != '.').map(convert).collect();
+    if base.is_empty() {
+        base.push(b'_');
+    }
+    base.truncate(8 - tail.len());
+    base.extend_from_slice(tail.as_bytes());
+    sfn.name[..base.len()].copy_from_slice(&base);
+
+    for (idx, ch) in ext.chars().take(3).enumerate() {
+        sfn.ext[idx] = convert(ch);
+    }
+
+    sfn
+}
+
+/// Encode `name` into the long file name entries of `sfn`, in on-disk order
+pub fn encode(name: &str, sfn: &ShortFileName) -> Vec<[u8; DirEntry::LEN]> {
+    let mut chars: Vec<u16> = name.encode_utf16().collect();
+    // 不足一项时以 0x0000 结束，其余填充 0xFFFF
+    if !chars.len().is_multiple_of(CHARS_PER_ENTRY) {
+        chars.push(0x0000);
+        chars.resize(chars.len().next_multiple_of(CHARS_PER_ENTRY), 0xFFFF);
+    }
+
+    let count = chars.len() / CHARS_PER_ENTRY;
+    let checksum = checksum(sfn);
+
+    (1..=count)
+        .rev()
+        .map(|seq| {
+            let mut data = [0u8; DirEntry::LEN];
+            data[0] = seq as u8;
+            if seq == count {
+                data[0] |= LAST_LONG_ENTRY;
+            }
+            data[0x0B] = Attributes::LFN.bits();
+            data[CHECKSUM_OFFSET] = checksum;
+
+            let fragment = &chars[(seq - 1) * CHARS_PER_ENTRY..seq * CHARS_PER_ENTRY];
+            for (&offset, ch) in CHAR_OFFSETS.iter().zip(fragment) {
+                data[offset..offset + 2].copy_from_slice(&ch.to_le_bytes());
+            }
+            data
+        })
+        .collect()
+}
+
+/// Collects the long file name entries preceding a short entry
+#[derive(Default)]
+pub struct LongNameBuilder {
+    chars: Vec<u16>,
+    checksum: u8,
+    /// The ordinal of the next expected entry, 0 when the name is complete
+    next: u8,
+}
+
+impl LongNameBuilder {
+    /// Add a long file name entry, an entry out of sequence discards the name
+    pub fn push(&mut self, data: &[u8]) {
+        let seq = data[0] & !LAST_LONG_ENTRY;
+
+        if data[0] & LAST_LONG_ENTRY != 0 {
+            self.chars = vec![0xFFFF; seq as usize * CHARS_PER_ENTRY];
+            self.checksum = data[CHECKSUM_OFFSET];
+            self.next = seq;
+        }
+
+        if seq == 0 || seq != self.next || data[CHECKSUM_OFFSET] != self.checksum {
+            self.reset();
+            return;
+        }
+
+        let start = (seq as usize - 1) * CHARS_PER_ENTRY;
+        for (idx, &offset) in CHAR_OFFSETS.iter().enumerate() {
+            self.chars[start + idx] = u16::from_le_bytes([data[offset], data[offset + 1]]);
+        }
+        self.next -= 1;
+    }
+
+    /// Discard the collected entries
+    pub fn reset(&mut self) {
+        self.chars.clear();
+        self.next = 0;
+    }
+
+    /// The long name of the short entry `sfn`, if the collected entries belong to it
+    pub fn finish(&mut self, sfn: &ShortFileName) -> Option<String> {
+        let complete = !self.chars.is_empty() && self.next == 0 && self.checksum == checksum(sfn);
+        let chars = core::mem::take(&mut self.chars);
+        self.reset();
+
+        if !complete {
+            return None;
+        }
+
+        let len = chars.iter().position(|&ch| ch == 0).unwrap_or(chars.len());
+        char::decode_utf16(chars[..len].iter().copied())
+            .collect::<Result<String, _>>()
+            .ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sfn(name: &[u8; 11]) -> ShortFileName {
+        ShortFileName::new(name)
+    }
+
+    #[test]
+    fn test_checksum() {
+        assert_eq!(checksum(&sfn(b"FORK_T~1   ")), 0x6E);
+        assert_eq!(checksum(&sfn(b"README  TXT")), 0x73);
+    }
+
+    #[test]
+    fn test_short_alias() {
+        assert_eq!(short_alias("fork_test", 1), sfn(b"FORK_T~1   "));
+        assert_eq!(short_alias("Long Name.markdown", 2), sfn(b"LONGNA~2MAR"));
+        assert_eq!(short_alias(".bashrc", 1), sfn(b"BASHRC~1   "));
+        assert_eq!(short_alias("a+b.c.txt", 10), sfn(b"A_BC~10 TXT"));
+        assert_eq!(short_alias("中文.md", 1), sfn(b"__~1    MD "));
+    }
+
+    #[test]
+    fn test_encode_and_parse() {
+        let name = "A file with a rather long name.txt";
+        let short = short_alias(name, 1);
+        let entries = encode(name, &short);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0][0], 0x43);
+        assert_eq!(entries[2][0], 0x01);
+        assert!(entries.iter().all(|entry| is_long_entry(entry)));
+
+        let mut builder = LongNameBuilder::default();
+        for entry in &entries {
+            builder.push(entry);
+        }
+        assert_eq!(builder.finish(&short).as_deref(), Some(name));
+
+        // 校验和不匹配时忽略长文件名
+        for entry in &entries {
+            builder.push(entry);
+        }
+        assert_eq!(builder.finish(&sfn(b"OTHER   TXT")), None);
+
+        // 缺少中间项
+        builder.push(&entries[0]);
+        builder.push(&entries[2]);
+        assert_eq!(builder.finish(&short), None);
+
+        // 恰好 13 个字符时没有结束符
+        let name = "exactly13char";
+        let entries = encode(name, &short);
+        assert_eq!(entries.len(), 1);
+        builder.push(&entries[0]);
+        assert_eq!(builder.finish(&short).as_deref(), Some(name));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(validate("fork_test"), Ok(()));
+        assert_eq!(validate("a b+c;d=[e].f"), Ok(()));
+        assert_eq!(
+            validate(""),
+            Err(FsError::FileNameError(FilenameError::FilenameEmpty))
+        );
+        assert_eq!(
+            validate("what?"),
+            Err(FsError::FileNameError(FilenameError::InvalidCharacter))
+        );
+        assert_eq!(
+            validate(&"x".repeat(256)),
+            Err(FsError::FileNameError(FilenameError::NameTooLong))
+        );
+        assert!(name_eq("Fork_Test", "FORK_TEST"));
+        assert!(!name_eq("fork", "fork_test"));
+    }
+}
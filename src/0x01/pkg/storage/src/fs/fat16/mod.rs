@@ -0,0 +1,114 @@
+pub mod bpb;
+pub mod directory;
+pub mod direntry;
+pub mod file;
+pub mod impls;
+pub mod lfn;
+
+#[cfg(test)]
+pub(crate) mod tests;
+
+use crate::*;
+use directory::{Directory, EntryLocation};
+use direntry::*;
+use file::File;
+use lfn::LongNameBuilder;
+
+use super::fat32::bpb::Fat32Bpb;
+use super::fat32::fsinfo::{self, FsInfo};
+use bpb::Fat16Bpb;
+
+const BLOCK_SIZE: usize = 512;
+
+/// Identifies a Fat16 filesystem on the disk.
+pub struct Fat16 {
+    handle: FatHandle,
+}
+
+impl Fat16 {
+    pub fn new(inner: impl BlockDevice<Block512>) -> FsResult<Self> {
+        Ok(Self {
+            handle: Arc::new(FatImpl::new(inner)?),
+        })
+    }
+}
+
+pub(crate) type FatHandle = Arc<FatImpl>;
+
+/// The FAT variants supported by `FatImpl`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FatType {
+    Fat16,
+    Fat32,
+}
+
+impl FatType {
+    /// Detect the FAT variant from the BPB
+    ///
+    /// FAT32 has neither a fixed root directory nor a 16-bit FAT size
+    pub fn detect(bpb: &Fat16Bpb) -> FatType {
+        if bpb.root_entries_count() == 0 && bpb.sectors_per_fat() == 0 {
+            FatType::Fat32
+        } else {
+            FatType::Fat16
+        }
+    }
+
+    /// Bytes of a FAT entry
+    pub fn entry_size(&self) -> usize {
+        match self {
+            FatType::Fat16 => 2,
+            FatType::Fat32 => 4,
+        }
+    }
+
+    /// The bits of a FAT entry holding the cluster number, FAT32 entries are 28 bits
+    pub fn entry_mask(&self) -> u32 {
+        match self {
+            FatType::Fat16 => 0xFFFF,
+            FatType::Fat32 => 0x0FFF_FFFF,
+        }
+    }
+}
+
+/// The FAT filesystem shared by `Fat16` and `Fat32`.
+///
+/// The partition is a collection of clusters.
+/// BPB (Boot Parameter Block) is the first sector of the partition.
+/// The BPB contains information about the filesystem.
+///
+/// [ BPB ] [ FSInfo (FAT32) ] [ FATs ] [ Root Directory (FAT16) ] [ Data ]
+pub struct FatImpl {
+    pub(crate) inner: Box<dyn BlockDevice<Block512>>,
+    pub bpb: Fat16Bpb,
+    /// The extended BPB, only for FAT32
+    pub bpb32: Option<Fat32Bpb>,
+    pub fat_type: FatType,
+    pub fat_start: usize,
+    pub sectors_per_fat: usize,
+    pub first_data_sector: usize,
+    /// The fixed root directory region, only for FAT16
+    pub first_root_dir_sector: usize,
+    /// The first cluster of the root directory, only for FAT32
+    pub root_cluster: Cluster,
+    /// The FSInfo sector and its content, only for FAT32
+    fs_info: Option<(usize, spin::Mutex<FsInfo>)>,
+}
+
+impl core::fmt::Debug for Fat16 {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Fat16")
+            .field("bpb", &self.handle.bpb)
+            .finish()
+    }
+}
+
+impl core::fmt::Debug for FatImpl {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("FatImpl")
+            .field("type", &self.fat_type)
+            .field("bpb", &self.bpb)
+            .field("bpb32", &self.bpb32)
+            .finish()
+    }
+}
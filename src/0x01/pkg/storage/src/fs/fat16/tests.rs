@@ -0,0 +1,837 @@
+//! Tests of the Fat16 filesystem on an image generated in memory
+
+use super::*;
+use chrono::{TimeZone, Utc};
+
+impl RamDisk {
+    /// The FAT entry of `cluster` in the `fat`-th copy of the FAT
+    fn fat_entry(&self, fat: usize, cluster: u16) -> u16 {
+        let block = &self.blocks()[FAT_START + fat];
+        let offset = cluster as usize * 2;
+        u16::from_le_bytes([block[offset], block[offset + 1]])
+    }
+
+    fn free_clusters(&self) -> usize {
+        (2..CLUSTERS as u16 + 2)
+            .filter(|&cluster| self.fat_entry(0, cluster) == 0)
+            .count()
+    }
+}
+
+const TOTAL_SECTORS: usize = 128;
+const ROOT_ENTRIES: usize = 32;
+const FAT_START: usize = 1;
+const ROOT_START: usize = 3;
+const DATA_START: usize = ROOT_START + ROOT_ENTRIES * DirEntry::LEN / BLOCK_SIZE;
+const CLUSTERS: usize = TOTAL_SECTORS - DATA_START;
+
+/// A minimal FAT16 formatter
+///
+/// one sector per cluster, two FATs of one sector each, and clusters are
+/// allocated with gaps so that files are never contiguous
+pub(crate) struct ImageBuilder {
+    sectors: Vec<[u8; BLOCK_SIZE]>,
+    next_cluster: u16,
+}
+
+impl ImageBuilder {
+    pub(crate) fn new() -> Self {
+        let mut sectors = vec![[0u8; BLOCK_SIZE]; TOTAL_SECTORS];
+
+        let bpb = &mut sectors[0];
+        bpb[0x03..0x0B].copy_from_slice(b"YSOSTEST");
+        bpb[0x0B..0x0D].copy_from_slice(&(BLOCK_SIZE as u16).to_le_bytes());
+        bpb[0x0D] = 1;
+        bpb[0x0E..0x10].copy_from_slice(&(FAT_START as u16).to_le_bytes());
+        bpb[0x10] = 2;
+        bpb[0x11..0x13].copy_from_slice(&(ROOT_ENTRIES as u16).to_le_bytes());
+        bpb[0x13..0x15].copy_from_slice(&(TOTAL_SECTORS as u16).to_le_bytes());
+        bpb[0x15] = 0xF8;
+        bpb[0x16..0x18].copy_from_slice(&1u16.to_le_bytes());
+        bpb[0x26] = 0x29;
+        bpb[0x2B..0x36].copy_from_slice(b"TEST       ");
+        bpb[0x36..0x3E].copy_from_slice(b"FAT16   ");
+        bpb[0x1FE..0x200].copy_from_slice(&[0x55, 0xAA]);
+
+        let mut builder = Self {
+            sectors,
+            next_cluster: 2,
+        };
+        builder.set_fat(0, 0xFFF8);
+        builder.set_fat(1, 0xFFFF);
+        builder
+    }
+
+    fn set_fat(&mut self, cluster: u16, value: u16) {
+        let offset = cluster as usize * 2;
+        for fat in 0..2 {
+            let sector = &mut self.sectors[FAT_START + fat + offset / BLOCK_SIZE];
+            let offset = offset % BLOCK_SIZE;
+            sector[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    fn cluster_sector(cluster: u16) -> usize {
+        DATA_START + cluster as usize - 2
+    }
+
+    /// Allocate a cluster chain holding `data`, returns the first cluster
+    fn alloc(&mut self, data: &[u8]) -> u16 {
+        let mut first = 0;
+        let mut prev = 0;
+        for chunk in data.chunks(BLOCK_SIZE) {
+            let cluster = self.next_cluster;
+            self.next_cluster += 2;
+            self.sectors[Self::cluster_sector(cluster)][..chunk.len()].copy_from_slice(chunk);
+            self.set_fat(cluster, 0xFFFF);
+            if prev == 0 {
+                first = cluster;
+            } else {
+                self.set_fat(prev, cluster);
+            }
+            prev = cluster;
+        }
+        first
+    }
+
+    fn entry(name: &[u8; 11], attr: u8, cluster: u16, size: u32) -> [u8; DirEntry::LEN] {
+        let mut entry = [0u8; DirEntry::LEN];
+        entry[..11].copy_from_slice(name);
+        entry[0x0B] = attr;
+        // 2020-06-16 23:48:30
+        for offset in [0x0E, 0x16] {
+            entry[offset..offset + 4].copy_from_slice(&[0x0f, 0xbe, 0xd0, 0x50]);
+        }
+        entry[0x12..0x14].copy_from_slice(&[0xd0, 0x50]);
+        entry[0x1A..0x1C].copy_from_slice(&cluster.to_le_bytes());
+        entry[0x1C..0x20].copy_from_slice(&size.to_le_bytes());
+        entry
+    }
+
+    /// Add an entry into the first free slot of the root directory,
+    /// or of the directory at `dir` cluster
+    fn add_entry(&mut self, dir: Option<u16>, entry: [u8; DirEntry::LEN]) {
+        let sectors = match dir {
+            None => ROOT_START..DATA_START,
+            Some(cluster) => Self::cluster_sector(cluster)..Self::cluster_sector(cluster) + 1,
+        };
+        for sector in sectors {
+            for slot in self.sectors[sector].chunks_mut(DirEntry::LEN) {
+                if slot[0] == 0 {
+                    slot.copy_from_slice(&entry);
+                    return;
+                }
+            }
+        }
+        panic!("directory is full");
+    }
+
+    fn add_file(&mut self, dir: Option<u16>, name: &[u8; 11], data: &[u8]) {
+        let cluster = if data.is_empty() { 0 } else { self.alloc(data) };
+        let entry = Self::entry(name, Attributes::ARCHIVE.bits(), cluster, data.len() as u32);
+        self.add_entry(dir, entry);
+    }
+
+    fn add_dir(&mut self, dir: Option<u16>, name: &[u8; 11]) -> u16 {
+        let cluster = self.alloc(&[0u8; BLOCK_SIZE]);
+        let attr = Attributes::DIRECTORY.bits();
+        self.add_entry(Some(cluster), Self::entry(b".          ", attr, cluster, 0));
+        self.add_entry(
+            Some(cluster),
+            Self::entry(b"..         ", attr, dir.unwrap_or(0), 0),
+        );
+        self.add_entry(dir, Self::entry(name, attr, cluster, 0));
+        cluster
+    }
+
+    pub(crate) fn disk(self) -> RamDisk {
+        let blocks = self.sectors.iter().map(Block::new).collect();
+        RamDisk::from_blocks(blocks)
+    }
+
+    fn build(self) -> Fat16 {
+        Fat16::new(self.disk()).unwrap()
+    }
+}
+
+pub(crate) fn pattern(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i * 7 % 251) as u8).collect()
+}
+
+/// The image used by most tests:
+///
+/// ```text
+/// /HELLO.TXT          "Hello, FAT16!"
+/// /BIG.BIN            1300 bytes, 3 clusters
+/// /EMPTY              0 bytes
+/// /APP/               directory
+/// /APP/FAB            2 clusters exactly
+/// /APP/SUB/NOTE.MD    "nested"
+/// ```
+fn sample() -> Fat16 {
+    sample_image().build()
+}
+
+pub(crate) fn sample_image() -> ImageBuilder {
+    let mut image = ImageBuilder::new();
+    image.add_entry(None, ImageBuilder::entry(b"TEST       ", 0x08, 0, 0));
+    image.add_file(None, b"HELLO   TXT", b"Hello, FAT16!");
+    image.add_file(None, b"BIG     BIN", &pattern(1300));
+    image.add_file(None, b"EMPTY      ", b"");
+    let app = image.add_dir(None, b"APP        ");
+    image.add_file(Some(app), b"FAB        ", &pattern(2 * BLOCK_SIZE));
+    let sub = image.add_dir(Some(app), b"SUB        ");
+    image.add_file(Some(sub), b"NOTE    MD ", b"nested");
+    image
+}
+
+pub(crate) fn read_to_end(fs: &impl FileSystem, path: &str) -> Vec<u8> {
+    let mut file = fs.open_file(path).unwrap();
+    let mut buf = Vec::new();
+    file.read_all(&mut buf).unwrap();
+    buf
+}
+
+#[test]
+fn test_ramdisk() {
+    let image: Vec<u8> = sample_image().sectors.concat();
+    let disk = RamDisk::from_bytes(&image[..image.len() - 100]);
+    assert_eq!(disk.block_count(), Ok(TOTAL_SECTORS));
+
+    let mut block = Block::default();
+    assert_eq!(
+        disk.read_block(TOTAL_SECTORS, &mut block),
+        Err(FsError::InvalidOffset)
+    );
+    assert_eq!(
+        disk.write_block(TOTAL_SECTORS, &block),
+        Err(FsError::InvalidOffset)
+    );
+    disk.read_block(TOTAL_SECTORS - 1, &mut block).unwrap();
+    assert!(block[BLOCK_SIZE - 100..].iter().all(|&b| b == 0));
+
+    // 克隆共享同一块内存，重新挂载后能看到写入的内容
+    let fs = Fat16::new(disk.clone()).unwrap();
+    write_file(&fs, "/NEW.TXT", b"in memory");
+    drop(fs);
+    let fs = Fat16::new(disk).unwrap();
+    assert_eq!(read_to_end(&fs, "/NEW.TXT"), b"in memory");
+    assert_eq!(read_to_end(&fs, "/HELLO.TXT"), b"Hello, FAT16!");
+    assert!(format!("{:?}", RamDisk::new(4)).contains("blocks: 4"));
+}
+
+#[test]
+fn test_read_root_dir() {
+    let fs = sample();
+
+    let entries: Vec<Metadata> = fs.read_dir("/").unwrap().collect();
+    let names: Vec<&str> = entries.iter().map(|meta| meta.name.as_str()).collect();
+
+    // 卷标不出现在目录列表中
+    assert_eq!(names, ["HELLO.TXT", "BIG.BIN", "EMPTY", "APP"]);
+    assert!(entries[0].is_file());
+    assert_eq!(entries[1].len, 1300);
+    assert!(entries[3].is_dir());
+    assert_eq!(
+        entries[0].modified,
+        Some(Utc.with_ymd_and_hms(2020, 6, 16, 23, 48, 30).unwrap())
+    );
+}
+
+#[test]
+fn test_read_sub_dir() {
+    let fs = sample();
+
+    let names: Vec<String> = fs.read_dir("/APP").unwrap().map(|meta| meta.name).collect();
+    assert_eq!(names, ["FAB", "SUB"]);
+
+    let names: Vec<String> = fs
+        .read_dir("/app/sub/")
+        .unwrap()
+        .map(|meta| meta.name)
+        .collect();
+    assert_eq!(names, ["NOTE.MD"]);
+
+    assert_eq!(
+        fs.read_dir("/HELLO.TXT").err(),
+        Some(FsError::NotADirectory)
+    );
+    assert_eq!(fs.read_dir("/NOPE").err(), Some(FsError::FileNotFound));
+}
+
+#[test]
+fn test_read_file() {
+    let fs = sample();
+
+    assert_eq!(read_to_end(&fs, "/HELLO.TXT"), b"Hello, FAT16!");
+    assert_eq!(read_to_end(&fs, "/hello.txt"), b"Hello, FAT16!");
+    assert_eq!(read_to_end(&fs, "/BIG.BIN"), pattern(1300));
+    assert_eq!(read_to_end(&fs, "/APP/FAB"), pattern(2 * BLOCK_SIZE));
+    assert_eq!(read_to_end(&fs, "/APP/SUB/NOTE.MD"), b"nested");
+    assert_eq!(read_to_end(&fs, "/APP/SUB/../../EMPTY"), b"");
+}
+
+#[test]
+fn test_read_in_small_chunks() {
+    let fs = sample();
+    let mut file = fs.open_file("/BIG.BIN").unwrap();
+
+    let mut data = Vec::new();
+    let mut buf = [0u8; 100];
+    loop {
+        let len = file.read(&mut buf).unwrap();
+        if len == 0 {
+            break;
+        }
+        data.extend_from_slice(&buf[..len]);
+    }
+    assert_eq!(data, pattern(1300));
+}
+
+#[test]
+fn test_open_errors() {
+    let fs = sample();
+
+    assert_eq!(fs.open_file("/APP").err(), Some(FsError::NotAFile));
+    assert_eq!(fs.open_file("/").err(), Some(FsError::NotAFile));
+    assert_eq!(
+        fs.open_file("/MISSING.TXT").err(),
+        Some(FsError::FileNotFound)
+    );
+    assert_eq!(
+        fs.open_file("/HELLO.TXT/FAB").err(),
+        Some(FsError::NotADirectory)
+    );
+    assert_eq!(
+        fs.open_file("/TOOLONGNAME.TXT").err(),
+        Some(FsError::FileNotFound)
+    );
+    assert_eq!(
+        fs.open_file(&format!("/{}", "X".repeat(256))).err(),
+        Some(FsError::FileNameError(FilenameError::NameTooLong))
+    );
+    assert_eq!(
+        fs.open_file("/WHAT?").err(),
+        Some(FsError::FileNameError(FilenameError::InvalidCharacter))
+    );
+}
+
+#[test]
+fn test_metadata_and_exists() {
+    let fs = sample();
+
+    let meta = fs.metadata("/APP/SUB/NOTE.MD").unwrap();
+    assert!(meta.is_file());
+    assert_eq!(meta.name, "NOTE.MD");
+    assert_eq!(meta.len, 6);
+
+    assert!(fs.metadata("/APP/SUB").unwrap().is_dir());
+    assert!(fs.metadata("/").unwrap().is_dir());
+    assert_eq!(fs.metadata("/NOPE").err(), Some(FsError::FileNotFound));
+
+    assert_eq!(fs.exists("/APP/FAB"), Ok(true));
+    assert_eq!(fs.exists("/APP/NOPE"), Ok(false));
+    assert_eq!(fs.exists("/"), Ok(true));
+}
+
+#[test]
+fn test_seek() {
+    let fs = sample();
+    let data = pattern(1300);
+    let mut file = fs.open_file("/BIG.BIN").unwrap();
+    let mut buf = [0u8; 16];
+
+    // 跨越簇边界读取
+    assert_eq!(file.seek(SeekFrom::Start(500)), Ok(500));
+    assert_eq!(file.read(&mut buf), Ok(16));
+    assert_eq!(buf, data[500..516]);
+
+    assert_eq!(file.seek(SeekFrom::Current(-400)), Ok(116));
+    assert_eq!(file.read(&mut buf), Ok(16));
+    assert_eq!(buf, data[116..132]);
+
+    assert_eq!(file.seek(SeekFrom::End(-4)), Ok(1296));
+    assert_eq!(file.read(&mut buf), Ok(4));
+    assert_eq!(buf[..4], data[1296..]);
+    assert_eq!(file.read(&mut buf), Ok(0));
+
+    // 恰好位于簇边界
+    assert_eq!(file.seek(SeekFrom::Start(1024)), Ok(1024));
+    assert_eq!(file.read(&mut buf), Ok(16));
+    assert_eq!(buf, data[1024..1040]);
+
+    assert_eq!(file.seek(SeekFrom::End(1)), Err(FsError::InvalidOffset));
+    assert_eq!(
+        file.seek(SeekFrom::Current(-2000)),
+        Err(FsError::InvalidOffset)
+    );
+}
+
+pub(crate) fn names(fs: &impl FileSystem, path: &str) -> Vec<String> {
+    fs.read_dir(path).unwrap().map(|meta| meta.name).collect()
+}
+
+pub(crate) fn write_file(fs: &impl FileSystem, path: &str, data: &[u8]) {
+    let mut file = fs.create_file(path).unwrap();
+    file.write_all(data).unwrap();
+    file.flush().unwrap();
+}
+
+#[test]
+fn test_create_and_reread() {
+    let disk = sample_image().disk();
+    let fs = Fat16::new(disk.clone()).unwrap();
+
+    write_file(&fs, "/NEW.TXT", &pattern(1500));
+    assert_eq!(read_to_end(&fs, "/NEW.TXT"), pattern(1500));
+
+    // 已存在的文件被截断
+    write_file(&fs, "/hello.txt", b"Hi");
+    assert_eq!(read_to_end(&fs, "/HELLO.TXT"), b"Hi");
+
+    // 重新挂载后从磁盘读取
+    let fs = Fat16::new(disk).unwrap();
+    assert_eq!(
+        names(&fs, "/"),
+        ["HELLO.TXT", "BIG.BIN", "EMPTY", "APP", "NEW.TXT"]
+    );
+    assert_eq!(fs.metadata("/NEW.TXT").unwrap().len, 1500);
+    assert_eq!(read_to_end(&fs, "/NEW.TXT"), pattern(1500));
+    assert_eq!(read_to_end(&fs, "/HELLO.TXT"), b"Hi");
+
+    assert_eq!(fs.create_file("/APP").err(), Some(FsError::NotAFile));
+    assert_eq!(
+        fs.create_file("/NOPE/A.TXT").err(),
+        Some(FsError::FileNotFound)
+    );
+}
+
+#[test]
+fn test_write_is_flushed_on_drop() {
+    let disk = sample_image().disk();
+    let fs = Fat16::new(disk.clone()).unwrap();
+
+    let mut file = fs.create_file("/DROP.BIN").unwrap();
+    file.write_all(&pattern(700)).unwrap();
+    drop(file);
+
+    assert_eq!(
+        read_to_end(&Fat16::new(disk).unwrap(), "/DROP.BIN"),
+        pattern(700)
+    );
+}
+
+#[test]
+fn test_overwrite_in_place() {
+    let fs = sample();
+    let mut data = pattern(1300);
+
+    let mut file = fs.open_file("/BIG.BIN").unwrap();
+    file.seek(SeekFrom::Start(500)).unwrap();
+    file.write_all(&[0xAA; 100]).unwrap();
+    drop(file);
+
+    data[500..600].fill(0xAA);
+    assert_eq!(read_to_end(&fs, "/BIG.BIN"), data);
+    assert_eq!(fs.metadata("/BIG.BIN").unwrap().len, 1300);
+}
+
+#[test]
+fn test_append() {
+    let disk = sample_image().disk();
+    let fs = Fat16::new(disk.clone()).unwrap();
+    let free = disk.free_clusters();
+
+    let mut file = fs.append_file("/HELLO.TXT").unwrap();
+    file.write_all(b" Bye.").unwrap();
+    drop(file);
+
+    // 空文件追加时分配首簇
+    let mut file = fs.append_file("/EMPTY").unwrap();
+    file.write_all(&pattern(600)).unwrap();
+    drop(file);
+
+    let fs = Fat16::new(disk.clone()).unwrap();
+    assert_eq!(read_to_end(&fs, "/HELLO.TXT"), b"Hello, FAT16! Bye.");
+    assert_eq!(read_to_end(&fs, "/EMPTY"), pattern(600));
+    assert_eq!(disk.free_clusters(), free - 2);
+
+    assert_eq!(fs.append_file("/NOPE").err(), Some(FsError::FileNotFound));
+}
+
+#[test]
+fn test_set_len() {
+    let disk = sample_image().disk();
+    let fs = Fat16::new(disk.clone()).unwrap();
+    let free = disk.free_clusters();
+    let data = pattern(1300);
+
+    let mut file = fs.open_file("/BIG.BIN").unwrap();
+    file.seek(SeekFrom::End(0)).unwrap();
+    file.set_len(600).unwrap();
+    // 偏移被限制在新的文件长度内
+    assert_eq!(file.seek(SeekFrom::Current(0)), Ok(600));
+    drop(file);
+
+    assert_eq!(read_to_end(&fs, "/BIG.BIN"), data[..600]);
+    assert_eq!(disk.free_clusters(), free + 1);
+
+    // 扩展部分以零填充
+    let mut file = fs.open_file("/BIG.BIN").unwrap();
+    file.set_len(2000).unwrap();
+    drop(file);
+
+    let mut expected = data[..600].to_vec();
+    expected.resize(2000, 0);
+    assert_eq!(
+        read_to_end(&Fat16::new(disk.clone()).unwrap(), "/BIG.BIN"),
+        expected
+    );
+    assert_eq!(disk.free_clusters(), free - 1);
+
+    let mut file = fs.open_file("/BIG.BIN").unwrap();
+    file.set_len(0).unwrap();
+    drop(file);
+
+    assert_eq!(
+        read_to_end(&Fat16::new(disk.clone()).unwrap(), "/BIG.BIN"),
+        b""
+    );
+    assert_eq!(disk.free_clusters(), free + 3);
+}
+
+#[test]
+fn test_create_dir() {
+    let disk = sample_image().disk();
+    let fs = Fat16::new(disk.clone()).unwrap();
+
+    fs.create_dir("/DOCS").unwrap();
+    fs.create_dir("/docs/inner/").unwrap();
+    write_file(&fs, "/DOCS/INNER/A.TXT", b"inner");
+
+    // 超过一个簇的目录项时目录随之扩展
+    let files: Vec<String> = (0..20).map(|i| format!("F{i}.TXT")).collect();
+    for name in &files {
+        write_file(&fs, &format!("/DOCS/{name}"), name.as_bytes());
+    }
+
+    assert_eq!(fs.create_dir("/DOCS").err(), Some(FsError::AlreadyExists));
+    assert_eq!(
+        fs.create_dir("/HELLO.TXT").err(),
+        Some(FsError::AlreadyExists)
+    );
+    assert_eq!(
+        fs.create_dir("/NOPE/DIR").err(),
+        Some(FsError::FileNotFound)
+    );
+
+    let fs = Fat16::new(disk).unwrap();
+    assert!(fs.metadata("/DOCS").unwrap().is_dir());
+    // 保留创建时的大小写
+    let mut expected = vec![String::from("inner")];
+    expected.extend(files.iter().cloned());
+    assert_eq!(names(&fs, "/DOCS"), expected);
+    for name in &files {
+        assert_eq!(read_to_end(&fs, &format!("/DOCS/{name}")), name.as_bytes());
+    }
+    assert_eq!(read_to_end(&fs, "/DOCS/INNER/A.TXT"), b"inner");
+
+    // `..` 指向父目录
+    assert_eq!(names(&fs, "/DOCS/INNER/.."), expected);
+    assert_eq!(
+        names(&fs, "/DOCS/.."),
+        ["HELLO.TXT", "BIG.BIN", "EMPTY", "APP", "DOCS"]
+    );
+}
+
+#[test]
+fn test_remove() {
+    let disk = sample_image().disk();
+    let fs = Fat16::new(disk.clone()).unwrap();
+    let free = disk.free_clusters();
+
+    assert_eq!(fs.remove_file("/APP").err(), Some(FsError::NotAFile));
+    assert_eq!(
+        fs.remove_dir("/HELLO.TXT").err(),
+        Some(FsError::NotADirectory)
+    );
+    assert_eq!(fs.remove_dir("/").err(), Some(FsError::InvalidOperation));
+    assert_eq!(fs.remove_file("/NOPE").err(), Some(FsError::FileNotFound));
+
+    fs.remove_file("/BIG.BIN").unwrap();
+    assert_eq!(disk.free_clusters(), free + 3);
+
+    // 递归删除 APP、FAB、SUB 与 NOTE.MD
+    fs.remove_dir("/APP").unwrap();
+    assert_eq!(disk.free_clusters(), free + 8);
+
+    let fs = Fat16::new(disk).unwrap();
+    assert_eq!(names(&fs, "/"), ["HELLO.TXT", "EMPTY"]);
+    assert_eq!(fs.exists("/APP/FAB"), Ok(false));
+    assert_eq!(fs.exists("/BIG.BIN"), Ok(false));
+
+    // 被删除的目录项可以重新使用
+    write_file(&fs, "/BIG.BIN", b"again");
+    assert_eq!(names(&fs, "/"), ["HELLO.TXT", "BIG.BIN", "EMPTY"]);
+}
+
+#[test]
+fn test_move() {
+    let disk = sample_image().disk();
+    let fs = Fat16::new(disk.clone()).unwrap();
+    let free = disk.free_clusters();
+
+    fs.move_file("/HELLO.TXT", "/APP/SUB/HI.TXT").unwrap();
+    fs.move_dir("/APP/SUB", "/NOTES").unwrap();
+    // 覆盖已存在的文件
+    fs.move_file("/BIG.BIN", "/APP/FAB").unwrap();
+    assert_eq!(disk.free_clusters(), free + 2);
+
+    assert_eq!(
+        fs.move_dir("/APP", "/APP/INNER").err(),
+        Some(FsError::InvalidOperation)
+    );
+    assert_eq!(
+        fs.move_dir("/APP", "/NOTES").err(),
+        Some(FsError::AlreadyExists)
+    );
+    assert_eq!(fs.move_file("/APP", "/X").err(), Some(FsError::NotAFile));
+    assert_eq!(
+        fs.move_dir("/EMPTY", "/X").err(),
+        Some(FsError::NotADirectory)
+    );
+    fs.move_file("/EMPTY", "/EMPTY").unwrap();
+
+    let fs = Fat16::new(disk).unwrap();
+    // NOTES 复用了 HELLO.TXT 被删除的目录项
+    assert_eq!(names(&fs, "/"), ["NOTES", "EMPTY", "APP"]);
+    assert_eq!(names(&fs, "/APP"), ["FAB"]);
+    assert_eq!(names(&fs, "/NOTES"), ["NOTE.MD", "HI.TXT"]);
+    assert_eq!(read_to_end(&fs, "/NOTES/HI.TXT"), b"Hello, FAT16!");
+    assert_eq!(read_to_end(&fs, "/APP/FAB"), pattern(1300));
+    // `..` 已更新为新的父目录
+    assert_eq!(names(&fs, "/NOTES/.."), ["NOTES", "EMPTY", "APP"]);
+}
+
+#[test]
+fn test_copy_file() {
+    let disk = sample_image().disk();
+    let fs = Fat16::new(disk.clone()).unwrap();
+
+    fs.copy_file("/BIG.BIN", "/APP/COPY.BIN").unwrap();
+    fs.copy_file("/HELLO.TXT", "/APP/FAB").unwrap();
+
+    let fs = Fat16::new(disk).unwrap();
+    assert_eq!(read_to_end(&fs, "/APP/COPY.BIN"), pattern(1300));
+    assert_eq!(read_to_end(&fs, "/BIG.BIN"), pattern(1300));
+    assert_eq!(read_to_end(&fs, "/APP/FAB"), b"Hello, FAT16!");
+}
+
+#[test]
+fn test_fat_copies() {
+    let disk = sample_image().disk();
+    let fs = Fat16::new(disk.clone()).unwrap();
+
+    write_file(&fs, "/NEW.TXT", &pattern(3000));
+    fs.create_dir("/DIR").unwrap();
+    fs.remove_file("/BIG.BIN").unwrap();
+
+    for cluster in 0..CLUSTERS as u16 + 2 {
+        assert_eq!(disk.fat_entry(0, cluster), disk.fat_entry(1, cluster));
+    }
+}
+
+#[test]
+fn test_timestamps() {
+    fn clock() -> FsTime {
+        Utc.with_ymd_and_hms(2024, 5, 20, 13, 14, 6).unwrap()
+    }
+    set_clock(clock);
+
+    let fs = sample();
+    write_file(&fs, "/NEW.TXT", b"now");
+    fs.create_dir("/DIR").unwrap();
+
+    for path in ["/NEW.TXT", "/DIR"] {
+        let meta = fs.metadata(path).unwrap();
+        assert_eq!(meta.created, Some(clock()));
+        assert_eq!(meta.modified, Some(clock()));
+    }
+
+    // 写入会更新修改时间
+    let mut file = fs.append_file("/HELLO.TXT").unwrap();
+    file.write_all(b"!").unwrap();
+    drop(file);
+    let meta = fs.metadata("/HELLO.TXT").unwrap();
+    assert_eq!(meta.modified, Some(clock()));
+    assert_eq!(
+        meta.created,
+        Some(Utc.with_ymd_and_hms(2020, 6, 16, 23, 48, 30).unwrap())
+    );
+}
+
+#[test]
+fn test_full() {
+    let disk = sample_image().disk();
+    let fs = Fat16::new(disk.clone()).unwrap();
+
+    // 根目录大小固定
+    for i in 0..ROOT_ENTRIES - 5 {
+        fs.create_file(&format!("/F{i}")).unwrap();
+    }
+    assert_eq!(fs.create_file("/LAST").err(), Some(FsError::WriteZero));
+    assert_eq!(fs.create_dir("/LAST").err(), Some(FsError::WriteZero));
+
+    // 磁盘空间耗尽时保留已写入的数据
+    let free = disk.free_clusters();
+    let mut file = fs.create_file("/APP/HUGE").unwrap();
+    assert_eq!(
+        file.write_all(&pattern(CLUSTERS * BLOCK_SIZE)),
+        Err(FsError::WriteZero)
+    );
+    drop(file);
+
+    assert_eq!(disk.free_clusters(), 0);
+    assert_eq!(read_to_end(&fs, "/APP/HUGE"), pattern(free * BLOCK_SIZE));
+}
+
+/// Raw entries of the root directory
+fn root_slots(disk: &RamDisk) -> Vec<[u8; DirEntry::LEN]> {
+    let blocks = disk.blocks();
+    blocks[ROOT_START..DATA_START]
+        .iter()
+        .flat_map(|block| block.chunks(DirEntry::LEN))
+        .map(|slot| slot.try_into().unwrap())
+        .collect()
+}
+
+#[test]
+fn test_read_long_names() {
+    let mut image = sample_image();
+    // "fork_test" 的长文件名项，校验和 0x6E 对应短文件名 FORK_T~1
+    let mut long = [0xFFu8; DirEntry::LEN];
+    long[0] = 0x41;
+    long[0x0B] = 0x0F;
+    long[0x0C] = 0;
+    long[0x0D] = 0x6E;
+    long[0x1A..0x1C].fill(0);
+    for (&offset, ch) in [1, 3, 5, 7, 9, 14, 16, 18, 20, 22]
+        .iter()
+        .zip(b"fork_test\0")
+    {
+        long[offset..offset + 2].copy_from_slice(&[*ch, 0]);
+    }
+    image.add_entry(None, long);
+    let cluster = image.alloc(b"ELF");
+    image.add_entry(
+        None,
+        ImageBuilder::entry(b"FORK_T~1   ", Attributes::ARCHIVE.bits(), cluster, 3),
+    );
+    // 校验和不匹配的长文件名项被忽略
+    long[0x0D] = 0;
+    image.add_entry(None, long);
+    image.add_file(None, b"ORPHAN     ", b"");
+    let fs = image.build();
+
+    let names = names(&fs, "/");
+    assert_eq!(names[4..], ["fork_test", "ORPHAN"]);
+
+    for path in ["/fork_test", "/FORK_TEST", "/Fork_Test", "/FORK_T~1"] {
+        assert_eq!(read_to_end(&fs, path), b"ELF");
+    }
+    assert_eq!(fs.metadata("/FORK_T~1").unwrap().name, "fork_test");
+}
+
+#[test]
+fn test_create_long_names() {
+    let disk = sample_image().disk();
+    let fs = Fat16::new(disk.clone()).unwrap();
+
+    fs.create_dir("/A Long Directory Name").unwrap();
+    write_file(&fs, "/a long directory name/notes for today.md", b"todo");
+    write_file(&fs, "/fork_test", b"1");
+    write_file(&fs, "/fork_test2", b"2");
+    // 合法的 8.3 文件名只转为大写，长文件名保留大小写
+    write_file(&fs, "/Readme.md", b"read me");
+    write_file(&fs, "/TOOLONGNAME.TXT", b"long");
+
+    let fs = Fat16::new(disk.clone()).unwrap();
+    assert_eq!(
+        names(&fs, "/"),
+        [
+            "HELLO.TXT",
+            "BIG.BIN",
+            "EMPTY",
+            "APP",
+            "A Long Directory Name",
+            "fork_test",
+            "fork_test2",
+            "Readme.md",
+            "TOOLONGNAME.TXT",
+        ]
+    );
+    assert_eq!(names(&fs, "/A LONG DIRECTORY NAME"), ["notes for today.md"]);
+    assert_eq!(
+        read_to_end(&fs, "/a long directory name/NOTES FOR TODAY.MD"),
+        b"todo"
+    );
+
+    // 短文件名别名
+    assert_eq!(read_to_end(&fs, "/FORK_T~1"), b"1");
+    assert_eq!(read_to_end(&fs, "/FORK_T~2"), b"2");
+    assert_eq!(read_to_end(&fs, "/README.MD"), b"read me");
+    assert_eq!(read_to_end(&fs, "/TOOLON~1.TXT"), b"long");
+    assert_eq!(read_to_end(&fs, "/ALONGD~1/NOTESF~1.MD"), b"todo");
+
+    // 已存在的长文件名按大小写不敏感匹配
+    write_file(&fs, "/FORK_TEST", b"one");
+    assert_eq!(read_to_end(&fs, "/fork_test"), b"one");
+    assert_eq!(
+        fs.create_dir("/a long directory name").err(),
+        Some(FsError::AlreadyExists)
+    );
+
+    assert_eq!(
+        fs.create_file("/bad|name").err(),
+        Some(FsError::FileNameError(FilenameError::InvalidCharacter))
+    );
+}
+
+#[test]
+fn test_long_name_slots() {
+    let disk = sample_image().disk();
+    let fs = Fat16::new(disk.clone()).unwrap();
+    let used = |disk: &RamDisk| {
+        root_slots(disk)
+            .iter()
+            .filter(|slot| slot[0] != 0 && slot[0] != 0xE5)
+            .count()
+    };
+    let base = used(&disk);
+
+    // 27 个字符需要 3 个长文件名项
+    let name = "a name of twenty-seven char";
+    write_file(&fs, &format!("/{name}"), b"slots");
+    assert_eq!(used(&disk), base + 4);
+
+    fs.move_file(&format!("/{name}"), "/APP/moved file")
+        .unwrap();
+    assert_eq!(used(&disk), base);
+    assert_eq!(read_to_end(&fs, "/app/MOVED FILE"), b"slots");
+
+    fs.move_dir("/APP", "/applications").unwrap();
+    assert_eq!(used(&disk), base + 1);
+    assert_eq!(
+        names(&Fat16::new(disk.clone()).unwrap(), "/applications"),
+        ["FAB", "SUB", "moved file"]
+    );
+
+    fs.remove_dir("/Applications").unwrap();
+    assert_eq!(used(&disk), base - 1);
+
+    // 空出的位置不足以连续存放时使用其后的空位
+    fs.remove_file("/BIG.BIN").unwrap();
+    write_file(&fs, "/another long name", b"");
+    assert_eq!(root_slots(&disk)[2][0], 0xE5);
+    assert_eq!(names(&fs, "/"), ["HELLO.TXT", "EMPTY", "another long name"]);
+}
@@ -0,0 +1,118 @@
+//! Fat32 Extended BIOS Parameter Block
+//!
+//! reference:
+//! - <https://wiki.osdev.org/FAT#FAT_32>
+//! - <https://en.wikipedia.org/wiki/Design_of_the_FAT_file_system#FAT32_Extended_BIOS_Parameter_Block>
+
+use crate::*;
+
+/// Represents the extended Boot Parameter Block of FAT32.
+///
+/// The fields before offset 0x24 are shared with FAT16 and read by `Fat16Bpb`,
+/// the fields after them are laid out differently on FAT32.
+pub struct Fat32Bpb {
+    data: [u8; 512],
+}
+
+impl Fat32Bpb {
+    /// Attempt to parse the extended Boot Parameter Block from a 512 byte sector.
+    pub fn new(data: &[u8]) -> FsResult<Fat32Bpb> {
+        let data = data.try_into().unwrap();
+        let bpb = Fat32Bpb { data };
+
+        if bpb.trail() != 0xAA55 || bpb.sectors_per_fat() == 0 {
+            return Err(FsError::InvalidOperation);
+        }
+
+        Ok(bpb)
+    }
+
+    /// Index of the only FAT in use, `None` if changes are mirrored to all FATs
+    pub fn active_fat(&self) -> Option<usize> {
+        // bit 7 置位时不做镜像，低 4 位为活动的 FAT
+        if self.ext_flags() & 0x80 != 0 {
+            Some((self.ext_flags() & 0x0F) as usize)
+        } else {
+            None
+        }
+    }
+
+    define_field!(u32, 0x24, sectors_per_fat);
+    define_field!(u16, 0x28, ext_flags);
+    define_field!(u16, 0x2A, fs_version);
+    define_field!(u32, 0x2C, root_cluster);
+    define_field!(u16, 0x30, fs_info_sector);
+    define_field!(u16, 0x32, backup_boot_sector);
+    define_field!(u8, 0x40, drive_number);
+    define_field!(u8, 0x41, reserved_flags);
+    define_field!(u8, 0x42, boot_signature);
+    define_field!(u32, 0x43, volume_id);
+    define_field!([u8; 11], 0x47, volume_label);
+    define_field!([u8; 8], 0x52, system_identifier);
+    define_field!(u16, 0x1FE, trail);
+}
+
+impl core::fmt::Debug for Fat32Bpb {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Fat32 BPB")
+            .field("Sectors per FAT", &self.sectors_per_fat())
+            .field("Ext Flags", &self.ext_flags())
+            .field("FS Version", &self.fs_version())
+            .field("Root Cluster", &self.root_cluster())
+            .field("FSInfo Sector", &self.fs_info_sector())
+            .field("Backup Boot Sector", &self.backup_boot_sector())
+            .field("Drive Number", &self.drive_number())
+            .field("Reserved Flags", &self.reserved_flags())
+            .field("Boot Signature", &self.boot_signature())
+            .field("Volume ID", &self.volume_id())
+            .field("Volume Label", &self.volume_label_str())
+            .field("System Identifier", &self.system_identifier_str())
+            .field("Trail", &self.trail())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::fat16::bpb::Fat16Bpb;
+
+    #[test]
+    fn test_fat32_bpb() {
+        // 64 MiB、每簇 1 扇区的 FAT32 镜像的引导扇区
+        const DATA: [u8; 96] = hex_literal::hex!(
+            "EB 58 90 6D 6B 66 73 2E 66 61 74 00 02 01 20 00
+            02 00 00 00 00 F8 00 00 20 00 08 00 00 00 00 00
+            00 00 02 00 F1 03 00 00 00 00 00 00 02 00 00 00
+            01 00 06 00 00 00 00 00 00 00 00 00 00 00 00 00
+            80 00 29 9C 3B 7A 5A 4E 4F 20 4E 41 4D 45 20 20
+            20 20 46 41 54 33 32 20 20 20 0E 1F BE 77 7C AC"
+        );
+
+        let mut bpb_data = Vec::with_capacity(512);
+        bpb_data.extend_from_slice(&DATA);
+        bpb_data.resize(510, 0u8);
+        bpb_data.extend_from_slice(&[0x55, 0xAA]);
+
+        let bpb = Fat32Bpb::new(&bpb_data).unwrap();
+
+        assert_eq!(bpb.sectors_per_fat(), 0x3F1);
+        assert_eq!(bpb.ext_flags(), 0);
+        assert_eq!(bpb.active_fat(), None);
+        assert_eq!(bpb.fs_version(), 0);
+        assert_eq!(bpb.root_cluster(), 2);
+        assert_eq!(bpb.fs_info_sector(), 1);
+        assert_eq!(bpb.backup_boot_sector(), 6);
+        assert_eq!(bpb.drive_number(), 0x80);
+        assert_eq!(bpb.boot_signature(), 0x29);
+        assert_eq!(bpb.volume_id(), 0x5A7A3B9C);
+        assert_eq!(bpb.volume_label_str(), "NO NAME    ");
+        assert_eq!(bpb.system_identifier_str(), "FAT32   ");
+
+        // FAT16 的 BPB 中这些位置没有每 FAT 扇区数
+        let fat16 = Fat16Bpb::new(&bpb_data).unwrap();
+        assert_eq!(fat16.sectors_per_fat(), 0);
+        assert_eq!(fat16.root_entries_count(), 0);
+        assert_eq!(fat16.reserved_sector_count(), 32);
+    }
+}
@@ -0,0 +1,62 @@
+//! FAT32 FSInfo Sector
+//!
+//! reference: <https://wiki.osdev.org/FAT#FSInfo_Structure_.28FAT32_only.29>
+
+use crate::*;
+
+const LEAD_SIGNATURE: u32 = 0x4161_5252;
+const STRUCT_SIGNATURE: u32 = 0x6141_7272;
+const TRAIL_SIGNATURE: u32 = 0xAA55_0000;
+
+/// Value of `free_count` and `next_free` when they are not known
+pub const UNKNOWN: u32 = 0xFFFF_FFFF;
+
+/// The FSInfo sector, which caches the free cluster count and
+/// a hint of where to look for the next free cluster
+pub struct FsInfo {
+    data: [u8; 512],
+}
+
+impl FsInfo {
+    /// Attempt to parse the FSInfo structure from a 512 byte sector.
+    pub fn new(data: &[u8]) -> FsResult<FsInfo> {
+        let data = data.try_into().unwrap();
+        let info = FsInfo { data };
+
+        if info.lead_signature() != LEAD_SIGNATURE
+            || info.struct_signature() != STRUCT_SIGNATURE
+            || info.trail_signature() != TRAIL_SIGNATURE
+        {
+            return Err(FsError::InvalidOperation);
+        }
+
+        Ok(info)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 512] {
+        &self.data
+    }
+
+    pub fn set_free_count(&mut self, count: u32) {
+        self.data[0x1E8..0x1EC].copy_from_slice(&count.to_le_bytes());
+    }
+
+    pub fn set_next_free(&mut self, cluster: u32) {
+        self.data[0x1EC..0x1F0].copy_from_slice(&cluster.to_le_bytes());
+    }
+
+    define_field!(u32, 0x000, lead_signature);
+    define_field!(u32, 0x1E4, struct_signature);
+    define_field!(u32, 0x1E8, free_count);
+    define_field!(u32, 0x1EC, next_free);
+    define_field!(u32, 0x1FC, trail_signature);
+}
+
+impl core::fmt::Debug for FsInfo {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("FsInfo")
+            .field("Free Count", &self.free_count())
+            .field("Next Free", &self.next_free())
+            .finish()
+    }
+}
@@ -0,0 +1,34 @@
+pub mod bpb;
+pub mod fsinfo;
+
+#[cfg(test)]
+mod tests;
+
+use super::fat16::{FatHandle, FatImpl};
+use crate::*;
+
+/// Identifies a Fat32 filesystem on the disk.
+///
+/// Directory entries, long file names and files are handled by the same
+/// implementation as `Fat16`, FAT32 differs in the 28-bit FAT entries,
+/// the root directory stored as a cluster chain and the FSInfo sector.
+pub struct Fat32 {
+    pub(crate) handle: FatHandle,
+}
+
+impl Fat32 {
+    pub fn new(inner: impl BlockDevice<Block512>) -> FsResult<Self> {
+        Ok(Self {
+            handle: Arc::new(FatImpl::new(inner)?),
+        })
+    }
+}
+
+impl core::fmt::Debug for Fat32 {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Fat32")
+            .field("bpb", &self.handle.bpb)
+            .field("bpb32", &self.handle.bpb32)
+            .finish()
+    }
+}
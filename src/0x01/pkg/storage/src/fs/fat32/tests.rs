@@ -0,0 +1,266 @@
+//! Tests of the Fat32 filesystem on an image generated in memory
+
+use super::*;
+use crate::fs::fat16::direntry::{Attributes, DirEntry};
+use crate::fs::fat16::tests::{names, pattern, read_to_end, sample_image, write_file};
+
+const BLOCK_SIZE: usize = 512;
+const TOTAL_SECTORS: usize = 256;
+const FS_INFO: usize = 1;
+const FAT_START: usize = 8;
+const SECTORS_PER_FAT: usize = 2;
+const DATA_START: usize = FAT_START + 2 * SECTORS_PER_FAT;
+const CLUSTERS: u32 = (TOTAL_SECTORS - DATA_START) as u32;
+
+/// A minimal FAT32 formatter
+///
+/// one sector per cluster and two FATs, the root directory spans
+/// clusters 2 and 5, `/DATA.BIN` uses clusters 3, 7 and 4 whose FAT entries
+/// have the reserved upper bits set, and cluster 6 is free with reserved bits set
+fn image(ext_flags: u16) -> RamDisk {
+    let mut sectors = vec![[0u8; BLOCK_SIZE]; TOTAL_SECTORS];
+
+    let bpb = &mut sectors[0];
+    bpb[0x03..0x0B].copy_from_slice(b"YSOSTEST");
+    bpb[0x0B..0x0D].copy_from_slice(&(BLOCK_SIZE as u16).to_le_bytes());
+    bpb[0x0D] = 1;
+    bpb[0x0E..0x10].copy_from_slice(&(FAT_START as u16).to_le_bytes());
+    bpb[0x10] = 2;
+    bpb[0x15] = 0xF8;
+    bpb[0x20..0x24].copy_from_slice(&(TOTAL_SECTORS as u32).to_le_bytes());
+    bpb[0x24..0x28].copy_from_slice(&(SECTORS_PER_FAT as u32).to_le_bytes());
+    bpb[0x28..0x2A].copy_from_slice(&ext_flags.to_le_bytes());
+    bpb[0x2C..0x30].copy_from_slice(&2u32.to_le_bytes());
+    bpb[0x30..0x32].copy_from_slice(&(FS_INFO as u16).to_le_bytes());
+    bpb[0x32..0x34].copy_from_slice(&6u16.to_le_bytes());
+    bpb[0x42] = 0x29;
+    bpb[0x47..0x52].copy_from_slice(b"TEST32     ");
+    bpb[0x52..0x5A].copy_from_slice(b"FAT32   ");
+    bpb[0x1FE..0x200].copy_from_slice(&[0x55, 0xAA]);
+
+    let info = &mut sectors[FS_INFO];
+    info[0x000..0x004].copy_from_slice(&0x4161_5252u32.to_le_bytes());
+    info[0x1E4..0x1E8].copy_from_slice(&0x6141_7272u32.to_le_bytes());
+    info[0x1E8..0x1EC].copy_from_slice(&(CLUSTERS - 5).to_le_bytes());
+    info[0x1EC..0x1F0].copy_from_slice(&6u32.to_le_bytes());
+    info[0x1FC..0x200].copy_from_slice(&0xAA55_0000u32.to_le_bytes());
+
+    let fat: [(usize, u32); 8] = [
+        (0, 0x0FFF_FFF8),
+        (1, 0x0FFF_FFFF),
+        (2, 5),
+        (5, 0x0FFF_FFFF),
+        (3, 0xA000_0007),
+        (7, 0x5000_0004),
+        (4, 0xFFFF_FFFF),
+        (6, 0x3000_0000),
+    ];
+    for fat_index in 0..2 {
+        for (cluster, value) in fat {
+            let offset = cluster * 4;
+            let sector =
+                &mut sectors[FAT_START + fat_index * SECTORS_PER_FAT + offset / BLOCK_SIZE];
+            let offset = offset % BLOCK_SIZE;
+            sector[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    let entry = |name: &[u8; 11], attr: Attributes, cluster: u32, size: u32| {
+        let mut entry = [0u8; DirEntry::LEN];
+        entry[..11].copy_from_slice(name);
+        entry[0x0B] = attr.bits();
+        entry[0x14..0x16].copy_from_slice(&((cluster >> 16) as u16).to_le_bytes());
+        entry[0x1A..0x1C].copy_from_slice(&(cluster as u16).to_le_bytes());
+        entry[0x1C..0x20].copy_from_slice(&size.to_le_bytes());
+        entry
+    };
+
+    // 根目录的第一个簇被卷标与 15 个空文件填满
+    let mut root = vec![entry(b"TEST32     ", Attributes::VOLUME_ID, 0, 0)];
+    for i in 0..15 {
+        let name = format!("F{i:02}        ");
+        root.push(entry(
+            name.as_bytes().try_into().unwrap(),
+            Attributes::ARCHIVE,
+            0,
+            0,
+        ));
+    }
+    root.push(entry(b"DATA    BIN", Attributes::ARCHIVE, 3, 1200));
+    for (idx, slot) in root.iter().enumerate() {
+        let cluster = if idx < 16 { 2 } else { 5 };
+        let offset = idx % 16 * DirEntry::LEN;
+        sectors[DATA_START + cluster - 2][offset..offset + DirEntry::LEN].copy_from_slice(slot);
+    }
+
+    let data = pattern(1200);
+    for (chunk, cluster) in data.chunks(BLOCK_SIZE).zip([3, 7, 4]) {
+        sectors[DATA_START + cluster - 2][..chunk.len()].copy_from_slice(chunk);
+    }
+
+    RamDisk::from_blocks(sectors.iter().map(Block::new).collect())
+}
+
+/// The raw FAT entry of `cluster` in the `fat`-th FAT, including the reserved bits
+fn fat_entry(disk: &RamDisk, fat: usize, cluster: u32) -> u32 {
+    let offset = cluster as usize * 4;
+    let block = &disk.blocks()[FAT_START + fat * SECTORS_PER_FAT + offset / BLOCK_SIZE];
+    let offset = offset % BLOCK_SIZE;
+    u32::from_le_bytes(block[offset..offset + 4].try_into().unwrap())
+}
+
+fn free_clusters(disk: &RamDisk) -> u32 {
+    (2..CLUSTERS + 2)
+        .filter(|&cluster| fat_entry(disk, 0, cluster) & 0x0FFF_FFFF == 0)
+        .count() as u32
+}
+
+/// Free cluster count and next free hint in the FSInfo sector
+fn fs_info(disk: &RamDisk) -> (u32, u32) {
+    let block = &disk.blocks()[FS_INFO];
+    let u32_at = |offset: usize| u32::from_le_bytes(block[offset..offset + 4].try_into().unwrap());
+    (u32_at(0x1E8), u32_at(0x1EC))
+}
+
+fn root_names() -> Vec<String> {
+    let mut names: Vec<String> = (0..15).map(|i| format!("F{i:02}")).collect();
+    names.push(String::from("DATA.BIN"));
+    names
+}
+
+#[test]
+fn test_open_fat() {
+    let fs = open_fat(image(0)).unwrap();
+    assert!(format!("{:?}", fs).starts_with("Fat32"));
+    assert_eq!(
+        read_to_end(&Mount::new(fs, "/".into()), "/DATA.BIN"),
+        pattern(1200)
+    );
+
+    let fs = open_fat(sample_image().disk()).unwrap();
+    assert!(format!("{:?}", fs).starts_with("Fat16"));
+}
+
+#[test]
+fn test_open_invalid() {
+    // 只有 0xAA55 结尾的非 FAT 分区会被识别为 FAT32
+    let mut sector = [0u8; BLOCK_SIZE];
+    sector[0x1FE..].copy_from_slice(&[0x55, 0xAA]);
+    let disk = RamDisk::from_bytes(&sector);
+    assert_eq!(open_fat(disk).err(), Some(FsError::InvalidOperation));
+
+    let corrupt = |offset: usize, bytes: &[u8]| {
+        let disk = sample_image().disk();
+        disk.blocks()[0].as_mut()[offset..offset + bytes.len()].copy_from_slice(bytes);
+        open_fat(disk).err()
+    };
+    assert_eq!(
+        corrupt(0x0B, &4096u16.to_le_bytes()),
+        Some(FsError::InvalidOperation)
+    );
+    assert_eq!(corrupt(0x0D, &[0]), Some(FsError::InvalidOperation));
+    assert_eq!(corrupt(0x0D, &[3]), Some(FsError::InvalidOperation));
+    assert_eq!(corrupt(0x10, &[0]), Some(FsError::InvalidOperation));
+    assert!(corrupt(0x0D, &[2]).is_none());
+}
+
+#[test]
+fn test_read() {
+    let fs = Fat32::new(image(0)).unwrap();
+
+    // 根目录是一条簇链
+    assert_eq!(names(&fs, "/"), root_names());
+    assert!(fs.metadata("/").unwrap().is_dir());
+    assert_eq!(fs.metadata("/DATA.BIN").unwrap().len, 1200);
+
+    // FAT 表项只有低 28 位有效
+    assert_eq!(read_to_end(&fs, "/data.bin"), pattern(1200));
+    let mut file = fs.open_file("/DATA.BIN").unwrap();
+    let mut buf = [0u8; 16];
+    file.seek(SeekFrom::Start(1100)).unwrap();
+    assert_eq!(file.read(&mut buf), Ok(16));
+    assert_eq!(buf, pattern(1200)[1100..1116]);
+}
+
+#[test]
+fn test_write_and_reread() {
+    let disk = image(0);
+    let fs = Fat32::new(disk.clone()).unwrap();
+
+    write_file(&fs, "/a long file name.txt", &pattern(3000));
+    fs.create_dir("/Docs").unwrap();
+    write_file(&fs, "/docs/note.md", b"fat32");
+    fs.create_dir("/Docs/Sub Dir").unwrap();
+    fs.move_file("/DATA.BIN", "/Docs/Sub Dir/data.bin").unwrap();
+
+    // 根目录没有固定大小，写满后继续分配簇
+    let files: Vec<String> = (0..20).map(|i| format!("G{i}")).collect();
+    for name in &files {
+        write_file(&fs, &format!("/{name}"), name.as_bytes());
+    }
+
+    let fs = Fat32::new(disk.clone()).unwrap();
+    // G0 复用了 DATA.BIN 移走后空出的目录项
+    let mut expected = root_names();
+    expected.pop();
+    expected.push(files[0].clone());
+    expected.extend([String::from("a long file name.txt"), String::from("Docs")]);
+    expected.extend(files[1..].iter().cloned());
+    assert_eq!(names(&fs, "/"), expected);
+    for name in &files {
+        assert_eq!(read_to_end(&fs, &format!("/{name}")), name.as_bytes());
+    }
+    assert_eq!(read_to_end(&fs, "/A LONG FILE NAME.TXT"), pattern(3000));
+    assert_eq!(names(&fs, "/Docs"), ["note.md", "Sub Dir"]);
+    assert_eq!(read_to_end(&fs, "/Docs/Sub Dir/DATA.BIN"), pattern(1200));
+    // 父目录为根目录时 `..` 的簇号为 0
+    assert_eq!(names(&fs, "/Docs/Sub Dir/../.."), expected);
+
+    fs.remove_dir("/Docs").unwrap();
+    assert_eq!(fs.exists("/Docs/note.md"), Ok(false));
+
+    for cluster in 0..CLUSTERS + 2 {
+        assert_eq!(fat_entry(&disk, 0, cluster), fat_entry(&disk, 1, cluster));
+    }
+}
+
+#[test]
+fn test_fs_info() {
+    let disk = image(0);
+    let fs = Fat32::new(disk.clone()).unwrap();
+    assert_eq!(fs_info(&disk), (free_clusters(&disk), 6));
+
+    // 从 FSInfo 提示的簇开始分配，并保留表项的高 4 位
+    write_file(&fs, "/NEW.BIN", &pattern(2 * BLOCK_SIZE));
+    assert_eq!(fat_entry(&disk, 0, 6), 0x3000_0008);
+    assert_eq!(fat_entry(&disk, 0, 8), 0x0FFF_FFFF);
+    assert_eq!(fs_info(&disk), (free_clusters(&disk), 9));
+
+    fs.remove_file("/DATA.BIN").unwrap();
+    assert_eq!(fs_info(&disk).0, free_clusters(&disk));
+    assert_eq!(fat_entry(&disk, 0, 3), 0xA000_0000);
+
+    let mut file = fs.open_file("/NEW.BIN").unwrap();
+    file.set_len(10).unwrap();
+    drop(file);
+    assert_eq!(fs_info(&disk).0, free_clusters(&disk));
+    assert_eq!(fat_entry(&disk, 0, 6), 0x3FFF_FFFF);
+}
+
+#[test]
+fn test_mirroring_disabled() {
+    // 只使用第 2 个 FAT
+    let disk = image(0x81);
+    let fs = Fat32::new(disk.clone()).unwrap();
+    let before: Vec<u32> = (0..CLUSTERS + 2).map(|c| fat_entry(&disk, 0, c)).collect();
+
+    write_file(&fs, "/NEW.BIN", &pattern(3 * BLOCK_SIZE));
+    fs.remove_file("/DATA.BIN").unwrap();
+
+    let after: Vec<u32> = (0..CLUSTERS + 2).map(|c| fat_entry(&disk, 0, c)).collect();
+    assert_eq!(before, after);
+    assert_eq!(fat_entry(&disk, 1, 3), 0xA000_0000);
+
+    let fs = Fat32::new(disk).unwrap();
+    assert_eq!(read_to_end(&fs, "/NEW.BIN"), pattern(3 * BLOCK_SIZE));
+}
@@ -0,0 +1,31 @@
+pub mod ext2;
+pub mod fat16;
+pub mod fat32;
+pub mod tmpfs;
+
+use crate::*;
+use fat16::bpb::Fat16Bpb;
+use fat16::{Fat16, FatType};
+use fat32::Fat32;
+
+/// Open a FAT volume as `Fat16` or `Fat32` according to its BPB
+pub fn open_fat(inner: impl BlockDevice<Block512>) -> FsResult<Box<dyn FileSystem>> {
+    let mut block = Block::default();
+    inner.read_block(0, &mut block)?;
+    let bpb = Fat16Bpb::new(block.as_ref())?;
+
+    Ok(match FatType::detect(&bpb) {
+        FatType::Fat16 => Box::new(Fat16::new(inner)?),
+        FatType::Fat32 => Box::new(Fat32::new(inner)?),
+    })
+}
+
+/// Split `path` into its parent directory and the last component
+pub(crate) fn split_path(path: &str) -> FsResult<(&str, &str)> {
+    let trimmed = path.trim_end_matches(PATH_SEPARATOR);
+    let (parent, name) = trimmed.rsplit_once(PATH_SEPARATOR).unwrap_or(("", trimmed));
+    if name.is_empty() || name == "." || name == ".." {
+        return Err(FsError::InvalidPath(path.into()));
+    }
+    Ok((parent, name))
+}
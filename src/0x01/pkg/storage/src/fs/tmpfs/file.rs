@@ -0,0 +1,92 @@
+//! File
+//!
+//! An open file of a `TmpFs`, reading and writing the shared content directly
+
+use super::*;
+
+pub struct TmpFile {
+    /// The current offset in the file
+    offset: usize,
+    /// The content of the file, shared with the tree and other handles
+    node: FileRef,
+}
+
+impl TmpFile {
+    pub(crate) fn new(node: FileRef) -> Self {
+        Self { offset: 0, node }
+    }
+
+    pub fn length(&self) -> usize {
+        self.node.lock().data.len()
+    }
+}
+
+impl Read for TmpFile {
+    fn read(&mut self, buf: &mut [u8]) -> FsResult<usize> {
+        let mut node = self.node.lock();
+        let data = node.data.get(self.offset..).unwrap_or_default();
+        let read = data.len().min(buf.len());
+        buf[..read].copy_from_slice(&data[..read]);
+        node.accessed = now();
+
+        self.offset += read;
+        Ok(read)
+    }
+}
+
+impl Seek for TmpFile {
+    /// Seek within the file, the offset can not go beyond the end of file
+    fn seek(&mut self, pos: SeekFrom) -> FsResult<usize> {
+        let length = self.length();
+        let offset = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(delta) => length.checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.offset.checked_add_signed(delta),
+        };
+        let offset = match offset {
+            Some(offset) if offset <= length => offset,
+            _ => return Err(FsError::InvalidOffset),
+        };
+
+        self.offset = offset;
+        Ok(offset)
+    }
+}
+
+impl Write for TmpFile {
+    /// Write at the current offset, only the part fitting in the limit is written
+    fn write(&mut self, buf: &[u8]) -> FsResult<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let mut node = self.node.lock();
+        let length = node.data.len();
+        let end = self.offset + buf.len();
+        if end > length {
+            let end = end.min(length + node.usage.available());
+            if end <= self.offset {
+                return Err(FsError::WriteZero);
+            }
+            node.resize(end)?;
+        }
+
+        let written = buf.len().min(node.data.len() - self.offset);
+        node.data[self.offset..self.offset + written].copy_from_slice(&buf[..written]);
+        node.modified = now();
+
+        self.offset += written;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> FsResult {
+        Ok(())
+    }
+
+    /// Truncate or extend the file with zeros
+    fn set_len(&mut self, len: usize) -> FsResult {
+        self.node.lock().resize(len)?;
+        self.offset = self.offset.min(len);
+        Ok(())
+    }
+}
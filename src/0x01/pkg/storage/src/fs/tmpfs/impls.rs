@@ -0,0 +1,176 @@
+use super::*;
+
+impl TmpFs {
+    fn open(name: &str, file: &FileRef) -> FileHandle {
+        let meta = file.lock().as_meta(name);
+        FileHandle::new(meta, Box::new(TmpFile::new(file.clone())))
+    }
+
+    /// The file at `path`
+    fn file(&self, path: &str) -> FsResult<FileRef> {
+        let (parent, name) = split(path)?;
+        let root = self.root.lock();
+        match root.walk(&parent)?.entries.get(name) {
+            Some(Node::File(file)) => Ok(file.clone()),
+            Some(Node::Dir(_)) => Err(FsError::NotAFile),
+            None => Err(FsError::FileNotFound),
+        }
+    }
+
+    /// Move the entry at `src` to `dst`, an existing file at `dst` is replaced
+    fn rename(&self, src: &str, dst: &str, is_dir: bool) -> FsResult {
+        let (src_parent, src_name) = split(src)?;
+        let (dst_parent, dst_name) = split(dst)?;
+        let mut root = self.root.lock();
+
+        match root.walk(&src_parent)?.entries.get(src_name) {
+            Some(Node::File(_)) if is_dir => return Err(FsError::NotADirectory),
+            Some(Node::Dir(_)) if !is_dir => return Err(FsError::NotAFile),
+            Some(_) => {}
+            None => return Err(FsError::FileNotFound),
+        }
+
+        if src_parent == dst_parent && src_name == dst_name {
+            return Ok(());
+        }
+
+        // 不能把目录移动到它自身之下
+        if is_dir
+            && dst_parent.len() > src_parent.len()
+            && dst_parent[..src_parent.len()] == src_parent[..]
+            && dst_parent[src_parent.len()] == src_name
+        {
+            return Err(FsError::InvalidOperation);
+        }
+
+        match root.walk(&dst_parent)?.entries.get(dst_name) {
+            Some(Node::Dir(_)) => return Err(FsError::AlreadyExists),
+            Some(Node::File(_)) if is_dir => return Err(FsError::AlreadyExists),
+            _ => {}
+        }
+
+        // 上面已检查过两个目录，移除与插入不会失败
+        let node = root.walk_mut(&src_parent)?.remove(src_name).unwrap();
+        root.walk_mut(&dst_parent)?.insert(dst_name, node);
+        Ok(())
+    }
+}
+
+impl FileSystem for TmpFs {
+    fn read_dir(&self, path: &str) -> FsResult<Box<dyn Iterator<Item = Metadata> + Send>> {
+        let root = self.root.lock();
+        let entries = root
+            .walk(&components(path))?
+            .entries
+            .iter()
+            .map(|(name, node)| node.as_meta(name))
+            .collect::<Vec<_>>();
+        Ok(Box::new(entries.into_iter()))
+    }
+
+    fn open_file(&self, path: &str) -> FsResult<FileHandle> {
+        let file = self.file(path)?;
+        Ok(Self::open(split(path)?.1, &file))
+    }
+
+    fn metadata(&self, path: &str) -> FsResult<Metadata> {
+        let root = self.root.lock();
+        let Ok((parent, name)) = split(path) else {
+            return Ok(root.as_meta("/"));
+        };
+        match root.walk(&parent)?.entries.get(name) {
+            Some(node) => Ok(node.as_meta(name)),
+            None => Err(FsError::FileNotFound),
+        }
+    }
+
+    fn exists(&self, path: &str) -> FsResult<bool> {
+        match self.metadata(path) {
+            Ok(_) => Ok(true),
+            Err(FsError::FileNotFound) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn create_file(&self, path: &str) -> FsResult<FileHandle> {
+        let (parent, name) = split(path)?;
+        let mut root = self.root.lock();
+        let dir = root.walk_mut(&parent)?;
+
+        let file = match dir.entries.get(name) {
+            Some(Node::File(file)) => {
+                file.lock().resize(0)?;
+                file.clone()
+            }
+            Some(Node::Dir(_)) => return Err(FsError::NotAFile),
+            None => {
+                let file = Arc::new(spin::Mutex::new(FileNode::new(self.usage.clone())));
+                dir.insert(name, Node::File(file.clone()));
+                file
+            }
+        };
+
+        Ok(Self::open(name, &file))
+    }
+
+    fn append_file(&self, path: &str) -> FsResult<FileHandle> {
+        let mut file = self.open_file(path)?;
+        file.seek(SeekFrom::End(0))?;
+        Ok(file)
+    }
+
+    fn create_dir(&self, path: &str) -> FsResult {
+        let (parent, name) = split(path)?;
+        let mut root = self.root.lock();
+        let dir = root.walk_mut(&parent)?;
+        if dir.entries.contains_key(name) {
+            return Err(FsError::AlreadyExists);
+        }
+        dir.insert(name, Node::Dir(Dir::new()));
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &str) -> FsResult {
+        let (parent, name) = split(path)?;
+        let mut root = self.root.lock();
+        let dir = root.walk_mut(&parent)?;
+        match dir.entries.get(name) {
+            Some(Node::File(_)) => {
+                // 已打开的句柄仍持有文件内容，关闭后才释放空间
+                dir.remove(name);
+                Ok(())
+            }
+            Some(Node::Dir(_)) => Err(FsError::NotAFile),
+            None => Err(FsError::FileNotFound),
+        }
+    }
+
+    fn remove_dir(&self, path: &str) -> FsResult {
+        let (parent, name) = split(path).map_err(|_| FsError::InvalidOperation)?;
+        let mut root = self.root.lock();
+        let dir = root.walk_mut(&parent)?;
+        match dir.entries.get(name) {
+            Some(Node::Dir(_)) => {
+                dir.remove(name);
+                Ok(())
+            }
+            Some(Node::File(_)) => Err(FsError::NotADirectory),
+            None => Err(FsError::FileNotFound),
+        }
+    }
+
+    fn copy_file(&self, src: &str, dst: &str) -> FsResult {
+        let data = self.file(src)?.lock().data.clone();
+        let mut file = self.create_file(dst)?;
+        file.write_all(&data)?;
+        file.flush()
+    }
+
+    fn move_file(&self, src: &str, dst: &str) -> FsResult {
+        self.rename(src, dst, false)
+    }
+
+    fn move_dir(&self, src: &str, dst: &str) -> FsResult {
+        self.rename(src, dst, true)
+    }
+}
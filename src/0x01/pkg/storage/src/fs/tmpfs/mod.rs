@@ -0,0 +1,250 @@
+pub mod file;
+pub mod impls;
+
+#[cfg(test)]
+mod tests;
+
+use crate::*;
+use alloc::collections::BTreeMap;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use file::TmpFile;
+
+/// A filesystem kept entirely in memory.
+///
+/// The whole tree is guarded by one lock, while the content of each file
+/// has its own lock so that open handles stay usable after the file is
+/// removed or moved.
+pub struct TmpFs {
+    root: spin::Mutex<Dir>,
+    usage: Arc<Usage>,
+}
+
+impl TmpFs {
+    /// An empty filesystem without a size limit
+    pub fn new() -> Self {
+        Self::with_limit(usize::MAX)
+    }
+
+    /// An empty filesystem holding at most `limit` bytes of file content
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            root: spin::Mutex::new(Dir::new()),
+            usage: Arc::new(Usage {
+                used: AtomicUsize::new(0),
+                limit,
+            }),
+        }
+    }
+
+    /// Bytes of file content currently stored, including removed files still open
+    pub fn used(&self) -> usize {
+        self.usage.used.load(Ordering::Relaxed)
+    }
+
+    /// The maximum bytes of file content
+    pub fn limit(&self) -> usize {
+        self.usage.limit
+    }
+}
+
+impl Default for TmpFs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Accounting of the bytes stored in the files of a `TmpFs`
+struct Usage {
+    used: AtomicUsize,
+    limit: usize,
+}
+
+impl Usage {
+    /// Take `bytes` from the space left, fails with `WriteZero` if it does not fit
+    fn reserve(&self, bytes: usize) -> FsResult {
+        let mut used = self.used.load(Ordering::Relaxed);
+        loop {
+            let new = match used.checked_add(bytes) {
+                Some(new) if new <= self.limit => new,
+                _ => return Err(FsError::WriteZero),
+            };
+            match self
+                .used
+                .compare_exchange_weak(used, new, Ordering::Relaxed, Ordering::Relaxed)
+            {
+                Ok(_) => return Ok(()),
+                Err(current) => used = current,
+            }
+        }
+    }
+
+    fn release(&self, bytes: usize) {
+        self.used.fetch_sub(bytes, Ordering::Relaxed);
+    }
+
+    fn available(&self) -> usize {
+        self.limit.saturating_sub(self.used.load(Ordering::Relaxed))
+    }
+}
+
+/// The content of a file, its space is given back when the last handle is dropped
+pub(crate) struct FileNode {
+    data: Vec<u8>,
+    created: FsTime,
+    modified: FsTime,
+    accessed: FsTime,
+    usage: Arc<Usage>,
+}
+
+pub(crate) type FileRef = Arc<spin::Mutex<FileNode>>;
+
+impl FileNode {
+    fn new(usage: Arc<Usage>) -> Self {
+        let time = now();
+        Self {
+            data: Vec::new(),
+            created: time,
+            modified: time,
+            accessed: time,
+            usage,
+        }
+    }
+
+    /// Truncate or extend the content with zeros
+    fn resize(&mut self, len: usize) -> FsResult {
+        let old = self.data.len();
+        if len > old {
+            self.usage.reserve(len - old)?;
+        } else {
+            self.usage.release(old - len);
+        }
+        self.data.resize(len, 0);
+        self.modified = now();
+        Ok(())
+    }
+
+    fn as_meta(&self, name: &str) -> Metadata {
+        Metadata::new(
+            name.into(),
+            FileType::File,
+            self.data.len(),
+            Some(self.created),
+            Some(self.modified),
+            Some(self.accessed),
+        )
+    }
+}
+
+impl Drop for FileNode {
+    fn drop(&mut self) {
+        self.usage.release(self.data.len());
+    }
+}
+
+enum Node {
+    File(FileRef),
+    Dir(Dir),
+}
+
+impl Node {
+    fn as_meta(&self, name: &str) -> Metadata {
+        match self {
+            Node::File(file) => file.lock().as_meta(name),
+            Node::Dir(dir) => dir.as_meta(name),
+        }
+    }
+}
+
+/// A directory, entries are kept sorted by name
+struct Dir {
+    entries: BTreeMap<String, Node>,
+    created: FsTime,
+    modified: FsTime,
+}
+
+impl Dir {
+    fn new() -> Self {
+        let time = now();
+        Self {
+            entries: BTreeMap::new(),
+            created: time,
+            modified: time,
+        }
+    }
+
+    /// Find the directory at `path` below this one
+    fn walk(&self, path: &[&str]) -> FsResult<&Dir> {
+        path.iter().try_fold(self, |dir, name| {
+            match dir.entries.get(*name).ok_or(FsError::FileNotFound)? {
+                Node::Dir(dir) => Ok(dir),
+                Node::File(_) => Err(FsError::NotADirectory),
+            }
+        })
+    }
+
+    fn walk_mut(&mut self, path: &[&str]) -> FsResult<&mut Dir> {
+        path.iter().try_fold(self, |dir, name| {
+            match dir.entries.get_mut(*name).ok_or(FsError::FileNotFound)? {
+                Node::Dir(dir) => Ok(dir),
+                Node::File(_) => Err(FsError::NotADirectory),
+            }
+        })
+    }
+
+    fn insert(&mut self, name: &str, node: Node) {
+        self.entries.insert(name.into(), node);
+        self.modified = now();
+    }
+
+    fn remove(&mut self, name: &str) -> Option<Node> {
+        let node = self.entries.remove(name);
+        self.modified = now();
+        node
+    }
+
+    fn as_meta(&self, name: &str) -> Metadata {
+        Metadata::new(
+            name.into(),
+            FileType::Directory,
+            0,
+            Some(self.created),
+            Some(self.modified),
+            None,
+        )
+    }
+}
+
+/// Split `path` into its components, resolving `.` and `..` lexically
+///
+/// `..` at the root stays at the root, an empty result is the root itself
+fn components(path: &str) -> Vec<&str> {
+    let mut components = Vec::new();
+    for name in path.split(PATH_SEPARATOR) {
+        match name {
+            "" | "." => {}
+            ".." => {
+                components.pop();
+            }
+            name => components.push(name),
+        }
+    }
+    components
+}
+
+/// Split `path` into the components of its parent and its name, fails on the root
+fn split(path: &str) -> FsResult<(Vec<&str>, &str)> {
+    let mut components = components(path);
+    match components.pop() {
+        Some(name) => Ok((components, name)),
+        None => Err(FsError::InvalidPath(path.into())),
+    }
+}
+
+impl core::fmt::Debug for TmpFs {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("TmpFs")
+            .field("used", &self.used())
+            .field("limit", &self.limit())
+            .finish()
+    }
+}
@@ -0,0 +1,234 @@
+//! Tests of the in-memory filesystem
+
+use super::*;
+use crate::fs::fat16::tests::{names, pattern, read_to_end, write_file};
+
+/// The tree used by most tests:
+///
+/// ```text
+/// /
+/// ├── hello.txt
+/// └── docs
+///     ├── big.bin (3000 bytes)
+///     └── empty
+/// ```
+fn sample() -> TmpFs {
+    let fs = TmpFs::new();
+    write_file(&fs, "/hello.txt", b"Hello, tmpfs!");
+    fs.create_dir("/docs").unwrap();
+    fs.create_dir("/docs/empty").unwrap();
+    write_file(&fs, "/docs/big.bin", &pattern(3000));
+    fs
+}
+
+#[test]
+fn test_create_and_read() {
+    let fs = sample();
+
+    assert_eq!(names(&fs, "/"), ["docs", "hello.txt"]);
+    assert_eq!(names(&fs, "/docs"), ["big.bin", "empty"]);
+    assert!(names(&fs, "/docs/empty").is_empty());
+
+    assert_eq!(read_to_end(&fs, "/hello.txt"), b"Hello, tmpfs!");
+    assert_eq!(read_to_end(&fs, "docs/big.bin"), pattern(3000));
+    assert_eq!(read_to_end(&fs, "/docs/./empty/../big.bin"), pattern(3000));
+    assert_eq!(read_to_end(&fs, "/../hello.txt"), b"Hello, tmpfs!");
+    assert_eq!(fs.used(), 13 + 3000);
+
+    let meta = fs.metadata("/docs/big.bin").unwrap();
+    assert!(meta.is_file());
+    assert_eq!((meta.name.as_str(), meta.len), ("big.bin", 3000));
+    assert!(fs.metadata("/docs/").unwrap().is_dir());
+    assert!(fs.metadata("/").unwrap().is_dir());
+    assert_eq!(fs.exists("/docs/empty"), Ok(true));
+    assert_eq!(fs.exists("/docs/missing"), Ok(false));
+    assert_eq!(fs.exists("/hello.txt/x"), Err(FsError::NotADirectory));
+
+    assert_eq!(fs.open_file("/docs").unwrap_err(), FsError::NotAFile);
+    assert_eq!(fs.open_file("/nope").unwrap_err(), FsError::FileNotFound);
+    assert_eq!(fs.create_file("/docs").unwrap_err(), FsError::NotAFile);
+    assert_eq!(
+        fs.create_file("/nope/a").unwrap_err(),
+        FsError::FileNotFound
+    );
+    assert_eq!(fs.create_dir("/docs/empty"), Err(FsError::AlreadyExists));
+    assert!(fs.read_dir("/hello.txt").is_err());
+    assert!(matches!(fs.create_file("/"), Err(FsError::InvalidPath(_))));
+
+    // 重新创建会截断已有文件
+    write_file(&fs, "/docs/big.bin", b"small");
+    assert_eq!(read_to_end(&fs, "/docs/big.bin"), b"small");
+    assert_eq!(fs.used(), 13 + 5);
+}
+
+#[test]
+fn test_seek_and_write() {
+    let fs = sample();
+
+    let mut file = fs.append_file("/hello.txt").unwrap();
+    file.write_all(b" Bye.").unwrap();
+    assert_eq!(file.seek(SeekFrom::Start(7)), Ok(7));
+    file.write_all(b"TMPFS").unwrap();
+    assert_eq!(file.seek(SeekFrom::Current(1)), Ok(13));
+    assert_eq!(file.seek(SeekFrom::End(1)), Err(FsError::InvalidOffset));
+    assert_eq!(
+        file.seek(SeekFrom::Current(-14)),
+        Err(FsError::InvalidOffset)
+    );
+    drop(file);
+    assert_eq!(read_to_end(&fs, "/hello.txt"), b"Hello, TMPFS! Bye.");
+
+    let mut file = fs.open_file("/docs/big.bin").unwrap();
+    let mut buf = [0u8; 100];
+    file.seek(SeekFrom::End(-50)).unwrap();
+    assert_eq!(file.read(&mut buf), Ok(50));
+    assert_eq!(buf[..50], pattern(3000)[2950..]);
+    assert_eq!(file.read(&mut buf), Ok(0));
+
+    // 截断后扩展，扩展部分读到零
+    file.set_len(10).unwrap();
+    assert_eq!(file.seek(SeekFrom::Current(0)), Ok(10));
+    file.set_len(20).unwrap();
+    assert_eq!(fs.metadata("/docs/big.bin").unwrap().len, 20);
+    let mut expected = pattern(10);
+    expected.resize(20, 0);
+    assert_eq!(read_to_end(&fs, "/docs/big.bin"), expected);
+    assert_eq!(fs.used(), 18 + 20);
+}
+
+#[test]
+fn test_copy_and_remove() {
+    let fs = sample();
+
+    fs.copy_file("/docs/big.bin", "/copy.bin").unwrap();
+    assert_eq!(read_to_end(&fs, "/copy.bin"), pattern(3000));
+    fs.copy_file("/copy.bin", "/copy.bin").unwrap();
+    assert_eq!(read_to_end(&fs, "/copy.bin"), pattern(3000));
+    assert_eq!(fs.used(), 13 + 6000);
+
+    assert_eq!(fs.remove_file("/docs"), Err(FsError::NotAFile));
+    assert_eq!(fs.remove_dir("/hello.txt"), Err(FsError::NotADirectory));
+    assert_eq!(fs.remove_file("/missing"), Err(FsError::FileNotFound));
+    assert_eq!(fs.remove_dir("/"), Err(FsError::InvalidOperation));
+
+    fs.remove_file("/copy.bin").unwrap();
+    assert_eq!(fs.used(), 13 + 3000);
+
+    // 删除目录时一并删除其中的内容
+    fs.remove_dir("/docs").unwrap();
+    assert_eq!(names(&fs, "/"), ["hello.txt"]);
+    assert_eq!(fs.used(), 13);
+}
+
+#[test]
+fn test_move() {
+    let fs = sample();
+
+    fs.move_file("/hello.txt", "/docs/empty/hi.txt").unwrap();
+    assert_eq!(fs.exists("/hello.txt"), Ok(false));
+    assert_eq!(read_to_end(&fs, "/docs/empty/hi.txt"), b"Hello, tmpfs!");
+
+    // 覆盖已有文件
+    fs.move_file("/docs/empty/hi.txt", "/docs/big.bin").unwrap();
+    assert_eq!(read_to_end(&fs, "/docs/big.bin"), b"Hello, tmpfs!");
+    assert_eq!(fs.used(), 13);
+    fs.move_file("/docs/big.bin", "/docs/big.bin").unwrap();
+
+    assert_eq!(fs.move_file("/docs", "/d"), Err(FsError::NotAFile));
+    assert_eq!(
+        fs.move_dir("/docs/big.bin", "/d"),
+        Err(FsError::NotADirectory)
+    );
+    assert_eq!(
+        fs.move_dir("/docs", "/docs/empty/docs"),
+        Err(FsError::InvalidOperation)
+    );
+    assert_eq!(
+        fs.move_dir("/docs", "/docs/x"),
+        Err(FsError::InvalidOperation)
+    );
+    assert_eq!(
+        fs.move_dir("/docs/empty", "/docs"),
+        Err(FsError::AlreadyExists)
+    );
+    assert_eq!(
+        fs.move_file("/docs/big.bin", "/docs/empty"),
+        Err(FsError::AlreadyExists)
+    );
+    assert_eq!(
+        fs.move_file("/docs/big.bin", "/missing/x"),
+        Err(FsError::FileNotFound)
+    );
+
+    fs.move_dir("/docs", "/documents").unwrap();
+    fs.move_dir("/documents/empty", "/empty").unwrap();
+    assert_eq!(names(&fs, "/"), ["documents", "empty"]);
+    assert_eq!(names(&fs, "/documents"), ["big.bin"]);
+}
+
+#[test]
+fn test_open_handles() {
+    let fs = sample();
+
+    // 删除或移动后，已打开的句柄仍能读写原来的内容
+    let mut file = fs.open_file("/docs/big.bin").unwrap();
+    fs.move_dir("/docs", "/moved").unwrap();
+    file.write_all(b"updated").unwrap();
+    assert_eq!(read_to_end(&fs, "/moved/big.bin")[..7], *b"updated");
+
+    fs.remove_dir("/moved").unwrap();
+    assert_eq!(fs.used(), 13 + 3000);
+    let mut buf = Vec::new();
+    file.seek(SeekFrom::Start(0)).unwrap();
+    file.read_all(&mut buf).unwrap();
+    assert_eq!(buf.len(), 3000);
+    drop(file);
+    assert_eq!(fs.used(), 13);
+
+    // 另一个句柄截断文件后，越过末尾的写入补零
+    let mut a = fs.open_file("/hello.txt").unwrap();
+    a.seek(SeekFrom::Start(5)).unwrap();
+    let mut b = fs.create_file("/hello.txt").unwrap();
+    a.write_all(b"!").unwrap();
+    b.write_all(b"Hi").unwrap();
+    assert_eq!(read_to_end(&fs, "/hello.txt"), b"Hi\0\0\0!");
+}
+
+#[test]
+fn test_limit() {
+    let fs = TmpFs::with_limit(1000);
+    assert_eq!(fs.limit(), 1000);
+
+    // 超出限制时只写入能放下的部分
+    let mut file = fs.create_file("/a").unwrap();
+    assert_eq!(file.write(&pattern(600)), Ok(600));
+    let mut other = fs.create_file("/b").unwrap();
+    assert_eq!(other.write(&pattern(600)), Ok(400));
+    assert_eq!(other.write(b"x"), Err(FsError::WriteZero));
+    assert_eq!(file.set_len(601), Err(FsError::WriteZero));
+    assert_eq!(fs.used(), 1000);
+
+    // 覆盖已有内容不占用新的空间
+    file.seek(SeekFrom::Start(0)).unwrap();
+    assert_eq!(file.write(b"overwrite"), Ok(9));
+
+    drop(other);
+    fs.remove_file("/b").unwrap();
+    assert_eq!(fs.used(), 600);
+    file.set_len(1000).unwrap();
+    assert_eq!(fs.used(), 1000);
+    assert_eq!(fs.copy_file("/a", "/c").unwrap_err(), FsError::WriteZero);
+}
+
+#[test]
+fn test_timestamps() {
+    let fs = TmpFs::new();
+    write_file(&fs, "/file", b"data");
+    fs.create_dir("/dir").unwrap();
+
+    let meta = fs.metadata("/file").unwrap();
+    assert!(meta.created.is_some() && meta.modified.is_some() && meta.accessed.is_some());
+    let meta = fs.metadata("/dir").unwrap();
+    assert!(meta.created.is_some() && meta.modified.is_some());
+    assert!(format!("{:?}", fs).starts_with("TmpFs"));
+}
@@ -0,0 +1,126 @@
+//! GPT Partition Entry
+//!
+//! reference: <https://uefi.org/specs/UEFI/2.10/05_GUID_Partition_Table_Format.html#gpt-partition-entry-array>
+
+use super::*;
+
+/// A GUID as stored on disk, the first three fields are little endian
+#[derive(Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub struct Guid([u8; 16]);
+
+impl Guid {
+    /// Type of unused partition entries
+    pub const UNUSED: Guid = Guid([0; 16]);
+
+    /// `C12A7328-F81F-11D2-BA4B-00A0C93EC93B`
+    pub const EFI_SYSTEM: Guid = Guid(hex_literal::hex!("28732AC1 1FF8 D211 BA4B 00A0C93EC93B"));
+
+    /// `EBD0A0A2-B9E5-4433-87C0-68B6B72699C7`, used for FAT partitions
+    pub const BASIC_DATA: Guid = Guid(hex_literal::hex!("A2A0D0EB E5B9 3344 87C0 68B6B72699C7"));
+
+    /// `0FC63DAF-8483-4772-8E79-3D69D8477DE4`
+    pub const LINUX_FILESYSTEM: Guid =
+        Guid(hex_literal::hex!("AF3DC60F 8384 7247 8E79 3D69D8477DE4"));
+
+    pub const fn from_bytes(bytes: [u8; 16]) -> Self {
+        Self(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        &self.0
+    }
+}
+
+impl core::fmt::Display for Guid {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let b = &self.0;
+        write!(
+            f,
+            "{:08X}-{:04X}-{:04X}-",
+            u32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+            u16::from_le_bytes([b[4], b[5]]),
+            u16::from_le_bytes([b[6], b[7]]),
+        )?;
+        for (i, byte) in b[8..].iter().enumerate() {
+            if i == 2 {
+                write!(f, "-")?;
+            }
+            write!(f, "{:02X}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl core::fmt::Debug for Guid {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+/// An entry of the partition entry array
+///
+/// entries may be larger than 128 bytes, the rest is reserved
+#[derive(Clone, Copy)]
+pub struct GptPartition {
+    data: [u8; 128],
+}
+
+impl GptPartition {
+    pub const LEN: usize = 128;
+
+    /// Parse a partition entry from the given data.
+    pub fn parse(data: &[u8; Self::LEN]) -> GptPartition {
+        GptPartition {
+            data: data.to_owned(),
+        }
+    }
+
+    define_field!([u8; 16], 0x00, type_guid_bytes);
+    define_field!([u8; 16], 0x10, unique_guid_bytes);
+    define_field!(u64, 0x20, first_lba);
+    define_field!(u64, 0x28, last_lba);
+    define_field!(u64, 0x30, attributes);
+
+    pub fn type_guid(&self) -> Guid {
+        Guid(*self.type_guid_bytes())
+    }
+
+    pub fn unique_guid(&self) -> Guid {
+        Guid(*self.unique_guid_bytes())
+    }
+
+    /// The name of the partition, stored as up to 36 UTF-16LE code units
+    pub fn name(&self) -> String {
+        let units = self.data[0x38..]
+            .as_chunks::<2>()
+            .0
+            .iter()
+            .map(|&unit| u16::from_le_bytes(unit))
+            .take_while(|&unit| unit != 0);
+        char::decode_utf16(units)
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect()
+    }
+
+    pub fn is_used(&self) -> bool {
+        self.type_guid() != Guid::UNUSED
+    }
+
+    /// Number of blocks in the partition, both ends are inclusive
+    pub fn total_lba(&self) -> u64 {
+        (self.last_lba() + 1).saturating_sub(self.first_lba())
+    }
+}
+
+impl core::fmt::Debug for GptPartition {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("GPT Partition Entry")
+            .field("Name", &self.name())
+            .field("Type GUID", &self.type_guid())
+            .field("Unique GUID", &self.unique_guid())
+            .field("First LBA", &format!("0x{:016x}", self.first_lba()))
+            .field("Last LBA", &format!("0x{:016x}", self.last_lba()))
+            .field("Attributes", &format!("0x{:016x}", self.attributes()))
+            .finish()
+    }
+}
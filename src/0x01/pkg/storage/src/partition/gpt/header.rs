@@ -0,0 +1,83 @@
+//! GPT Header
+//!
+//! reference: <https://uefi.org/specs/UEFI/2.10/05_GUID_Partition_Table_Format.html#gpt-header>
+
+use super::*;
+
+const SIGNATURE: &[u8; 8] = b"EFI PART";
+
+/// The GPT header, stored in the second block of the disk and
+/// backed up in the last block
+///
+/// only the fields defined by the spec are kept, the rest of the block is reserved
+#[derive(Clone, Copy)]
+pub struct GptHeader {
+    data: [u8; 92],
+}
+
+impl GptHeader {
+    /// Size of the fields defined by the spec
+    pub const LEN: usize = 92;
+
+    /// Parse the header from the block at `lba`, checking its signature, size, location and CRC32
+    pub fn parse(block: &[u8], lba: u64) -> FsResult<GptHeader> {
+        let header = GptHeader {
+            data: block[..Self::LEN].try_into().unwrap(),
+        };
+
+        let size = header.header_size() as usize;
+        if header.signature() != SIGNATURE
+            || size < Self::LEN
+            || size > block.len()
+            || header.current_lba() != lba
+        {
+            return Err(FsError::InvalidOperation);
+        }
+
+        // 计算 CRC32 时，校验和字段按 0 处理
+        let mut data = block[..size].to_vec();
+        data[0x10..0x14].fill(0);
+        if crc32(&data) != header.header_crc32() {
+            return Err(FsError::InvalidOperation);
+        }
+
+        Ok(header)
+    }
+
+    define_field!([u8; 8], 0x00, signature);
+    define_field!(u32, 0x08, revision);
+    define_field!(u32, 0x0C, header_size);
+    define_field!(u32, 0x10, header_crc32);
+    define_field!(u64, 0x18, current_lba);
+    define_field!(u64, 0x20, backup_lba);
+    define_field!(u64, 0x28, first_usable_lba);
+    define_field!(u64, 0x30, last_usable_lba);
+    define_field!([u8; 16], 0x38, disk_guid_bytes);
+    define_field!(u64, 0x48, entries_lba);
+    define_field!(u32, 0x50, entry_count);
+    define_field!(u32, 0x54, entry_size);
+    define_field!(u32, 0x58, entries_crc32);
+
+    pub fn disk_guid(&self) -> Guid {
+        Guid::from_bytes(*self.disk_guid_bytes())
+    }
+}
+
+impl core::fmt::Debug for GptHeader {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("GPT Header")
+            .field("Revision", &format!("0x{:08x}", self.revision()))
+            .field("Header Size", &self.header_size())
+            .field("Header CRC32", &format!("0x{:08x}", self.header_crc32()))
+            .field("Current LBA", &self.current_lba())
+            .field("Backup LBA", &self.backup_lba())
+            .field("First Usable LBA", &self.first_usable_lba())
+            .field("Last Usable LBA", &self.last_usable_lba())
+            .field("Disk GUID", &self.disk_guid())
+            .field("Entries LBA", &self.entries_lba())
+            .field("Entry Count", &self.entry_count())
+            .field("Entry Size", &self.entry_size())
+            .field("Entries CRC32", &format!("0x{:08x}", self.entries_crc32()))
+            .finish()
+    }
+}
@@ -0,0 +1,192 @@
+//! GptTable
+
+mod entry;
+mod header;
+
+#[cfg(test)]
+mod tests;
+
+use core::marker::PhantomData;
+
+use super::mbr::MbrPartition;
+use crate::*;
+pub use entry::*;
+pub use header::*;
+
+/// Type of the MBR partition covering a GPT disk
+const PROTECTIVE_TYPE: u8 = 0xEE;
+
+/// Upper bound of the partition entry array, 128 entries take 16 KiB
+const MAX_ENTRIES_SIZE: usize = 1024 * 1024;
+
+/// The GUID Partition Table
+///
+/// The first block is a protective MBR, so that tools knowing only MBR
+/// see the disk as occupied. The header follows in the second block and
+/// points to the partition entry array, both are backed up at the end of the disk.
+///
+/// [ MBR ] [ Header ] [ Entries ] [ Partitions ... ] [ Entries ] [ Header ]
+pub struct GptTable<T, B>
+where
+    T: BlockDevice<B> + Clone,
+    B: BlockTrait,
+{
+    inner: T,
+    header: GptHeader,
+    entries: Vec<GptPartition>,
+    _block: PhantomData<B>,
+}
+
+impl<T, B> GptTable<T, B>
+where
+    T: BlockDevice<B> + Clone,
+    B: BlockTrait,
+{
+    /// The header in use, the backup one if the primary one is corrupted
+    pub fn header(&self) -> &GptHeader {
+        &self.header
+    }
+
+    /// The used entries of the partition entry array
+    pub fn entries(&self) -> &[GptPartition] {
+        &self.entries
+    }
+
+    /// Read and validate the header at `lba` and the entry array it points to
+    fn read_table(inner: &T, lba: u64) -> FsResult<(GptHeader, Vec<GptPartition>)> {
+        let mut block = B::default();
+        inner.read_block(lba as usize, &mut block)?;
+        let header = GptHeader::parse(block.as_ref(), lba)?;
+
+        let count = inner.block_count()? as u64;
+        let entry_size = header.entry_size() as usize;
+        let size = (header.entry_count() as usize)
+            .checked_mul(entry_size)
+            .filter(|&size| size <= MAX_ENTRIES_SIZE)
+            .ok_or(FsError::InvalidOperation)?;
+        if entry_size < GptPartition::LEN
+            || !entry_size.is_multiple_of(GptPartition::LEN)
+            || header.first_usable_lba() > header.last_usable_lba()
+            || header.last_usable_lba() >= count
+        {
+            return Err(FsError::InvalidOperation);
+        }
+
+        let mut data = Vec::with_capacity(size.div_ceil(B::size()) * B::size());
+        for i in 0..size.div_ceil(B::size()) {
+            inner.read_block(header.entries_lba() as usize + i, &mut block)?;
+            data.extend_from_slice(block.as_ref());
+        }
+        if crc32(&data[..size]) != header.entries_crc32() {
+            return Err(FsError::InvalidOperation);
+        }
+
+        let mut entries = Vec::new();
+        for (i, chunk) in data[..size].chunks_exact(entry_size).enumerate() {
+            let entry = GptPartition::parse(chunk[..GptPartition::LEN].try_into().unwrap());
+            if !entry.is_used() {
+                continue;
+            }
+
+            // 分区必须位于可用区域内
+            if entry.first_lba() > entry.last_lba()
+                || entry.first_lba() < header.first_usable_lba()
+                || entry.last_lba() > header.last_usable_lba()
+            {
+                return Err(FsError::InvalidOperation);
+            }
+
+            trace!("Partition {}: {:#?}", i, entry);
+            entries.push(entry);
+        }
+
+        Ok((header, entries))
+    }
+}
+
+impl<T, B> PartitionTable<T, B> for GptTable<T, B>
+where
+    T: BlockDevice<B> + Clone,
+    B: BlockTrait,
+{
+    fn parse(inner: T) -> FsResult<Self> {
+        let mut block = B::default();
+        inner.read_block(0, &mut block)?;
+        if !is_protective_mbr(block.as_ref()) {
+            return Err(FsError::InvalidOperation);
+        }
+
+        let backup_lba = inner.block_count()?.saturating_sub(1) as u64;
+        if backup_lba < 2 {
+            return Err(FsError::InvalidOperation);
+        }
+
+        // 主 GPT 损坏时使用磁盘末尾的备份
+        let (header, entries) = Self::read_table(&inner, 1).or_else(|err| {
+            warn!("Primary GPT is corrupted ({:?}), using the backup.", err);
+            Self::read_table(&inner, backup_lba)
+        })?;
+        trace!("{:#?}", header);
+
+        Ok(Self {
+            inner,
+            header,
+            entries,
+            _block: PhantomData,
+        })
+    }
+
+    fn partitions(&self) -> FsResult<Vec<Partition<T, B>>> {
+        Ok(self
+            .entries
+            .iter()
+            .map(|entry| {
+                Partition::new(
+                    self.inner.clone(),
+                    entry.first_lba() as usize,
+                    entry.total_lba() as usize,
+                )
+            })
+            .collect())
+    }
+}
+
+/// Whether `block`, the first block of a disk, is the protective MBR of a GPT
+pub fn is_protective_mbr(block: &[u8]) -> bool {
+    if block[0x1FE..0x200] != [0x55, 0xAA] {
+        return false;
+    }
+
+    (0..4).any(|i| {
+        let offset = 0x1BE + i * 16;
+        let part = MbrPartition::parse(block[offset..offset + 16].try_into().unwrap());
+        part.partition_type() == PROTECTIVE_TYPE
+    })
+}
+
+/// CRC32 used by GPT, i.e. the IEEE 802.3 one
+pub fn crc32(data: &[u8]) -> u32 {
+    const TABLE: [u32; 256] = {
+        let mut table = [0; 256];
+        let mut i = 0;
+        while i < 256 {
+            let mut crc = i as u32;
+            let mut bit = 0;
+            while bit < 8 {
+                crc = if crc & 1 != 0 {
+                    (crc >> 1) ^ 0xEDB8_8320
+                } else {
+                    crc >> 1
+                };
+                bit += 1;
+            }
+            table[i] = crc;
+            i += 1;
+        }
+        table
+    };
+
+    !data.iter().fold(!0, |crc, &byte| {
+        TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
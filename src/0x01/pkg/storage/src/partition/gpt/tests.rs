@@ -0,0 +1,208 @@
+//! Tests of the GPT on images generated in memory
+
+use super::*;
+
+const BLOCK_SIZE: usize = 512;
+const BLOCKS: usize = 64;
+const ENTRY_COUNT: usize = 16;
+const ENTRY_BLOCKS: usize = ENTRY_COUNT * GptPartition::LEN / BLOCK_SIZE;
+const BACKUP_LBA: usize = BLOCKS - 1;
+const BACKUP_ENTRIES_LBA: usize = BACKUP_LBA - ENTRY_BLOCKS;
+const FIRST_USABLE: usize = 2 + ENTRY_BLOCKS;
+const LAST_USABLE: usize = BACKUP_ENTRIES_LBA - 1;
+
+const DISK_GUID: Guid = Guid::from_bytes([0x11; 16]);
+
+/// A minimal GPT partitioner, both copies of the table are written
+struct ImageBuilder {
+    entries: Vec<[u8; GptPartition::LEN]>,
+}
+
+impl ImageBuilder {
+    fn new() -> Self {
+        Self {
+            entries: vec![[0; GptPartition::LEN]; ENTRY_COUNT],
+        }
+    }
+
+    fn add(&mut self, type_guid: Guid, first: usize, last: usize, name: &str) -> &mut Self {
+        let index = self
+            .entries
+            .iter()
+            .position(|e| e[..16] == [0; 16])
+            .unwrap();
+        let entry = &mut self.entries[index];
+        entry[0x00..0x10].copy_from_slice(type_guid.as_bytes());
+        entry[0x10..0x20].fill(index as u8 + 1);
+        entry[0x20..0x28].copy_from_slice(&(first as u64).to_le_bytes());
+        entry[0x28..0x30].copy_from_slice(&(last as u64).to_le_bytes());
+        for (i, unit) in name.encode_utf16().enumerate() {
+            entry[0x38 + i * 2..0x3A + i * 2].copy_from_slice(&unit.to_le_bytes());
+        }
+        self
+    }
+
+    fn header(&self, current: usize, backup: usize, entries_lba: usize) -> [u8; BLOCK_SIZE] {
+        let mut block = [0; BLOCK_SIZE];
+        block[0x00..0x08].copy_from_slice(b"EFI PART");
+        block[0x08..0x0C].copy_from_slice(&0x0001_0000u32.to_le_bytes());
+        block[0x0C..0x10].copy_from_slice(&(GptHeader::LEN as u32).to_le_bytes());
+        block[0x18..0x20].copy_from_slice(&(current as u64).to_le_bytes());
+        block[0x20..0x28].copy_from_slice(&(backup as u64).to_le_bytes());
+        block[0x28..0x30].copy_from_slice(&(FIRST_USABLE as u64).to_le_bytes());
+        block[0x30..0x38].copy_from_slice(&(LAST_USABLE as u64).to_le_bytes());
+        block[0x38..0x48].copy_from_slice(DISK_GUID.as_bytes());
+        block[0x48..0x50].copy_from_slice(&(entries_lba as u64).to_le_bytes());
+        block[0x50..0x54].copy_from_slice(&(ENTRY_COUNT as u32).to_le_bytes());
+        block[0x54..0x58].copy_from_slice(&(GptPartition::LEN as u32).to_le_bytes());
+        block[0x58..0x5C].copy_from_slice(&crc32(&self.entries.concat()).to_le_bytes());
+        let crc = crc32(&block[..GptHeader::LEN]);
+        block[0x10..0x14].copy_from_slice(&crc.to_le_bytes());
+        block
+    }
+
+    fn build(&self) -> RamDisk {
+        let mut image = vec![0; BLOCKS * BLOCK_SIZE];
+
+        // 保护性 MBR 覆盖整个磁盘
+        let mbr = &mut image[..BLOCK_SIZE];
+        mbr[0x1BE + 4] = PROTECTIVE_TYPE;
+        mbr[0x1BE + 8..0x1BE + 12].copy_from_slice(&1u32.to_le_bytes());
+        mbr[0x1BE + 12..0x1BE + 16].copy_from_slice(&(BLOCKS as u32 - 1).to_le_bytes());
+        mbr[0x1FE..0x200].copy_from_slice(&[0x55, 0xAA]);
+
+        let entries = self.entries.concat();
+        let mut put = |lba: usize, data: &[u8]| {
+            image[lba * BLOCK_SIZE..lba * BLOCK_SIZE + data.len()].copy_from_slice(data);
+        };
+        put(1, &self.header(1, BACKUP_LBA, 2));
+        put(2, &entries);
+        put(BACKUP_ENTRIES_LBA, &entries);
+        put(BACKUP_LBA, &self.header(BACKUP_LBA, 1, BACKUP_ENTRIES_LBA));
+
+        RamDisk::from_bytes(&image)
+    }
+}
+
+fn sample() -> RamDisk {
+    ImageBuilder::new()
+        .add(Guid::EFI_SYSTEM, FIRST_USABLE, 15, "EFI system partition")
+        .add(Guid::BASIC_DATA, 16, LAST_USABLE, "数据")
+        .build()
+}
+
+#[test]
+fn test_crc32() {
+    assert_eq!(crc32(b""), 0);
+    assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+}
+
+#[test]
+fn test_guid() {
+    assert_eq!(
+        Guid::EFI_SYSTEM.to_string(),
+        "C12A7328-F81F-11D2-BA4B-00A0C93EC93B"
+    );
+    assert_eq!(
+        Guid::BASIC_DATA.to_string(),
+        "EBD0A0A2-B9E5-4433-87C0-68B6B72699C7"
+    );
+    assert_eq!(
+        Guid::LINUX_FILESYSTEM.to_string(),
+        "0FC63DAF-8483-4772-8E79-3D69D8477DE4"
+    );
+}
+
+#[test]
+fn test_parse() {
+    let disk = sample();
+    let table = GptTable::parse(disk.clone()).unwrap();
+
+    let header = table.header();
+    assert_eq!(header.current_lba(), 1);
+    assert_eq!(header.backup_lba(), BACKUP_LBA as u64);
+    assert_eq!(header.disk_guid(), DISK_GUID);
+    assert_eq!(header.entry_count(), ENTRY_COUNT as u32);
+
+    let entries = table.entries();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].name(), "EFI system partition");
+    assert_eq!(entries[0].type_guid(), Guid::EFI_SYSTEM);
+    assert_eq!(entries[0].unique_guid(), Guid::from_bytes([1; 16]));
+    assert_eq!(entries[1].name(), "数据");
+    assert_eq!(entries[1].type_guid(), Guid::BASIC_DATA);
+
+    let parts = table.partitions().unwrap();
+    assert_eq!(parts[0].block_count(), Ok(16 - FIRST_USABLE));
+    assert_eq!(parts[1].block_count(), Ok(LAST_USABLE - 15));
+
+    // 分区内的块号从分区起始处计算
+    disk.blocks()[16].as_mut()[..4].copy_from_slice(b"data");
+    let mut block = Block512::default();
+    parts[1].read_block(0, &mut block).unwrap();
+    assert_eq!(&block.as_ref()[..4], b"data");
+    assert_eq!(
+        parts[0].read_block(16 - FIRST_USABLE, &mut block),
+        Err(FsError::InvalidOffset)
+    );
+}
+
+#[test]
+fn test_backup() {
+    // 主 GPT 头损坏
+    let disk = sample();
+    disk.blocks()[1].as_mut()[0x28] ^= 0xFF;
+    let table = GptTable::parse(disk.clone()).unwrap();
+    assert_eq!(table.header().current_lba(), BACKUP_LBA as u64);
+    assert_eq!(table.partitions().unwrap().len(), 2);
+
+    // 主分区表项损坏
+    let disk = sample();
+    disk.blocks()[2].as_mut()[0x38] = b'X';
+    let table = GptTable::parse(disk.clone()).unwrap();
+    assert_eq!(table.header().current_lba(), BACKUP_LBA as u64);
+    assert_eq!(table.entries()[0].name(), "EFI system partition");
+
+    // 两份都损坏
+    disk.blocks()[BACKUP_LBA].as_mut()[0] = 0;
+    assert!(GptTable::parse(disk).is_err());
+}
+
+#[test]
+fn test_invalid_entry() {
+    let disk = ImageBuilder::new()
+        .add(Guid::BASIC_DATA, FIRST_USABLE, BACKUP_LBA, "too large")
+        .build();
+    assert_eq!(GptTable::parse(disk).err(), Some(FsError::InvalidOperation));
+
+    let disk = ImageBuilder::new()
+        .add(Guid::BASIC_DATA, 20, 10, "reversed")
+        .build();
+    assert_eq!(GptTable::parse(disk).err(), Some(FsError::InvalidOperation));
+}
+
+#[test]
+fn test_probe() {
+    let parts = probe(sample()).unwrap();
+    assert_eq!(parts.len(), 2);
+    assert_eq!(parts[0].block_count(), Ok(16 - FIRST_USABLE));
+
+    // 没有保护性 MBR 时按 MBR 解析
+    let disk = RamDisk::new(BLOCKS);
+    {
+        let mut blocks = disk.blocks();
+        let mbr = blocks[0].as_mut();
+        mbr[0x1BE] = 0x80;
+        mbr[0x1BE + 4] = 0x0B;
+        mbr[0x1BE + 8..0x1BE + 12].copy_from_slice(&8u32.to_le_bytes());
+        mbr[0x1BE + 12..0x1BE + 16].copy_from_slice(&32u32.to_le_bytes());
+        mbr[0x1FE..0x200].copy_from_slice(&[0x55, 0xAA]);
+    }
+    assert_eq!(
+        GptTable::parse(disk.clone()).err(),
+        Some(FsError::InvalidOperation)
+    );
+    let parts = probe(disk).unwrap();
+    assert_eq!(parts.len(), 1);
+    assert_eq!(parts[0].block_count(), Ok(32));
+}
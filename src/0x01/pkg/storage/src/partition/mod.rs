@@ -0,0 +1,107 @@
+use core::marker::PhantomData;
+
+use crate::*;
+
+pub mod gpt;
+pub mod mbr;
+
+use gpt::{GptTable, is_protective_mbr};
+use mbr::MbrTable;
+
+/// Partition table trait
+pub trait PartitionTable<T, B>
+where
+    T: BlockDevice<B>,
+    B: BlockTrait,
+    Self: Sized,
+{
+    /// Parse the partition table
+    fn parse(inner: T) -> FsResult<Self>;
+
+    /// Returns the partitions
+    fn partitions(&self) -> FsResult<Vec<Partition<T, B>>>;
+}
+
+/// The partitions of `inner`, read from the GPT if the disk has
+/// a protective MBR, or from the MBR otherwise
+pub fn probe<T, B>(inner: T) -> FsResult<Vec<Partition<T, B>>>
+where
+    T: BlockDevice<B> + Clone,
+    B: BlockTrait,
+{
+    let mut block = B::default();
+    inner.read_block(0, &mut block)?;
+
+    if is_protective_mbr(block.as_ref()) {
+        GptTable::parse(inner)?.partitions()
+    } else {
+        MbrTable::parse(inner)?.partitions()
+    }
+}
+
+/// Identifies a partition on the disk.
+#[derive(Clone, Copy)]
+pub struct Partition<T, B>
+where
+    T: BlockDevice<B>,
+    B: BlockTrait,
+{
+    inner: T,
+    offset: usize,
+    size: usize,
+    _block: PhantomData<B>,
+}
+
+impl<T, B> Partition<T, B>
+where
+    T: BlockDevice<B>,
+    B: BlockTrait,
+{
+    pub fn new(inner: T, offset: usize, size: usize) -> Self {
+        Self {
+            inner,
+            offset,
+            size,
+            _block: PhantomData,
+        }
+    }
+}
+
+impl<T, B> core::fmt::Debug for Partition<T, B>
+where
+    T: BlockDevice<B>,
+    B: BlockTrait,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Partition")
+            .field("offset", &self.offset)
+            .field("size", &self.size)
+            .finish()
+    }
+}
+
+impl<T, B> BlockDevice<B> for Partition<T, B>
+where
+    T: BlockDevice<B>,
+    B: BlockTrait,
+{
+    fn block_count(&self) -> FsResult<usize> {
+        Ok(self.size)
+    }
+
+    fn read_block(&self, offset: usize, block: &mut B) -> FsResult {
+        if offset >= self.size {
+            return Err(FsError::InvalidOffset);
+        }
+
+        self.inner.read_block(self.offset + offset, block)
+    }
+
+    fn write_block(&self, offset: usize, block: &B) -> FsResult {
+        if offset >= self.size {
+            return Err(FsError::InvalidOffset);
+        }
+
+        self.inner.write_block(self.offset + offset, block)
+    }
+}
@@ -0,0 +1,135 @@
+use num_enum::FromPrimitive;
+
+/// Error numbers shared by the kernel and the user library.
+///
+/// A syscall reports an error by returning `-(errno as isize)` in `rax`,
+/// the values follow the Linux numbering.
+#[repr(isize)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, FromPrimitive)]
+pub enum Errno {
+    /// Operation not permitted
+    EPERM = 1,
+    /// No such file or directory
+    ENOENT = 2,
+    /// No such process
+    ESRCH = 3,
+    /// Input/output error
+    EIO = 5,
+    /// Argument list too long, e.g. a message larger than the buffer
+    E2BIG = 7,
+    /// Bad file descriptor
+    EBADF = 9,
+    /// No child processes
+    ECHILD = 10,
+    /// Resource temporarily unavailable
+    EAGAIN = 11,
+    /// Cannot allocate memory
+    ENOMEM = 12,
+    /// Bad address
+    EFAULT = 14,
+    /// Device or resource busy, e.g. a mount point in use
+    EBUSY = 16,
+    /// File exists
+    EEXIST = 17,
+    /// Invalid cross-device link, e.g. renaming across mounts
+    EXDEV = 18,
+    /// Not a directory
+    ENOTDIR = 20,
+    /// Is a directory
+    EISDIR = 21,
+    /// Invalid argument
+    EINVAL = 22,
+    /// Too many open files
+    EMFILE = 24,
+    /// Inappropriate ioctl for device
+    ENOTTY = 25,
+    /// No space left on device
+    ENOSPC = 28,
+    /// Illegal seek, e.g. on a pipe
+    ESPIPE = 29,
+    /// Read-only file system
+    EROFS = 30,
+    /// Broken pipe
+    EPIPE = 32,
+    /// Numerical result out of range, e.g. a buffer too small for the result
+    ERANGE = 34,
+    /// Resource deadlock avoided
+    EDEADLK = 35,
+    /// Function not implemented
+    ENOSYS = 38,
+    /// No message of desired type
+    ENOMSG = 42,
+    /// Identifier removed
+    EIDRM = 43,
+    /// Timed out
+    ETIMEDOUT = 110,
+
+    #[num_enum(default)]
+    EUNKNOWN = 4095,
+}
+
+pub type SyscallResult<T = usize> = Result<T, Errno>;
+
+impl Errno {
+    /// Encode a syscall result into the value returned in `rax`
+    #[inline]
+    pub fn encode(res: SyscallResult) -> usize {
+        match res {
+            Ok(ret) => ret,
+            Err(errno) => (-(errno as isize)) as usize,
+        }
+    }
+
+    /// Decode the value returned in `rax` into a syscall result
+    ///
+    /// only `[-4095, -1]` is treated as error, so that large values
+    /// like addresses will not be mistaken for errors
+    #[inline]
+    pub fn decode(ret: usize) -> SyscallResult {
+        match ret as isize {
+            -4095..=-1 => Err(Errno::from(-(ret as isize))),
+            _ => Ok(ret),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Errno::EPERM => "Operation not permitted",
+            Errno::ENOENT => "No such file or directory",
+            Errno::ESRCH => "No such process",
+            Errno::EIO => "Input/output error",
+            Errno::E2BIG => "Argument list too long",
+            Errno::EBADF => "Bad file descriptor",
+            Errno::ECHILD => "No child processes",
+            Errno::EAGAIN => "Resource temporarily unavailable",
+            Errno::ENOMEM => "Cannot allocate memory",
+            Errno::EFAULT => "Bad address",
+            Errno::EBUSY => "Device or resource busy",
+            Errno::EEXIST => "File exists",
+            Errno::EXDEV => "Invalid cross-device link",
+            Errno::ENOTDIR => "Not a directory",
+            Errno::EISDIR => "Is a directory",
+            Errno::EINVAL => "Invalid argument",
+            Errno::EMFILE => "Too many open files",
+            Errno::ENOTTY => "Inappropriate ioctl for device",
+            Errno::ENOSPC => "No space left on device",
+            Errno::ESPIPE => "Illegal seek",
+            Errno::EROFS => "Read-only file system",
+            Errno::EPIPE => "Broken pipe",
+            Errno::ERANGE => "Numerical result out of range",
+            Errno::EDEADLK => "Resource deadlock avoided",
+            Errno::ENOSYS => "Function not implemented",
+            Errno::ENOMSG => "No message of desired type",
+            Errno::EIDRM => "Identifier removed",
+            Errno::ETIMEDOUT => "Connection timed out",
+            Errno::EUNKNOWN => "Unknown error",
+        }
+    }
+}
+
+impl core::fmt::Display for Errno {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{} ({:?})", self.as_str(), self)
+    }
+}
@@ -0,0 +1,26 @@
+use num_enum::FromPrimitive;
+
+/// Standard input
+pub const STDIN: usize = 0;
+/// Standard output
+pub const STDOUT: usize = 1;
+/// Standard error
+pub const STDERR: usize = 2;
+
+/// Close the fd when spawning a new process, see `FcntlCmd::SetFd`
+pub const FD_CLOEXEC: usize = 1;
+
+/// Commands of the `Fcntl` syscall
+#[repr(usize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, FromPrimitive)]
+pub enum FcntlCmd {
+    /// Duplicate the fd to the lowest free fd not less than `arg`
+    DupFd = 0,
+    /// Get the fd flags, e.g. `FD_CLOEXEC`
+    GetFd = 1,
+    /// Set the fd flags to `arg`
+    SetFd = 2,
+
+    #[num_enum(default)]
+    Unknown = 65535,
+}
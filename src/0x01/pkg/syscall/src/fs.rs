@@ -0,0 +1,138 @@
+use bitflags::bitflags;
+use num_enum::FromPrimitive;
+
+/// Max length of a file name in `DirEntry`, in bytes
+pub const NAME_MAX: usize = 255;
+
+/// Value of the times in `FileStat` not recorded by the filesystem
+pub const TIME_UNKNOWN: i64 = i64::MIN;
+
+bitflags! {
+    /// Flags of the `Open` syscall
+    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+    pub struct OpenFlags: usize {
+        /// Open for reading
+        const READ = 1 << 0;
+        /// Open for writing
+        const WRITE = 1 << 1;
+        /// Create the file if it does not exist
+        const CREATE = 1 << 2;
+        /// With `CREATE`, fail with `EEXIST` if the file exists
+        const EXCL = 1 << 3;
+        /// Truncate the file to zero length
+        const TRUNCATE = 1 << 4;
+        /// Every write goes to the end of the file
+        const APPEND = 1 << 5;
+    }
+}
+
+/// Where the offset of the `Seek` syscall starts from
+#[repr(usize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, FromPrimitive)]
+pub enum SeekWhence {
+    /// The start of the file
+    Set = 0,
+    /// The current offset
+    Current = 1,
+    /// The end of the file
+    End = 2,
+
+    #[num_enum(default)]
+    Unknown = 65535,
+}
+
+/// Type of a file in `FileStat`
+#[repr(usize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, FromPrimitive)]
+pub enum FileKind {
+    File = 1,
+    Directory = 2,
+    Symlink = 3,
+    /// A character device, e.g. the console
+    CharDevice = 4,
+    /// One end of a pipe
+    Pipe = 5,
+    /// A block device, e.g. a disk
+    BlockDevice = 6,
+
+    #[num_enum(default)]
+    Unknown = 0,
+}
+
+/// File metadata written by the `StatFile` and `Fstat` syscalls
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FileStat {
+    /// The type of the file, see `FileKind`
+    pub kind: usize,
+    /// Length in bytes, 0 for directories
+    pub size: usize,
+    /// Milliseconds since the unix epoch, or `TIME_UNKNOWN`
+    pub created: i64,
+    /// Milliseconds since the unix epoch, or `TIME_UNKNOWN`
+    pub modified: i64,
+    /// Milliseconds since the unix epoch, or `TIME_UNKNOWN`
+    pub accessed: i64,
+}
+
+impl FileStat {
+    pub const fn new(kind: FileKind, size: usize) -> Self {
+        Self {
+            kind: kind as usize,
+            size,
+            created: TIME_UNKNOWN,
+            modified: TIME_UNKNOWN,
+            accessed: TIME_UNKNOWN,
+        }
+    }
+
+    pub fn kind(&self) -> FileKind {
+        FileKind::from(self.kind)
+    }
+}
+
+impl Default for FileStat {
+    fn default() -> Self {
+        Self::new(FileKind::Unknown, 0)
+    }
+}
+
+/// A directory entry written by the `ReadDir` syscall
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct DirEntry {
+    pub stat: FileStat,
+    /// Length of the name in bytes
+    pub name_len: usize,
+    /// The name in UTF-8, only the first `name_len` bytes are valid
+    pub name: [u8; NAME_MAX],
+}
+
+impl DirEntry {
+    /// An entry named `name`, truncated to `NAME_MAX` bytes at a char boundary
+    pub fn new(name: &str, stat: FileStat) -> Self {
+        let mut len = name.len().min(NAME_MAX);
+        while !name.is_char_boundary(len) {
+            len -= 1;
+        }
+
+        let mut entry = Self {
+            stat,
+            name_len: len,
+            name: [0; NAME_MAX],
+        };
+        entry.name[..len].copy_from_slice(&name.as_bytes()[..len]);
+        entry
+    }
+
+    pub fn name(&self) -> &str {
+        let len = self.name_len.min(NAME_MAX);
+        core::str::from_utf8(&self.name[..len]).unwrap_or_default()
+    }
+}
+
+impl Default for DirEntry {
+    fn default() -> Self {
+        Self::new("", FileStat::default())
+    }
+}
@@ -0,0 +1,14 @@
+use num_enum::FromPrimitive;
+
+/// Operations of the `Futex` syscall
+#[repr(usize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, FromPrimitive)]
+pub enum FutexOp {
+    /// Block while the futex word equals `val`
+    Wait = 0,
+    /// Wake up at most `val` processes waiting on the futex
+    Wake = 1,
+
+    #[num_enum(default)]
+    Unknown = 65535,
+}
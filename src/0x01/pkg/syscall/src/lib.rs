@@ -4,27 +4,48 @@ use num_enum::FromPrimitive;
 
 pub mod macros;
 
-#[repr(usize)]
-#[derive(Clone, Debug, FromPrimitive)]
-pub enum Syscall {
-    Read = 0,
-    Write = 1,
-
-    Brk = 12,
-    GetPid = 39,
-    Sem = 41,
-    Fork = 58,
-    Spawn = 59,
-    Exit = 60,
-    WaitPid = 61,
-
-    Time = 201,
-    
-    ListApp = 65531,
-    Stat = 65532,
-    Allocate = 65533,
-    Deallocate = 65534,
-
-    #[num_enum(default)]
-    Unknown = 65535,
+mod errno;
+pub use errno::*;
+
+mod fd;
+pub use fd::*;
+
+mod fs;
+pub use fs::*;
+
+mod futex;
+pub use futex::*;
+
+mod msg;
+pub use msg::*;
+
+mod sem;
+pub use sem::*;
+
+mod tty;
+pub use tty::*;
+
+#[macro_use]
+mod table;
+pub use table::SyscallArg;
+
+macro_rules! define_syscall_enum {
+    ($(
+        $(#[$meta:meta])*
+        $variant:ident = $nr:literal => $(@$kind:ident)? fn $name:ident ($($arg:ident : $ty:ty),*) -> $ret:ty;
+    )*) => {
+        #[repr(usize)]
+        #[derive(Clone, Debug, FromPrimitive)]
+        pub enum Syscall {
+            $(
+                $(#[$meta])*
+                $variant = $nr,
+            )*
+
+            #[num_enum(default)]
+            Unknown = 65535,
+        }
+    };
 }
+
+syscall_table!(define_syscall_enum);
@@ -1,9 +1,28 @@
 use crate::Syscall;
 use core::arch::asm;
 
+// 开启 `fast_syscall` 后使用 `syscall` 指令，否则使用 `int 0x80`
+// 两种方式都按 `syscall` 指令的约定声明 rcx、r11 被破坏
+#[cfg(feature = "fast_syscall")]
+macro_rules! syscall_insn {
+    () => {
+        "syscall"
+    };
+}
+
+#[cfg(not(feature = "fast_syscall"))]
+macro_rules! syscall_insn {
+    () => {
+        "int 0x80"
+    };
+}
+
+/// Syscall without arguments through `int 0x80`
+///
+/// available regardless of the `fast_syscall` feature, e.g. for benchmarks
 #[doc(hidden)]
 #[inline(always)]
-pub fn syscall0(n: Syscall) -> usize {
+pub fn int80_syscall0(n: Syscall) -> usize {
     let ret: usize;
     unsafe {
         asm!(
@@ -14,15 +33,44 @@ pub fn syscall0(n: Syscall) -> usize {
     ret
 }
 
+/// Syscall without arguments through the `syscall` instruction
+///
+/// available regardless of the `fast_syscall` feature, e.g. for benchmarks
+#[doc(hidden)]
+#[inline(always)]
+pub fn fast_syscall0(n: Syscall) -> usize {
+    let ret: usize;
+    unsafe {
+        asm!(
+            "syscall", in("rax") n as usize,
+            lateout("rax") ret, lateout("rcx") _, lateout("r11") _
+        );
+    }
+    ret
+}
+
+#[doc(hidden)]
+#[inline(always)]
+pub fn syscall0(n: Syscall) -> usize {
+    let ret: usize;
+    unsafe {
+        asm!(
+            syscall_insn!(), in("rax") n as usize,
+            lateout("rax") ret, lateout("rcx") _, lateout("r11") _
+        );
+    }
+    ret
+}
+
 #[doc(hidden)]
 #[inline(always)]
 pub fn syscall1(n: Syscall, arg0: usize) -> usize {
     let ret: usize;
     unsafe {
         asm!(
-            "int 0x80", in("rax") n as usize,
+            syscall_insn!(), in("rax") n as usize,
             in("rdi") arg0,
-            lateout("rax") ret
+            lateout("rax") ret, lateout("rcx") _, lateout("r11") _
         );
     }
     ret
@@ -34,9 +82,9 @@ pub fn syscall2(n: Syscall, arg0: usize, arg1: usize) -> usize {
     let ret: usize;
     unsafe {
         asm!(
-            "int 0x80", in("rax") n as usize,
+            syscall_insn!(), in("rax") n as usize,
             in("rdi") arg0, in("rsi") arg1,
-            lateout("rax") ret
+            lateout("rax") ret, lateout("rcx") _, lateout("r11") _
         );
     }
     ret
@@ -48,9 +96,67 @@ pub fn syscall3(n: Syscall, arg0: usize, arg1: usize, arg2: usize) -> usize {
     let ret: usize;
     unsafe {
         asm!(
-            "int 0x80", in("rax") n as usize,
+            syscall_insn!(), in("rax") n as usize,
             in("rdi") arg0, in("rsi") arg1, in("rdx") arg2,
-            lateout("rax") ret
+            lateout("rax") ret, lateout("rcx") _, lateout("r11") _
+        );
+    }
+    ret
+}
+
+#[doc(hidden)]
+#[inline(always)]
+pub fn syscall4(n: Syscall, arg0: usize, arg1: usize, arg2: usize, arg3: usize) -> usize {
+    let ret: usize;
+    unsafe {
+        asm!(
+            syscall_insn!(), in("rax") n as usize,
+            in("rdi") arg0, in("rsi") arg1, in("rdx") arg2, in("r10") arg3,
+            lateout("rax") ret, lateout("rcx") _, lateout("r11") _
+        );
+    }
+    ret
+}
+
+#[doc(hidden)]
+#[inline(always)]
+pub fn syscall5(
+    n: Syscall,
+    arg0: usize,
+    arg1: usize,
+    arg2: usize,
+    arg3: usize,
+    arg4: usize,
+) -> usize {
+    let ret: usize;
+    unsafe {
+        asm!(
+            syscall_insn!(), in("rax") n as usize,
+            in("rdi") arg0, in("rsi") arg1, in("rdx") arg2, in("r10") arg3, in("r8") arg4,
+            lateout("rax") ret, lateout("rcx") _, lateout("r11") _
+        );
+    }
+    ret
+}
+
+#[doc(hidden)]
+#[inline(always)]
+pub fn syscall6(
+    n: Syscall,
+    arg0: usize,
+    arg1: usize,
+    arg2: usize,
+    arg3: usize,
+    arg4: usize,
+    arg5: usize,
+) -> usize {
+    let ret: usize;
+    unsafe {
+        asm!(
+            syscall_insn!(), in("rax") n as usize,
+            in("rdi") arg0, in("rsi") arg1, in("rdx") arg2,
+            in("r10") arg3, in("r8") arg4, in("r9") arg5,
+            lateout("rax") ret, lateout("rcx") _, lateout("r11") _
         );
     }
     ret
@@ -70,4 +176,28 @@ macro_rules! syscall {
     ($n:expr, $a1:expr, $a2:expr, $a3:expr) => {
         $crate::macros::syscall3($n, $a1 as usize, $a2 as usize, $a3 as usize)
     };
+    ($n:expr, $a1:expr, $a2:expr, $a3:expr, $a4:expr) => {
+        $crate::macros::syscall4($n, $a1 as usize, $a2 as usize, $a3 as usize, $a4 as usize)
+    };
+    ($n:expr, $a1:expr, $a2:expr, $a3:expr, $a4:expr, $a5:expr) => {
+        $crate::macros::syscall5(
+            $n,
+            $a1 as usize,
+            $a2 as usize,
+            $a3 as usize,
+            $a4 as usize,
+            $a5 as usize,
+        )
+    };
+    ($n:expr, $a1:expr, $a2:expr, $a3:expr, $a4:expr, $a5:expr, $a6:expr) => {
+        $crate::macros::syscall6(
+            $n,
+            $a1 as usize,
+            $a2 as usize,
+            $a3 as usize,
+            $a4 as usize,
+            $a5 as usize,
+            $a6 as usize,
+        )
+    };
 }
@@ -0,0 +1,11 @@
+/// Max data size of a message in bytes
+pub const MSG_MAX_SIZE: usize = 256;
+
+/// Max number of messages in a queue
+pub const MSG_QUEUE_MAX_LEN: usize = 64;
+
+/// Receive a message of any type
+pub const MSG_ANY_TYPE: usize = 0;
+
+/// Timeout in milliseconds meaning to wait forever, `0` means not to wait at all
+pub const TIMEOUT_FOREVER: usize = usize::MAX;
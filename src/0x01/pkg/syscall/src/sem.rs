@@ -0,0 +1,39 @@
+use bitflags::bitflags;
+use num_enum::FromPrimitive;
+
+/// Max length of a semaphore name in bytes
+pub const SEM_NAME_MAX: usize = 64;
+
+bitflags! {
+    /// Flags of the `SemOpen` syscall
+    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+    pub struct SemFlags: usize {
+        /// Create the semaphore if it does not exist
+        const CREATE = 1 << 0;
+        /// With `CREATE`, fail with `EEXIST` if the semaphore exists
+        const EXCL = 1 << 1;
+        /// With `CREATE`, the semaphore is a lock owned by the processes
+        /// which acquired it, only they may signal it
+        const MUTEX = 1 << 2;
+    }
+}
+
+/// Operations of the `Sem` syscall on an opened semaphore
+#[repr(usize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, FromPrimitive)]
+pub enum SemOp {
+    /// Close the semaphore, it is removed after the last close
+    Close = 1,
+    /// Signal the semaphore (V)
+    Signal = 2,
+    /// Wait the semaphore (P), up to `timeout` ms
+    Wait = 3,
+    /// Wait the semaphore without blocking, fails with `EAGAIN`
+    TryWait = 4,
+    /// Like `Wait`, but fails with `EDEADLK` instead of blocking
+    /// if the wait would close a cycle of processes waiting on each other
+    WaitChecked = 5,
+
+    #[num_enum(default)]
+    Unknown = 65535,
+}
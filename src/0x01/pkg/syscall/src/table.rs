@@ -0,0 +1,185 @@
+/// The syscall table, the single source of truth of all syscalls
+///
+/// Each entry looks like:
+///
+/// ```text
+/// Variant = number => [@context] fn handler(arg: Type, ...) -> Ret;
+/// ```
+///
+/// It is consumed by a callback macro, which generates:
+///
+/// - the `Syscall` enum in this crate
+/// - the dispatcher in the kernel, which decodes the arguments and calls `handler`
+/// - the raw wrappers in `yslib`
+///
+/// Handlers return `SyscallResult<Ret>`, which is encoded into `rax` by the dispatcher.
+/// Handlers marked with `@context` take `&mut ProcessContext` as the first argument
+/// and set the return value by themselves, since they may block or switch the process.
+///
+/// Arguments are passed in `rdi`, `rsi`, `rdx`, `r10`, `r8` and `r9`, at most six.
+#[macro_export]
+macro_rules! syscall_table {
+    ($callback:ident) => {
+        $callback! {
+            /// Read from fd into `buf`, returns the length read, blocks if no data
+            Read = 0 => @context fn sys_read(fd: usize, buf: *mut u8, len: usize) -> usize;
+            /// Write `buf` to fd, returns the length written, blocks if a pipe is full
+            Write = 1 => @context fn sys_write(fd: usize, buf: *const u8, len: usize) -> usize;
+            /// Open the file at `path`, see `OpenFlags`, returns the new fd
+            Open = 2 => fn sys_open(path: *const u8, len: usize, flags: usize) -> usize;
+            /// Close the fd
+            Close = 3 => fn sys_close(fd: usize) -> ();
+            /// Write the metadata of the file at `path` to `stat`
+            StatFile = 4 => fn sys_stat_file(path: *const u8, len: usize, stat: *mut $crate::FileStat) -> ();
+            /// Write the metadata of the file opened as fd to `stat`
+            Fstat = 5 => fn sys_fstat(fd: usize, stat: *mut $crate::FileStat) -> ();
+            /// Move the offset of fd, see `SeekWhence`, returns the new offset,
+            /// the offset can not go beyond the end of file
+            Seek = 8 => fn sys_seek(fd: usize, offset: isize, whence: usize) -> usize;
+
+            /// Set the heap end (0 to query), returns the new heap end
+            Brk = 12 => fn sys_brk(addr: usize) -> usize;
+            /// Device specific operations, see `IoctlRequest`
+            Ioctl = 16 => fn sys_ioctl(fd: usize, request: usize, arg: usize) -> usize;
+            /// Create a pipe, the read fd and write fd are written to `fds[0]` and `fds[1]`
+            Pipe = 22 => fn sys_pipe(fds: *mut usize) -> ();
+            /// Duplicate the fd to the lowest free fd
+            Dup = 32 => fn sys_dup(fd: usize) -> usize;
+            /// Duplicate `old` to `new`, `new` is closed first if it is open
+            Dup2 = 33 => fn sys_dup2(old: usize, new: usize) -> usize;
+            /// Get the pid of the current process
+            GetPid = 39 => fn sys_get_pid() -> u16;
+            /// Semaphore operations on an opened semaphore, see `SemOp`, waits block up to `timeout` ms
+            Sem = 41 => @context fn sys_sem(op: usize, id: u32, timeout: usize) -> ();
+            /// Open the named semaphore, see `SemFlags`, returns its id
+            SemOpen = 42 => fn sys_sem_open(name: *const u8, len: usize, value: usize, flags: usize) -> u32;
+            /// Fork the current process, returns 0 in the child
+            Fork = 58 => @context fn sys_fork() -> u16;
+            /// Spawn an app by path, returns its pid
+            Spawn = 59 => fn sys_spawn(path: *const u8, len: usize) -> u16;
+            /// Exit the current process
+            Exit = 60 => @context fn sys_exit(code: isize) -> ();
+            /// Wait for a process to exit, its exit code is written to `status`
+            WaitPid = 61 => @context fn sys_wait_pid(pid: u16, status: *mut isize) -> u16;
+
+            /// Create a message queue holding at most `capacity` messages
+            MsgNew = 68 => fn sys_msg_new(key: u32, capacity: usize) -> ();
+            /// Send a message of type `mtype` (> 0), blocks up to `timeout` ms if the queue is full
+            MsgSend = 69 => @context fn sys_msg_send(key: u32, mtype: usize, buf: *const u8, len: usize, timeout: usize) -> ();
+            /// Receive the first message of type `mtype` (0 for any) into `buf`, returns its length
+            /// and writes its type to `mtype_out`, blocks up to `timeout` ms if there is none
+            MsgRecv = 70 => @context fn sys_msg_recv(key: u32, mtype: usize, buf: *mut u8, len: usize, mtype_out: *mut usize, timeout: usize) -> usize;
+            /// Remove a message queue, blocked processes fail with `EIDRM`
+            MsgRemove = 71 => fn sys_msg_remove(key: u32) -> ();
+            /// Manipulate the fd, see `FcntlCmd`
+            Fcntl = 72 => fn sys_fcntl(fd: usize, cmd: usize, arg: usize) -> usize;
+
+            /// Write the current working directory to `buf`, returns its length,
+            /// fails with `ERANGE` if `buf` is too small
+            GetCwd = 79 => fn sys_getcwd(buf: *mut u8, len: usize) -> usize;
+            /// Change the current working directory, relative paths start from the current one
+            Chdir = 80 => fn sys_chdir(path: *const u8, len: usize) -> ();
+            /// Rename the file or directory `src` to `dst`, an existing file at `dst` is replaced,
+            /// an existing directory fails with `EEXIST`
+            Rename = 82 => fn sys_rename(src: *const u8, src_len: usize, dst: *const u8, dst_len: usize) -> ();
+            /// Create a directory at `path`
+            Mkdir = 83 => fn sys_mkdir(path: *const u8, len: usize) -> ();
+            /// Remove the file at `path`, or the directory and all of its contents if `dir` is set
+            Unlink = 87 => fn sys_unlink(path: *const u8, len: usize, dir: bool) -> ();
+            /// Mount a new filesystem of type `fstype` (e.g. `tmpfs`) at `target`
+            Mount = 165 => fn sys_mount(fstype: *const u8, fstype_len: usize, target: *const u8, target_len: usize) -> ();
+            /// Unmount the filesystem at `target`, fails with `EBUSY` if others are mounted below it
+            Umount = 166 => fn sys_umount(target: *const u8, len: usize) -> ();
+
+            /// Get the current time in milliseconds
+            Time = 201 => fn sys_time() -> u64;
+            /// Futex operations on the `u32` at `addr`, see `FutexOp`,
+            /// waits block up to `timeout` ms, wakes return the number of processes woken
+            Futex = 202 => @context fn sys_futex(addr: *const u32, op: usize, val: usize, timeout: usize) -> usize;
+            /// Write at most `count` entries of the directory at `path` to `buf`, skipping the
+            /// first `start` entries, returns the number written (0 at the end)
+            ReadDir = 217 => fn sys_read_dir(path: *const u8, len: usize, buf: *mut $crate::DirEntry, count: usize, start: usize) -> usize;
+
+            /// List the apps loaded by the bootloader
+            ListApp = 65531 => fn sys_list_app() -> ();
+            /// List all processes
+            Stat = 65532 => fn sys_stat() -> ();
+            /// Allocate memory from the kernel managed user heap
+            Allocate = 65533 => fn sys_allocate(size: usize, align: usize) -> *mut u8;
+            /// Deallocate memory allocated by `Allocate`
+            Deallocate = 65534 => fn sys_deallocate(ptr: *mut u8, size: usize, align: usize) -> ();
+        }
+    };
+}
+
+/// Conversion between syscall arguments (or return values) and registers
+pub trait SyscallArg: Sized {
+    fn from_arg(arg: usize) -> Self;
+    fn into_arg(self) -> usize;
+}
+
+macro_rules! impl_syscall_arg {
+    ($($ty:ty),*) => {
+        $(
+            impl SyscallArg for $ty {
+                #[inline(always)]
+                fn from_arg(arg: usize) -> Self {
+                    arg as $ty
+                }
+
+                #[inline(always)]
+                fn into_arg(self) -> usize {
+                    self as usize
+                }
+            }
+        )*
+    };
+}
+
+impl_syscall_arg!(u8, u16, u32, u64, usize, i32, i64, isize);
+
+impl SyscallArg for () {
+    #[inline(always)]
+    fn from_arg(_: usize) -> Self {}
+
+    #[inline(always)]
+    fn into_arg(self) -> usize {
+        0
+    }
+}
+
+impl SyscallArg for bool {
+    #[inline(always)]
+    fn from_arg(arg: usize) -> Self {
+        arg != 0
+    }
+
+    #[inline(always)]
+    fn into_arg(self) -> usize {
+        self as usize
+    }
+}
+
+impl<T> SyscallArg for *const T {
+    #[inline(always)]
+    fn from_arg(arg: usize) -> Self {
+        arg as *const T
+    }
+
+    #[inline(always)]
+    fn into_arg(self) -> usize {
+        self as usize
+    }
+}
+
+impl<T> SyscallArg for *mut T {
+    #[inline(always)]
+    fn from_arg(arg: usize) -> Self {
+        arg as *mut T
+    }
+
+    #[inline(always)]
+    fn into_arg(self) -> usize {
+        self as usize
+    }
+}
@@ -0,0 +1,36 @@
+use bitflags::bitflags;
+use num_enum::FromPrimitive;
+
+bitflags! {
+    /// Terminal modes, switched by `Ioctl` with `IoctlRequest::TtySetMode`
+    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+    pub struct TtyMode: usize {
+        /// Line editing by the kernel, reads return at most one line
+        const CANONICAL = 1 << 0;
+        /// Echo input back to the terminal
+        const ECHO = 1 << 1;
+        /// Ctrl-C kills the foreground process
+        const SIGNAL = 1 << 2;
+    }
+}
+
+impl Default for TtyMode {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+/// Requests of the `Ioctl` syscall
+#[repr(usize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, FromPrimitive)]
+pub enum IoctlRequest {
+    /// Get the `TtyMode` of the terminal
+    TtyGetMode = 1,
+    /// Set the `TtyMode` of the terminal, `arg` is the mode bits
+    TtySetMode = 2,
+    /// Set the process receiving Ctrl-C, `arg` is the pid (0 for none)
+    TtySetForeground = 3,
+
+    #[num_enum(default)]
+    Unknown = 0,
+}